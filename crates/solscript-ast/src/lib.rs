@@ -88,6 +88,9 @@ pub struct ModifierInvocation {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ContractDef {
+    /// `///` doc comment lines above the contract
+    #[serde(default)]
+    pub docs: Vec<String>,
     pub attributes: Vec<Attribute>,
     pub is_abstract: bool,
     pub name: Ident,
@@ -110,6 +113,9 @@ pub enum ContractMember {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StateVar {
+    /// `///` doc comment lines above the variable
+    #[serde(default)]
+    pub docs: Vec<String>,
     pub attributes: Vec<Attribute>,
     pub ty: TypeExpr,
     pub visibility: Option<Visibility>,
@@ -124,6 +130,9 @@ pub struct StateVar {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct InterfaceDef {
+    /// `///` doc comment lines above the interface
+    #[serde(default)]
+    pub docs: Vec<String>,
     pub attributes: Vec<Attribute>,
     pub name: Ident,
     pub bases: Vec<TypePath>,
@@ -133,6 +142,9 @@ pub struct InterfaceDef {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FnSig {
+    /// `///` doc comment lines above the function
+    #[serde(default)]
+    pub docs: Vec<String>,
     pub name: Ident,
     pub generic_params: Option<GenericParams>,
     pub params: Vec<Param>,
@@ -140,9 +152,29 @@ pub struct FnSig {
     pub state_mutability: Vec<StateMutability>,
     pub modifiers: Vec<ModifierInvocation>,
     pub return_params: Vec<ReturnParam>,
+    /// Anchor instruction metadata, set for interfaces imported from an IDL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<AnchorInstruction>,
     pub span: Span,
 }
 
+/// An Anchor instruction backing an interface function imported from an IDL.
+///
+/// The function's leading parameters are the instruction accounts (as
+/// `address` values, in IDL order), followed by the instruction arguments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnchorInstruction {
+    pub discriminator: [u8; 8],
+    pub accounts: Vec<AnchorAccountMeta>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnchorAccountMeta {
+    pub name: SmolStr,
+    pub is_mut: bool,
+    pub is_signer: bool,
+}
+
 // =============================================================================
 // Struct Definition
 // =============================================================================
@@ -220,6 +252,9 @@ pub struct ErrorParam {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ConstructorDef {
+    /// `///` doc comment lines above the constructor
+    #[serde(default)]
+    pub docs: Vec<String>,
     pub params: Vec<Param>,
     pub modifiers: Vec<ModifierInvocation>,
     pub body: Block,
@@ -244,6 +279,9 @@ pub struct ModifierDef {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FnDef {
+    /// `///` doc comment lines above the function
+    #[serde(default)]
+    pub docs: Vec<String>,
     pub attributes: Vec<Attribute>,
     pub name: Ident,
     pub generic_params: Option<GenericParams>,
@@ -258,6 +296,7 @@ pub struct FnDef {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Param {
+    pub attributes: Vec<Attribute>,
     pub ty: TypeExpr,
     pub storage_location: Option<StorageLocation>,
     pub name: Ident,
@@ -316,6 +355,8 @@ pub struct AttributeArg {
 pub enum AttributeValue {
     Ident(Ident),
     Literal(Literal),
+    /// An arbitrary expression, e.g. `#[constraint(constraint = vault.amount > 0)]`
+    Expr(Box<Expr>),
 }
 
 // =============================================================================
@@ -344,6 +385,25 @@ pub enum Stmt {
     Expr(ExprStmt),
 }
 
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::VarDecl(v) => v.span,
+            Stmt::Return(r) => r.span,
+            Stmt::If(i) => i.span,
+            Stmt::While(w) => w.span,
+            Stmt::For(f) => f.span,
+            Stmt::Emit(e) => e.span,
+            Stmt::Require(r) => r.span,
+            Stmt::Revert(r) => r.span,
+            Stmt::Delete(d) => d.span,
+            Stmt::Selfdestruct(s) => s.span,
+            Stmt::Placeholder(span) => *span,
+            Stmt::Expr(e) => e.span,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VarDeclStmt {
     pub ty: TypeExpr,
@@ -652,6 +712,9 @@ pub enum Literal {
     String(SmolStr, Span),
     HexString(SmolStr, Span),
     Address(SmolStr, Span),
+    /// A decimal literal such as `1.5`: its digits without the point (`15`) and the
+    /// number of digits after the point (`1`)
+    Decimal(u128, u8, Span),
 }
 
 impl Literal {
@@ -663,6 +726,7 @@ impl Literal {
             Literal::String(_, span) => *span,
             Literal::HexString(_, span) => *span,
             Literal::Address(_, span) => *span,
+            Literal::Decimal(_, _, span) => *span,
         }
     }
 }
@@ -482,3 +482,60 @@ impl PrimitiveType {
         }
     }
 }
+
+/// A fixed-point decimal type: `fixed64x6`, `ufixed128x18`, ...
+///
+/// Values are stored as integers of `bits` bits, scaled by `10^decimals`: `1.5` is
+/// `1500000` in a `ufixed64x6`. `fixed` and `ufixed` are `fixed128x18` and
+/// `ufixed128x18`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FixedType {
+    pub signed: bool,
+    /// 64 or 128
+    pub bits: u16,
+    pub decimals: u8,
+}
+
+impl FixedType {
+    /// Parse a fixed-point type name
+    pub fn parse(s: &str) -> Option<Self> {
+        let (signed, rest) = match s.strip_prefix('u') {
+            Some(rest) => (false, rest),
+            None => (true, s),
+        };
+        let rest = rest.strip_prefix("fixed")?;
+        if rest.is_empty() {
+            return Some(Self {
+                signed,
+                bits: 128,
+                decimals: 18,
+            });
+        }
+        let (bits, decimals) = rest.split_once('x')?;
+        if bits.starts_with('0') || decimals.len() > 1 && decimals.starts_with('0') {
+            return None;
+        }
+        let ty = Self {
+            signed,
+            bits: bits.parse().ok()?,
+            decimals: decimals.parse().ok()?,
+        };
+        (matches!(ty.bits, 64 | 128) && ty.decimals <= ty.max_decimals()).then_some(ty)
+    }
+
+    /// The most decimals the type can have: `10^decimals` must fit its integer
+    pub fn max_decimals(&self) -> u8 {
+        if self.bits == 64 {
+            18
+        } else {
+            38
+        }
+    }
+}
+
+impl std::fmt::Display for FixedType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.signed { "" } else { "u" };
+        write!(f, "{}fixed{}x{}", sign, self.bits, self.decimals)
+    }
+}
@@ -988,6 +988,9 @@ impl<'a, 'ctx> Compiler<'a, 'ctx> {
                     .collect();
                 Ok(self.context.i8_type().const_array(&values).into())
             }
+            Literal::Decimal(..) => Err(BpfError::Unsupported(
+                "Fixed-point decimal literals".to_string(),
+            )),
         }
     }
 
@@ -71,6 +71,8 @@ pub struct CompileOptions {
     pub use_cargo_sbf: bool,
     /// Keep intermediate files
     pub keep_intermediate: bool,
+    /// SolScript source file, named when build errors are mapped back to it
+    pub source_path: Option<PathBuf>,
 }
 
 impl Default for CompileOptions {
@@ -81,6 +83,7 @@ impl Default for CompileOptions {
             output_dir: PathBuf::from("target/deploy"),
             use_cargo_sbf: true,
             keep_intermediate: false,
+            source_path: None,
         }
     }
 }
@@ -130,8 +133,11 @@ fn compile_via_anchor(
     }
 
     // Generate Anchor code
-    let generated =
+    let mut generated =
         solscript_codegen::generate(program).map_err(|e| BpfError::CodegenError(e.to_string()))?;
+    if let Some(path) = &options.source_path {
+        generated.source_map.source = path.display().to_string();
+    }
 
     // Write to output directory
     let anchor_dir = options.output_dir.join("anchor_project");
@@ -197,6 +203,9 @@ fn compile_via_anchor(
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = generated
+            .source_map
+            .rewrite_diagnostics(&stderr, |_| Some(source));
         return Err(BpfError::BuildError(format!("Build failed:\n{}", stderr)));
     }
 
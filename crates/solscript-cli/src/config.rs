@@ -1,25 +1,37 @@
 //! Configuration file parsing for SolScript projects
 
-use miette::{IntoDiagnostic, Result, WrapErr};
-use serde::{Deserialize, Serialize};
+use miette::{IntoDiagnostic, LabeledSpan, NamedSource, Result, WrapErr};
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::BTreeMap;
-use std::path::Path;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
 /// The main configuration file structure (solscript.toml)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
-    pub project: ProjectConfig,
+    /// Absent from the manifest of a workspace root that is not a project itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<ProjectConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<WorkspaceConfig>,
     #[serde(default)]
     pub contract: ContractConfig,
     #[serde(default)]
     pub build: BuildConfig,
     #[serde(default)]
     pub solana: SolanaConfig,
+    #[serde(default, skip_serializing_if = "RegistryConfig::is_default")]
+    pub registry: RegistryConfig,
+    #[serde(default, skip_serializing_if = "NetConfig::is_default")]
+    pub net: NetConfig,
     #[serde(default)]
     pub dependencies: BTreeMap<String, Dependency>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ProjectConfig {
     pub name: String,
     #[serde(default = "default_version")]
@@ -34,11 +46,20 @@ pub struct ProjectConfig {
     pub repository: Option<String>,
 }
 
+/// The projects of a workspace, which share `solscript.lock` and `.solscript/packages`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    /// Directories of the member projects, relative to the workspace root
+    pub members: Vec<String>,
+}
+
 fn default_version() -> String {
     "0.1.0".to_string()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ContractConfig {
     #[serde(default = "default_main")]
     pub main: String,
@@ -46,32 +67,122 @@ pub struct ContractConfig {
     pub name: Option<String>,
 }
 
+impl Default for ContractConfig {
+    fn default() -> Self {
+        Self {
+            main: default_main(),
+            name: None,
+        }
+    }
+}
+
 fn default_main() -> String {
     "src/main.sol".to_string()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct BuildConfig {
     #[serde(default = "default_output")]
     pub output: String,
 }
 
+impl Default for BuildConfig {
+    fn default() -> Self {
+        Self {
+            output: default_output(),
+        }
+    }
+}
+
 fn default_output() -> String {
     "output".to_string()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SolanaConfig {
     #[serde(default = "default_cluster")]
     pub cluster: String,
+    /// Program IDs per cluster: `[solana.programs.<cluster>]` maps program name to address
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub programs: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Default for SolanaConfig {
+    fn default() -> Self {
+        Self {
+            cluster: default_cluster(),
+            programs: BTreeMap::new(),
+        }
+    }
+}
+
+impl SolanaConfig {
+    /// Configured address of `program` on `cluster`
+    pub fn program_id(&self, cluster: &str, program: &str) -> Option<&str> {
+        self.programs
+            .get(cluster)
+            .and_then(|ids| ids.get(program))
+            .map(String::as_str)
+    }
+
+    /// Address of every program configured for `program`, keyed by cluster
+    pub fn cluster_program_ids(&self, program: &str) -> BTreeMap<String, String> {
+        self.programs
+            .iter()
+            .filter_map(|(cluster, ids)| Some((cluster.clone(), ids.get(program)?.clone())))
+            .collect()
+    }
 }
 
 fn default_cluster() -> String {
     "devnet".to_string()
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegistryConfig {
+    /// Package index version dependencies are resolved against: a directory (relative
+    /// to the project root) or a git URL
+    #[serde(default = "default_index")]
+    pub index: String,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            index: default_index(),
+        }
+    }
+}
+
+impl RegistryConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn default_index() -> String {
+    "https://github.com/cryptuon-packages/index.git".to_string()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetConfig {
+    /// Install dependencies only from `.solscript/packages` and `vendor/`
+    #[serde(default)]
+    pub offline: bool,
+}
+
+impl NetConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 /// A dependency specification
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 pub enum Dependency {
     /// Simple version string: `package = "1.0.0"`
@@ -81,6 +192,7 @@ pub enum Dependency {
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct DependencySpec {
     /// Version requirement (semver)
     #[serde(default)]
@@ -105,6 +217,33 @@ pub struct DependencySpec {
     pub github: Option<String>,
 }
 
+// Not `untagged`: that would turn an unknown key in a table into "data did not match
+// any variant" instead of naming the key
+impl<'de> Deserialize<'de> for Dependency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DependencyVisitor;
+
+        impl<'de> Visitor<'de> for DependencyVisitor {
+            type Value = Dependency;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a version string or a dependency table")
+            }
+
+            fn visit_str<E: de::Error>(self, version: &str) -> Result<Dependency, E> {
+                Ok(Dependency::Version(version.to_string()))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Dependency, A::Error> {
+                DependencySpec::deserialize(de::value::MapAccessDeserializer::new(map))
+                    .map(Dependency::Detailed)
+            }
+        }
+
+        deserializer.deserialize_any(DependencyVisitor)
+    }
+}
+
 impl Dependency {
     /// Get the version string if available
     pub fn version(&self) -> Option<&str> {
@@ -150,6 +289,11 @@ impl Dependency {
         }
     }
 
+    /// Check if this is a registry dependency, resolved against the package index
+    pub fn is_registry(&self) -> bool {
+        !self.is_git() && !self.is_path()
+    }
+
     /// Check if this is a path dependency
     pub fn is_path(&self) -> bool {
         match self {
@@ -174,9 +318,42 @@ impl Config {
             .into_diagnostic()
             .wrap_err_with(|| format!("Failed to read config file: {}", path.display()))?;
 
-        toml::from_str(&content)
-            .into_diagnostic()
-            .wrap_err("Failed to parse solscript.toml")
+        Self::parse(&content)
+            .map_err(|e| e.with_source_code(NamedSource::new(path.display().to_string(), content)))
+    }
+
+    /// Parse and validate the contents of a `solscript.toml`
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content).map_err(|e| {
+            let labels = e
+                .span()
+                .map(|span| LabeledSpan::at(span, "here"))
+                .into_iter()
+                .collect::<Vec<_>>();
+            miette::miette!(
+                code = "solscript::config::invalid",
+                labels = labels,
+                "Invalid solscript.toml: {}",
+                e.message()
+            )
+        })?;
+        if config.project.is_none() && config.workspace.is_none() {
+            return Err(miette::miette!(
+                code = "solscript::config::invalid",
+                "Invalid solscript.toml: missing a [project] or [workspace] section"
+            ));
+        }
+        Ok(config)
+    }
+
+    /// The [project] section, which only a workspace root may leave out
+    pub fn project(&self) -> Result<&ProjectConfig> {
+        self.project.as_ref().ok_or_else(|| {
+            miette::miette!(
+                help = "run the command in the directory of a workspace member, or build every member with `solscript build --workspace`",
+                "This solscript.toml is a workspace root, not a project"
+            )
+        })
     }
 
     /// Save configuration to a file
@@ -204,6 +381,37 @@ impl Config {
         }
     }
 
+    /// Directory the build output goes in, for a project rooted at `root`
+    pub fn output_dir(&self, root: &Path) -> PathBuf {
+        root.join(&self.build.output)
+    }
+
+    /// The source files of a project rooted at `root`, compiled as one unit: every `.sol`
+    /// file under `src/`, sorted, with `contract.main` (the entry point) last
+    pub fn source_files(&self, root: &Path) -> Result<Vec<PathBuf>> {
+        let main = root.join(&self.contract.main);
+        if !main.is_file() {
+            return Err(miette::miette!(
+                help = "set `main` in the [contract] section of solscript.toml",
+                "The project's main contract {} does not exist",
+                main.display()
+            ));
+        }
+
+        let mut files = Vec::new();
+        let src = root.join("src");
+        if src.is_dir() {
+            collect_sources(&src, &mut files)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to read {}", src.display()))?;
+        }
+        files.sort();
+        let main = main.canonicalize().into_diagnostic()?;
+        files.retain(|file| file.canonicalize().map_or(true, |f| f != main));
+        files.push(root.join(&self.contract.main));
+        Ok(files)
+    }
+
     /// Add a dependency
     pub fn add_dependency(&mut self, name: String, dep: Dependency) {
         self.dependencies.insert(name, dep);
@@ -215,6 +423,18 @@ impl Config {
     }
 }
 
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_sources(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "sol") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -268,4 +488,112 @@ mylib = { path = "../mylib" }
         assert!(dep.is_path());
         assert_eq!(dep.local_path(), Some("../mylib"));
     }
+
+    #[test]
+    fn test_parse_program_ids() {
+        let toml_str = r#"
+[project]
+name = "test"
+
+[solana]
+cluster = "devnet"
+
+[solana.programs.devnet]
+counter = "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS"
+
+[solana.programs.mainnet-beta]
+counter = "3ARMH9zfVCnU2TKiphU4xcEyWdA45fc1sjKEtYMdf3gr"
+"#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.solana.program_id("devnet", "counter"),
+            Some("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS")
+        );
+        assert_eq!(config.solana.program_id("localnet", "counter"), None);
+        assert_eq!(config.solana.cluster_program_ids("counter").len(), 2);
+
+        let defaults: Config = toml::from_str("[project]\nname = \"test\"\n").unwrap();
+        assert_eq!(defaults.solana.cluster, "devnet");
+        assert_eq!(defaults.build.output, "output");
+        assert_eq!(defaults.contract.main, "src/main.sol");
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let err = Config::parse("[package]\nname = \"test\"\n").unwrap_err();
+        assert!(
+            err.to_string().contains("unknown field `package`"),
+            "{}",
+            err
+        );
+
+        let toml_str = "[project]\nname = \"test\"\n\n[build]\noutput_dir = \"./target\"\n";
+        let err = Config::parse(toml_str).unwrap_err();
+        assert!(
+            err.to_string().contains("unknown field `output_dir`"),
+            "{}",
+            err
+        );
+        let label = err.labels().unwrap().next().unwrap();
+        assert_eq!(
+            &toml_str[label.offset()..label.offset() + label.len()],
+            "output_dir"
+        );
+
+        let toml_str =
+            "[project]\nname = \"test\"\n\n[dependencies]\nmylib = { paht = \"../mylib\" }\n";
+        let err = Config::parse(toml_str).unwrap_err();
+        assert!(err.to_string().contains("unknown field `paht`"), "{}", err);
+    }
+
+    #[test]
+    fn test_workspace_root_needs_no_project() {
+        let config = Config::parse("[workspace]\nmembers = [\"programs/vault\"]\n").unwrap();
+        assert_eq!(config.workspace.unwrap().members, ["programs/vault"]);
+        assert!(config.project.is_none());
+
+        let err = Config::parse("[build]\noutput = \"out\"\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("missing a [project] or [workspace]"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_source_files() {
+        let root = std::env::temp_dir().join(format!("solscript-sources-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src/lib")).unwrap();
+        for file in [
+            "src/main.sol",
+            "src/token.sol",
+            "src/lib/math.sol",
+            "src/notes.md",
+        ] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+
+        let config = Config::parse("[project]\nname = \"test\"\n").unwrap();
+        let files = config.source_files(&root).unwrap();
+        let files: Vec<_> = files
+            .iter()
+            .map(|f| f.strip_prefix(&root).unwrap())
+            .collect();
+        assert_eq!(
+            files,
+            [
+                Path::new("src/lib/math.sol"),
+                Path::new("src/token.sol"),
+                Path::new("src/main.sol")
+            ]
+        );
+
+        let missing =
+            Config::parse("[project]\nname = \"test\"\n[contract]\nmain = \"app.sol\"\n").unwrap();
+        assert!(missing.source_files(&root).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 }
@@ -0,0 +1,116 @@
+//! Program keypairs in `target/deploy`
+//!
+//! `anchor build` and `cargo build-sbf` deploy a program at the address of
+//! `target/deploy/<program>-keypair.json`. The keypair is created on the first build so the
+//! generated `declare_id!`, Anchor.toml, IDL and client agree with it from the start.
+
+use miette::{IntoDiagnostic, Result, WrapErr};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Path of the keypair `program` is deployed with
+pub fn keypair_path(output: &Path, program: &str) -> PathBuf {
+    output
+        .join("target")
+        .join("deploy")
+        .join(format!("{}-keypair.json", program))
+}
+
+/// Base58 address of a Solana keypair file
+pub fn read_address(path: &Path) -> Result<String> {
+    let bytes: Vec<u8> = fs::read_to_string(path)
+        .into_diagnostic()
+        .and_then(|json| serde_json::from_str(&json).into_diagnostic())
+        .wrap_err_with(|| format!("Failed to read program keypair {}", path.display()))?;
+    // Solana keypair files hold the 32-byte secret key followed by the public key
+    if bytes.len() != 64 {
+        return Err(miette::miette!(
+            "Program keypair {} should hold 64 bytes, found {}",
+            path.display(),
+            bytes.len()
+        ));
+    }
+    Ok(bs58::encode(&bytes[32..]).into_string())
+}
+
+/// Address of the keypair at `path`, generating a new keypair there if none exists.
+/// The flag is true when the keypair was just created.
+pub fn load_or_create(path: &Path) -> Result<(String, bool)> {
+    if path.exists() {
+        return Ok((read_address(path)?, false));
+    }
+
+    let mut secret = [0u8; 32];
+    getrandom::getrandom(&mut secret)
+        .map_err(|e| miette::miette!("Failed to generate program keypair: {}", e))?;
+    let keypair = ed25519_dalek::SigningKey::from_bytes(&secret).to_keypair_bytes();
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let json = serde_json::to_string(&keypair.to_vec()).into_diagnostic()?;
+    write_secret(path, &json)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to write program keypair {}", path.display()))?;
+
+    Ok((bs58::encode(&keypair[32..]).into_string(), true))
+}
+
+/// Every program keypair in `output/target/deploy`, as `(program, address)` sorted by name
+pub fn list(output: &Path) -> Result<Vec<(String, String)>> {
+    let Ok(entries) = fs::read_dir(output.join("target").join("deploy")) else {
+        return Ok(Vec::new());
+    };
+
+    let mut keys = Vec::new();
+    for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+        let Some(program) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix("-keypair.json"))
+        else {
+            continue;
+        };
+        keys.push((program.to_string(), read_address(&path)?));
+    }
+    keys.sort();
+    Ok(keys)
+}
+
+/// Write a secret key file readable only by its owner
+fn write_secret(path: &Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_keypair_round_trips() {
+        let dir = std::env::temp_dir().join(format!("solscript-keys-{}", std::process::id()));
+        let path = keypair_path(&dir, "counter");
+
+        let (address, created) = load_or_create(&path).unwrap();
+        assert!(created);
+        assert_eq!(bs58::decode(&address).into_vec().unwrap().len(), 32);
+
+        let (again, created) = load_or_create(&path).unwrap();
+        assert!(!created);
+        assert_eq!(again, address);
+        assert_eq!(list(&dir).unwrap(), vec![("counter".to_string(), address)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
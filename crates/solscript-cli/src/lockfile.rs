@@ -0,0 +1,336 @@
+//! `solscript.lock`: the exact code every dependency was installed from
+//!
+//! `solscript install` records, for each git and registry dependency, where it was
+//! fetched from, the commit it was checked out at and a checksum of its files. Later
+//! installs check out the recorded commits instead of whatever the branch or tag points
+//! to now, and every install and build fails if a package's files no longer match their
+//! checksum. `solscript update` re-resolves the dependencies and rewrites the lock.
+//! Path dependencies are local code and are not locked.
+
+use crate::config::Dependency;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// File name of the lockfile, next to `solscript.toml`
+pub const LOCKFILE: &str = "solscript.lock";
+
+const HEADER: &str = "# This file is generated by `solscript install`. Do not edit it by hand.\n\n";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lockfile {
+    /// Format version
+    pub version: u32,
+    #[serde(default, rename = "package", skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<LockedPackage>,
+}
+
+/// One installed dependency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedPackage {
+    pub name: String,
+    /// Version the package was resolved to, for registry packages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// `git+<url>[?tag=..|?branch=..|?rev=..]` or `registry+<archive url>`
+    pub source: String,
+    /// Commit the package was checked out at, for git packages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    /// `sha256:<hex>` of the package's files, see [`checksum`]
+    pub checksum: String,
+    /// Registry packages this one depends on, for registry packages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            packages: Vec::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// The lockfile of the project rooted at `root`, if it has one
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(LOCKFILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        let lock: Self = toml::from_str(&content)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+        if lock.version != 1 {
+            return Err(miette::miette!(
+                "{} has format version {}, but this solscript understands version 1",
+                path.display(),
+                lock.version
+            ));
+        }
+        Ok(Some(lock))
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = root.join(LOCKFILE);
+        let content = toml::to_string_pretty(self)
+            .into_diagnostic()
+            .wrap_err("Failed to serialize solscript.lock")?;
+        std::fs::write(&path, format!("{}{}", HEADER, content))
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    /// Add or replace the entry for a package, keeping entries sorted by name
+    pub fn insert(&mut self, package: LockedPackage) {
+        self.packages.retain(|p| p.name != package.name);
+        self.packages.push(package);
+        self.packages.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Drop the entries of packages that are no longer dependencies
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.packages.retain(|p| keep(&p.name));
+    }
+
+    /// Check every locked package installed under `packages_dir` against its checksum
+    pub fn verify(&self, packages_dir: &Path) -> Result<()> {
+        for package in &self.packages {
+            let dir = packages_dir.join(&package.name);
+            if dir.exists() {
+                package.verify(&dir)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LockedPackage {
+    /// Whether this entry was resolved from `dep` as it is written now. An entry for an
+    /// edited dependency is stale and the dependency is resolved again.
+    pub fn matches(&self, dep: &Dependency) -> bool {
+        match git_source(dep) {
+            Some(source) => self.source == source,
+            None => {
+                let requirement = VersionReq::parse(dep.version().unwrap_or("*"));
+                let version = self.version.as_deref().map(Version::parse);
+                match (requirement, version) {
+                    (Ok(requirement), Some(Ok(version))) => {
+                        self.source.starts_with("registry+") && requirement.matches(&version)
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    /// Fail unless the files in `dir` have the recorded checksum
+    pub fn verify(&self, dir: &Path) -> Result<()> {
+        let actual = checksum(dir)?;
+        if actual != self.checksum {
+            return Err(miette::miette!(
+                help =
+                    "if the change is intended, run `solscript update` to re-lock the dependencies",
+                "Checksum mismatch for package '{}': {} records {}, but {} has {}",
+                self.name,
+                LOCKFILE,
+                self.checksum,
+                dir.display(),
+                actual
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Source a git dependency is locked under: its URL and the ref it asks for
+pub fn git_source(dep: &Dependency) -> Option<String> {
+    let url = dep.git_url()?;
+    let Dependency::Detailed(spec) = dep else {
+        return Some(format!("git+{}", url));
+    };
+    let reference = [
+        ("tag", &spec.tag),
+        ("branch", &spec.branch),
+        ("rev", &spec.rev),
+    ]
+    .into_iter()
+    .find_map(|(kind, value)| Some(format!("?{}={}", kind, value.as_ref()?)));
+    Some(format!("git+{}{}", url, reference.unwrap_or_default()))
+}
+
+/// Name of the file a packaged package records its own checksum in, which is not part of
+/// the checksum
+pub const CHECKSUM_FILE: &str = ".solscript-checksum";
+
+/// `sha256:<hex>` over the relative path and contents of every file under `dir`, in
+/// path order, skipping `.git` and [`CHECKSUM_FILE`]
+pub fn checksum(dir: &Path) -> Result<String> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to read {}", dir.display()))?;
+    files.retain(|relative| relative != Path::new(CHECKSUM_FILE));
+    files.sort();
+
+    let mut contents = Vec::new();
+    for relative in files {
+        let content = std::fs::read(dir.join(&relative))
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to read {}", dir.join(&relative).display()))?;
+        contents.push((relative.to_string_lossy().replace('\\', "/"), content));
+    }
+    Ok(checksum_files(&contents))
+}
+
+/// [`checksum`] of files given as `/`-separated relative paths and contents, in path
+/// order
+pub fn checksum_files(files: &[(String, Vec<u8>)]) -> String {
+    let mut hasher = Sha256::new();
+    for (name, contents) in files {
+        // Separate path from contents and files from each other, so no two trees collide
+        hasher.update((name.len() as u64).to_le_bytes());
+        hasher.update(name.as_bytes());
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(contents);
+    }
+    let hex: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256:{}", hex)
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            if entry.file_name() != ".git" {
+                collect_files(root, &path, files)?;
+            }
+        } else {
+            files.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DependencySpec;
+
+    #[test]
+    fn test_lockfile_round_trip() {
+        let mut lock = Lockfile::default();
+        lock.insert(LockedPackage {
+            name: "token".to_string(),
+            version: None,
+            source: "git+https://github.com/cryptuon/token-lib.git?tag=v1.0.0".to_string(),
+            rev: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+            checksum: "sha256:00".to_string(),
+            dependencies: Vec::new(),
+        });
+        lock.insert(LockedPackage {
+            name: "math".to_string(),
+            version: Some("1.2.0".to_string()),
+            source: "registry+https://example.com/math-1.2.0.tar.gz".to_string(),
+            rev: None,
+            checksum: "sha256:01".to_string(),
+            dependencies: vec!["bits".to_string()],
+        });
+        assert_eq!(lock.packages[0].name, "math");
+
+        let root = std::env::temp_dir().join(format!("solscript-lock-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        lock.save(&root).unwrap();
+        let content = std::fs::read_to_string(root.join(LOCKFILE)).unwrap();
+        assert!(content.starts_with("# This file is generated"));
+        assert!(content.contains("[[package]]"));
+        assert_eq!(Lockfile::load(&root).unwrap(), Some(lock));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_locked_package_matches_its_dependency() {
+        let dep = Dependency::Detailed(DependencySpec {
+            github: Some("cryptuon/token-lib".to_string()),
+            tag: Some("v1.0.0".to_string()),
+            ..Default::default()
+        });
+        let mut locked = LockedPackage {
+            name: "token".to_string(),
+            version: None,
+            source: git_source(&dep).unwrap(),
+            rev: None,
+            checksum: String::new(),
+            dependencies: Vec::new(),
+        };
+        assert_eq!(
+            locked.source,
+            "git+https://github.com/cryptuon/token-lib.git?tag=v1.0.0"
+        );
+        assert!(locked.matches(&dep));
+
+        let retagged = Dependency::Detailed(DependencySpec {
+            github: Some("cryptuon/token-lib".to_string()),
+            tag: Some("v2.0.0".to_string()),
+            ..Default::default()
+        });
+        assert!(!locked.matches(&retagged));
+
+        locked.source = "registry+https://example.com/token-1.0.0.tar.gz".to_string();
+        locked.version = Some("1.0.0".to_string());
+        assert!(locked.matches(&Dependency::Version("1.0.0".to_string())));
+        assert!(locked.matches(&Dependency::Version("^1.0".to_string())));
+        assert!(!locked.matches(&Dependency::Version("1.1.0".to_string())));
+    }
+
+    #[test]
+    fn test_checksum_covers_names_and_contents() {
+        let dir = std::env::temp_dir().join(format!("solscript-checksum-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join("src/lib.sol"), "contract Lib {}").unwrap();
+        let original = checksum(&dir).unwrap();
+        assert!(original.starts_with("sha256:"));
+
+        // Git metadata is not package content
+        std::fs::write(dir.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+        assert_eq!(checksum(&dir).unwrap(), original);
+
+        std::fs::write(dir.join("src/lib.sol"), "contract Lib { }").unwrap();
+        let edited = checksum(&dir).unwrap();
+        assert_ne!(edited, original);
+
+        let locked = LockedPackage {
+            name: "lib".to_string(),
+            version: None,
+            source: "git+file:///lib".to_string(),
+            rev: None,
+            checksum: original,
+            dependencies: Vec::new(),
+        };
+        let err = locked.verify(&dir).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Checksum mismatch for package 'lib'"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
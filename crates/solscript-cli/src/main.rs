@@ -3,12 +3,19 @@
 //! Command-line interface for the SolScript compiler.
 
 mod config;
+mod keys;
+mod lockfile;
 mod package;
+mod publish;
+mod registry;
 mod templates;
+mod workspace;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use miette::{IntoDiagnostic, Result, WrapErr};
 use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+use solscript_driver::Session;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::channel;
@@ -50,9 +57,9 @@ enum Commands {
     },
     /// Parse a SolScript file and check for syntax errors
     Check {
-        /// The source file to check
+        /// The source file to check (default: the project's sources)
         #[arg(value_name = "FILE")]
-        file: PathBuf,
+        file: Option<PathBuf>,
     },
     /// Parse a SolScript file and print the AST
     Parse {
@@ -66,23 +73,55 @@ enum Commands {
     },
     /// Compile a SolScript file to an Anchor project
     Build {
-        /// The source file to compile
+        /// The source file to compile (default: the project's sources)
         #[arg(value_name = "FILE")]
-        file: PathBuf,
+        file: Option<PathBuf>,
 
-        /// Output directory for the generated Anchor project
-        #[arg(short, long, default_value = "output")]
-        output: PathBuf,
+        /// Output directory for the generated Anchor project (default: the project's build output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Rust backend for the on-chain program
+        #[arg(long, value_enum, default_value_t = BackendArg::Anchor)]
+        backend: BackendArg,
+
+        /// Write the pre-0.30 Anchor IDL format instead of the current spec
+        #[arg(long)]
+        legacy_idl: bool,
+
+        /// Build every member of the workspace, in dependency order
+        #[arg(long, conflicts_with_all = ["file", "output"])]
+        workspace: bool,
     },
     /// Generate Rust/Anchor code without writing to disk
     Codegen {
-        /// The source file to compile
+        /// The source file to compile (default: the project's sources)
         #[arg(value_name = "FILE")]
-        file: PathBuf,
+        file: Option<PathBuf>,
+    },
+    /// Compile a standard-JSON request, for editors and CI (like `solc --standard-json`)
+    Compile {
+        /// Read a standard-JSON request and print a standard-JSON response
+        #[arg(long, required = true)]
+        json: bool,
+
+        /// File holding the request (default: stdin)
+        #[arg(value_name = "INPUT")]
+        input: Option<PathBuf>,
+    },
+    /// Print the byte layout of the program's accounts and structs
+    Layout {
+        /// The source file to inspect (default: the project's sources)
+        #[arg(value_name = "FILE")]
+        file: Option<PathBuf>,
+
+        /// Compare two versions of a contract and flag breaking layout changes
+        #[arg(long, num_args = 2, value_names = ["OLD", "NEW"], conflicts_with = "file")]
+        diff: Option<Vec<PathBuf>>,
     },
     /// Format SolScript source files
     Fmt {
-        /// The source file(s) to format
+        /// The source file(s) to format (default: the project's sources)
         #[arg(value_name = "FILE")]
         files: Vec<PathBuf>,
 
@@ -92,13 +131,13 @@ enum Commands {
     },
     /// Watch for changes and rebuild automatically
     Watch {
-        /// The source file to watch and compile
+        /// The source file to watch and compile (default: the project's sources)
         #[arg(value_name = "FILE")]
-        file: PathBuf,
+        file: Option<PathBuf>,
 
-        /// Output directory for the generated Anchor project
-        #[arg(short, long, default_value = "output")]
-        output: PathBuf,
+        /// Output directory for the generated Anchor project (default: the project's build output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
 
         /// Watch additional directories for changes
         #[arg(long)]
@@ -110,13 +149,13 @@ enum Commands {
     },
     /// Run tests defined in the SolScript source
     Test {
-        /// The source file with #[test] functions
+        /// The source file with #[test] functions (default: the project's sources)
         #[arg(value_name = "FILE")]
-        file: PathBuf,
+        file: Option<PathBuf>,
 
-        /// Output directory for the generated Anchor project
-        #[arg(short, long, default_value = "output")]
-        output: PathBuf,
+        /// Output directory for the generated Anchor project (default: the project's build output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
 
         /// Only run tests matching this filter
         #[arg(long)]
@@ -128,9 +167,9 @@ enum Commands {
     },
     /// Deploy the compiled program to a Solana cluster
     Deploy {
-        /// The source file or generated output directory
+        /// The source file or generated output directory (default: the project's build output)
         #[arg(value_name = "PATH")]
-        path: PathBuf,
+        path: Option<PathBuf>,
 
         /// Solana cluster to deploy to (localnet, devnet, testnet, mainnet-beta)
         #[arg(short, long, default_value = "localnet")]
@@ -144,6 +183,11 @@ enum Commands {
         #[arg(short = 'y', long)]
         yes: bool,
     },
+    /// Manage program keypairs and program IDs
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
     /// Add a dependency to the project
     Add {
         /// Package name
@@ -181,16 +225,42 @@ enum Commands {
         name: String,
     },
     /// Install all dependencies
-    Install,
+    Install {
+        /// Install only from .solscript/packages and vendor/, without network access
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Copy all locked dependencies into vendor/ for offline installs
+    Vendor,
     /// Update all dependencies to their latest versions
     Update,
+    /// Build a package archive of the project
+    Package {
+        /// Package even with uncommitted changes
+        #[arg(long)]
+        allow_dirty: bool,
+    },
+    /// Package the project and add it to a registry index
+    Publish {
+        /// Index to publish to: a directory or a git URL
+        #[arg(long, value_name = "PATH_OR_GIT")]
+        registry: String,
+
+        /// Publish even with uncommitted changes
+        #[arg(long)]
+        allow_dirty: bool,
+    },
     /// List installed packages
-    List,
+    List {
+        /// Show the full dependency graph as a tree
+        #[arg(long)]
+        tree: bool,
+    },
     /// Compile directly to BPF bytecode
     BuildBpf {
-        /// The source file to compile
+        /// The source file to compile (default: the project's sources)
         #[arg(value_name = "FILE")]
-        file: PathBuf,
+        file: Option<PathBuf>,
 
         /// Output directory for the compiled program
         #[arg(short, long, default_value = "target/deploy")]
@@ -212,6 +282,45 @@ enum Commands {
     Doctor,
 }
 
+/// `--backend` values for `solscript build`
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum BackendArg {
+    /// Anchor program, built with `anchor build`
+    Anchor,
+    /// Plain `solana-program` crate, built with `cargo build-sbf`
+    Native,
+}
+
+impl From<BackendArg> for solscript_codegen::Backend {
+    fn from(backend: BackendArg) -> Self {
+        match backend {
+            BackendArg::Anchor => Self::Anchor,
+            BackendArg::Native => Self::Native,
+        }
+    }
+}
+
+/// `solscript keys` subcommands
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// List program keypairs and the program IDs configured in solscript.toml
+    List {
+        /// Output directory holding `target/deploy` (default: the project's build output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write the keypair program IDs into solscript.toml
+    Sync {
+        /// Output directory holding `target/deploy` (default: the project's build output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Cluster to record the IDs for (default: the project's cluster)
+        #[arg(short, long)]
+        cluster: Option<String>,
+    },
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
@@ -222,29 +331,46 @@ fn main() -> Result<()> {
             template,
             list,
         } => new_project(name, &template, list),
-        Commands::Check { file } => check_file(&file),
+        Commands::Check { file } => check_file(file),
         Commands::Parse { file, format } => parse_file(&file, &format),
-        Commands::Build { file, output } => build_project(&file, &output),
-        Commands::Codegen { file } => codegen_file(&file),
+        Commands::Build {
+            file,
+            output,
+            backend,
+            legacy_idl,
+            workspace,
+        } => build_project(file, output, backend.into(), legacy_idl, workspace),
+        Commands::Codegen { file } => codegen_file(file),
+        Commands::Compile { json: _, input } => compile_standard_json(input.as_deref()),
+        Commands::Layout { file, diff } => match (file, diff) {
+            (_, Some(files)) => diff_layouts(&files[0], &files[1]),
+            (file, None) => print_layout(file),
+        },
         Commands::Fmt { files, check } => format_files(&files, check),
         Commands::Watch {
             file,
             output,
             include,
             check_only,
-        } => watch_project(&file, &output, &include, check_only),
+        } => watch_project(file, output, &include, check_only),
         Commands::Test {
             file,
             output,
             filter,
             verbose,
-        } => run_tests(&file, &output, filter.as_deref(), verbose),
+        } => run_tests(file, output, filter.as_deref(), verbose),
         Commands::Deploy {
             path,
             cluster,
             keypair,
             yes,
-        } => deploy_program(&path, &cluster, keypair.as_deref(), yes),
+        } => deploy_program(path, &cluster, keypair.as_deref(), yes),
+        Commands::Keys { command } => match command {
+            KeysCommand::List { output } => list_keys(output.as_deref()),
+            KeysCommand::Sync { output, cluster } => {
+                sync_keys(output.as_deref(), cluster.as_deref())
+            }
+        },
         Commands::Add {
             name,
             version,
@@ -263,40 +389,38 @@ fn main() -> Result<()> {
             path.as_deref(),
         ),
         Commands::Remove { name } => remove_dependency(&name),
-        Commands::Install => install_dependencies(),
+        Commands::Install { offline } => install_dependencies(offline),
+        Commands::Vendor => vendor_dependencies(),
         Commands::Update => update_dependencies(),
-        Commands::List => list_dependencies(),
+        Commands::List { tree } => list_dependencies(tree),
+        Commands::Package { allow_dirty } => package_project(allow_dirty),
+        Commands::Publish {
+            registry,
+            allow_dirty,
+        } => publish_project(&registry, allow_dirty),
         Commands::BuildBpf {
             file,
             output,
             opt_level,
             keep_intermediate,
             llvm,
-        } => build_bpf(&file, &output, opt_level, keep_intermediate, llvm),
+        } => build_bpf(file, &output, opt_level, keep_intermediate, llvm),
         Commands::Doctor => check_doctor(),
     }
 }
 
-fn check_file(path: &Path) -> Result<()> {
-    let source = std::fs::read_to_string(path)
-        .into_diagnostic()
-        .wrap_err_with(|| format!("Failed to read file: {}", path.display()))?;
+fn check_file(file: Option<PathBuf>) -> Result<()> {
+    let target = Target::resolve(file)?;
+    let mut session = target.session()?;
 
-    match solscript_parser::parse(&source) {
-        Ok(program) => {
-            let item_count = program.items.len();
-            println!(
-                "✓ {} parsed successfully ({} top-level items)",
-                path.display(),
-                item_count
-            );
-            Ok(())
-        }
-        Err(err) => {
-            eprintln!("Error parsing {}:", path.display());
-            Err(err).into_diagnostic()
-        }
-    }
+    let program = session.parse().ok_or_else(|| emit_errors(&mut session))?;
+    emit_warnings(&mut session);
+    println!(
+        "✓ {} parsed successfully ({} top-level items)",
+        target,
+        program.items.len()
+    );
+    Ok(())
 }
 
 fn parse_file(path: &Path, format: &str) -> Result<()> {
@@ -326,36 +450,68 @@ fn parse_file(path: &Path, format: &str) -> Result<()> {
     }
 }
 
-fn build_project(file: &Path, output: &Path) -> Result<()> {
-    let source = std::fs::read_to_string(file)
-        .into_diagnostic()
-        .wrap_err_with(|| format!("Failed to read file: {}", file.display()))?;
+fn build_project(
+    file: Option<PathBuf>,
+    output: Option<PathBuf>,
+    backend: solscript_codegen::Backend,
+    legacy_idl: bool,
+    workspace: bool,
+) -> Result<()> {
+    if !workspace {
+        let target = Target::resolve(file)?;
+        return build_target(&target, &target.output(output), backend, legacy_idl);
+    }
+
+    // Members a member imports must be built first, for their IDL
+    let workspace = find_workspace()?;
+    let members = workspace.build_order()?;
+    for member in &members {
+        println!("Building {}...", member.name());
+        let target = Target::Project {
+            root: member.root.clone(),
+            config: Box::new(member.config.clone()),
+        };
+        build_target(&target, &target.output(None), backend, legacy_idl)?;
+        println!();
+    }
+    println!("✓ Built {} workspace members", members.len());
+    Ok(())
+}
+
+fn build_target(
+    target: &Target,
+    output: &Path,
+    backend: solscript_codegen::Backend,
+    legacy_idl: bool,
+) -> Result<()> {
+    let mut session = target.session()?;
 
     // Parse
-    let program =
-        solscript_parser::parse(&source).map_err(|e| miette::miette!("Parse error: {:?}", e))?;
+    let program = session.parse().ok_or_else(|| emit_errors(&mut session))?;
+    emit_warnings(&mut session);
 
-    println!(
-        "✓ Parsed {} ({} items)",
-        file.display(),
-        program.items.len()
-    );
+    println!("✓ Parsed {} ({} items)", target, program.items.len());
 
     // Type check
-    if let Err(errors) = solscript_typeck::typecheck(&program, &source) {
-        for err in errors {
-            // Use miette's Report for nice formatting with source code snippets
-            let report = miette::Report::new(err);
-            eprintln!("{:?}", report);
-        }
-        return Err(miette::miette!("Type checking failed"));
+    if !session.typecheck(&program) {
+        return Err(emit_errors(&mut session));
     }
 
     println!("✓ Type checked successfully");
 
     // Generate code
-    let generated = solscript_codegen::generate(&program)
-        .map_err(|e| miette::miette!("Codegen error: {:?}", e))?;
+    configure_program_ids(&mut session, output, &program, None)?;
+    let options = session.options_mut();
+    options.backend = backend;
+    options.idl_format = if legacy_idl {
+        solscript_codegen::IdlFormat::Legacy
+    } else {
+        solscript_codegen::IdlFormat::Spec
+    };
+    let artifacts = session
+        .generate(&program)
+        .ok_or_else(|| emit_errors(&mut session))?;
+    let generated = &artifacts.project;
 
     // Write to output directory
     generated
@@ -363,40 +519,247 @@ fn build_project(file: &Path, output: &Path) -> Result<()> {
         .into_diagnostic()
         .wrap_err("Failed to write generated project")?;
 
-    println!("✓ Generated Anchor project in {}", output.display());
+    let native = backend == solscript_codegen::Backend::Native;
+    println!(
+        "✓ Generated {} project in {}",
+        if native { "solana-program" } else { "Anchor" },
+        output.display()
+    );
+
+    // Account sizes and rent, from the same layout that sizes INIT_SPACE
+    let costs = &artifacts.account_costs;
+    let report_path = output.join("account-sizes.json");
+    let report = serde_json::to_string_pretty(&costs)
+        .into_diagnostic()
+        .wrap_err("Failed to serialize account size report")?;
+    fs::write(&report_path, report)
+        .into_diagnostic()
+        .wrap_err("Failed to write account size report")?;
     println!();
-    println!("To build the Solana program:");
-    println!("  cd {}", output.display());
-    println!("  anchor build");
+    print_account_costs(costs);
+    println!("  (JSON report: {})", report_path.display());
+
     println!();
-    println!("To deploy:");
-    println!("  anchor deploy");
+    println!("To build the Solana program:");
+    if native {
+        println!(
+            "  cd {}",
+            output.join("programs/solscript_program").display()
+        );
+        println!("  cargo build-sbf");
+        println!();
+        println!("To deploy:");
+        println!(
+            "  solana program deploy target/deploy/{0}.so --program-id ../../target/deploy/{0}-keypair.json",
+            artifacts.program_name
+        );
+    } else {
+        println!("  cd {}", output.display());
+        println!("  anchor build");
+        println!();
+        println!("To deploy:");
+        println!("  anchor deploy");
+    }
+
+    Ok(())
+}
+
+/// Set the program ids of a session that builds into `output`. The program id is the
+/// address configured in `solscript.toml` for `cluster` (the config's own cluster when
+/// `None`), otherwise the program keypair in `output/target/deploy`, which is generated on
+/// first build.
+fn configure_program_ids(
+    session: &mut Session,
+    output: &Path,
+    program: &solscript_ast::Program,
+    cluster: Option<&str>,
+) -> Result<()> {
+    let name = session
+        .program_name(program)
+        .ok_or_else(|| emit_errors(session))?;
 
+    let source_dir = session
+        .path()
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let config = match source_dir
+        .canonicalize()
+        .ok()
+        .and_then(|dir| config::Config::find(&dir))
+    {
+        Some(path) => Some(config::Config::load(&path)?),
+        None => None,
+    };
+    let cluster = cluster
+        .map(str::to_string)
+        .or_else(|| config.as_ref().map(|c| c.solana.cluster.clone()))
+        .unwrap_or_else(|| "localnet".to_string());
+
+    let keypair = keys::keypair_path(output, &name);
+    let (keypair_id, created) = keys::load_or_create(&keypair)?;
+    if created {
+        println!("✓ Generated program keypair {}", keypair.display());
+    }
+
+    let solana = config.map(|c| c.solana).unwrap_or_default();
+    let program_id = match solana.program_id(&cluster, &name) {
+        Some(configured) => {
+            if configured != keypair_id {
+                eprintln!(
+                    "warning: solscript.toml sets {} on {} to {}, but {} holds {}",
+                    name,
+                    cluster,
+                    configured,
+                    keypair.display(),
+                    keypair_id
+                );
+                eprintln!("         run `solscript keys sync` to use the keypair's address");
+            }
+            configured.to_string()
+        }
+        None => keypair_id.clone(),
+    };
+    println!("✓ Program ID {} ({})", program_id, cluster);
+
+    // Local validators deploy straight from the keypair
+    let mut cluster_program_ids = solana.cluster_program_ids(&name);
+    cluster_program_ids
+        .entry("localnet".to_string())
+        .or_insert(keypair_id);
+
+    let options = session.options_mut();
+    options.program_id = Some(program_id);
+    options.cluster_program_ids = cluster_program_ids;
     Ok(())
 }
 
-fn codegen_file(file: &Path) -> Result<()> {
-    let source = std::fs::read_to_string(file)
+/// Open a compilation session on a source file
+fn load_session(file: &Path) -> Result<Session> {
+    Session::load(file)
         .into_diagnostic()
-        .wrap_err_with(|| format!("Failed to read file: {}", file.display()))?;
+        .wrap_err_with(|| format!("Failed to read file: {}", file.display()))
+}
 
-    // Parse
-    let program =
-        solscript_parser::parse(&source).map_err(|e| miette::miette!("Parse error: {:?}", e))?;
+/// What a command compiles: the FILE it was given, or else the project whose
+/// `solscript.toml` is in the current directory or a parent
+enum Target {
+    File(PathBuf),
+    Project {
+        root: PathBuf,
+        config: Box<config::Config>,
+    },
+}
 
-    // Type check
-    if let Err(errors) = solscript_typeck::typecheck(&program, &source) {
-        for err in errors {
-            // Use miette's Report for nice formatting with source code snippets
-            let report = miette::Report::new(err);
-            eprintln!("{:?}", report);
+impl Target {
+    fn resolve(file: Option<PathBuf>) -> Result<Self> {
+        if let Some(file) = file {
+            return Ok(Self::File(file));
         }
-        return Err(miette::miette!("Type checking failed"));
+        let (root, config) = find_project()?;
+        Ok(Self::Project {
+            root,
+            config: Box::new(config),
+        })
     }
 
-    // Generate code
-    let generated = solscript_codegen::generate(&program)
-        .map_err(|e| miette::miette!("Codegen error: {:?}", e))?;
+    /// A session on the file, or on every source file of the project as one unit
+    fn session(&self) -> Result<Session> {
+        match self {
+            Self::File(file) => load_session(file),
+            Self::Project { root, config } => {
+                let files = config.source_files(root)?;
+                // Members of a workspace share its lock and packages
+                let workspace = workspace::Workspace::find(root)?;
+                let install_root = workspace.as_ref().map_or(root, |workspace| &workspace.root);
+                // Packages must still hold the code solscript.lock recorded
+                if let Some(lock) = lockfile::Lockfile::load(install_root)? {
+                    lock.verify(&install_root.join(package::PACKAGES_DIR))?;
+                }
+                let mut session = Session::load_files(&files)
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("Failed to read the sources of {}", self))?;
+                let graph = package::DependencyGraph::load(root, config, install_root)?;
+                for (name, dir) in graph.package_dirs() {
+                    session.add_package(name, dir);
+                }
+                // Other members it depends on can be called through their IDL
+                let member = workspace
+                    .as_ref()
+                    .and_then(|workspace| Some((workspace, workspace.member(root)?)));
+                if let Some((workspace, member)) = member {
+                    for (name, dependency) in workspace.member_dependencies(member) {
+                        session.add_program(name, dependency.idl_path());
+                    }
+                }
+                Ok(session)
+            }
+        }
+    }
+
+    /// The `-o` directory if given, else the project's build output
+    fn output(&self, output: Option<PathBuf>) -> PathBuf {
+        output.unwrap_or_else(|| match self {
+            Self::File(_) => PathBuf::from("output"),
+            Self::Project { root, config } => config.output_dir(root),
+        })
+    }
+
+    /// Directory holding every file that can change the build
+    fn watch_dir(&self) -> PathBuf {
+        match self {
+            Self::File(file) => file
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map_or_else(|| PathBuf::from("."), Path::to_path_buf),
+            Self::Project { root, .. } => root.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(file) => write!(f, "{}", file.display()),
+            Self::Project { root, config } => match &config.project {
+                Some(project) => write!(f, "project {}", project.name),
+                None => write!(f, "workspace {}", root.display()),
+            },
+        }
+    }
+}
+
+/// Print the diagnostics a failed stage left in the session, and the error to fail with
+fn emit_errors(session: &mut Session) -> miette::Report {
+    let diagnostics = session.take_diagnostics();
+    for diagnostic in &diagnostics {
+        eprintln!("{:?}", diagnostic.report());
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == solscript_driver::Severity::Error)
+        .count();
+    miette::miette!(
+        "Could not compile {} due to {} previous error{}",
+        session.path().display(),
+        errors,
+        if errors == 1 { "" } else { "s" }
+    )
+}
+
+/// Print the warnings of a stage that succeeded
+fn emit_warnings(session: &mut Session) {
+    for diagnostic in session.take_diagnostics() {
+        eprintln!("{:?}", diagnostic.report());
+    }
+}
+
+fn codegen_file(file: Option<PathBuf>) -> Result<()> {
+    let mut session = Target::resolve(file)?.session()?;
+
+    let artifacts = session.compile().ok_or_else(|| emit_errors(&mut session))?;
+    emit_warnings(&mut session);
+    let generated = artifacts.project;
 
     // Print generated lib.rs
     println!("=== lib.rs ===");
@@ -417,9 +780,161 @@ fn codegen_file(file: &Path) -> Result<()> {
     Ok(())
 }
 
-fn watch_project(file: &Path, output: &Path, include: &[PathBuf], check_only: bool) -> Result<()> {
+/// Answer a standard-JSON request. Compile errors are reported in the response, so this
+/// only fails when the request can't be read.
+fn compile_standard_json(input: Option<&Path>) -> Result<()> {
+    use std::io::Read;
+
+    let request = match input {
+        Some(path) if path != Path::new("-") => fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?,
+        _ => {
+            let mut request = String::new();
+            std::io::stdin()
+                .read_to_string(&mut request)
+                .into_diagnostic()
+                .wrap_err("Failed to read the request from stdin")?;
+            request
+        }
+    };
+
+    let response = solscript_driver::standard_json::compile_json(&request);
+    let json = serde_json::to_string_pretty(&response)
+        .into_diagnostic()
+        .wrap_err("Failed to serialize the response")?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Parse, type check and lower a source file to the IR of its deployable contract
+fn lower_file(file: &Path) -> Result<solscript_codegen::SolanaProgram> {
+    lower_session(load_session(file)?)
+}
+
+/// Parse, type check and lower a session to the IR of its deployable contract
+fn lower_session(mut session: Session) -> Result<solscript_codegen::SolanaProgram> {
+    let lowered = session
+        .check()
+        .and_then(|program| session.lower(&program))
+        .ok_or_else(|| emit_errors(&mut session))?;
+    emit_warnings(&mut session);
+    Ok(lowered)
+}
+
+fn print_account_costs(costs: &[solscript_codegen::AccountCost]) {
+    println!("Account sizes (rent-exempt minimum at default rent):");
+    println!(
+        "  {:<28} {:<16} {:>8} {:>14} {:>12}",
+        "account", "created", "bytes", "lamports", "SOL"
+    );
+    for cost in costs {
+        let created = match cost.kind {
+            solscript_codegen::AccountCostKind::State => "once",
+            solscript_codegen::AccountCostKind::MappingEntry => "per entry",
+        };
+        println!(
+            "  {:<28} {:<16} {:>8} {:>14} {:>12.9}",
+            cost.name,
+            created,
+            cost.size,
+            cost.rent_exempt_lamports,
+            cost.rent_exempt_lamports as f64 / 1_000_000_000.0
+        );
+    }
+}
+
+fn print_layout(file: Option<PathBuf>) -> Result<()> {
+    let ir = lower_session(Target::resolve(file)?.session()?)?;
+    let layout = solscript_codegen::program_layout(&ir)
+        .map_err(|e| miette::miette!("Layout error: {:?}", e))?;
+
+    for account in layout.accounts() {
+        let kind = if account.is_account {
+            format!(
+                "account, {}-byte discriminator",
+                solscript_codegen::DISCRIMINATOR_SIZE
+            )
+        } else {
+            "struct".to_string()
+        };
+        let dynamic = account.fields.iter().any(|f| f.dynamic);
+        println!(
+            "{} ({}, {}{} bytes)",
+            account.name,
+            kind,
+            if dynamic { "up to " } else { "" },
+            account.size
+        );
+        println!("  {:>6}  {:>6}  {:<24} type", "offset", "size", "field");
+        for field in &account.fields {
+            let offset = field.offset.map_or("?".to_string(), |o| o.to_string());
+            let size = if field.dynamic {
+                format!("≤{}", field.size)
+            } else {
+                field.size.to_string()
+            };
+            println!(
+                "  {:>6}  {:>6}  {:<24} {}",
+                offset, size, field.name, field.ty
+            );
+        }
+        println!();
+    }
+    if layout
+        .accounts()
+        .any(|a| a.fields.iter().any(|f| f.offset.is_none()))
+    {
+        println!("? = offset depends on the length of a preceding dynamic field");
+    }
+
+    Ok(())
+}
+
+fn diff_layouts(old: &Path, new: &Path) -> Result<()> {
+    let old_layout = solscript_codegen::program_layout(&lower_file(old)?)
+        .map_err(|e| miette::miette!("Layout error: {:?}", e))?;
+    let new_layout = solscript_codegen::program_layout(&lower_file(new)?)
+        .map_err(|e| miette::miette!("Layout error: {:?}", e))?;
+
+    let changes = solscript_codegen::diff_layouts(&old_layout, &new_layout);
+    if changes.is_empty() {
+        println!("✓ Layouts are identical");
+        return Ok(());
+    }
+
+    for change in &changes {
+        if change.is_breaking() {
+            println!("  ✗ {} (breaking)", change);
+        } else {
+            println!("  + {}", change);
+        }
+    }
+
+    let breaking = changes.iter().filter(|c| c.is_breaking()).count();
+    if breaking > 0 {
+        return Err(miette::miette!(
+            "{} breaking layout change(s): existing accounts can't be read by the new program",
+            breaking
+        ));
+    }
+    println!("✓ No breaking layout changes");
+    Ok(())
+}
+
+fn watch_project(
+    file: Option<PathBuf>,
+    output: Option<PathBuf>,
+    include: &[PathBuf],
+    check_only: bool,
+) -> Result<()> {
+    let target = Target::resolve(file)?;
+    let output = &target.output(output);
+    // Watch the source file's directory, or the whole project
+    let watch_dir = target.watch_dir();
+
     println!("Starting watch mode...");
-    println!("Watching: {}", file.display());
+    println!("Watching: {}", watch_dir.display());
     if !include.is_empty() {
         for dir in include {
             println!("Also watching: {}", dir.display());
@@ -431,7 +946,7 @@ fn watch_project(file: &Path, output: &Path, include: &[PathBuf], check_only: bo
 
     // Perform initial build
     println!("--- Initial build ---");
-    let _ = do_build(file, output, check_only);
+    let _ = do_build(&target, output, check_only);
     println!();
 
     // Set up file watcher
@@ -441,12 +956,6 @@ fn watch_project(file: &Path, output: &Path, include: &[PathBuf], check_only: bo
         .into_diagnostic()
         .wrap_err("Failed to create file watcher")?;
 
-    // Watch the source file's directory
-    let watch_dir = file
-        .parent()
-        .map(|p| p.to_path_buf())
-        .unwrap_or_else(|| PathBuf::from("."));
-
     debouncer
         .watcher()
         .watch(&watch_dir, RecursiveMode::Recursive)
@@ -476,7 +985,7 @@ fn watch_project(file: &Path, output: &Path, include: &[PathBuf], check_only: bo
                     print!("\x1B[2J\x1B[1;1H");
                     println!("--- Change detected, rebuilding... ---");
                     println!();
-                    let _ = do_build(file, output, check_only);
+                    let _ = do_build(&target, output, check_only);
                     println!();
                     println!("Watching for changes... (Ctrl+C to stop)");
                 }
@@ -494,35 +1003,28 @@ fn watch_project(file: &Path, output: &Path, include: &[PathBuf], check_only: bo
     Ok(())
 }
 
-fn do_build(file: &Path, output: &Path, check_only: bool) -> Result<()> {
-    let source = match std::fs::read_to_string(file) {
+fn do_build(target: &Target, output: &Path, check_only: bool) -> Result<()> {
+    // Re-read the project's file list too, to pick up added and removed sources
+    let mut session = match target.session() {
         Ok(s) => s,
         Err(e) => {
-            eprintln!("✗ Failed to read {}: {}", file.display(), e);
-            return Err(miette::miette!("Failed to read file"));
+            eprintln!("✗ {:?}", e);
+            return Err(miette::miette!("Failed to read the sources of {}", target));
         }
     };
 
     // Parse
-    let program = match solscript_parser::parse(&source) {
-        Ok(p) => {
-            println!("✓ Parsed {} ({} items)", file.display(), p.items.len());
-            p
-        }
-        Err(e) => {
-            eprintln!("✗ Parse error: {:?}", e);
-            return Err(miette::miette!("Parse error"));
-        }
+    let Some(program) = session.parse() else {
+        eprintln!("✗ Parse failed:");
+        return Err(emit_errors(&mut session));
     };
+    emit_warnings(&mut session);
+    println!("✓ Parsed {} ({} items)", target, program.items.len());
 
     // Type check
-    if let Err(errors) = solscript_typeck::typecheck(&program, &source) {
+    if !session.typecheck(&program) {
         eprintln!("✗ Type check failed:");
-        for err in errors {
-            let report = miette::Report::new(err);
-            eprintln!("{:?}", report);
-        }
-        return Err(miette::miette!("Type checking failed"));
+        return Err(emit_errors(&mut session));
     }
     println!("✓ Type checked successfully");
 
@@ -532,16 +1034,14 @@ fn do_build(file: &Path, output: &Path, check_only: bool) -> Result<()> {
     }
 
     // Generate code
-    let generated = match solscript_codegen::generate(&program) {
-        Ok(g) => g,
-        Err(e) => {
-            eprintln!("✗ Codegen error: {:?}", e);
-            return Err(miette::miette!("Codegen error"));
-        }
+    configure_program_ids(&mut session, output, &program, None)?;
+    let Some(artifacts) = session.generate(&program) else {
+        eprintln!("✗ Codegen failed:");
+        return Err(emit_errors(&mut session));
     };
 
     // Write to output directory
-    if let Err(e) = generated.write_to_dir(output) {
+    if let Err(e) = artifacts.project.write_to_dir(output) {
         eprintln!("✗ Failed to write output: {}", e);
         return Err(miette::miette!("Failed to write output"));
     }
@@ -551,32 +1051,29 @@ fn do_build(file: &Path, output: &Path, check_only: bool) -> Result<()> {
     Ok(())
 }
 
-fn run_tests(file: &Path, output: &Path, filter: Option<&str>, verbose: bool) -> Result<()> {
+fn run_tests(
+    file: Option<PathBuf>,
+    output: Option<PathBuf>,
+    filter: Option<&str>,
+    verbose: bool,
+) -> Result<()> {
     use std::process::Command;
 
     println!("Running SolScript tests...\n");
 
     // First, build the project
-    let source = std::fs::read_to_string(file)
-        .into_diagnostic()
-        .wrap_err_with(|| format!("Failed to read file: {}", file.display()))?;
-
-    // Parse
-    let program =
-        solscript_parser::parse(&source).map_err(|e| miette::miette!("Parse error: {:?}", e))?;
-
-    // Type check
-    if let Err(errors) = solscript_typeck::typecheck(&program, &source) {
-        for err in errors {
-            let report = miette::Report::new(err);
-            eprintln!("{:?}", report);
-        }
-        return Err(miette::miette!("Type checking failed"));
-    }
+    let target = Target::resolve(file)?;
+    let output = &target.output(output);
+    let mut session = target.session()?;
+    let program = session.check().ok_or_else(|| emit_errors(&mut session))?;
+    emit_warnings(&mut session);
 
     // Generate code
-    let generated = solscript_codegen::generate(&program)
-        .map_err(|e| miette::miette!("Codegen error: {:?}", e))?;
+    configure_program_ids(&mut session, output, &program, None)?;
+    let artifacts = session
+        .generate(&program)
+        .ok_or_else(|| emit_errors(&mut session))?;
+    let generated = artifacts.project;
 
     if !generated.has_tests {
         println!("No tests found. Add #[test] functions to your contract.");
@@ -611,10 +1108,7 @@ fn run_tests(file: &Path, output: &Path, filter: Option<&str>, verbose: bool) ->
     println!("Running: cargo test in {}", program_dir.display());
     println!();
 
-    let status = cmd
-        .status()
-        .into_diagnostic()
-        .wrap_err("Failed to run cargo test")?;
+    let status = run_mapped(&mut cmd, output).wrap_err("Failed to run cargo test")?;
 
     if status.success() {
         println!("\n✓ All tests passed!");
@@ -625,8 +1119,49 @@ fn run_tests(file: &Path, output: &Path, filter: Option<&str>, verbose: bool) ->
     Ok(())
 }
 
+/// Run a cargo or anchor command on a generated project, rewriting locations in its
+/// output from the generated Rust to the SolScript source via `program.solmap`
+fn run_mapped(cmd: &mut std::process::Command, project: &Path) -> Result<std::process::ExitStatus> {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    let Some(map) = fs::read_to_string(project.join("program.solmap"))
+        .ok()
+        .and_then(|json| serde_json::from_str::<solscript_codegen::SourceMap>(&json).ok())
+    else {
+        return cmd.status().into_diagnostic();
+    };
+    let sources: HashMap<&str, String> = map
+        .sources()
+        .into_iter()
+        .filter_map(|path| Some((path, fs::read_to_string(path).ok()?)))
+        .collect();
+    let source_text = |path: &str| sources.get(path).map(String::as_str);
+
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .into_diagnostic()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+                println!("{}", map.rewrite_diagnostics(&line, source_text));
+            }
+        });
+        for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+            eprintln!("{}", map.rewrite_diagnostics(&line, source_text));
+        }
+    });
+
+    child.wait().into_diagnostic()
+}
+
 fn deploy_program(
-    path: &Path,
+    path: Option<PathBuf>,
     cluster: &str,
     keypair: Option<&std::path::Path>,
     skip_confirm: bool,
@@ -635,38 +1170,32 @@ fn deploy_program(
     use std::process::Command;
 
     // Determine if path is a source file or output directory
-    let output_dir = if path.extension().map(|e| e == "sol").unwrap_or(false) {
-        // It's a source file, need to build first
-        println!("Building project from source...\n");
-
-        let source = std::fs::read_to_string(path)
-            .into_diagnostic()
-            .wrap_err_with(|| format!("Failed to read file: {}", path.display()))?;
-
-        let program = solscript_parser::parse(&source)
-            .map_err(|e| miette::miette!("Parse error: {:?}", e))?;
-
-        if let Err(errors) = solscript_typeck::typecheck(&program, &source) {
-            for err in errors {
-                let report = miette::Report::new(err);
-                eprintln!("{:?}", report);
-            }
-            return Err(miette::miette!("Type checking failed"));
+    let output_dir = match path {
+        Some(path) if path.extension().is_none_or(|e| e != "sol") => path,
+        path => {
+            // A source file or the project, need to build first
+            println!("Building project from source...\n");
+
+            let target = Target::resolve(path)?;
+            let output = target.output(None);
+            let mut session = target.session()?;
+            let program = session.check().ok_or_else(|| emit_errors(&mut session))?;
+            emit_warnings(&mut session);
+
+            configure_program_ids(&mut session, &output, &program, Some(cluster))?;
+            let generated = session
+                .generate(&program)
+                .ok_or_else(|| emit_errors(&mut session))?
+                .project;
+
+            generated
+                .write_to_dir(&output)
+                .into_diagnostic()
+                .wrap_err("Failed to write output")?;
+
+            println!("✓ Generated Anchor project\n");
+            output
         }
-
-        let generated = solscript_codegen::generate(&program)
-            .map_err(|e| miette::miette!("Codegen error: {:?}", e))?;
-
-        let output = PathBuf::from("output");
-        generated
-            .write_to_dir(&output)
-            .into_diagnostic()
-            .wrap_err("Failed to write output")?;
-
-        println!("✓ Generated Anchor project\n");
-        output
-    } else {
-        path.to_path_buf()
     };
 
     // Validate cluster
@@ -699,12 +1228,11 @@ fn deploy_program(
 
     // Run anchor build first
     println!("Building with Anchor...");
-    let build_status = Command::new("anchor")
-        .arg("build")
-        .current_dir(&output_dir)
-        .status()
-        .into_diagnostic()
-        .wrap_err("Failed to run 'anchor build'. Is Anchor installed?")?;
+    let build_status = run_mapped(
+        Command::new("anchor").arg("build").current_dir(&output_dir),
+        &output_dir,
+    )
+    .wrap_err("Failed to run 'anchor build'. Is Anchor installed?")?;
 
     if !build_status.success() {
         return Err(miette::miette!("Anchor build failed"));
@@ -877,8 +1405,8 @@ fn create_project_from_template(name: &str, template: &templates::Template) -> R
     println!();
     println!("Next steps:");
     println!("  cd {}", name);
-    println!("  solscript check src/main.sol");
-    println!("  solscript build src/main.sol");
+    println!("  solscript check");
+    println!("  solscript build");
     println!();
 
     Ok(())
@@ -907,11 +1435,14 @@ fn to_pascal_case(s: &str) -> String {
 }
 
 fn format_files(files: &[PathBuf], check_only: bool) -> Result<()> {
-    if files.is_empty() {
-        return Err(miette::miette!(
-            "No files specified. Usage: solscript fmt <FILE>..."
-        ));
-    }
+    let project_files;
+    let files = if files.is_empty() {
+        let (root, config) = find_project()?;
+        project_files = config.source_files(&root)?;
+        &project_files
+    } else {
+        files
+    };
 
     let mut any_changes = false;
     let mut any_errors = false;
@@ -1365,6 +1896,130 @@ fn find_config() -> Result<PathBuf> {
     })
 }
 
+/// The root directory and validated config of the project around the working directory
+fn find_project() -> Result<(PathBuf, config::Config)> {
+    let path = find_config()?;
+    let config = config::Config::load(&path)?;
+    config.project()?;
+    let root = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    Ok((root, config))
+}
+
+/// The workspace around the working directory
+fn find_workspace() -> Result<workspace::Workspace> {
+    let path = find_config()?;
+    let root = path.parent().unwrap_or(Path::new("."));
+    workspace::Workspace::find(root)?.ok_or_else(|| {
+        miette::miette!(
+            help = "list the projects under `members` in a [workspace] section of the root solscript.toml",
+            "{} is not in a workspace",
+            root.display()
+        )
+    })
+}
+
+// ============ Program Keys ============
+
+/// The project's config (if any) and the output directory holding `target/deploy`
+fn keys_context(output: Option<&Path>) -> Result<(Option<(PathBuf, config::Config)>, PathBuf)> {
+    let cwd = std::env::current_dir()
+        .into_diagnostic()
+        .wrap_err("Failed to get current directory")?;
+    let config = match config::Config::find(&cwd) {
+        Some(path) => {
+            let config = config::Config::load(&path)?;
+            Some((path, config))
+        }
+        None => None,
+    };
+    let output = match (output, &config) {
+        (Some(output), _) => output.to_path_buf(),
+        (None, Some((path, config))) => path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(&config.build.output),
+        (None, None) => PathBuf::from("output"),
+    };
+    Ok((config, output))
+}
+
+fn list_keys(output: Option<&Path>) -> Result<()> {
+    let (config, output) = keys_context(output)?;
+    let keypairs = keys::list(&output)?;
+
+    let deploy_dir = output.join("target").join("deploy");
+    if keypairs.is_empty() {
+        println!("No program keypairs in {}", deploy_dir.display());
+    } else {
+        println!("Program keypairs ({}):", deploy_dir.display());
+        for (program, address) in &keypairs {
+            println!("  {}: {}", program, address);
+        }
+    }
+
+    let Some((path, config)) = config else {
+        return Ok(());
+    };
+    println!();
+    if config.solana.programs.is_empty() {
+        println!("No program IDs configured in {}", path.display());
+        return Ok(());
+    }
+    println!("Configured program IDs ({}):", path.display());
+    for (cluster, ids) in &config.solana.programs {
+        for (program, address) in ids {
+            let keypair = keypairs.iter().find(|(name, _)| name == program);
+            let note = match keypair {
+                Some((_, key)) if key == address => "",
+                Some(_) => "  (differs from keypair)",
+                None => "  (no keypair)",
+            };
+            println!("  [{}] {}: {}{}", cluster, program, address, note);
+        }
+    }
+
+    Ok(())
+}
+
+fn sync_keys(output: Option<&Path>, cluster: Option<&str>) -> Result<()> {
+    let (config, output) = keys_context(output)?;
+    let Some((path, mut config)) = config else {
+        return Err(miette::miette!(
+            "No solscript.toml found in current directory or any parent.\n\
+             Run 'solscript init <project-name>' to create a new project."
+        ));
+    };
+
+    let keypairs = keys::list(&output)?;
+    if keypairs.is_empty() {
+        return Err(miette::miette!(
+            "No program keypairs in {}. Run 'solscript build' first.",
+            output.join("target").join("deploy").display()
+        ));
+    }
+
+    let cluster = cluster.unwrap_or(&config.solana.cluster).to_string();
+    let ids = config.solana.programs.entry(cluster.clone()).or_default();
+    let mut changed = false;
+    for (program, address) in keypairs {
+        if ids.get(&program) != Some(&address) {
+            println!("✓ {} on {}: {}", program, cluster, address);
+            ids.insert(program, address);
+            changed = true;
+        }
+    }
+
+    if changed {
+        config.save(&path)?;
+        println!("✓ Updated {}", path.display());
+        println!("Rebuild to regenerate declare_id!, Anchor.toml, the IDL and the client.");
+    } else {
+        println!("✓ Program IDs for {} are already in sync", cluster);
+    }
+
+    Ok(())
+}
+
 fn add_dependency(
     name: &str,
     version: Option<&str>,
@@ -1398,23 +2053,52 @@ fn remove_dependency(name: &str) -> Result<()> {
     Ok(())
 }
 
-fn install_dependencies() -> Result<()> {
+fn install_dependencies(offline: bool) -> Result<()> {
     let config_path = find_config()?;
     let config = config::Config::load(&config_path)?;
+    let project_root = config_path.parent().unwrap_or(std::path::Path::new("."));
 
-    if config.dependencies.is_empty() {
+    // A workspace installs the dependencies of all its members
+    let count: usize = match workspace::Workspace::find(project_root)? {
+        Some(workspace) => workspace
+            .members
+            .iter()
+            .map(|member| member.config.dependencies.len())
+            .sum(),
+        None => config.dependencies.len(),
+    };
+    if count == 0 {
         println!("No dependencies to install.");
         return Ok(());
     }
 
-    println!("Installing {} dependencies...\n", config.dependencies.len());
+    println!("Installing {} dependencies...\n", count);
 
-    let project_root = config_path.parent().unwrap_or(std::path::Path::new("."));
-    let pm = package::PackageManager::new(project_root.to_path_buf());
+    package::install_project(project_root, &config, offline, false)?;
+
+    println!(
+        "\n✓ All dependencies installed (locked in {})",
+        lockfile::LOCKFILE
+    );
+
+    Ok(())
+}
+
+fn vendor_dependencies() -> Result<()> {
+    let config_path = find_config()?;
+    let config = config::Config::load(&config_path)?;
+    let root = config_path.parent().unwrap_or(Path::new("."));
 
-    pm.install_all(&config)?;
+    // Install first, so every locked package is present and verified
+    let (pm, lock) = package::install_project(root, &config, false, false)?;
 
-    println!("\n✓ All dependencies installed");
+    let vendored = pm.vendor(&lock)?;
+    println!(
+        "\n✓ Vendored {} packages into {}/",
+        vendored.len(),
+        package::VENDOR_DIR
+    );
+    println!("  Commit it, then install with `solscript install --offline`");
 
     Ok(())
 }
@@ -1431,43 +2115,77 @@ fn update_dependencies() -> Result<()> {
     Ok(())
 }
 
-fn list_dependencies() -> Result<()> {
+fn list_dependencies(tree: bool) -> Result<()> {
     let config_path = find_config()?;
     let config = config::Config::load(&config_path)?;
+    let project_root = config_path.parent().unwrap_or(std::path::Path::new("."));
+
+    // A workspace root that is not a project lists each member
+    let workspace = workspace::Workspace::find(project_root)?;
+    let install_root = workspace
+        .as_ref()
+        .map_or(project_root, |workspace| &workspace.root);
+    let projects = match (&config.project, &workspace) {
+        (None, Some(workspace)) => workspace
+            .members
+            .iter()
+            .map(|member| (member.root.clone(), member.config.clone()))
+            .collect(),
+        _ => vec![(project_root.to_path_buf(), config)],
+    };
 
-    if config.dependencies.is_empty() {
+    if projects
+        .iter()
+        .all(|(_, config)| config.dependencies.is_empty())
+    {
         println!("No dependencies installed.");
         return Ok(());
     }
 
-    println!("Dependencies:\n");
+    for (root, config) in &projects {
+        let name = config.project()?.name.as_str();
+        if tree {
+            let graph = package::DependencyGraph::load(root, config, install_root)?;
+            print!("{}", graph.render_tree(name));
+            continue;
+        }
 
-    for (name, dep) in &config.dependencies {
-        let source = if dep.is_path() {
-            format!("path: {}", dep.local_path().unwrap_or("unknown"))
-        } else if dep.is_git() {
-            if let Some(url) = dep.git_url() {
-                let git_ref = dep.git_ref().unwrap_or_else(|| "HEAD".to_string());
-                format!("git: {} ({})", url, git_ref)
-            } else {
-                "git".to_string()
-            }
-        } else if let Some(v) = dep.version() {
-            format!("version: {}", v)
+        if projects.len() == 1 {
+            println!("Dependencies:\n");
         } else {
-            "unknown".to_string()
-        };
+            println!("Dependencies of {}:\n", name);
+        }
+
+        for (name, dep) in &config.dependencies {
+            let source = if dep.is_path() {
+                format!("path: {}", dep.local_path().unwrap_or("unknown"))
+            } else if dep.is_git() {
+                if let Some(url) = dep.git_url() {
+                    let git_ref = dep.git_ref().unwrap_or_else(|| "HEAD".to_string());
+                    format!("git: {} ({})", url, git_ref)
+                } else {
+                    "git".to_string()
+                }
+            } else if let Some(v) = dep.version() {
+                format!("version: {}", v)
+            } else {
+                "unknown".to_string()
+            };
 
-        println!("  {} - {}", name, source);
+            println!("  {} - {}", name, source);
+        }
+        println!();
+    }
+    if tree {
+        return Ok(());
     }
 
     // Also show installed packages
-    let project_root = config_path.parent().unwrap_or(std::path::Path::new("."));
-    let pm = package::PackageManager::new(project_root.to_path_buf());
+    let pm = package::PackageManager::new(install_root.to_path_buf());
 
     if let Ok(installed) = pm.list_installed() {
         if !installed.is_empty() {
-            println!("\nInstalled packages:");
+            println!("Installed packages:");
             for pkg in installed {
                 println!("  {}", pkg);
             }
@@ -1477,36 +2195,44 @@ fn list_dependencies() -> Result<()> {
     Ok(())
 }
 
+fn package_project(allow_dirty: bool) -> Result<()> {
+    let (root, config) = find_project()?;
+    let packaged = publish::package(&root, &config, allow_dirty)?;
+    println!("✓ Packaged {}", packaged.archive.display());
+    println!("  checksum: {}", packaged.checksum);
+    Ok(())
+}
+
+fn publish_project(registry: &str, allow_dirty: bool) -> Result<()> {
+    let (root, config) = find_project()?;
+    publish::publish(&root, &config, registry, allow_dirty)
+}
+
 // =============================================================================
 // BPF Compilation Commands
 // =============================================================================
 
 fn build_bpf(
-    file: &Path,
+    file: Option<PathBuf>,
     output: &Path,
     opt_level: u8,
     keep_intermediate: bool,
     use_llvm: bool,
 ) -> Result<()> {
+    let target = Target::resolve(file)?;
     if use_llvm {
-        println!("Compiling {} to BPF using LLVM...\n", file.display());
+        println!("Compiling {} to BPF using LLVM...\n", target);
     } else {
-        println!("Compiling {} to BPF...\n", file.display());
+        println!("Compiling {} to BPF...\n", target);
     }
 
-    let source = std::fs::read_to_string(file)
-        .into_diagnostic()
-        .wrap_err_with(|| format!("Failed to read file: {}", file.display()))?;
+    let mut session = target.session()?;
 
     // Parse
-    let program =
-        solscript_parser::parse(&source).map_err(|e| miette::miette!("Parse error: {:?}", e))?;
+    let program = session.parse().ok_or_else(|| emit_errors(&mut session))?;
+    emit_warnings(&mut session);
 
-    println!(
-        "✓ Parsed {} ({} items)",
-        file.display(),
-        program.items.len()
-    );
+    println!("✓ Parsed {} ({} items)", target, program.items.len());
 
     // Configure compilation options
     let options = solscript_bpf::CompileOptions {
@@ -1515,10 +2241,11 @@ fn build_bpf(
         output_dir: output.to_path_buf(),
         use_cargo_sbf: !use_llvm, // Use direct LLVM if --llvm flag is passed
         keep_intermediate,
+        source_path: Some(session.path().to_path_buf()),
     };
 
     // Compile to BPF
-    let result = solscript_bpf::compile(&program, &source, &options)
+    let result = solscript_bpf::compile(&program, session.source(), &options)
         .map_err(|e| miette::miette!("Compilation error: {}", e))?;
 
     println!("✓ Type checked successfully");
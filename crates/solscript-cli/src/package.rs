@@ -3,13 +3,20 @@
 //! Handles fetching, installing, and managing SolScript packages.
 
 use crate::config::{Config, Dependency, DependencySpec};
+use crate::lockfile::{self, LockedPackage, Lockfile, LOCKFILE};
+use crate::registry::{self, Index, Requirement, Resolved};
+use crate::workspace::Workspace;
 use miette::{IntoDiagnostic, Result, WrapErr};
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use semver::{Version, VersionReq};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
 
 /// The packages directory name
-const PACKAGES_DIR: &str = ".solscript/packages";
+pub const PACKAGES_DIR: &str = ".solscript/packages";
+
+/// Where `solscript vendor` copies packages to, relative to the project root
+pub const VENDOR_DIR: &str = "vendor";
 
 /// Package manager for handling dependencies
 pub struct PackageManager {
@@ -17,6 +24,8 @@ pub struct PackageManager {
     project_root: PathBuf,
     /// Packages cache directory
     packages_dir: PathBuf,
+    /// Install only from the packages directory and `vendor/`, without network access
+    offline: bool,
 }
 
 impl PackageManager {
@@ -26,9 +35,16 @@ impl PackageManager {
         Self {
             project_root,
             packages_dir,
+            offline: false,
         }
     }
 
+    /// Install without network access, from the packages directory and `vendor/` only
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
     /// Initialize the packages directory
     pub fn init(&self) -> Result<()> {
         if !self.packages_dir.exists() {
@@ -39,199 +55,466 @@ impl PackageManager {
         Ok(())
     }
 
-    /// Install all dependencies from the config
-    pub fn install_all(&self, config: &Config) -> Result<InstalledPackages> {
+    /// Install all dependencies from the config, and the dependencies declared in each
+    /// git or path package's own `solscript.toml`, at the commits and contents `lock`
+    /// records. Git dependencies without an entry, or with one for an edited dependency,
+    /// are fetched afresh and (re-)entered in the lock. Registry dependencies are
+    /// resolved together against the package index, keeping locked versions that still
+    /// satisfy their requirements.
+    ///
+    /// Every package is installed once. When two packages declare the same git or path
+    /// dependency differently, the first declaration (the project's own, then in
+    /// breadth-first order) wins and the mismatch is reported.
+    pub fn install_all(&self, config: &Config, lock: &mut Lockfile) -> Result<InstalledPackages> {
+        let queue = config
+            .dependencies
+            .iter()
+            .map(|(name, dep)| Declaration {
+                name: name.clone(),
+                dep: dep.clone(),
+                by: registry::PROJECT.to_string(),
+                base: self.project_root.clone(),
+            })
+            .collect();
+        self.install_declared(queue, &config.registry.index, lock)
+    }
+
+    /// Like [`install_all`](Self::install_all), for the dependencies of every member of
+    /// `workspace` together, in the order the members are listed
+    pub fn install_workspace(
+        &self,
+        workspace: &Workspace,
+        lock: &mut Lockfile,
+    ) -> Result<InstalledPackages> {
+        let queue = workspace
+            .members
+            .iter()
+            .flat_map(|member| {
+                member
+                    .config
+                    .dependencies
+                    .iter()
+                    .map(|(name, dep)| Declaration {
+                        name: name.clone(),
+                        dep: dep.clone(),
+                        by: member.name().to_string(),
+                        base: member.root.clone(),
+                    })
+            })
+            .collect();
+        self.install_declared(queue, &workspace.config.registry.index, lock)
+    }
+
+    /// Install the declared dependencies and, breadth-first, theirs, resolving registry
+    /// dependencies against the index at `index`
+    fn install_declared(
+        &self,
+        mut queue: VecDeque<Declaration>,
+        index: &str,
+        lock: &mut Lockfile,
+    ) -> Result<InstalledPackages> {
         self.init()?;
 
         let mut installed = InstalledPackages::new();
+        let mut declared: BTreeMap<String, Declaration> = BTreeMap::new();
+        let mut requirements = Vec::new();
+
+        while let Some(declaration) = queue.pop_front() {
+            let Declaration {
+                name,
+                dep,
+                by,
+                base,
+            } = &declaration;
+            if dep.is_registry() {
+                requirements.push(Requirement {
+                    by: by.clone(),
+                    name: name.clone(),
+                    version: dep.version().unwrap_or("*").to_string(),
+                });
+                continue;
+            }
+            if let Some(first) = declared.get(name) {
+                if !first.same_source(&declaration) {
+                    println!(
+                        "  ⚠ {} is required as {} by {}, but as {} by {}; using the first",
+                        name,
+                        first.describe(),
+                        first.by,
+                        declaration.describe(),
+                        by
+                    );
+                }
+                continue;
+            }
 
-        for (name, dep) in &config.dependencies {
             println!("Installing {}...", name);
-            let pkg_path = self.install_package(name, dep)?;
+            let locked = lock.get(name).filter(|locked| locked.matches(dep)).cloned();
+            let (pkg_path, entry) = self.install_package(name, dep, base, locked.as_ref())?;
+            if let Some(entry) = entry {
+                lock.insert(entry);
+            }
+            for (child, child_dep) in manifest_dependencies(&pkg_path)? {
+                queue.push_back(Declaration {
+                    name: child,
+                    dep: child_dep,
+                    by: name.clone(),
+                    base: pkg_path.clone(),
+                });
+            }
             installed.packages.insert(name.clone(), pkg_path);
             println!("  ✓ Installed {}", name);
+            declared.insert(name.clone(), declaration);
         }
 
+        let registry_packages = if self.offline {
+            self.install_locked_registry(&requirements, &declared, lock, &mut installed)?
+        } else {
+            self.install_registry(index, &requirements, &declared, lock, &mut installed)?
+        };
+
+        lock.retain(|name| declared.contains_key(name) || registry_packages.contains(name));
+
         Ok(installed)
     }
 
-    /// Install a single package
-    pub fn install_package(&self, name: &str, dep: &Dependency) -> Result<PathBuf> {
-        self.init()?;
-
-        let pkg_dir = self.packages_dir.join(name);
+    /// Resolve the registry `requirements` against the index and install the picked
+    /// versions. Returns the names of the installed packages.
+    fn install_registry(
+        &self,
+        index: &str,
+        requirements: &[Requirement],
+        declared: &BTreeMap<String, Declaration>,
+        lock: &mut Lockfile,
+        installed: &mut InstalledPackages,
+    ) -> Result<BTreeSet<String>> {
+        if requirements.is_empty() {
+            return Ok(BTreeSet::new());
+        }
+        let index = Index::open(index, &self.project_root)?;
+        let preferred = lock
+            .packages
+            .iter()
+            .filter(|locked| locked.source.starts_with("registry+"))
+            .filter_map(|locked| {
+                let version = Version::parse(locked.version.as_deref()?).ok()?;
+                Some((locked.name.clone(), version))
+            })
+            .collect();
+        let resolution = registry::resolve(&index, requirements, &preferred)?;
+
+        for package in resolution.values() {
+            check_not_declared(&package.name, requirements, declared)?;
+
+            println!("Installing {} {}...", package.name, package.entry.version);
+            let version = package.entry.version.to_string();
+            let source = format!("registry+{}", package.entry.url);
+            let locked = lock
+                .get(&package.name)
+                .filter(|locked| {
+                    locked.source == source && locked.version.as_deref() == Some(&version)
+                })
+                .cloned();
+            let entry = self.install_registry_package(package, locked.as_ref())?;
+            lock.insert(entry);
+            installed
+                .packages
+                .insert(package.name.clone(), self.packages_dir.join(&package.name));
+            println!("  ✓ Installed {}", package.name);
+        }
+        Ok(resolution.into_keys().collect())
+    }
 
-        // If already installed, check if we need to update
-        if pkg_dir.exists() {
-            // For git dependencies, we might need to update
-            if dep.is_git() {
-                return self.update_git_package(&pkg_dir, dep);
+    /// Offline, install the registry packages `lock` records, which must satisfy
+    /// `requirements`. Returns the names of the installed packages.
+    fn install_locked_registry(
+        &self,
+        requirements: &[Requirement],
+        declared: &BTreeMap<String, Declaration>,
+        lock: &Lockfile,
+        installed: &mut InstalledPackages,
+    ) -> Result<BTreeSet<String>> {
+        let mut names = BTreeSet::new();
+        let mut pending = requirements.iter().cloned().collect::<VecDeque<_>>();
+        while let Some(requirement) = pending.pop_front() {
+            let name = &requirement.name;
+            check_not_declared(name, requirements, declared)?;
+            let locked = lock
+                .get(name)
+                .filter(|locked| locked.source.starts_with("registry+"))
+                .ok_or_else(|| {
+                    miette::miette!(
+                        help = "run `solscript install` with network access to resolve it",
+                        "Cannot resolve {} {} offline: {} has no entry for it",
+                        name,
+                        requirement.version,
+                        LOCKFILE
+                    )
+                })?;
+            let version = locked.version.as_deref().unwrap_or_default();
+            let satisfied = match (
+                VersionReq::parse(&requirement.version),
+                Version::parse(version),
+            ) {
+                (Ok(req), Ok(version)) => req.matches(&version),
+                _ => false,
+            };
+            if !satisfied {
+                return Err(miette::miette!(
+                    help = "run `solscript install` with network access to resolve it again",
+                    "Cannot resolve {} {} offline: {} locks version {}",
+                    name,
+                    requirement.version,
+                    LOCKFILE,
+                    version
+                ));
             }
-            // For path dependencies, just return the path
-            if dep.is_path() {
-                if let Some(path) = dep.local_path() {
-                    return Ok(self.project_root.join(path));
-                }
+            if !names.insert(name.clone()) {
+                continue;
+            }
+
+            println!("Installing {} {}...", name, version);
+            let pkg_dir = self.install_offline(locked)?;
+            installed.packages.insert(name.clone(), pkg_dir);
+            println!("  ✓ Installed {}", name);
+            for dependency in &locked.dependencies {
+                pending.push_back(Requirement {
+                    by: format!("{} {}", name, version),
+                    name: dependency.clone(),
+                    version: "*".to_string(),
+                });
             }
-            // Already installed
-            return Ok(pkg_dir);
         }
+        Ok(names)
+    }
 
-        // Install based on dependency type
-        if dep.is_path() {
-            if let Some(path) = dep.local_path() {
-                return Ok(self.project_root.join(path));
+    /// Offline, make the locked package available in the packages directory: the copy
+    /// already installed there, or else the one in `vendor/`
+    fn install_offline(&self, locked: &LockedPackage) -> Result<PathBuf> {
+        let pkg_dir = self.packages_dir.join(&locked.name);
+        if !pkg_dir.exists() {
+            let vendored = self.project_root.join(VENDOR_DIR).join(&locked.name);
+            if !vendored.is_dir() {
+                return Err(miette::miette!(
+                    help = "run `solscript vendor` with network access and commit vendor/",
+                    "Package '{}' is not available offline: it is neither installed in {} nor vendored in {}",
+                    locked.name,
+                    PACKAGES_DIR,
+                    VENDOR_DIR
+                ));
             }
+            copy_dir(&vendored, &pkg_dir)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to copy {}", vendored.display()))?;
         }
+        locked.verify(&pkg_dir)?;
+        Ok(pkg_dir)
+    }
 
-        if dep.is_git() {
-            return self.install_git_package(name, dep);
+    /// Copy every locked package into `vendor/`, replacing what is there, so installs
+    /// can run offline. Returns the names of the vendored packages.
+    pub fn vendor(&self, lock: &Lockfile) -> Result<Vec<String>> {
+        let vendor_dir = self.project_root.join(VENDOR_DIR);
+        if vendor_dir.exists() {
+            std::fs::remove_dir_all(&vendor_dir)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to remove {}", vendor_dir.display()))?;
         }
 
-        // Registry-based dependency (GitHub releases)
-        self.install_registry_package(name, dep)
+        let mut vendored = Vec::new();
+        for locked in &lock.packages {
+            let pkg_dir = self.packages_dir.join(&locked.name);
+            locked.verify(&pkg_dir)?;
+            copy_dir(&pkg_dir, &vendor_dir.join(&locked.name))
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to vendor {}", locked.name))?;
+            vendored.push(locked.name.clone());
+        }
+        Ok(vendored)
     }
 
-    /// Install a package from git
-    fn install_git_package(&self, name: &str, dep: &Dependency) -> Result<PathBuf> {
+    /// Install a single git or path dependency, at the commit and contents of `locked`
+    /// if given. Path dependencies are relative to `base`, the directory of the
+    /// `solscript.toml` declaring them. Returns where the package is installed and its
+    /// lock entry (none for path dependencies).
+    fn install_package(
+        &self,
+        name: &str,
+        dep: &Dependency,
+        base: &Path,
+        locked: Option<&LockedPackage>,
+    ) -> Result<(PathBuf, Option<LockedPackage>)> {
+        // Path dependencies are used in place
+        if let Some(path) = dep.local_path() {
+            return Ok((normalize(&base.join(path)), None));
+        }
+
         let pkg_dir = self.packages_dir.join(name);
+        let entry = self.install_git_package(name, dep, &pkg_dir, locked)?;
+        Ok((pkg_dir, Some(entry)))
+    }
+
+    /// Install a package from git
+    fn install_git_package(
+        &self,
+        name: &str,
+        dep: &Dependency,
+        pkg_dir: &Path,
+        locked: Option<&LockedPackage>,
+    ) -> Result<LockedPackage> {
+        if self.offline {
+            let locked = locked.ok_or_else(|| {
+                miette::miette!(
+                    help = "run `solscript install` with network access to lock it",
+                    "Cannot install {} offline: {} has no entry for it",
+                    name,
+                    LOCKFILE
+                )
+            })?;
+            self.install_offline(locked)?;
+            return Ok(locked.clone());
+        }
 
         let git_url = dep
             .git_url()
             .ok_or_else(|| miette::miette!("No git URL for package {}", name))?;
 
-        // Clone the repository
-        let mut cmd = Command::new("git");
-        cmd.arg("clone").arg("--depth").arg("1"); // Shallow clone
-
-        // Add branch/tag if specified
-        if let Some(git_ref) = dep.git_ref() {
-            cmd.arg("--branch").arg(&git_ref);
+        // A copy from vendor/ has no git metadata; replace it with a clone
+        if pkg_dir.exists() && !pkg_dir.join(".git").exists() {
+            self.remove_package(name)?;
+        }
+        let existed = pkg_dir.exists();
+        if !existed {
+            // Shallow clone of the requested branch or tag
+            let mut args = vec!["clone", "--depth", "1"];
+            let git_ref = dep.git_ref();
+            if let Some(git_ref) = &git_ref {
+                args.extend(["--branch", git_ref]);
+            }
+            let dir = pkg_dir.to_string_lossy();
+            args.extend([git_url.as_str(), &dir]);
+            run_git(&self.packages_dir, &args).wrap_err("Git clone failed")?;
         }
 
-        cmd.arg(&git_url).arg(&pkg_dir);
-
-        let output = cmd
-            .output()
-            .into_diagnostic()
-            .wrap_err("Failed to run git clone")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(miette::miette!("Git clone failed: {}", stderr));
+        if let Some(locked) = locked {
+            let rev = locked.rev.as_deref().ok_or_else(|| {
+                miette::miette!("{} has no commit for git package {}", LOCKFILE, name)
+            })?;
+            checkout_git_rev(pkg_dir, rev).wrap_err_with(|| {
+                format!("Failed to check out {} at locked commit {}", name, rev)
+            })?;
+            locked.verify(pkg_dir)?;
+            return Ok(locked.clone());
         }
 
-        Ok(pkg_dir)
+        if existed {
+            self.update_git_package(pkg_dir, dep)?;
+        }
+        Ok(LockedPackage {
+            name: name.to_string(),
+            version: None,
+            source: lockfile::git_source(dep).unwrap_or_default(),
+            rev: Some(run_git(pkg_dir, &["rev-parse", "HEAD"])?),
+            checksum: lockfile::checksum(pkg_dir)?,
+            dependencies: Vec::new(),
+        })
     }
 
-    /// Update a git package
-    fn update_git_package(&self, pkg_dir: &Path, dep: &Dependency) -> Result<PathBuf> {
-        let git_ref = dep.git_ref();
+    /// Update a git package to the head of its branch or tag
+    fn update_git_package(&self, pkg_dir: &Path, dep: &Dependency) -> Result<()> {
+        let git_ref = dep.git_ref().unwrap_or_else(|| "HEAD".to_string());
 
-        // Fetch latest
-        let mut fetch = Command::new("git");
-        fetch.arg("fetch").arg("--depth").arg("1");
+        run_git(pkg_dir, &["fetch", "--depth", "1", "origin", &git_ref])
+            .wrap_err("Git fetch failed")?;
+        run_git(pkg_dir, &["reset", "--hard", "FETCH_HEAD"]).wrap_err("Git reset failed")?;
 
-        if let Some(ref_name) = &git_ref {
-            fetch.arg("origin").arg(ref_name);
-        }
-
-        fetch.current_dir(pkg_dir);
+        Ok(())
+    }
 
-        let output = fetch
-            .output()
-            .into_diagnostic()
-            .wrap_err("Failed to run git fetch")?;
+    /// Install a package version picked from the registry index, or the download
+    /// `locked` records for it
+    fn install_registry_package(
+        &self,
+        package: &Resolved,
+        locked: Option<&LockedPackage>,
+    ) -> Result<LockedPackage> {
+        let name = &package.name;
+        let version = &package.entry.version;
+        let pkg_dir = self.packages_dir.join(name);
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(miette::miette!("Git fetch failed: {}", stderr));
+        if let Some(locked) = locked {
+            // Another version, e.g. installed before the lock was bumped, is replaced
+            let current = pkg_dir.exists() && lockfile::checksum(&pkg_dir)? == locked.checksum;
+            if !current {
+                self.remove_package(name)?;
+                println!("  Downloading from {}...", package.url);
+                if !self.download_archive(name, &package.url, &pkg_dir)? {
+                    return Err(miette::miette!(
+                        "Failed to download {} {} from {}",
+                        name,
+                        version,
+                        package.url
+                    ));
+                }
+            }
+            locked.verify(&pkg_dir)?;
+            return Ok(locked.clone());
         }
 
-        // Reset to the fetched ref
-        let mut reset = Command::new("git");
-        reset.arg("reset").arg("--hard");
-
-        if let Some(ref_name) = git_ref {
-            reset.arg(format!("origin/{}", ref_name));
-        } else {
-            reset.arg("origin/HEAD");
+        // Resolved afresh, so replace whatever is installed
+        self.remove_package(name)?;
+        println!("  Downloading from {}...", package.url);
+        if !self.download_archive(name, &package.url, &pkg_dir)? {
+            return Err(miette::miette!(
+                "Failed to download {} {} from {}",
+                name,
+                version,
+                package.url
+            ));
         }
 
-        reset.current_dir(pkg_dir);
-
-        let output = reset
-            .output()
-            .into_diagnostic()
-            .wrap_err("Failed to run git reset")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(miette::miette!("Git reset failed: {}", stderr));
+        let checksum = lockfile::checksum(&pkg_dir)?;
+        if let Some(expected) = &package.entry.checksum {
+            if *expected != checksum {
+                return Err(miette::miette!(
+                    "Checksum mismatch for package '{}' {}: the registry index records {}, but the download has {}",
+                    name,
+                    version,
+                    expected,
+                    checksum
+                ));
+            }
         }
-
-        Ok(pkg_dir.to_path_buf())
+        Ok(LockedPackage {
+            name: name.clone(),
+            version: Some(version.to_string()),
+            source: format!("registry+{}", package.entry.url),
+            rev: None,
+            checksum,
+            dependencies: package.entry.dependencies.keys().cloned().collect(),
+        })
     }
 
-    /// Install a package from the registry (GitHub releases)
-    fn install_registry_package(&self, name: &str, dep: &Dependency) -> Result<PathBuf> {
-        let version = dep
-            .version()
-            .ok_or_else(|| miette::miette!("No version specified for package {}", name))?;
-
-        // For now, we use a simple GitHub-based registry
-        // Package format: https://github.com/cryptuon-packages/{name}/releases/download/v{version}/{name}.tar.gz
-        let pkg_dir = self.packages_dir.join(name);
-
-        // Try to find the package on GitHub
-        // Default organization for SolScript packages
-        let github_url = format!(
-            "https://github.com/cryptuon-packages/{}/archive/refs/tags/v{}.tar.gz",
-            name, version
-        );
-
-        println!("  Downloading from {}...", github_url);
+    /// Download a `.tar.gz` archive and extract it into `pkg_dir`. Returns false if the
+    /// download fails.
+    fn download_archive(&self, name: &str, url: &str, pkg_dir: &Path) -> Result<bool> {
+        let archive_path = self.packages_dir.join(format!("{}.tar.gz", name));
 
         // Download using curl
-        let archive_path = self
-            .packages_dir
-            .join(format!("{}-{}.tar.gz", name, version));
-
         let output = Command::new("curl")
             .arg("-fsSL")
             .arg("-o")
             .arg(&archive_path)
-            .arg(&github_url)
+            .arg(url)
             .output()
             .into_diagnostic()
             .wrap_err("Failed to run curl")?;
-
         if !output.status.success() {
-            // Try alternative URL format
-            let alt_url = format!(
-                "https://github.com/cryptuon/{}/archive/refs/tags/v{}.tar.gz",
-                name, version
-            );
-
-            let output = Command::new("curl")
-                .arg("-fsSL")
-                .arg("-o")
-                .arg(&archive_path)
-                .arg(&alt_url)
-                .output()
-                .into_diagnostic()
-                .wrap_err("Failed to download package")?;
-
-            if !output.status.success() {
-                return Err(miette::miette!(
-                    "Package {} version {} not found. Try using a git dependency instead:\n\n  [dependencies]\n  {} = {{ github = \"owner/{}\", tag = \"v{}\" }}",
-                    name, version, name, name, version
-                ));
-            }
+            return Ok(false);
         }
 
         // Extract the archive
-        std::fs::create_dir_all(&pkg_dir)
+        std::fs::create_dir_all(pkg_dir)
             .into_diagnostic()
             .wrap_err("Failed to create package directory")?;
 
@@ -239,21 +522,21 @@ impl PackageManager {
             .arg("-xzf")
             .arg(&archive_path)
             .arg("-C")
-            .arg(&pkg_dir)
+            .arg(pkg_dir)
             .arg("--strip-components=1")
             .output()
             .into_diagnostic()
             .wrap_err("Failed to extract package")?;
 
+        // Clean up archive
+        let _ = std::fs::remove_file(&archive_path);
+
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(miette::miette!("Failed to extract package: {}", stderr));
         }
 
-        // Clean up archive
-        let _ = std::fs::remove_file(&archive_path);
-
-        Ok(pkg_dir)
+        Ok(true)
     }
 
     /// Remove a package
@@ -296,6 +579,284 @@ impl PackageManager {
     }
 }
 
+/// Run git in `dir`, returning its trimmed stdout
+pub fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .into_diagnostic()
+        .wrap_err("Failed to run git")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(miette::miette!("git {}: {}", args[0], stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Check out commit `rev` in a (possibly shallow) clone
+fn checkout_git_rev(dir: &Path, rev: &str) -> Result<()> {
+    if run_git(dir, &["rev-parse", "HEAD"])? == rev {
+        return Ok(());
+    }
+    // Servers may refuse to serve a commit by hash; then fetch the full history
+    if run_git(dir, &["fetch", "--depth", "1", "origin", rev]).is_err() {
+        run_git(dir, &["fetch", "--unshallow", "--tags", "origin"])?;
+    }
+    run_git(dir, &["checkout", "--quiet", "--detach", rev])?;
+    Ok(())
+}
+
+/// A git or path dependency as some `solscript.toml` declares it
+struct Declaration {
+    name: String,
+    dep: Dependency,
+    /// The project, or the package declaring the dependency
+    by: String,
+    /// Directory of the declaring `solscript.toml`, which paths are relative to
+    base: PathBuf,
+}
+
+impl Declaration {
+    /// Whether both declarations name the same code
+    fn same_source(&self, other: &Declaration) -> bool {
+        match (self.dep.local_path(), other.dep.local_path()) {
+            (Some(path), Some(other_path)) => {
+                let dir = normalize(&self.base.join(path));
+                let other_dir = normalize(&other.base.join(other_path));
+                dir == other_dir
+                    || matches!(
+                        (dir.canonicalize(), other_dir.canonicalize()),
+                        (Ok(dir), Ok(other_dir)) if dir == other_dir
+                    )
+            }
+            (None, None) => lockfile::git_source(&self.dep) == lockfile::git_source(&other.dep),
+            _ => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        describe(&self.dep, &self.base)
+    }
+}
+
+/// Where a dependency comes from, for messages and `solscript list`
+fn describe(dep: &Dependency, base: &Path) -> String {
+    if let Some(path) = dep.local_path() {
+        format!("path {}", normalize(&base.join(path)).display())
+    } else if let Some(url) = dep.git_url() {
+        match dep.git_ref() {
+            Some(git_ref) => format!("git {} ({})", url, git_ref),
+            None => format!("git {}", url),
+        }
+    } else {
+        format!("version {}", dep.version().unwrap_or("*"))
+    }
+}
+
+/// Fail if `name`, required from the registry, is also a git or path dependency: both
+/// would be installed in the same place
+fn check_not_declared(
+    name: &str,
+    requirements: &[Requirement],
+    declared: &BTreeMap<String, Declaration>,
+) -> Result<()> {
+    let Some(first) = declared.get(name) else {
+        return Ok(());
+    };
+    let requirement = requirements
+        .iter()
+        .find(|requirement| requirement.name == name)
+        .expect("registry packages are required");
+    Err(miette::miette!(
+        "Package '{}' is required as {} by {}, but from the registry as {} by {}",
+        name,
+        first.describe(),
+        first.by,
+        requirement.version,
+        requirement.by
+    ))
+}
+
+/// Copy the files under `from` to `to`, leaving out git metadata
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            if entry.file_name() != ".git" {
+                copy_dir(&entry.path(), &target)?;
+            }
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// `path` without `.` and `dir/..` components
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normal.file_name().is_some() => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+/// The `[dependencies]` of the package in `dir`, empty if it has no `solscript.toml`
+fn manifest_dependencies(dir: &Path) -> Result<BTreeMap<String, Dependency>> {
+    let manifest = dir.join("solscript.toml");
+    if !manifest.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(Config::load(&manifest)?.dependencies)
+}
+
+/// The dependency graph of a project as installed, read from the manifests of git and
+/// path packages and from `solscript.lock` for registry packages
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    /// The project's own dependencies
+    pub roots: Vec<String>,
+    pub packages: BTreeMap<String, GraphNode>,
+}
+
+#[derive(Debug)]
+pub struct GraphNode {
+    /// Where the package is installed
+    pub dir: PathBuf,
+    /// Where it comes from: a path, git URL or registry version
+    pub source: String,
+    pub dependencies: Vec<String>,
+}
+
+impl DependencyGraph {
+    /// The graph of the project rooted at `root`, whose packages are installed and
+    /// locked in `install_root` (its workspace's root, or `root`). A package declared
+    /// more than once is taken from its first declaration, as `solscript install` does.
+    pub fn load(root: &Path, config: &Config, install_root: &Path) -> Result<Self> {
+        let lock = Lockfile::load(install_root)?.unwrap_or_default();
+        let packages_dir = install_root.join(PACKAGES_DIR);
+
+        let mut graph = DependencyGraph {
+            roots: config.dependencies.keys().cloned().collect(),
+            packages: BTreeMap::new(),
+        };
+        let mut queue = config
+            .dependencies
+            .iter()
+            .map(|(name, dep)| (name.clone(), dep.clone(), root.to_path_buf()))
+            .collect::<VecDeque<_>>();
+        while let Some((name, dep, base)) = queue.pop_front() {
+            if graph.packages.contains_key(&name) {
+                continue;
+            }
+            let locked = lock.get(&name);
+
+            let node = if dep.is_registry() {
+                // Registry packages depend on registry packages only, as the index says
+                let version = locked.and_then(|locked| locked.version.clone());
+                let dependencies = locked
+                    .map(|locked| locked.dependencies.clone())
+                    .unwrap_or_default();
+                for child in &dependencies {
+                    let child_dep = Dependency::Version("*".to_string());
+                    queue.push_back((child.clone(), child_dep, base.clone()));
+                }
+                GraphNode {
+                    dir: packages_dir.join(&name),
+                    source: match version {
+                        Some(version) => format!("v{}", version),
+                        None => describe(&dep, &base),
+                    },
+                    dependencies,
+                }
+            } else {
+                let dir = match dep.local_path() {
+                    Some(path) => normalize(&base.join(path)),
+                    None => packages_dir.join(&name),
+                };
+                let children = manifest_dependencies(&dir)?;
+                let dependencies = children.keys().cloned().collect();
+                for (child, child_dep) in children {
+                    queue.push_back((child, child_dep, dir.clone()));
+                }
+                let mut source = describe(&dep, &base);
+                if let Some(rev) = locked.and_then(|locked| locked.rev.as_deref()) {
+                    source = format!("{} @ {}", source, &rev[..rev.len().min(10)]);
+                }
+                GraphNode {
+                    dir,
+                    source,
+                    dependencies,
+                }
+            };
+            graph.packages.insert(name, node);
+        }
+        Ok(graph)
+    }
+
+    /// Directory of every package, which `import { .. } from "<name>/<path>"` resolves
+    /// against
+    pub fn package_dirs(&self) -> BTreeMap<String, PathBuf> {
+        self.packages
+            .iter()
+            .map(|(name, node)| (name.clone(), node.dir.clone()))
+            .collect()
+    }
+
+    /// The graph as a tree under `project`. Packages already shown are marked `(*)`
+    /// instead of being expanded again.
+    pub fn render_tree(&self, project: &str) -> String {
+        let mut out = format!("{}\n", project);
+        let mut shown = BTreeSet::new();
+        self.render_children(&self.roots, "", &mut shown, &mut out);
+        out
+    }
+
+    fn render_children(
+        &self,
+        names: &[String],
+        prefix: &str,
+        shown: &mut BTreeSet<String>,
+        out: &mut String,
+    ) {
+        for (i, name) in names.iter().enumerate() {
+            let last = i + 1 == names.len();
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            let Some(node) = self.packages.get(name) else {
+                out.push_str(&format!("{}{}{} (not installed)\n", prefix, branch, name));
+                continue;
+            };
+            let repeated = !shown.insert(name.clone()) && !node.dependencies.is_empty();
+            out.push_str(&format!(
+                "{}{}{} {}{}\n",
+                prefix,
+                branch,
+                name,
+                node.source,
+                if repeated { " (*)" } else { "" }
+            ));
+            if !repeated {
+                let prefix = format!("{}{}", prefix, indent);
+                self.render_children(&node.dependencies, &prefix, shown, out);
+            }
+        }
+    }
+}
+
 /// Represents installed packages and their locations
 #[derive(Debug, Default)]
 pub struct InstalledPackages {
@@ -327,6 +888,7 @@ pub fn add_package(
     path: Option<&str>,
 ) -> Result<()> {
     let mut config = Config::load(config_path)?;
+    config.project()?;
 
     let dep = if let Some(path) = path {
         // Local path dependency
@@ -361,11 +923,9 @@ pub fn add_package(
     config.add_dependency(name.to_string(), dep);
     config.save(config_path)?;
 
-    // Install the package
+    // Install the package, resolving registry dependencies again
     let project_root = config_path.parent().unwrap_or(Path::new("."));
-    let pm = PackageManager::new(project_root.to_path_buf());
-    pm.install_package(name, &config.dependencies[name])?;
-
+    install_project(project_root, &config, false, false)?;
     Ok(())
 }
 
@@ -382,29 +942,240 @@ pub fn remove_package(config_path: &Path, name: &str) -> Result<()> {
 
     config.save(config_path)?;
 
-    // Remove the package files
+    // Other members of the workspace may still use the package
     let project_root = config_path.parent().unwrap_or(Path::new("."));
+    if Workspace::find(project_root)?.is_some() {
+        return Ok(());
+    }
+
+    // Remove the package files
     let pm = PackageManager::new(project_root.to_path_buf());
     pm.remove_package(name)?;
 
+    if let Some(mut lock) = Lockfile::load(project_root)? {
+        lock.retain(|locked| locked != name);
+        lock.save(project_root)?;
+    }
+
     Ok(())
 }
 
-/// Update all packages
+/// Update all packages to the latest code their dependency allows, and re-lock them
 pub fn update_packages(config_path: &Path) -> Result<()> {
     let config = Config::load(config_path)?;
     let project_root = config_path.parent().unwrap_or(Path::new("."));
-    let pm = PackageManager::new(project_root.to_path_buf());
 
-    for (name, dep) in &config.dependencies {
-        println!("Updating {}...", name);
+    // Reinstall everything, ignoring the old lock
+    install_project(project_root, &config, false, true)?;
+    println!("✓ Wrote {}", LOCKFILE);
 
-        // Remove and reinstall
-        let _ = pm.remove_package(name);
-        pm.install_package(name, dep)?;
+    Ok(())
+}
 
-        println!("  ✓ Updated {}", name);
+/// Install the dependencies of the project in `root`, or of every member of the
+/// workspace it belongs to, and save the lock. Packages and the lock are shared at the
+/// workspace root. With `relock`, the old lock is ignored and every dependency is
+/// resolved afresh. Returns the package manager used and the new lock.
+pub fn install_project(
+    root: &Path,
+    config: &Config,
+    offline: bool,
+    relock: bool,
+) -> Result<(PackageManager, Lockfile)> {
+    let workspace = Workspace::find(root)?;
+    let (install_root, net) = match &workspace {
+        Some(workspace) => (workspace.root.clone(), &workspace.config.net),
+        None => (root.to_path_buf(), &config.net),
+    };
+    if relock && net.offline {
+        return Err(miette::miette!(
+            help = "`offline` is set in the [net] section of solscript.toml",
+            "Cannot update dependencies offline"
+        ));
     }
+    let pm = PackageManager::new(install_root.clone()).offline(offline || net.offline);
 
-    Ok(())
+    let mut lock = match relock {
+        true => Lockfile::default(),
+        false => Lockfile::load(&install_root)?.unwrap_or_default(),
+    };
+    match &workspace {
+        Some(workspace) => pm.install_workspace(workspace, &mut lock)?,
+        None => pm.install_all(config, &mut lock)?,
+    };
+    lock.save(&install_root)?;
+    Ok((pm, lock))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_package(dir: &Path, name: &str, dependencies: &str) {
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(
+            dir.join("solscript.toml"),
+            format!(
+                "[project]\nname = \"{}\"\n\n[dependencies]\n{}",
+                name, dependencies
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_install_reads_package_manifests() {
+        let root = std::env::temp_dir().join(format!("solscript-graph-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let app = root.join("app");
+        // app -> token -> math, app -> vault -> (token, math from elsewhere)
+        write_package(
+            &app,
+            "app",
+            "token = { path = \"../token\" }\nvault = { path = \"../vault\" }\n",
+        );
+        write_package(
+            &root.join("token"),
+            "token",
+            "math = { path = \"../math\" }\n",
+        );
+        write_package(
+            &root.join("vault"),
+            "vault",
+            "math = { path = \"../math-fork\" }\ntoken = { path = \"../token\" }\n",
+        );
+        write_package(&root.join("math"), "math", "");
+        write_package(&root.join("math-fork"), "math", "");
+
+        let config = Config::load(&app.join("solscript.toml")).unwrap();
+        let pm = PackageManager::new(app.clone());
+        let mut lock = Lockfile::default();
+        let installed = pm.install_all(&config, &mut lock).unwrap();
+        assert_eq!(installed.packages.len(), 3);
+        // The first declaration of math wins
+        assert_eq!(installed.packages["math"], root.join("math"));
+        assert!(lock.packages.is_empty());
+
+        let graph = DependencyGraph::load(&app, &config, &app).unwrap();
+        assert_eq!(graph.package_dirs()["vault"], root.join("vault"));
+        let tree = graph.render_tree("app");
+        let expected = [
+            "app".to_string(),
+            format!("├── token path {}", root.join("token").display()),
+            format!("│   └── math path {}", root.join("math").display()),
+            format!("└── vault path {}", root.join("vault").display()),
+            format!("    ├── math path {}", root.join("math").display()),
+            format!("    └── token path {} (*)", root.join("token").display()),
+        ];
+        assert_eq!(tree.lines().collect::<Vec<_>>(), expected);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_locked_install_replaces_other_versions() {
+        let root = std::env::temp_dir().join(format!("solscript-relock-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let publish = |version: &str| {
+            let dir = root.join(format!("math-{}", version));
+            let manifest = format!(
+                "[project]\nname = \"math\"\nversion = \"{}\"\nlicense = \"MIT\"\n",
+                version
+            );
+            std::fs::create_dir_all(dir.join("src")).unwrap();
+            std::fs::write(dir.join("solscript.toml"), &manifest).unwrap();
+            std::fs::write(
+                dir.join("src/math.sol"),
+                format!("// {}\ncontract Math {{}}", version),
+            )
+            .unwrap();
+            let config = Config::parse(&manifest).unwrap();
+            let packaged = crate::publish::package(&dir, &config, true).unwrap();
+            let entry = registry::IndexEntry {
+                version: packaged.version.clone(),
+                url: format!("file://{}", packaged.archive.display()),
+                checksum: Some(packaged.checksum.clone()),
+                dependencies: BTreeMap::new(),
+                yanked: false,
+            };
+            let locked = LockedPackage {
+                name: "math".to_string(),
+                version: Some(version.to_string()),
+                source: format!("registry+{}", entry.url),
+                rev: None,
+                checksum: packaged.checksum,
+                dependencies: Vec::new(),
+            };
+            let resolved = Resolved {
+                name: "math".to_string(),
+                url: entry.url.clone(),
+                entry,
+            };
+            (resolved, locked)
+        };
+        let (old, old_lock) = publish("1.0.0");
+        let (new, new_lock) = publish("1.1.0");
+
+        let app = root.join("app");
+        let pm = PackageManager::new(app.clone());
+        pm.init().unwrap();
+        let installed = app.join(PACKAGES_DIR).join("math/src/math.sol");
+        pm.install_registry_package(&old, Some(&old_lock)).unwrap();
+        assert!(std::fs::read_to_string(&installed)
+            .unwrap()
+            .starts_with("// 1.0.0"));
+
+        // The lock moved to 1.1.0 over the 1.0.0 install
+        let locked = pm.install_registry_package(&new, Some(&new_lock)).unwrap();
+        assert_eq!(locked, new_lock);
+        assert!(std::fs::read_to_string(&installed)
+            .unwrap()
+            .starts_with("// 1.1.0"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_offline_install_uses_vendored_packages() {
+        let root = std::env::temp_dir().join(format!("solscript-offline-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        write_package(&root, "app", "math = \"^1.2\"\n");
+        std::fs::create_dir_all(root.join("vendor/math/src")).unwrap();
+        std::fs::write(root.join("vendor/math/src/math.sol"), "contract Math {}").unwrap();
+
+        let config = Config::load(&root.join("solscript.toml")).unwrap();
+        let pm = PackageManager::new(root.clone()).offline(true);
+        let mut lock = Lockfile::default();
+        let err = pm.install_all(&config, &mut lock).unwrap_err();
+        assert!(err.to_string().contains("Cannot resolve math ^1.2 offline"));
+
+        lock.insert(LockedPackage {
+            name: "math".to_string(),
+            version: Some("1.2.3".to_string()),
+            source: "registry+math/math-1.2.3.tar.gz".to_string(),
+            rev: None,
+            checksum: lockfile::checksum(&root.join("vendor/math")).unwrap(),
+            dependencies: vec!["bits".to_string()],
+        });
+        let err = pm.install_all(&config, &mut lock).unwrap_err();
+        assert!(err.to_string().contains("Cannot resolve bits * offline"));
+
+        lock.packages[0].dependencies.clear();
+        let installed = pm.install_all(&config, &mut lock).unwrap();
+        assert_eq!(
+            installed.packages["math"],
+            root.join(PACKAGES_DIR).join("math")
+        );
+        assert!(root.join(PACKAGES_DIR).join("math/src/math.sol").is_file());
+
+        // Not installed and not vendored
+        std::fs::remove_dir_all(root.join(PACKAGES_DIR)).unwrap();
+        std::fs::remove_dir_all(root.join("vendor")).unwrap();
+        let err = pm.install_all(&config, &mut lock).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Package 'math' is not available offline"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 }
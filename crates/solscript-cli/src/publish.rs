@@ -0,0 +1,426 @@
+//! `solscript package` and `solscript publish`
+//!
+//! A package is a `<name>-<version>.tar.gz` holding `solscript.toml`, everything under
+//! `src/` and a [`CHECKSUM_FILE`] with the checksum of the other files, all under a
+//! `<name>-<version>/` directory. The archive is deterministic: entries are sorted and
+//! carry no timestamps or owners, so packaging the same tree twice gives the same bytes.
+
+use crate::config::Config;
+use crate::lockfile::{self, CHECKSUM_FILE};
+use crate::package::run_git;
+use crate::registry::{Index, IndexEntry};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use semver::{Version, VersionReq};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A packaged project
+#[derive(Debug)]
+pub struct Packaged {
+    pub name: String,
+    pub version: Version,
+    /// The `.tar.gz` archive
+    pub archive: PathBuf,
+    /// [`lockfile::checksum`] of the packaged files, as installs compute it
+    pub checksum: String,
+    /// Version requirement of each dependency
+    pub dependencies: BTreeMap<String, String>,
+}
+
+/// Package the project rooted at `root` into `<output>/package/`. Unless `allow_dirty`,
+/// a project in a git work tree with uncommitted changes is refused.
+pub fn package(root: &Path, config: &Config, allow_dirty: bool) -> Result<Packaged> {
+    let manifest_path = root.join("solscript.toml");
+    let manifest = std::fs::read(&manifest_path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to read {}", manifest_path.display()))?;
+    let (version, dependencies) = validate(config, &String::from_utf8_lossy(&manifest))?;
+    if !allow_dirty {
+        check_clean(root)?;
+    }
+
+    let mut files = vec![("solscript.toml".to_string(), manifest)];
+    let src = root.join("src");
+    if src.is_dir() {
+        collect_files(root, &src, &mut files)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to read {}", src.display()))?;
+    }
+    files.sort();
+    let checksum = lockfile::checksum_files(&files);
+    files.push((
+        CHECKSUM_FILE.to_string(),
+        format!("{}\n", checksum).into_bytes(),
+    ));
+    files.sort();
+
+    let prefix = format!("{}-{}", config.project()?.name, version);
+    let archive = gzip(&tar(&prefix, &files)?)?;
+    let dir = config.output_dir(root).join("package");
+    std::fs::create_dir_all(&dir)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{}.tar.gz", prefix));
+    std::fs::write(&path, archive)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(Packaged {
+        name: config.project()?.name.clone(),
+        version,
+        archive: path,
+        checksum,
+        dependencies,
+    })
+}
+
+/// Package the project and add it to the index at `registry`: a directory, or a git
+/// URL the new version is committed and pushed to
+pub fn publish(root: &Path, config: &Config, registry: &str, allow_dirty: bool) -> Result<()> {
+    let packaged = package(root, config, allow_dirty)?;
+    println!("✓ Packaged {}", packaged.archive.display());
+
+    let dir = Path::new(registry);
+    if dir.is_dir() {
+        let index = Index::from_dir(dir.to_path_buf());
+        index.publish(&packaged.name, &entry_for(&packaged), &packaged.archive)?;
+        println!(
+            "✓ Published {} {} to {}",
+            packaged.name,
+            packaged.version,
+            dir.display()
+        );
+        return Ok(());
+    }
+
+    let checkout = std::env::temp_dir().join(format!("solscript-publish-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&checkout);
+    let temp = std::env::temp_dir();
+    let result = run_git(
+        &temp,
+        &[
+            "clone",
+            "--quiet",
+            "--depth",
+            "1",
+            registry,
+            &checkout.to_string_lossy(),
+        ],
+    )
+    .wrap_err_with(|| format!("Failed to clone the registry index {}", registry))
+    .and_then(|_| {
+        let index = Index::from_dir(checkout.clone());
+        index.publish(&packaged.name, &entry_for(&packaged), &packaged.archive)?;
+        let message = format!("Publish {} {}", packaged.name, packaged.version);
+        run_git(&checkout, &["add", "--all"])?;
+        run_git(&checkout, &["commit", "--quiet", "-m", &message])?;
+        run_git(&checkout, &["push", "--quiet", "origin", "HEAD"])
+            .wrap_err_with(|| format!("Failed to push to the registry index {}", registry))?;
+        Ok(())
+    });
+    let _ = std::fs::remove_dir_all(&checkout);
+    result?;
+    println!(
+        "✓ Published {} {} to {}",
+        packaged.name, packaged.version, registry
+    );
+    Ok(())
+}
+
+/// Index entry of a package stored in the index, next to its `versions.json`
+fn entry_for(packaged: &Packaged) -> IndexEntry {
+    IndexEntry {
+        version: packaged.version.clone(),
+        url: format!(
+            "{}/{}",
+            packaged.name,
+            packaged.archive.file_name().unwrap().to_string_lossy()
+        ),
+        checksum: Some(packaged.checksum.clone()),
+        dependencies: packaged.dependencies.clone(),
+        yanked: false,
+    }
+}
+
+/// Check the project can be published: an explicit semver `version`, a `license`, and
+/// only registry dependencies. Returns the version and the dependency requirements.
+fn validate(config: &Config, manifest: &str) -> Result<(Version, BTreeMap<String, String>)> {
+    let project = config.project()?;
+    let mut problems = Vec::new();
+
+    let explicit_version = toml::from_str::<toml::Value>(manifest)
+        .ok()
+        .and_then(|value| value.get("project")?.get("version").cloned())
+        .is_some();
+    let version = Version::parse(&project.version);
+    if !explicit_version {
+        problems.push("`version` is not set in [project]".to_string());
+    } else if let Err(e) = &version {
+        problems.push(format!(
+            "`version` \"{}\" is not a semver version: {}",
+            project.version, e
+        ));
+    }
+    if project
+        .license
+        .as_deref()
+        .is_none_or(|license| license.trim().is_empty())
+    {
+        problems.push("`license` is not set in [project]".to_string());
+    }
+
+    let mut dependencies = BTreeMap::new();
+    for (name, dep) in &config.dependencies {
+        if dep.is_path() {
+            problems.push(format!("dependency `{}` is a path dependency", name));
+        } else if dep.is_git() {
+            problems.push(format!(
+                "dependency `{}` is a git dependency; registry packages can only depend on registry versions",
+                name
+            ));
+        } else {
+            let requirement = dep.version().unwrap_or("*");
+            match VersionReq::parse(requirement) {
+                Ok(_) => {
+                    dependencies.insert(name.clone(), requirement.to_string());
+                }
+                Err(e) => problems.push(format!(
+                    "dependency `{}` has an invalid version requirement \"{}\": {}",
+                    name, requirement, e
+                )),
+            }
+        }
+    }
+
+    match version {
+        Ok(version) if problems.is_empty() => Ok((version, dependencies)),
+        _ => Err(miette::miette!(
+            code = "solscript::package::invalid",
+            help = "fix these in solscript.toml",
+            "Cannot package {}:\n{}",
+            project.name,
+            problems
+                .iter()
+                .map(|problem| format!("  - {}", problem))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )),
+    }
+}
+
+/// Fail if the project is in a git work tree and has uncommitted changes
+fn check_clean(root: &Path) -> Result<()> {
+    // Not in a git work tree: nothing to compare against
+    let Ok(status) = run_git(root, &["status", "--porcelain", "--", "."]) else {
+        return Ok(());
+    };
+    if status.is_empty() {
+        return Ok(());
+    }
+    Err(miette::miette!(
+        help = "commit the changes first, or pass --allow-dirty",
+        "The project has uncommitted changes:\n{}",
+        status
+            .lines()
+            .map(|line| format!("  {}", line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ))
+}
+
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<(String, Vec<u8>)>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let name = relative.to_string_lossy().replace('\\', "/");
+            files.push((name, std::fs::read(&path)?));
+        }
+    }
+    Ok(())
+}
+
+/// A ustar archive of `files` under the directory `prefix`, with fixed modes, owners
+/// and timestamps
+fn tar(prefix: &str, files: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, contents) in files {
+        let mut header = tar::Header::new_ustar();
+        header.set_mode(0o644);
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_mtime(0);
+        header.set_size(contents.len() as u64);
+        builder
+            .append_data(
+                &mut header,
+                format!("{}/{}", prefix, name),
+                contents.as_slice(),
+            )
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to package {}", name))?;
+    }
+    builder
+        .into_inner()
+        .into_diagnostic()
+        .wrap_err("Failed to write the package archive")
+}
+
+/// A gzip member with no file name or timestamp
+fn gzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = flate2::GzBuilder::new()
+        .mtime(0)
+        .write(Vec::new(), flate2::Compression::best());
+    encoder
+        .write_all(data)
+        .and_then(|()| encoder.finish())
+        .into_diagnostic()
+        .wrap_err("Failed to compress the package archive")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str, manifest: &str) -> (PathBuf, Config) {
+        let root =
+            std::env::temp_dir().join(format!("solscript-package-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src/lib")).unwrap();
+        std::fs::write(root.join("solscript.toml"), manifest).unwrap();
+        std::fs::write(root.join("src/main.sol"), "contract Main {}").unwrap();
+        std::fs::write(root.join("src/lib/math.sol"), "contract Math {}").unwrap();
+        let config = Config::parse(manifest).unwrap();
+        (root, config)
+    }
+
+    #[test]
+    fn test_package_is_deterministic() {
+        let (root, config) = project(
+            "deterministic",
+            "[project]\nname = \"math\"\nversion = \"1.2.0\"\nlicense = \"MIT\"\n\n[dependencies]\nbits = \"^0.3\"\n",
+        );
+        let packaged = package(&root, &config, true).unwrap();
+        assert!(packaged
+            .archive
+            .ends_with("output/package/math-1.2.0.tar.gz"));
+        assert_eq!(packaged.dependencies["bits"], "^0.3");
+        let first = std::fs::read(&packaged.archive).unwrap();
+        assert_eq!(&first[..2], &[0x1f, 0x8b]);
+
+        std::fs::remove_file(&packaged.archive).unwrap();
+        let again = package(&root, &config, true).unwrap();
+        assert_eq!(std::fs::read(&again.archive).unwrap(), first);
+        assert_eq!(again.checksum, packaged.checksum);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_package_validation() {
+        let (root, config) = project(
+            "invalid",
+            "[project]\nname = \"vault\"\n\n[dependencies]\nmath = { path = \"../math\" }\ntoken = { github = \"cryptuon/token\" }\n",
+        );
+        let message = package(&root, &config, true).unwrap_err().to_string();
+        assert!(message.contains("`version` is not set"));
+        assert!(message.contains("`license` is not set"));
+        assert!(message.contains("dependency `math` is a path dependency"));
+        assert!(message.contains("dependency `token` is a git dependency"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_publish_refuses_a_dirty_git_tree() {
+        let (root, config) = project(
+            "dirty",
+            "[project]\nname = \"math\"\nversion = \"1.0.0\"\nlicense = \"MIT\"\n",
+        );
+        std::fs::write(root.join(".gitignore"), "output/\n").unwrap();
+        let registry = root.join("registry");
+        std::fs::create_dir_all(&registry).unwrap();
+        let git = |args: &[&str]| run_git(&root, args).unwrap();
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&[
+            "-c",
+            "user.name=test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "--quiet",
+            "-m",
+            "math 1.0.0",
+        ]);
+
+        std::fs::write(root.join("src/main.sol"), "contract Main { }").unwrap();
+        let registry_path = registry.to_string_lossy();
+        let err = publish(&root, &config, &registry_path, false).unwrap_err();
+        assert!(err.to_string().contains("uncommitted changes"), "{}", err);
+        assert!(err.to_string().contains("src/main.sol"), "{}", err);
+        assert!(!registry.join("math").exists());
+
+        // Committed, it publishes
+        git(&["add", "."]);
+        git(&[
+            "-c",
+            "user.name=test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "--quiet",
+            "-m",
+            "Tweak",
+        ]);
+        publish(&root, &config, &registry_path, false).unwrap();
+        assert!(registry.join("math/versions.json").is_file());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_tar_and_gzip_framing() {
+        let long = format!("{}/main.sol", "nested".repeat(20));
+        let files = vec![
+            (long.clone(), Vec::new()),
+            ("src/main.sol".to_string(), b"contract Main {}".to_vec()),
+        ];
+        let archive = gzip(&tar("main-1.0.0", &files).unwrap()).unwrap();
+        // No timestamp in the gzip header
+        assert_eq!(&archive[..2], &[0x1f, 0x8b]);
+        assert_eq!(&archive[4..8], &[0, 0, 0, 0]);
+
+        let mut entries = Vec::new();
+        let mut reader = tar::Archive::new(flate2::read::GzDecoder::new(archive.as_slice()));
+        for entry in reader.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let header = entry.header();
+            assert_eq!(header.mode().unwrap(), 0o644);
+            assert_eq!(header.mtime().unwrap(), 0);
+            assert_eq!((header.uid().unwrap(), header.gid().unwrap()), (0, 0));
+            let path = entry.path().unwrap().to_string_lossy().into_owned();
+            let mut contents = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut contents).unwrap();
+            entries.push((path, contents));
+        }
+        assert_eq!(
+            entries,
+            [
+                (format!("main-1.0.0/{}", long), String::new()),
+                (
+                    "main-1.0.0/src/main.sol".to_string(),
+                    "contract Main {}".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,524 @@
+//! Package registry index and version resolution
+//!
+//! The index is a directory, or a git repository of one, with a `<name>/versions.json`
+//! file per package listing its published versions:
+//!
+//! ```json
+//! {
+//!   "versions": [
+//!     {
+//!       "version": "1.2.0",
+//!       "url": "https://example.com/math-1.2.0.tar.gz",
+//!       "checksum": "sha256:...",
+//!       "dependencies": { "bits": "^0.3" }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Registry dependencies are resolved together: [`resolve`] picks the highest version of
+//! every package, including dependencies of dependencies, that satisfies all the
+//! requirements on it.
+
+use miette::{IntoDiagnostic, Result, WrapErr};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where a git index is checked out, relative to the project root
+pub const INDEX_DIR: &str = ".solscript/index";
+
+/// Requirer of the project's own dependencies, in conflict reports
+pub const PROJECT: &str = "the project";
+
+/// A package registry index
+pub struct Index {
+    root: PathBuf,
+    versions: RefCell<HashMap<String, Vec<IndexEntry>>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VersionsFile {
+    versions: Vec<IndexEntry>,
+}
+
+/// One published version of a package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexEntry {
+    pub version: Version,
+    /// `.tar.gz` archive of the package: a URL, or a path relative to the index root
+    pub url: String,
+    /// `sha256:<hex>` of the package's files, checked after download
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// Registry packages this version depends on, with their version requirements
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, String>,
+    /// Yanked versions are never picked
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub yanked: bool,
+}
+
+/// A version requirement on a registry package
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+    /// The project, or the package declaring the dependency
+    pub by: String,
+    pub name: String,
+    pub version: String,
+}
+
+/// A package version picked by [`resolve`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolved {
+    pub name: String,
+    pub entry: IndexEntry,
+    /// Where the archive is downloaded from
+    pub url: String,
+}
+
+impl Index {
+    /// The index at `location`: a directory (relative to `project_root`) if one exists
+    /// there, otherwise a git URL, cloned or refreshed under [`INDEX_DIR`]
+    pub fn open(location: &str, project_root: &Path) -> Result<Self> {
+        let dir = project_root.join(location);
+        if dir.is_dir() {
+            return Ok(Self::from_dir(dir));
+        }
+
+        let checkout = project_root.join(INDEX_DIR);
+        let (args, cwd): (Vec<&str>, &Path) = if checkout.join(".git").is_dir() {
+            (vec!["pull", "--quiet", "--ff-only"], &checkout)
+        } else {
+            let _ = std::fs::remove_dir_all(&checkout);
+            (
+                vec!["clone", "--quiet", "--depth", "1", location, INDEX_DIR],
+                project_root,
+            )
+        };
+        let output = Command::new("git")
+            .args(&args)
+            .current_dir(cwd)
+            .output()
+            .into_diagnostic()
+            .wrap_err("Failed to run git")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(miette::miette!(
+                help = "set `index` in the [registry] section of solscript.toml",
+                "Failed to fetch the registry index {}: {}",
+                location,
+                stderr.trim()
+            ));
+        }
+        Ok(Self::from_dir(checkout))
+    }
+
+    pub fn from_dir(root: PathBuf) -> Self {
+        Self {
+            root,
+            versions: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Where to download the archive of `entry` from: its URL, or for a path, the
+    /// archive stored in the index itself
+    pub fn download_url(&self, entry: &IndexEntry) -> String {
+        if entry.url.contains("://") {
+            return entry.url.clone();
+        }
+        let path = self.root.join(&entry.url);
+        let path = path.canonicalize().unwrap_or(path);
+        format!("file://{}", path.display())
+    }
+
+    /// Every published version of `name`
+    pub fn versions(&self, name: &str) -> Result<Vec<IndexEntry>> {
+        if let Some(versions) = self.versions.borrow().get(name) {
+            return Ok(versions.clone());
+        }
+
+        let path = self.root.join(name).join("versions.json");
+        if !path.is_file() {
+            return Err(miette::miette!(
+                help = format!(
+                    "use a git dependency instead:\n\n  [dependencies]\n  {} = {{ github = \"owner/{}\", tag = \"v1.0.0\" }}",
+                    name, name
+                ),
+                "Package '{}' is not in the registry index at {}",
+                name,
+                self.root.display()
+            ));
+        }
+        let content = std::fs::read_to_string(&path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        let file: VersionsFile = serde_json::from_str(&content)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Invalid registry index entry {}", path.display()))?;
+
+        self.versions
+            .borrow_mut()
+            .insert(name.to_string(), file.versions.clone());
+        Ok(file.versions)
+    }
+
+    /// Add a version of `name` to the index, storing its `archive` at `entry.url`
+    pub fn publish(&self, name: &str, entry: &IndexEntry, archive: &Path) -> Result<()> {
+        let dir = self.root.join(name);
+        let mut versions = if dir.join("versions.json").is_file() {
+            self.versions(name)?
+        } else {
+            Vec::new()
+        };
+        if versions
+            .iter()
+            .any(|published| published.version == entry.version)
+        {
+            return Err(miette::miette!(
+                help = "bump `version` in the [project] section of solscript.toml",
+                "{} {} is already published to {}",
+                name,
+                entry.version,
+                self.root.display()
+            ));
+        }
+
+        let stored = self.root.join(&entry.url);
+        if let Some(parent) = stored.parent() {
+            std::fs::create_dir_all(parent)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::copy(archive, &stored)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to write {}", stored.display()))?;
+
+        versions.push(entry.clone());
+        versions.sort_by(|a, b| a.version.cmp(&b.version));
+        let content = serde_json::to_string_pretty(&VersionsFile {
+            versions: versions.clone(),
+        })
+        .into_diagnostic()?;
+        let path = dir.join("versions.json");
+        std::fs::write(&path, format!("{}\n", content))
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+        self.versions
+            .borrow_mut()
+            .insert(name.to_string(), versions);
+        Ok(())
+    }
+}
+
+/// Pick a version of every package `requirements` need, directly or through other
+/// packages: the highest version satisfying every requirement on it, or the `preferred`
+/// (previously locked) version when that still does.
+pub fn resolve(
+    index: &Index,
+    requirements: &[Requirement],
+    preferred: &BTreeMap<String, Version>,
+) -> Result<BTreeMap<String, Resolved>> {
+    let mut resolver = Resolver {
+        index,
+        preferred,
+        requirements: BTreeMap::new(),
+        selected: BTreeMap::new(),
+        conflict: None,
+    };
+    for requirement in requirements {
+        resolver.require(&requirement.by, &requirement.name, &requirement.version)?;
+    }
+
+    if resolver.step()? {
+        return Ok(resolver
+            .selected
+            .into_iter()
+            .map(|(name, entry)| {
+                let resolved = Resolved {
+                    name: name.clone(),
+                    url: index.download_url(&entry),
+                    entry,
+                };
+                (name, resolved)
+            })
+            .collect());
+    }
+
+    let (name, requirements) = resolver
+        .conflict
+        .expect("resolution fails only on a conflict");
+    let available = index
+        .versions(&name)?
+        .iter()
+        .filter(|entry| !entry.yanked)
+        .map(|entry| entry.version.to_string())
+        .collect::<Vec<_>>();
+    let required = requirements
+        .iter()
+        .map(|(requirer, req)| format!("\n  {} requires {} {}", requirer, name, req))
+        .collect::<String>();
+    Err(miette::miette!(
+        code = "solscript::registry::conflict",
+        help = format!(
+            "available versions of {}: {}",
+            name,
+            if available.is_empty() {
+                "none".to_string()
+            } else {
+                available.join(", ")
+            }
+        ),
+        "No version of package '{}' satisfies every requirement on it:{}",
+        name,
+        required
+    ))
+}
+
+/// Backtracking search over package versions, newest first
+struct Resolver<'a> {
+    index: &'a Index,
+    preferred: &'a BTreeMap<String, Version>,
+    /// Requirements on each package, with who requires it
+    requirements: BTreeMap<String, Vec<(String, VersionReq)>>,
+    selected: BTreeMap<String, IndexEntry>,
+    /// The last package no version could be picked for, and the requirements on it
+    conflict: Option<(String, Vec<(String, VersionReq)>)>,
+}
+
+impl Resolver<'_> {
+    fn require(&mut self, requirer: &str, name: &str, requirement: &str) -> Result<()> {
+        let req = VersionReq::parse(requirement).map_err(|e| {
+            miette::miette!(
+                "Invalid version requirement '{}' on package '{}' (required by {}): {}",
+                requirement,
+                name,
+                requirer,
+                e
+            )
+        })?;
+        self.requirements
+            .entry(name.to_string())
+            .or_default()
+            .push((requirer.to_string(), req));
+        Ok(())
+    }
+
+    fn unrequire(&mut self, name: &str) {
+        if let Some(requirements) = self.requirements.get_mut(name) {
+            requirements.pop();
+            if requirements.is_empty() {
+                self.requirements.remove(name);
+            }
+        }
+    }
+
+    /// Pick a version for the next required package and the ones after it. Returns false
+    /// if there is none that works with the versions picked so far.
+    fn step(&mut self) -> Result<bool> {
+        let Some(name) = self
+            .requirements
+            .keys()
+            .find(|name| !self.selected.contains_key(*name))
+            .cloned()
+        else {
+            return Ok(true);
+        };
+
+        let requirements = &self.requirements[&name];
+        let mut candidates = self
+            .index
+            .versions(&name)?
+            .into_iter()
+            .filter(|entry| !entry.yanked)
+            .filter(|entry| {
+                requirements
+                    .iter()
+                    .all(|(_, req)| req.matches(&entry.version))
+            })
+            .collect::<Vec<_>>();
+        let preferred = self.preferred.get(&name);
+        candidates.sort_by(|a, b| {
+            (Some(&b.version) == preferred, &b.version)
+                .cmp(&(Some(&a.version) == preferred, &a.version))
+        });
+        if candidates.is_empty() {
+            self.conflict = Some((name, requirements.clone()));
+            return Ok(false);
+        }
+
+        for entry in candidates {
+            let requirer = format!("{} {}", name, entry.version);
+            let mut added = Vec::new();
+            let mut consistent = true;
+            for (dependency, requirement) in &entry.dependencies {
+                self.require(&requirer, dependency, requirement)?;
+                added.push(dependency.clone());
+                // A version already picked for the dependency must satisfy the new requirement
+                if let Some(picked) = self.selected.get(dependency) {
+                    let (_, req) = self.requirements[dependency].last().unwrap();
+                    if !req.matches(&picked.version) {
+                        self.conflict =
+                            Some((dependency.clone(), self.requirements[dependency].clone()));
+                        consistent = false;
+                        break;
+                    }
+                }
+            }
+
+            if consistent {
+                self.selected.insert(name.clone(), entry);
+                if self.step()? {
+                    return Ok(true);
+                }
+                self.selected.remove(&name);
+            }
+            for dependency in added.iter().rev() {
+                self.unrequire(dependency);
+            }
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A published version and its dependencies
+    type Published<'a> = (&'a str, &'a [(&'a str, &'a str)]);
+
+    /// Write a directory index: package name to its published versions
+    fn index(name: &str, packages: &[(&str, &[Published])]) -> Index {
+        let root =
+            std::env::temp_dir().join(format!("solscript-index-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (package, versions) in packages {
+            let versions = versions
+                .iter()
+                .map(|(version, dependencies)| IndexEntry {
+                    version: Version::parse(version).unwrap(),
+                    url: format!("file:///packages/{}-{}.tar.gz", package, version),
+                    checksum: None,
+                    dependencies: dependencies
+                        .iter()
+                        .map(|(name, req)| (name.to_string(), req.to_string()))
+                        .collect(),
+                    yanked: false,
+                })
+                .collect::<Vec<_>>();
+            std::fs::create_dir_all(root.join(package)).unwrap();
+            std::fs::write(
+                root.join(package).join("versions.json"),
+                serde_json::json!({ "versions": versions }).to_string(),
+            )
+            .unwrap();
+        }
+        Index::from_dir(root)
+    }
+
+    fn requirements(reqs: &[(&str, &str)]) -> Vec<Requirement> {
+        reqs.iter()
+            .map(|(name, version)| Requirement {
+                by: PROJECT.to_string(),
+                name: name.to_string(),
+                version: version.to_string(),
+            })
+            .collect()
+    }
+
+    fn versions(resolved: &BTreeMap<String, Resolved>) -> Vec<String> {
+        resolved
+            .values()
+            .map(|r| format!("{} {}", r.name, r.entry.version))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_picks_highest_compatible_versions() {
+        let index = index(
+            "highest",
+            &[
+                (
+                    "math",
+                    &[
+                        ("1.1.0", &[]),
+                        ("1.2.0", &[("bits", "^0.3")]),
+                        ("1.4.1", &[("bits", "^0.3.2")]),
+                        ("2.0.0", &[]),
+                    ],
+                ),
+                ("bits", &[("0.3.0", &[]), ("0.3.5", &[]), ("0.4.0", &[])]),
+            ],
+        );
+        let resolved =
+            resolve(&index, &requirements(&[("math", "^1.2")]), &BTreeMap::new()).unwrap();
+        assert_eq!(versions(&resolved), ["bits 0.3.5", "math 1.4.1"]);
+
+        // A locked version is kept while it still satisfies the requirements
+        let preferred = BTreeMap::from([("math".to_string(), Version::new(1, 2, 0))]);
+        let resolved = resolve(&index, &requirements(&[("math", "^1.2")]), &preferred).unwrap();
+        assert_eq!(versions(&resolved), ["bits 0.3.5", "math 1.2.0"]);
+    }
+
+    #[test]
+    fn test_resolve_backtracks_to_a_compatible_version() {
+        // token 1.1 needs math 2, which the project rules out; token 1.0 works
+        let index = index(
+            "backtrack",
+            &[
+                (
+                    "token",
+                    &[
+                        ("1.0.0", &[("math", "^1.0")]),
+                        ("1.1.0", &[("math", "^2.0")]),
+                    ],
+                ),
+                ("math", &[("1.3.0", &[]), ("2.0.0", &[])]),
+            ],
+        );
+        let resolved = resolve(
+            &index,
+            &requirements(&[("math", "~1.3"), ("token", "^1.0")]),
+            &BTreeMap::new(),
+        )
+        .unwrap();
+        assert_eq!(versions(&resolved), ["math 1.3.0", "token 1.0.0"]);
+    }
+
+    #[test]
+    fn test_resolve_reports_conflicts() {
+        let index = index(
+            "conflict",
+            &[
+                ("token", &[("1.0.0", &[("math", "^2.0")])]),
+                ("math", &[("1.3.0", &[]), ("2.0.0", &[])]),
+            ],
+        );
+        let err = resolve(
+            &index,
+            &requirements(&[("math", "^1.0"), ("token", "^1.0")]),
+            &BTreeMap::new(),
+        )
+        .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("No version of package 'math' satisfies"));
+        assert!(message.contains("the project requires math ^1.0"));
+        assert!(message.contains("token 1.0.0 requires math ^2.0"));
+
+        let err = resolve(
+            &index,
+            &requirements(&[("oracle", "^1.0")]),
+            &BTreeMap::new(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Package 'oracle' is not in the registry index"));
+    }
+}
@@ -0,0 +1,290 @@
+//! Workspaces: several projects built and installed together
+//!
+//! A `solscript.toml` with a `[workspace]` section lists member projects by directory:
+//!
+//! ```toml
+//! [workspace]
+//! members = ["programs/vault", "programs/router"]
+//! ```
+//!
+//! The members share the workspace root's `solscript.lock`, `.solscript/packages`,
+//! `vendor/` and `[registry]`/`[net]` settings. A path dependency of one member on
+//! another makes the other program importable by name for cross-program calls, typed
+//! from its Anchor IDL, and `solscript build --workspace` builds members in dependency
+//! order so the IDL exists first. The root can be a project itself, and is then a
+//! member too.
+
+use crate::config::Config;
+use crate::package::normalize;
+use miette::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A workspace, with the config of its root and of every member
+#[derive(Debug)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub config: Config,
+    /// The root first if it is a project, then the members in the order listed
+    pub members: Vec<Member>,
+}
+
+/// A project of a workspace
+#[derive(Debug)]
+pub struct Member {
+    pub root: PathBuf,
+    pub config: Config,
+}
+
+impl Member {
+    pub fn name(&self) -> &str {
+        self.config
+            .project
+            .as_ref()
+            .map_or("", |project| &project.name)
+    }
+
+    /// Where building the member writes its Anchor IDL
+    pub fn idl_path(&self) -> PathBuf {
+        self.config
+            .output_dir(&self.root)
+            .join("target/idl/program.json")
+    }
+}
+
+impl Workspace {
+    /// Load the workspace whose root manifest, with `config`, is in `root`
+    pub fn load(root: &Path, config: Config) -> Result<Self> {
+        let Some(workspace) = &config.workspace else {
+            return Err(miette::miette!(
+                "{} has no [workspace] section",
+                root.join("solscript.toml").display()
+            ));
+        };
+
+        let mut members = Vec::new();
+        if config.project.is_some() {
+            members.push(Member {
+                root: root.to_path_buf(),
+                config: config.clone(),
+            });
+        }
+        for path in &workspace.members {
+            let member_root = normalize(&root.join(path));
+            let manifest = member_root.join("solscript.toml");
+            if !manifest.is_file() {
+                return Err(miette::miette!(
+                    help = "fix `members` in the [workspace] section of solscript.toml",
+                    "Workspace member '{}' has no solscript.toml",
+                    path
+                ));
+            }
+            let member_config = Config::load(&manifest)?;
+            if member_config.project.is_none() {
+                return Err(miette::miette!(
+                    "Workspace member '{}' has no [project] section",
+                    path
+                ));
+            }
+            members.push(Member {
+                root: member_root,
+                config: member_config,
+            });
+        }
+
+        let mut names = BTreeMap::new();
+        for member in &members {
+            if let Some(other) = names.insert(member.name(), &member.root) {
+                return Err(miette::miette!(
+                    "Workspace members {} and {} are both named '{}'",
+                    other.display(),
+                    member.root.display(),
+                    member.name()
+                ));
+            }
+        }
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            config,
+            members,
+        })
+    }
+
+    /// The workspace the project in `root` belongs to, or whose root `root` is: the
+    /// nearest directory, `root` itself included, whose manifest has a `[workspace]`
+    /// listing it. Only the manifest in `root` must load; an unreadable one further up
+    /// is warned about and passed over.
+    pub fn find(root: &Path) -> Result<Option<Self>> {
+        let mut dir = Some(root);
+        while let Some(current) = dir {
+            let manifest = current.join("solscript.toml");
+            if manifest.is_file() {
+                let config = match Config::load(&manifest) {
+                    Ok(config) => config,
+                    Err(e) if !same_dir(current, root) => {
+                        eprintln!(
+                            "warning: ignoring {} while looking for a workspace: {}",
+                            manifest.display(),
+                            e
+                        );
+                        dir = current.parent();
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                };
+                if config.workspace.is_some() {
+                    let workspace = Self::load(current, config)?;
+                    if same_dir(current, root) || workspace.member(root).is_some() {
+                        return Ok(Some(workspace));
+                    }
+                }
+            }
+            dir = current.parent();
+        }
+        Ok(None)
+    }
+
+    /// The member whose project is in `root`
+    pub fn member(&self, root: &Path) -> Option<&Member> {
+        self.members
+            .iter()
+            .find(|member| same_dir(&member.root, root))
+    }
+
+    /// The members `member` has path dependencies on, by dependency name
+    pub fn member_dependencies<'a>(&'a self, member: &'a Member) -> Vec<(&'a str, &'a Member)> {
+        member
+            .config
+            .dependencies
+            .iter()
+            .filter_map(|(name, dep)| {
+                let dir = member.root.join(dep.local_path()?);
+                Some((name.as_str(), self.member(&dir)?))
+            })
+            .collect()
+    }
+
+    /// Every member after the members it depends on
+    pub fn build_order(&self) -> Result<Vec<&Member>> {
+        let mut order = Vec::new();
+        let mut visiting = Vec::new();
+        for member in &self.members {
+            self.visit(member, &mut visiting, &mut order)?;
+        }
+        Ok(order)
+    }
+
+    fn visit<'a>(
+        &'a self,
+        member: &'a Member,
+        visiting: &mut Vec<&'a str>,
+        order: &mut Vec<&'a Member>,
+    ) -> Result<()> {
+        if order.iter().any(|done| done.name() == member.name()) {
+            return Ok(());
+        }
+        if let Some(start) = visiting.iter().position(|name| *name == member.name()) {
+            let mut cycle = visiting[start..].to_vec();
+            cycle.push(member.name());
+            return Err(miette::miette!(
+                "Workspace members depend on each other in a cycle: {}",
+                cycle.join(" -> ")
+            ));
+        }
+        visiting.push(member.name());
+        for (_, dependency) in self.member_dependencies(member) {
+            self.visit(dependency, visiting, order)?;
+        }
+        visiting.pop();
+        order.push(member);
+        Ok(())
+    }
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    let (a, b) = (normalize(a), normalize(b));
+    a == b
+        || matches!(
+            (a.canonicalize(), b.canonicalize()),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, text: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn test_workspace_members_and_build_order() {
+        let root = std::env::temp_dir().join(format!("solscript-workspace-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        write(
+            &root.join("solscript.toml"),
+            "[workspace]\nmembers = [\"programs/router\", \"programs/vault\"]\n",
+        );
+        write(
+            &root.join("programs/router/solscript.toml"),
+            "[project]\nname = \"router\"\n\n[dependencies]\nvault = { path = \"../vault\" }\n",
+        );
+        write(
+            &root.join("programs/vault/solscript.toml"),
+            "[project]\nname = \"vault\"\n",
+        );
+
+        let router = root.join("programs/router");
+        let workspace = Workspace::find(&router).unwrap().expect("a member");
+        assert_eq!(workspace.root, root);
+        let order: Vec<_> = workspace
+            .build_order()
+            .unwrap()
+            .iter()
+            .map(|member| member.name())
+            .collect();
+        assert_eq!(order, ["vault", "router"]);
+        let member = workspace.member(&router).unwrap();
+        let dependencies = workspace.member_dependencies(member);
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(dependencies[0].0, "vault");
+        assert_eq!(
+            dependencies[0].1.idl_path(),
+            root.join("programs/vault/output/target/idl/program.json")
+        );
+
+        // Not listed as a member
+        write(
+            &root.join("tools/solscript.toml"),
+            "[project]\nname = \"tools\"\n",
+        );
+        assert!(Workspace::find(&root.join("tools")).unwrap().is_none());
+
+        // A broken manifest above the project is not a workspace, but its own must load
+        let outside = root.join("outside");
+        write(&outside.join("solscript.toml"), "[workspace\n");
+        write(
+            &outside.join("app/solscript.toml"),
+            "[project]\nname = \"app\"\n",
+        );
+        assert!(Workspace::find(&outside.join("app")).unwrap().is_none());
+        assert!(Workspace::find(&outside).is_err());
+
+        write(
+            &root.join("programs/vault/solscript.toml"),
+            "[project]\nname = \"vault\"\n\n[dependencies]\nrouter = { path = \"../router\" }\n",
+        );
+        let workspace = Workspace::find(&router).unwrap().unwrap();
+        let err = workspace.build_order().unwrap_err();
+        assert!(
+            err.to_string().contains("router -> vault -> router"),
+            "{}",
+            err
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
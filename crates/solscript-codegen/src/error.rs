@@ -1,22 +1,156 @@
 //! Code generation errors
 
+use miette::{Diagnostic, SourceSpan};
+use solscript_ast::Span;
 use thiserror::Error;
 
 /// Errors that can occur during code generation
-#[derive(Error, Debug)]
+///
+/// Errors raised while lowering carry the span of the offending source; attach the
+/// source text with [`CodegenError::with_source_code`] to render it.
+#[derive(Error, Debug, Diagnostic)]
 pub enum CodegenError {
-    #[error("Unsupported feature: {0}")]
-    UnsupportedFeature(String),
+    #[error("Unsupported feature: {message}")]
+    #[diagnostic(
+        code(solscript::codegen::unsupported_feature),
+        help("this construct cannot be compiled for Solana yet; rewrite it without the feature")
+    )]
+    UnsupportedFeature {
+        message: String,
+        #[label("not supported")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        src: Option<String>,
+    },
 
-    #[error("Invalid state: {0}")]
-    InvalidState(String),
+    #[error("Invalid state: {message}")]
+    #[diagnostic(code(solscript::codegen::invalid_state))]
+    InvalidState {
+        message: String,
+        #[label("here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        src: Option<String>,
+    },
 
-    #[error("Type conversion error: {0}")]
-    TypeConversion(String),
+    #[error("Type conversion error: {message}")]
+    #[diagnostic(code(solscript::codegen::type_conversion))]
+    TypeConversion {
+        message: String,
+        #[label("cannot convert")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        src: Option<String>,
+    },
 
-    #[error("Missing required element: {0}")]
-    MissingElement(String),
+    #[error("Missing required element: {message}")]
+    #[diagnostic(code(solscript::codegen::missing_element))]
+    MissingElement {
+        message: String,
+        #[label("required here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        src: Option<String>,
+    },
 
-    #[error("Code generation failed: {0}")]
-    GenerationFailed(String),
+    #[error("Code generation failed: {message}")]
+    #[diagnostic(code(solscript::codegen::generation_failed))]
+    GenerationFailed {
+        message: String,
+        #[label("while generating this")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        src: Option<String>,
+    },
+}
+
+impl CodegenError {
+    pub fn unsupported_feature(message: impl Into<String>, span: Span) -> Self {
+        Self::UnsupportedFeature {
+            message: message.into(),
+            span: source_span(span),
+            src: None,
+        }
+    }
+
+    pub fn invalid_state(message: impl Into<String>, span: Span) -> Self {
+        Self::InvalidState {
+            message: message.into(),
+            span: source_span(span),
+            src: None,
+        }
+    }
+
+    pub fn type_conversion(message: impl Into<String>, span: Span) -> Self {
+        Self::TypeConversion {
+            message: message.into(),
+            span: source_span(span),
+            src: None,
+        }
+    }
+
+    pub fn missing_element(message: impl Into<String>, span: Span) -> Self {
+        Self::MissingElement {
+            message: message.into(),
+            span: source_span(span),
+            src: None,
+        }
+    }
+
+    pub fn generation_failed(message: impl Into<String>) -> Self {
+        Self::GenerationFailed {
+            message: message.into(),
+            span: None,
+            src: None,
+        }
+    }
+
+    /// The error message, without the kind prefix
+    pub fn message(&self) -> &str {
+        match self {
+            Self::UnsupportedFeature { message, .. }
+            | Self::InvalidState { message, .. }
+            | Self::TypeConversion { message, .. }
+            | Self::MissingElement { message, .. }
+            | Self::GenerationFailed { message, .. } => message,
+        }
+    }
+
+    /// The source span the error points at, if it has one
+    pub fn span(&self) -> Option<Span> {
+        let (Self::UnsupportedFeature { span, .. }
+        | Self::InvalidState { span, .. }
+        | Self::TypeConversion { span, .. }
+        | Self::MissingElement { span, .. }
+        | Self::GenerationFailed { span, .. }) = self;
+        span.map(|s| Span::new(s.offset(), s.offset() + s.len()))
+    }
+
+    /// Attach the source text the span points into, so the error renders with a snippet
+    pub fn with_source_code(mut self, source: &str) -> Self {
+        let (Self::UnsupportedFeature { src, .. }
+        | Self::InvalidState { src, .. }
+        | Self::TypeConversion { src, .. }
+        | Self::MissingElement { src, .. }
+        | Self::GenerationFailed { src, .. }) = &mut self;
+        *src = Some(source.to_string());
+        self
+    }
+
+    /// Point the error at `span` unless it already has a location
+    pub(crate) fn or_at(mut self, at: Span) -> Self {
+        let (Self::UnsupportedFeature { span, .. }
+        | Self::InvalidState { span, .. }
+        | Self::TypeConversion { span, .. }
+        | Self::MissingElement { span, .. }
+        | Self::GenerationFailed { span, .. }) = &mut self;
+        if span.is_none() {
+            *span = source_span(at);
+        }
+        self
+    }
+}
+
+fn source_span(span: Span) -> Option<SourceSpan> {
+    (!span.is_dummy()).then(|| SourceSpan::new(span.start.into(), span.len()))
 }
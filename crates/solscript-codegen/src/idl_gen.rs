@@ -1,14 +1,35 @@
 //! IDL Generator
 //!
-//! Generates Anchor IDL (Interface Definition Language) JSON for the program.
+//! Generates Anchor IDL (Interface Definition Language) JSON for the program, in
+//! the Anchor 0.30+ format by default or in the legacy pre-0.30 format.
 
 use crate::ir::*;
 use crate::CodegenError;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Address written to the IDL when no program keypair is known
+pub const PLACEHOLDER_PROGRAM_ID: &str = "11111111111111111111111111111111";
+
+const SYSTEM_PROGRAM_ID: &str = "11111111111111111111111111111111";
+const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+/// Shape of the generated IDL
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdlFormat {
+    /// Anchor 0.30+ IDL (`metadata.spec` 0.1.0): discriminators, PDA seeds, docs
+    #[default]
+    Spec,
+    /// Pre-0.30 IDL (`isMut`/`isSigner`, `publicKey`), for older tooling
+    Legacy,
+}
 
 /// IDL generator
 pub struct IdlGenerator {
     program_name: String,
+    format: IdlFormat,
+    address: String,
 }
 
 impl Default for IdlGenerator {
@@ -21,14 +42,36 @@ impl IdlGenerator {
     pub fn new() -> Self {
         Self {
             program_name: String::new(),
+            format: IdlFormat::default(),
+            address: PLACEHOLDER_PROGRAM_ID.to_string(),
         }
     }
 
+    /// Emit the given IDL format
+    pub fn with_format(mut self, format: IdlFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Use `address` (base58) as the program address
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.address = address.into();
+        self
+    }
+
     /// Generate the IDL JSON
     pub fn generate(&mut self, ir: &SolanaProgram) -> Result<String, CodegenError> {
         self.program_name = to_snake_case(&ir.name);
 
-        let idl = Idl {
+        let json = match self.format {
+            IdlFormat::Spec => serde_json::to_string_pretty(&self.generate_spec(ir)),
+            IdlFormat::Legacy => serde_json::to_string_pretty(&self.generate_legacy(ir)?),
+        };
+        json.map_err(|e| CodegenError::generation_failed(format!("Failed to serialize IDL: {}", e)))
+    }
+
+    fn generate_legacy(&self, ir: &SolanaProgram) -> Result<Idl, CodegenError> {
+        Ok(Idl {
             version: "0.1.0".to_string(),
             name: self.program_name.clone(),
             instructions: self.generate_instructions(ir)?,
@@ -37,12 +80,9 @@ impl IdlGenerator {
             events: self.generate_events(ir)?,
             errors: self.generate_errors(ir)?,
             metadata: IdlMetadata {
-                address: "11111111111111111111111111111111".to_string(),
+                address: self.address.clone(),
             },
-        };
-
-        serde_json::to_string_pretty(&idl)
-            .map_err(|e| CodegenError::GenerationFailed(format!("Failed to serialize IDL: {}", e)))
+        })
     }
 
     fn generate_instructions(
@@ -55,6 +95,7 @@ impl IdlGenerator {
             let args: Vec<IdlField> = instr
                 .params
                 .iter()
+                .filter(|p| !matches!(p.ty, SolanaType::Account(_) | SolanaType::RemainingAccounts))
                 .map(|p| IdlField {
                     name: to_camel_case_lower(&p.name),
                     ty: self.solana_type_to_idl_type(&p.ty),
@@ -75,8 +116,19 @@ impl IdlGenerator {
                 },
             ];
 
-            // Add system program for initialize
-            if instr.name.to_lowercase() == "initialize" {
+            // Add account params
+            for param in &instr.params {
+                if matches!(param.ty, SolanaType::Account(_)) {
+                    accounts.push(IdlAccount {
+                        name: to_camel_case_lower(&param.name),
+                        is_mut: param.is_mut,
+                        is_signer: false,
+                    });
+                }
+            }
+
+            // Add system program for initialize and for reallocating #[growable] state
+            if instr.name.to_lowercase() == "initialize" || !instr.state_growth.is_empty() {
                 accounts.push(IdlAccount {
                     name: "systemProgram".to_string(),
                     is_mut: false,
@@ -97,10 +149,34 @@ impl IdlGenerator {
                 name: to_camel_case_lower(&instr.name),
                 accounts,
                 args,
-                returns: instr
-                    .returns
-                    .as_ref()
-                    .map(|t| self.solana_type_to_idl_type(t)),
+                returns: match instr.return_struct() {
+                    Some((name, _)) => Some(IdlType::Defined(name)),
+                    None => instr
+                        .returns
+                        .as_ref()
+                        .map(|t| self.solana_type_to_idl_type(t)),
+                },
+            });
+        }
+
+        // Generated `migrate`, gated by the program's upgrade authority
+        if ir.state.migratable().is_some() {
+            let account = |name: &str, is_mut, is_signer| IdlAccount {
+                name: name.to_string(),
+                is_mut,
+                is_signer,
+            };
+            instructions.push(IdlInstruction {
+                name: "migrate".to_string(),
+                accounts: vec![
+                    account("state", true, false),
+                    account("signer", true, true),
+                    account("program", false, false),
+                    account("programData", false, false),
+                    account("systemProgram", false, false),
+                ],
+                args: Vec::new(),
+                returns: None,
             });
         }
 
@@ -166,6 +242,24 @@ impl IdlGenerator {
             });
         }
 
+        // Return structs of public functions with multiple return values
+        for instr in ir.instructions.iter().filter(|i| i.is_public) {
+            if let Some((name, field_types)) = instr.return_struct() {
+                let fields = field_types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| IdlField {
+                        name: format!("field{}", i),
+                        ty: self.solana_type_to_idl_type(ty),
+                    })
+                    .collect();
+                types.push(IdlTypeDef {
+                    name,
+                    ty: IdlTypeDefType::Struct { fields },
+                });
+            }
+        }
+
         // Enums
         for e in &ir.enums {
             let variants: Vec<IdlEnumVariant> = e
@@ -225,9 +319,401 @@ impl IdlGenerator {
             });
         }
 
+        // Errors of the generated `migrate`, after RequireFailed, InvalidRecipient and the custom errors
+        if ir.state.migratable().is_some() {
+            let first = 6002 + ir.errors.len() as u32;
+            for (i, (name, msg)) in [
+                (
+                    "NotUpgradeAuthority",
+                    "Signer is not the program's upgrade authority",
+                ),
+                (
+                    "StateAlreadyMigrated",
+                    "State account already has the current layout",
+                ),
+                (
+                    "UnknownStateVersion",
+                    "State account has an unknown layout version",
+                ),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                errors.push(IdlError {
+                    code: first + i as u32,
+                    name: name.to_string(),
+                    msg: msg.to_string(),
+                });
+            }
+        }
+
         Ok(errors)
     }
 
+    /// Anchor 0.30+ IDL. Names follow the generated Rust (snake_case), accounts are
+    /// listed in the order of the `#[derive(Accounts)]` structs, and account and
+    /// event layouts live in `types`.
+    fn generate_spec(&self, ir: &SolanaProgram) -> SpecIdl {
+        let state_name = format!("{}State", to_camel_case(&ir.name));
+
+        let mut instructions: Vec<SpecInstruction> = ir
+            .instructions
+            .iter()
+            .filter(|i| i.is_public)
+            .map(|instr| SpecInstruction {
+                name: to_snake_case(&instr.name),
+                docs: instr.docs.clone(),
+                discriminator: discriminator(&format!("global:{}", to_snake_case(&instr.name))),
+                accounts: self.spec_instruction_accounts(instr, &state_name),
+                args: instr
+                    .params
+                    .iter()
+                    .filter(|p| !p.ty.is_account())
+                    .map(|p| SpecField {
+                        name: to_snake_case(&p.name),
+                        docs: fixed_point_docs(&[], &p.ty),
+                        ty: self.solana_type_to_spec_type(&p.ty),
+                    })
+                    .collect(),
+                returns: match instr.return_struct() {
+                    Some((name, _)) => Some(SpecType::Defined {
+                        defined: SpecDefined { name },
+                    }),
+                    None => instr
+                        .returns
+                        .as_ref()
+                        .map(|t| self.solana_type_to_spec_type(t)),
+                },
+            })
+            .collect();
+
+        if ir.state.migratable().is_some() {
+            instructions.push(SpecInstruction {
+                name: "migrate".to_string(),
+                docs: vec![
+                    "Migrate the state account to the current layout (upgrade authority only)"
+                        .to_string(),
+                ],
+                discriminator: discriminator("global:migrate"),
+                accounts: vec![
+                    SpecAccount::new("state").writable(),
+                    SpecAccount::new("signer").writable().signer(),
+                    SpecAccount::new("program").address(&self.address),
+                    SpecAccount::new("program_data"),
+                    SpecAccount::new("system_program").address(SYSTEM_PROGRAM_ID),
+                ],
+                args: Vec::new(),
+                returns: None,
+            });
+        }
+
+        let entry_names: Vec<String> = ir
+            .mappings
+            .iter()
+            .map(|m| format!("{}Entry", to_camel_case(&m.name)))
+            .collect();
+        let accounts = std::iter::once(&state_name)
+            .chain(&entry_names)
+            .map(|name| SpecNamed {
+                name: name.clone(),
+                discriminator: discriminator(&format!("account:{}", name)),
+            })
+            .collect();
+        let events = ir
+            .events
+            .iter()
+            .map(|e| {
+                let name = to_camel_case(&e.name);
+                SpecNamed {
+                    discriminator: discriminator(&format!("event:{}", name)),
+                    name,
+                }
+            })
+            .collect();
+
+        SpecIdl {
+            address: self.address.clone(),
+            metadata: SpecMetadata {
+                name: self.program_name.clone(),
+                version: "0.1.0".to_string(),
+                spec: "0.1.0".to_string(),
+                description: "Generated by SolScript compiler".to_string(),
+            },
+            docs: ir.docs.clone(),
+            instructions,
+            accounts,
+            events,
+            errors: self.spec_errors(ir),
+            types: self.spec_types(ir, &state_name, &entry_names),
+        }
+    }
+
+    /// Accounts of an instruction, mirroring the generated Anchor context struct
+    fn spec_instruction_accounts(&self, instr: &Instruction, state_name: &str) -> Vec<SpecAccount> {
+        let mut accounts = Vec::new();
+
+        let state = SpecAccount::new("state");
+        accounts.push(if instr.name == "initialize" {
+            // A fresh keypair account created by `init`
+            state.writable().signer()
+        } else if instr.is_view {
+            state
+        } else {
+            state.writable()
+        });
+        accounts.push(SpecAccount::new("signer").writable().signer());
+
+        for param in &instr.params {
+            if matches!(param.ty, SolanaType::Signer) {
+                let account = SpecAccount::new(&to_snake_case(&param.name)).signer();
+                accounts.push(if param.is_mut {
+                    account.writable()
+                } else {
+                    account
+                });
+            }
+        }
+        for param in &instr.params {
+            if matches!(param.ty, SolanaType::Account(_)) {
+                let account = SpecAccount::new(&to_snake_case(&param.name));
+                accounts.push(if param.is_mut {
+                    account.writable()
+                } else {
+                    account
+                });
+            }
+        }
+
+        for access in &instr.mapping_accesses {
+            let mut account = SpecAccount::new(&to_snake_case(&access.account_name));
+            if access.is_write || access.should_close {
+                account = account.writable();
+            }
+            let mut seeds = vec![SpecSeed::Const {
+                value: to_snake_case(&access.mapping_name).into_bytes(),
+            }];
+            let keys: Option<Vec<SpecSeed>> = access
+                .key_exprs
+                .iter()
+                .map(|key| spec_seed(key, instr, state_name))
+                .collect();
+            // Seeds that are not derivable from args or accounts are left to the client
+            if let Some(keys) = keys {
+                seeds.extend(keys);
+                account.pda = Some(SpecPda { seeds });
+            }
+            accounts.push(account);
+        }
+
+        if instr.uses_sol_transfer {
+            accounts.push(SpecAccount::new("recipient").writable());
+        }
+        let needs_system_program = instr.name == "initialize"
+            || !instr.state_growth.is_empty()
+            || instr.mapping_accesses.iter().any(|a| a.is_write)
+            || instr.is_payable
+            || instr.uses_sol_transfer;
+        if needs_system_program {
+            accounts.push(SpecAccount::new("system_program").address(SYSTEM_PROGRAM_ID));
+        }
+        if instr.uses_token_program {
+            accounts.push(SpecAccount::new("token_program").address(TOKEN_PROGRAM_ID));
+        }
+
+        accounts
+    }
+
+    /// Errors with the codes of the generated `CustomError` enum
+    fn spec_errors(&self, ir: &SolanaProgram) -> Vec<IdlError> {
+        let mut errors = vec![
+            (
+                "RequireFailed".to_string(),
+                "Requirement failed".to_string(),
+            ),
+            (
+                "InvalidRecipient".to_string(),
+                "Invalid recipient account".to_string(),
+            ),
+        ];
+        for error in &ir.errors {
+            errors.push((to_camel_case(&error.name), error.name.clone()));
+        }
+        if ir.state.migratable().is_some() {
+            for (name, msg) in [
+                (
+                    "NotUpgradeAuthority",
+                    "Signer is not the program's upgrade authority",
+                ),
+                (
+                    "StateAlreadyMigrated",
+                    "State account already has the current layout",
+                ),
+                (
+                    "UnknownStateVersion",
+                    "State account has an unknown layout version",
+                ),
+            ] {
+                errors.push((name.to_string(), msg.to_string()));
+            }
+        }
+        errors
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, msg))| IdlError {
+                code: 6000 + i as u32,
+                name,
+                msg,
+            })
+            .collect()
+    }
+
+    fn spec_types(
+        &self,
+        ir: &SolanaProgram,
+        state_name: &str,
+        entry_names: &[String],
+    ) -> Vec<SpecTypeDef> {
+        let field = |name: &str, docs: &[String], ty: &SolanaType| SpecField {
+            name: to_snake_case(name),
+            docs: fixed_point_docs(docs, ty),
+            ty: self.solana_type_to_spec_type(ty),
+        };
+        let mut types = Vec::new();
+
+        let zero_copy = ir.state.zero_copy;
+        types.push(SpecTypeDef {
+            name: state_name.to_string(),
+            docs: ir.docs.clone(),
+            serialization: zero_copy.then(|| "bytemuck".to_string()),
+            repr: zero_copy.then(|| SpecRepr {
+                kind: "c".to_string(),
+            }),
+            ty: SpecTypeDefTy::Struct {
+                fields: ir
+                    .state
+                    .fields
+                    .iter()
+                    .map(|f| field(&f.name, &f.docs, &f.ty))
+                    .collect(),
+            },
+        });
+
+        for (mapping, name) in ir.mappings.iter().zip(entry_names) {
+            let mut value_ty = &mapping.value_ty;
+            while let SolanaType::Mapping(_, inner) = value_ty {
+                value_ty = inner;
+            }
+            types.push(SpecTypeDef::plain(
+                name,
+                SpecTypeDefTy::Struct {
+                    fields: vec![
+                        field("key", &[], &mapping.key_ty),
+                        field("value", &[], value_ty),
+                    ],
+                },
+            ));
+        }
+
+        for s in &ir.structs {
+            types.push(SpecTypeDef::plain(
+                &to_camel_case(&s.name),
+                SpecTypeDefTy::Struct {
+                    fields: s
+                        .fields
+                        .iter()
+                        .map(|f| field(&f.name, &[], &f.ty))
+                        .collect(),
+                },
+            ));
+        }
+        for instr in ir.instructions.iter().filter(|i| i.is_public) {
+            if let Some((name, field_types)) = instr.return_struct() {
+                types.push(SpecTypeDef::plain(
+                    &name,
+                    SpecTypeDefTy::Struct {
+                        fields: field_types
+                            .iter()
+                            .enumerate()
+                            .map(|(i, ty)| field(&format!("field{}", i), &[], ty))
+                            .collect(),
+                    },
+                ));
+            }
+        }
+        for e in &ir.enums {
+            types.push(SpecTypeDef::plain(
+                &to_camel_case(&e.name),
+                SpecTypeDefTy::Enum {
+                    variants: e
+                        .variants
+                        .iter()
+                        .map(|v| IdlEnumVariant {
+                            name: to_camel_case(v),
+                        })
+                        .collect(),
+                },
+            ));
+        }
+        for event in &ir.events {
+            types.push(SpecTypeDef::plain(
+                &to_camel_case(&event.name),
+                SpecTypeDefTy::Struct {
+                    fields: event
+                        .fields
+                        .iter()
+                        .map(|f| field(&f.name, &[], &f.ty))
+                        .collect(),
+                },
+            ));
+        }
+
+        types
+    }
+
+    fn solana_type_to_spec_type(&self, ty: &SolanaType) -> SpecType {
+        let primitive = |name: &str| SpecType::Primitive(name.to_string());
+        match ty {
+            SolanaType::U8 => primitive("u8"),
+            SolanaType::U16 => primitive("u16"),
+            SolanaType::U32 => primitive("u32"),
+            SolanaType::U64 => primitive("u64"),
+            SolanaType::U128 => primitive("u128"),
+            SolanaType::I8 => primitive("i8"),
+            SolanaType::I16 => primitive("i16"),
+            SolanaType::I32 => primitive("i32"),
+            SolanaType::I64 => primitive("i64"),
+            SolanaType::I128 => primitive("i128"),
+            SolanaType::Bool => primitive("bool"),
+            SolanaType::String => primitive("string"),
+            SolanaType::Bytes => primitive("bytes"),
+            SolanaType::Pubkey | SolanaType::Signer | SolanaType::Account(_) => primitive("pubkey"),
+            SolanaType::RemainingAccounts => SpecType::Vec {
+                vec: Box::new(primitive("pubkey")),
+            },
+            SolanaType::FixedBytes(n) => SpecType::Array {
+                array: (Box::new(primitive("u8")), *n),
+            },
+            SolanaType::Array(inner, size) => SpecType::Array {
+                array: (Box::new(self.solana_type_to_spec_type(inner)), *size),
+            },
+            SolanaType::Vec(inner) => SpecType::Vec {
+                vec: Box::new(self.solana_type_to_spec_type(inner)),
+            },
+            SolanaType::Option(inner) => SpecType::Option {
+                option: Box::new(self.solana_type_to_spec_type(inner)),
+            },
+            SolanaType::Mapping(_, _) => primitive("bytes"), // Mappings are PDAs
+            SolanaType::Fixed(fixed) => primitive(fixed_point_repr(fixed)),
+            SolanaType::Custom(name) => SpecType::Defined {
+                defined: SpecDefined {
+                    name: to_camel_case(name),
+                },
+            },
+            // Only returned, and instructions return them as their return struct
+            SolanaType::Tuple(_) => primitive("bytes"),
+        }
+    }
+
     fn solana_type_to_idl_type(&self, ty: &SolanaType) -> IdlType {
         match ty {
             SolanaType::U8 => IdlType::Primitive("u8".to_string()),
@@ -243,7 +729,12 @@ impl IdlGenerator {
             SolanaType::Bool => IdlType::Primitive("bool".to_string()),
             SolanaType::String => IdlType::Primitive("string".to_string()),
             SolanaType::Pubkey => IdlType::Primitive("publicKey".to_string()),
-            SolanaType::Signer => IdlType::Primitive("publicKey".to_string()),
+            SolanaType::Signer | SolanaType::Account(_) => {
+                IdlType::Primitive("publicKey".to_string())
+            }
+            SolanaType::RemainingAccounts => IdlType::Vec {
+                vec: Box::new(IdlType::Primitive("publicKey".to_string())),
+            },
             SolanaType::Bytes => IdlType::Primitive("bytes".to_string()),
             SolanaType::FixedBytes(n) => IdlType::Array {
                 array: (Box::new(IdlType::Primitive("u8".to_string())), *n),
@@ -258,11 +749,42 @@ impl IdlGenerator {
                 option: Box::new(self.solana_type_to_idl_type(inner)),
             },
             SolanaType::Mapping(_, _) => IdlType::Primitive("bytes".to_string()), // Mappings are PDAs
+            SolanaType::Fixed(fixed) => IdlType::Primitive(fixed_point_repr(fixed).to_string()),
             SolanaType::Custom(name) => IdlType::Defined(name.clone()),
+            // Only returned, and instructions return them as their return struct
+            SolanaType::Tuple(_) => IdlType::Primitive("bytes".to_string()),
         }
     }
 }
 
+/// The integer a fixed-point type is encoded as
+fn fixed_point_repr(fixed: &solscript_ast::FixedType) -> &'static str {
+    match (fixed.signed, fixed.bits) {
+        (true, 64) => "i64",
+        (true, _) => "i128",
+        (false, 64) => "u64",
+        (false, _) => "u128",
+    }
+}
+
+/// Field docs, noting the scale of a fixed-point field, which the IDL only sees as
+/// its raw integer
+fn fixed_point_docs(docs: &[String], ty: &SolanaType) -> Vec<String> {
+    let mut docs = docs.to_vec();
+    let mut inner = ty;
+    while let SolanaType::Array(elem, _) | SolanaType::Vec(elem) | SolanaType::Option(elem) = inner
+    {
+        inner = elem;
+    }
+    if let SolanaType::Fixed(fixed) = inner {
+        docs.push(format!(
+            "{}: fixed-point with {} decimals, encoded as the value times 10^{}",
+            fixed, fixed.decimals, fixed.decimals
+        ));
+    }
+    docs
+}
+
 // IDL structure types
 #[derive(Serialize)]
 struct Idl {
@@ -369,6 +891,203 @@ enum IdlType {
     Option { option: Box<IdlType> },
 }
 
+// Anchor 0.30+ IDL structure types
+#[derive(Serialize)]
+struct SpecIdl {
+    address: String,
+    metadata: SpecMetadata,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    docs: Vec<String>,
+    instructions: Vec<SpecInstruction>,
+    accounts: Vec<SpecNamed>,
+    events: Vec<SpecNamed>,
+    errors: Vec<IdlError>,
+    types: Vec<SpecTypeDef>,
+}
+
+#[derive(Serialize)]
+struct SpecMetadata {
+    name: String,
+    version: String,
+    spec: String,
+    description: String,
+}
+
+#[derive(Serialize)]
+struct SpecInstruction {
+    name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    docs: Vec<String>,
+    discriminator: Vec<u8>,
+    accounts: Vec<SpecAccount>,
+    args: Vec<SpecField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    returns: Option<SpecType>,
+}
+
+#[derive(Serialize)]
+struct SpecAccount {
+    name: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    writable: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    signer: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pda: Option<SpecPda>,
+}
+
+impl SpecAccount {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            writable: false,
+            signer: false,
+            address: None,
+            pda: None,
+        }
+    }
+
+    fn writable(mut self) -> Self {
+        self.writable = true;
+        self
+    }
+
+    fn signer(mut self) -> Self {
+        self.signer = true;
+        self
+    }
+
+    fn address(mut self, address: &str) -> Self {
+        self.address = Some(address.to_string());
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct SpecPda {
+    seeds: Vec<SpecSeed>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum SpecSeed {
+    Const {
+        value: Vec<u8>,
+    },
+    Arg {
+        path: String,
+    },
+    Account {
+        path: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        account: Option<String>,
+    },
+}
+
+/// An account or event: its name and 8-byte discriminator (the layout is in `types`)
+#[derive(Serialize)]
+struct SpecNamed {
+    name: String,
+    discriminator: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct SpecField {
+    name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    docs: Vec<String>,
+    #[serde(rename = "type")]
+    ty: SpecType,
+}
+
+#[derive(Serialize)]
+struct SpecTypeDef {
+    name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    docs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    serialization: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repr: Option<SpecRepr>,
+    #[serde(rename = "type")]
+    ty: SpecTypeDefTy,
+}
+
+impl SpecTypeDef {
+    fn plain(name: &str, ty: SpecTypeDefTy) -> Self {
+        Self {
+            name: name.to_string(),
+            docs: Vec::new(),
+            serialization: None,
+            repr: None,
+            ty,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SpecRepr {
+    kind: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum SpecTypeDefTy {
+    Struct { fields: Vec<SpecField> },
+    Enum { variants: Vec<IdlEnumVariant> },
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum SpecType {
+    Primitive(String),
+    Defined { defined: SpecDefined },
+    Array { array: (Box<SpecType>, usize) },
+    Vec { vec: Box<SpecType> },
+    Option { option: Box<SpecType> },
+}
+
+#[derive(Serialize)]
+struct SpecDefined {
+    name: String,
+}
+
+/// PDA seed for a mapping key, if the client can derive it from args or accounts
+fn spec_seed(key: &Expression, instr: &Instruction, state_name: &str) -> Option<SpecSeed> {
+    match key {
+        Expression::MsgSender => Some(SpecSeed::Account {
+            path: "signer".to_string(),
+            account: None,
+        }),
+        Expression::Var(name) => {
+            let param = instr.params.iter().find(|p| &p.name == name)?;
+            let path = to_snake_case(name);
+            Some(if param.ty.is_account() {
+                SpecSeed::Account {
+                    path,
+                    account: None,
+                }
+            } else {
+                SpecSeed::Arg { path }
+            })
+        }
+        Expression::StateAccess(field) => Some(SpecSeed::Account {
+            path: format!("state.{}", to_snake_case(field)),
+            account: Some(state_name.to_string()),
+        }),
+        Expression::Literal(Literal::ZeroAddress) => Some(SpecSeed::Const { value: vec![0; 32] }),
+        Expression::Literal(Literal::ZeroBytes(n)) => Some(SpecSeed::Const { value: vec![0; *n] }),
+        _ => None,
+    }
+}
+
+/// First 8 bytes of `sha256(preimage)`, as Anchor derives discriminators
+pub(crate) fn discriminator(preimage: &str) -> Vec<u8> {
+    Sha256::digest(preimage.as_bytes())[..8].to_vec()
+}
+
 // Helper functions
 fn to_camel_case(s: &str) -> String {
     let mut result = String::new();
@@ -399,13 +1118,20 @@ fn to_camel_case_lower(s: &str) -> String {
 
 fn to_snake_case(s: &str) -> String {
     let mut result = String::new();
+    let mut prev_upper = false;
+
     for (i, c) in s.chars().enumerate() {
-        if c.is_uppercase() && i > 0 {
-            result.push('_');
+        if c.is_uppercase() {
+            if i > 0 && !prev_upper {
+                result.push('_');
+            }
             result.push(c.to_ascii_lowercase());
+            prev_upper = true;
         } else {
-            result.push(c.to_ascii_lowercase());
+            result.push(c);
+            prev_upper = false;
         }
     }
+
     result
 }
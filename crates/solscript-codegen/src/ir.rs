@@ -4,12 +4,17 @@
 //! making it easier to generate Anchor Rust code.
 
 use crate::error::CodegenError;
+use smol_str::SmolStr;
 use solscript_ast::{self as ast, StateMutability, Visibility};
 
 /// A Solana program (corresponds to a SolScript contract)
 #[derive(Debug, Clone)]
 pub struct SolanaProgram {
     pub name: String,
+    /// Doc comments of the contract
+    pub docs: Vec<String>,
+    /// Span of the contract definition
+    pub span: ast::Span,
     pub state: ProgramState,
     pub mappings: Vec<MappingDef>,
     pub modifiers: Vec<ModifierDefinition>,
@@ -20,6 +25,38 @@ pub struct SolanaProgram {
     pub enums: Vec<EnumDef>,
     /// Test functions marked with #[test]
     pub tests: Vec<TestFunction>,
+    /// External Anchor programs imported from an IDL
+    pub cpi_interfaces: Vec<CpiInterface>,
+    /// Whether the program imports `Math`, whose functions are generated as helpers
+    pub uses_math: bool,
+    /// Whether the program uses fixed-point types, whose arithmetic is generated as helpers
+    pub uses_fixed: bool,
+}
+
+/// An external Anchor program imported from an IDL, callable via CPI
+#[derive(Debug, Clone)]
+pub struct CpiInterface {
+    pub name: String,
+    pub instructions: Vec<CpiInstruction>,
+}
+
+/// An instruction of an imported Anchor program
+#[derive(Debug, Clone)]
+pub struct CpiInstruction {
+    pub name: String,
+    pub discriminator: [u8; 8],
+    /// Accounts in IDL order; the call's leading arguments map onto these
+    pub accounts: Vec<CpiAccount>,
+    /// Instruction arguments, serialized after the discriminator
+    pub args: Vec<InstructionParam>,
+}
+
+/// An account expected by an imported Anchor instruction
+#[derive(Debug, Clone)]
+pub struct CpiAccount {
+    pub name: String,
+    pub is_mut: bool,
+    pub is_signer: bool,
 }
 
 /// A test function
@@ -73,6 +110,38 @@ pub struct MappingDef {
 #[derive(Debug, Clone)]
 pub struct ProgramState {
     pub fields: Vec<StateField>,
+    /// `#[zero_copy]`: the state is accessed in place through an `AccountLoader`.
+    /// Fields are ordered by descending alignment so the layout has no inner padding.
+    pub zero_copy: bool,
+    /// `#[upgradeable]`: the state carries a layout version and can be migrated
+    pub upgrade: Option<StateUpgrade>,
+}
+
+/// Layout versioning of an `#[upgradeable]` contract's state.
+///
+/// Fields are ordered by the version that introduced them, after a leading
+/// `layoutVersion` byte, so every older layout is a prefix of the current one.
+#[derive(Debug, Clone)]
+pub struct StateUpgrade {
+    /// Current layout version, written by `initialize` and `migrate`
+    pub version: u8,
+    pub migrations: Vec<Migration>,
+}
+
+/// A `#[migration(from = a, to = b)]` function, lowered to an internal helper
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub from: u8,
+    pub to: u8,
+    pub function: String,
+}
+
+impl ProgramState {
+    /// Upgrade info of a state with older layouts to migrate from; a `migrate`
+    /// instruction is generated only in that case
+    pub fn migratable(&self) -> Option<&StateUpgrade> {
+        self.upgrade.as_ref().filter(|u| u.version > 1)
+    }
 }
 
 /// A field in the state account
@@ -81,12 +150,22 @@ pub struct StateField {
     pub name: String,
     pub ty: SolanaType,
     pub is_public: bool,
+    /// `#[growable]`: the state account is reallocated when this field grows
+    pub growable: bool,
+    /// `#[version(n)]`: the layout version that introduced this field
+    pub version: u8,
+    pub docs: Vec<String>,
+    /// Span of the state variable (dummy for generated fields)
+    pub span: ast::Span,
 }
 
 /// An instruction (function) in the program
 #[derive(Debug, Clone)]
 pub struct Instruction {
     pub name: String,
+    pub docs: Vec<String>,
+    /// Span of the function or constructor (dummy for a synthesized `initialize`)
+    pub span: ast::Span,
     pub params: Vec<InstructionParam>,
     pub returns: Option<SolanaType>,
     pub body: Vec<Statement>,
@@ -100,6 +179,44 @@ pub struct Instruction {
     pub mapping_accesses: Vec<MappingAccess>,
     /// If true, this instruction closes the state account (selfdestruct)
     pub closes_state: bool,
+    /// `#[constraint(...)]` on the function, applied to the state account
+    pub state_constraints: Vec<AccountConstraint>,
+    /// Writes that grow `#[growable]` state fields; the state account is
+    /// reallocated to fit them before the handler runs
+    pub state_growth: Vec<StateGrowth>,
+}
+
+impl Instruction {
+    /// The struct a public function with multiple return values hands them back
+    /// in, with its field types: `getReserves` → `GetReservesReturn`
+    pub fn return_struct(&self) -> Option<(String, &[SolanaType])> {
+        match &self.returns {
+            Some(SolanaType::Tuple(types)) => Some((
+                format!("{}Return", crate::rust_gen::to_pascal_case(&self.name)),
+                types,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// A push or assignment to a `#[growable]` state field
+#[derive(Debug, Clone)]
+pub struct StateGrowth {
+    pub field: String,
+    /// Type of the field (`string`, `bytes` or a vector)
+    pub ty: SolanaType,
+    pub kind: GrowthKind,
+    /// The statement that grows the field
+    pub span: ast::Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum GrowthKind {
+    /// `field.push(value)`
+    Push(Expression),
+    /// `field = value`
+    Assign(Expression),
 }
 
 /// A mapping access within an instruction
@@ -122,6 +239,26 @@ pub struct MappingAccess {
 pub struct InstructionParam {
     pub name: String,
     pub ty: SolanaType,
+    /// `#[constraint(...)]` on an account or signer parameter
+    pub constraints: Vec<AccountConstraint>,
+    /// Whether an account or signer parameter is writable: marked `#[mut]`,
+    /// written by the function, or receiving the lamports of the closed state
+    pub is_mut: bool,
+}
+
+/// A declarative account constraint, emitted into `#[account(...)]`
+#[derive(Debug, Clone)]
+pub enum AccountConstraint {
+    /// `has_one = field`: the account's `field` must be the key of the account of that name
+    HasOne(String),
+    /// `owner = expr`: the program owning the account
+    Owner(Expression),
+    /// `address = expr`: the account's expected key
+    Address(Expression),
+    /// `constraint = expr`: an arbitrary boolean check
+    Constraint(Expression),
+    /// `close = account`: close the account, sending its lamports to `account`
+    Close(String),
 }
 
 /// A modifier invocation
@@ -186,11 +323,94 @@ pub enum SolanaType {
     Mapping(Box<SolanaType>, Box<SolanaType>),
     // User-defined types
     Custom(String),
+    /// An account parameter passed through the instruction's accounts context
+    Account(AccountKind),
+    /// Variadic accounts passed as `ctx.remaining_accounts` (`accounts[]`)
+    RemainingAccounts,
+    /// Fixed-point decimal (`ufixed64x6` etc.), stored as a scaled integer
+    Fixed(ast::FixedType),
+    /// Multiple return values: `returns (uint64, uint64)` → `(u64, u64)`
+    Tuple(Vec<SolanaType>),
+}
+
+/// Kinds of account parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountKind {
+    /// `account<TokenAccount>`: an SPL token account
+    TokenAccount,
+    /// `account<Mint>`: an SPL mint
+    Mint,
+    /// `unchecked_account`: any account, not validated by Anchor
+    Unchecked,
+}
+
+impl SolanaType {
+    /// Whether values of this type are passed as accounts rather than instruction data
+    pub fn is_account(&self) -> bool {
+        matches!(
+            self,
+            SolanaType::Signer | SolanaType::Account(_) | SolanaType::RemainingAccounts
+        )
+    }
+
+    /// Whether this is a fixed-width integer type
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            SolanaType::U8
+                | SolanaType::U16
+                | SolanaType::U32
+                | SolanaType::U64
+                | SolanaType::U128
+                | SolanaType::I8
+                | SolanaType::I16
+                | SolanaType::I32
+                | SolanaType::I64
+                | SolanaType::I128
+        )
+    }
+
+    /// Whether this type is, or contains, a fixed-point decimal
+    pub fn contains_fixed(&self) -> bool {
+        match self {
+            SolanaType::Fixed(_) => true,
+            SolanaType::Array(inner, _) | SolanaType::Vec(inner) | SolanaType::Option(inner) => {
+                inner.contains_fixed()
+            }
+            SolanaType::Mapping(key, value) => key.contains_fixed() || value.contains_fixed(),
+            _ => false,
+        }
+    }
+
+    /// Size and alignment in a `#[repr(C)]` zero-copy account, or `None` if the
+    /// type is not plain old data
+    pub fn pod_layout(&self) -> Option<(usize, usize)> {
+        match self {
+            SolanaType::U8 | SolanaType::I8 => Some((1, 1)),
+            SolanaType::U16 | SolanaType::I16 => Some((2, 2)),
+            SolanaType::U32 | SolanaType::I32 => Some((4, 4)),
+            SolanaType::U64 | SolanaType::I64 => Some((8, 8)),
+            SolanaType::U128 | SolanaType::I128 => Some((16, 16)),
+            SolanaType::Pubkey => Some((32, 1)),
+            SolanaType::FixedBytes(n) => Some((*n, 1)),
+            SolanaType::Array(inner, n) => {
+                inner.pod_layout().map(|(size, align)| (size * n, align))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A statement in IR, with the span of the SolScript statement it was lowered from
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: ast::Span,
 }
 
 /// Statements in IR
 #[derive(Debug, Clone)]
-pub enum Statement {
+pub enum StatementKind {
     VarDecl {
         name: String,
         ty: SolanaType,
@@ -269,6 +489,16 @@ pub enum Expression {
         lamports: Box<Expression>,
         data_len: Box<Expression>,
     },
+    /// A function of the `@solana/math` module: Math.mulDiv(x, y, d) → solscript_math::mul_div(x, y, d)?
+    Math {
+        function: MathFunction,
+        args: Vec<Expression>,
+    },
+    /// Conversion to a fixed-point type: ufixed64x6(x) → UFixed64::<6>::from_int(x)
+    ToFixed {
+        ty: ast::FixedType,
+        value: Box<Expression>,
+    },
     Binary {
         op: BinaryOp,
         left: Box<Expression>,
@@ -304,6 +534,8 @@ pub enum Expression {
         method: String,
         /// Arguments to the CPI call
         args: Vec<Expression>,
+        /// Span of the method name, for diagnostics
+        span: ast::Span,
     },
     /// SPL Token transfer CPI
     TokenTransfer {
@@ -352,6 +584,8 @@ pub enum Expression {
         /// token mint address
         mint: Box<Expression>,
     },
+    /// Tuple of values returned from a function with multiple return values
+    Tuple(Vec<Expression>),
     Index {
         expr: Box<Expression>,
         index: Box<Expression>,
@@ -418,6 +652,11 @@ pub enum Literal {
     Pubkey(String),   // Base58 encoded
     ZeroAddress,      // address(0) - the default/null address
     ZeroBytes(usize), // bytes32(0) etc. - zero-filled fixed bytes
+    /// Decimal literal `digits / 10^decimals`, typed by its context
+    Decimal {
+        digits: u128,
+        decimals: u8,
+    },
 }
 
 /// Binary operators
@@ -451,6 +690,46 @@ pub enum UnaryOp {
     BitNot,
 }
 
+/// Functions of the `@solana/math` module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathFunction {
+    Sqrt,
+    Min,
+    Max,
+    MulDiv,
+    MulDivUp,
+    MulBps,
+    MulBpsUp,
+}
+
+impl MathFunction {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sqrt" => Self::Sqrt,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "mulDiv" => Self::MulDiv,
+            "mulDivUp" => Self::MulDivUp,
+            "mulBps" => Self::MulBps,
+            "mulBpsUp" => Self::MulBpsUp,
+            _ => return None,
+        })
+    }
+
+    /// Name of the generated Rust helper
+    pub fn helper_name(self) -> &'static str {
+        match self {
+            Self::Sqrt => "sqrt",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::MulDiv => "mul_div",
+            Self::MulDivUp => "mul_div_up",
+            Self::MulBps => "mul_bps",
+            Self::MulBpsUp => "mul_bps_up",
+        }
+    }
+}
+
 /// Lower the AST to Solana IR
 pub fn lower_to_ir(program: &ast::Program) -> Result<Vec<SolanaProgram>, CodegenError> {
     let mut programs = Vec::new();
@@ -461,6 +740,7 @@ pub fn lower_to_ir(program: &ast::Program) -> Result<Vec<SolanaProgram>, Codegen
 
     // First pass: collect events, errors, structs, enums, and interfaces
     let mut interface_names: std::collections::HashSet<String> = std::collections::HashSet::new();
+    let mut cpi_interfaces = Vec::new();
     for item in &program.items {
         match item {
             ast::Item::Event(e) => {
@@ -475,8 +755,13 @@ pub fn lower_to_ir(program: &ast::Program) -> Result<Vec<SolanaProgram>, Codegen
             ast::Item::Enum(e) => {
                 enums.push(lower_enum(e));
             }
+            // Intrinsics are lowered at their call sites instead
+            ast::Item::Interface(i) if solscript_std::is_intrinsic(i) => {}
             ast::Item::Interface(i) => {
                 interface_names.insert(i.name.name.to_string());
+                if let Some(cpi) = lower_cpi_interface(i)? {
+                    cpi_interfaces.push(cpi);
+                }
             }
             ast::Item::Contract(c) => {
                 // Also collect events, errors, structs, and enums defined inside contracts
@@ -515,6 +800,8 @@ pub fn lower_to_ir(program: &ast::Program) -> Result<Vec<SolanaProgram>, Codegen
         })
         .collect();
 
+    let intrinsics = solscript_std::in_scope(program);
+
     // Second pass: process contracts (skip abstract contracts)
     for item in &program.items {
         if let ast::Item::Contract(contract) = item {
@@ -522,7 +809,7 @@ pub fn lower_to_ir(program: &ast::Program) -> Result<Vec<SolanaProgram>, Codegen
             if contract.is_abstract {
                 continue;
             }
-            let prog = lower_contract(
+            let mut prog = lower_contract(
                 contract,
                 &events,
                 &errors,
@@ -530,7 +817,10 @@ pub fn lower_to_ir(program: &ast::Program) -> Result<Vec<SolanaProgram>, Codegen
                 &enums,
                 &contracts,
                 &interface_names,
+                &cpi_interfaces,
+                &intrinsics,
             )?;
+            prog.cpi_interfaces = cpi_interfaces.clone();
             programs.push(prog);
         }
     }
@@ -544,6 +834,14 @@ struct LoweringContext {
     mapping_names: std::collections::HashSet<String>,
     mappings: Vec<MappingDef>,
     interface_names: std::collections::HashSet<String>,
+    /// Programs imported from an IDL
+    cpi_interfaces: Vec<CpiInterface>,
+    /// `#[growable]` state fields and their types
+    growable_fields: std::collections::HashMap<String, SolanaType>,
+    /// Standard library intrinsics in scope, by the name they are used by
+    intrinsics: std::collections::HashMap<SmolStr, &'static solscript_std::Intrinsic>,
+    /// Set when a function body declares or converts to a fixed-point type
+    uses_fixed: std::cell::Cell<bool>,
 }
 
 impl LoweringContext {
@@ -553,7 +851,20 @@ impl LoweringContext {
             mapping_names: std::collections::HashSet::new(),
             mappings: Vec::new(),
             interface_names: std::collections::HashSet::new(),
+            cpi_interfaces: Vec::new(),
+            growable_fields: std::collections::HashMap::new(),
+            intrinsics: std::collections::HashMap::new(),
+            uses_fixed: std::cell::Cell::new(false),
+        }
+    }
+
+    /// Lower the type of a local variable, noting fixed-point usage
+    fn lower_local_type(&self, ty: &ast::TypeExpr) -> Result<SolanaType, CodegenError> {
+        let ty = lower_type(ty)?;
+        if ty.contains_fixed() {
+            self.uses_fixed.set(true);
         }
+        Ok(ty)
     }
 
     fn is_state_field(&self, name: &str) -> bool {
@@ -564,9 +875,26 @@ impl LoweringContext {
         self.interface_names.contains(name)
     }
 
+    /// The instruction `method` of a program imported from an IDL
+    fn cpi_instruction(&self, interface_name: &str, method: &str) -> Option<&CpiInstruction> {
+        self.cpi_interfaces
+            .iter()
+            .find(|i| i.name == interface_name)?
+            .instructions
+            .iter()
+            .find(|ix| ix.name == method)
+    }
+
     fn is_mapping(&self, name: &str) -> bool {
         self.mapping_names.contains(name)
     }
+
+    /// Name of the intrinsic `name` refers to: `Token` for `token` or an imported `Token`
+    fn intrinsic(&self, name: &str) -> Option<&str> {
+        self.intrinsics
+            .get(name)
+            .map(|intrinsic| intrinsic.name.as_str())
+    }
 }
 
 /// Collector for mapping accesses within a function
@@ -575,6 +903,8 @@ struct MappingAccessCollector {
     counter: usize,
     uses_token_program: bool,
     uses_sol_transfer: bool,
+    /// Names of the accounts passed where a CPI writes to them
+    written_accounts: std::collections::HashSet<String>,
 }
 
 impl MappingAccessCollector {
@@ -584,6 +914,14 @@ impl MappingAccessCollector {
             counter: 0,
             uses_token_program: false,
             uses_sol_transfer: false,
+            written_accounts: std::collections::HashSet::new(),
+        }
+    }
+
+    /// Record that `account` is written, if it names an account
+    fn mark_written(&mut self, account: &Expression) {
+        if let Expression::Var(name) = account {
+            self.written_accounts.insert(name.clone());
         }
     }
 
@@ -639,6 +977,7 @@ fn to_snake_case(s: &str) -> String {
     result
 }
 
+#[allow(clippy::too_many_arguments)]
 fn lower_contract(
     contract: &ast::ContractDef,
     events: &[Event],
@@ -647,6 +986,8 @@ fn lower_contract(
     enums: &[EnumDef],
     all_contracts: &std::collections::HashMap<String, &ast::ContractDef>,
     interface_names: &std::collections::HashSet<String>,
+    cpi_interfaces: &[CpiInterface],
+    intrinsics: &std::collections::HashMap<SmolStr, &'static solscript_std::Intrinsic>,
 ) -> Result<SolanaProgram, CodegenError> {
     let name = contract.name.name.to_string();
 
@@ -676,6 +1017,11 @@ fn lower_contract(
     let mut fields = Vec::new();
     let mut ctx = LoweringContext::new();
     ctx.interface_names = interface_names.clone();
+    ctx.cpi_interfaces = cpi_interfaces.to_vec();
+    ctx.intrinsics = intrinsics.clone();
+    let uses_math = intrinsics
+        .values()
+        .any(|intrinsic| intrinsic.name == "Math");
     let mut seen_fields = std::collections::HashSet::new();
 
     for member in &all_members {
@@ -702,10 +1048,25 @@ fn lower_contract(
                 });
             } else {
                 ctx.state_fields.insert(field_name.clone());
+                let growable = var.attributes.iter().any(|a| a.name.name == "growable");
+                if growable {
+                    ctx.growable_fields
+                        .insert(field_name.clone(), field_ty.clone());
+                }
+                let version = var
+                    .attributes
+                    .iter()
+                    .find(|a| a.name.name == "version")
+                    .and_then(|a| attribute_version(a, None))
+                    .unwrap_or(1);
                 fields.push(StateField {
                     name: field_name,
                     ty: field_ty,
                     is_public,
+                    growable,
+                    version,
+                    docs: var.docs.clone(),
+                    span: var.span,
                 });
             }
         }
@@ -775,9 +1136,77 @@ fn lower_contract(
         }
     }
 
+    let zero_copy = contract
+        .attributes
+        .iter()
+        .any(|a| a.name.name == "zero_copy");
+    if zero_copy {
+        for field in &fields {
+            if field.ty.pod_layout().is_none() {
+                return Err(CodegenError::unsupported_feature(
+                    format!(
+                        "State field '{}' in a #[zero_copy] contract must be plain old data",
+                        field.name
+                    ),
+                    field.span,
+                ));
+            }
+        }
+    }
+
+    let upgradeable = contract
+        .attributes
+        .iter()
+        .any(|a| a.name.name == "upgradeable");
+    let upgrade = if upgradeable {
+        Some(lower_state_upgrade(
+            &all_members,
+            &mut fields,
+            &mut instructions,
+        )?)
+    } else {
+        None
+    };
+    if zero_copy {
+        insert_pod_padding(&mut fields);
+    }
+
+    let uses_fixed = ctx.uses_fixed.get()
+        || fields.iter().any(|f| f.ty.contains_fixed())
+        || ctx
+            .mappings
+            .iter()
+            .any(|m| m.key_ty.contains_fixed() || m.value_ty.contains_fixed())
+        || structs
+            .iter()
+            .flat_map(|s| &s.fields)
+            .any(|f| f.ty.contains_fixed())
+        || events
+            .iter()
+            .flat_map(|e| &e.fields)
+            .any(|f| f.ty.contains_fixed())
+        || errors
+            .iter()
+            .flat_map(|e| &e.fields)
+            .any(|f| f.ty.contains_fixed())
+        || modifiers
+            .iter()
+            .flat_map(|m| &m.params)
+            .any(|p| p.ty.contains_fixed())
+        || instructions.iter().any(|i| {
+            i.params.iter().any(|p| p.ty.contains_fixed())
+                || i.returns.as_ref().is_some_and(SolanaType::contains_fixed)
+        });
+
     Ok(SolanaProgram {
         name,
-        state: ProgramState { fields },
+        docs: contract.docs.clone(),
+        span: contract.span,
+        state: ProgramState {
+            fields,
+            zero_copy,
+            upgrade,
+        },
         mappings: ctx.mappings,
         modifiers,
         instructions,
@@ -786,9 +1215,202 @@ fn lower_contract(
         structs: structs.to_vec(),
         enums: enums.to_vec(),
         tests,
+        cpi_interfaces: Vec::new(),
+        uses_math,
+        uses_fixed,
+    })
+}
+
+/// Collect the layout version and migrations of an `#[upgradeable]` contract.
+///
+/// State fields are reordered by version behind a `layoutVersion` byte, migration
+/// functions become internal helpers, and a contract without a constructor gets an
+/// empty `initialize` so that the version is always written.
+fn lower_state_upgrade(
+    members: &[&ast::ContractMember],
+    fields: &mut Vec<StateField>,
+    instructions: &mut Vec<Instruction>,
+) -> Result<StateUpgrade, CodegenError> {
+    if let Some(user_migrate) = instructions.iter().find(|i| i.name == "migrate") {
+        return Err(CodegenError::unsupported_feature(
+            "'migrate' is generated for #[upgradeable] contracts and cannot be user-defined",
+            user_migrate.span,
+        ));
+    }
+
+    let mut migrations = Vec::new();
+    for member in members {
+        let ast::ContractMember::Function(func) = member else {
+            continue;
+        };
+        let Some(attr) = func.attributes.iter().find(|a| a.name.name == "migration") else {
+            continue;
+        };
+        let (Some(from), Some(to)) = (
+            attribute_version(attr, Some("from")),
+            attribute_version(attr, Some("to")),
+        ) else {
+            return Err(CodegenError::missing_element(
+                format!(
+                    "Migration '{}' needs #[migration(from = <version>, to = <version>)]",
+                    func.name.name
+                ),
+                attr.span,
+            ));
+        };
+        let function = func.name.name.to_string();
+        if let Some(helper) = instructions.iter_mut().find(|i| i.name == function) {
+            helper.is_public = false;
+        }
+        migrations.push(Migration { from, to, function });
+    }
+    migrations.sort_by_key(|m| m.from);
+
+    let version = fields.iter().map(|f| f.version).max().unwrap_or(1);
+    fields.sort_by_key(|f| f.version);
+    fields.insert(
+        0,
+        StateField {
+            name: "layoutVersion".to_string(),
+            ty: SolanaType::U8,
+            is_public: true,
+            growable: false,
+            version: 1,
+            docs: vec!["Layout version of this account".to_string()],
+            span: ast::Span::dummy(),
+        },
+    );
+
+    if !instructions.iter().any(|i| i.name == "initialize") {
+        instructions.insert(
+            0,
+            Instruction {
+                name: "initialize".to_string(),
+                docs: Vec::new(),
+                span: ast::Span::dummy(),
+                params: Vec::new(),
+                returns: None,
+                body: Vec::new(),
+                is_public: true,
+                is_view: false,
+                is_payable: false,
+                uses_token_program: false,
+                uses_sol_transfer: false,
+                modifiers: Vec::new(),
+                mapping_accesses: Vec::new(),
+                closes_state: false,
+                state_constraints: Vec::new(),
+                state_growth: Vec::new(),
+            },
+        );
+    }
+
+    Ok(StateUpgrade {
+        version,
+        migrations,
     })
 }
 
+/// Keep `#[zero_copy]` fields in declaration order, inserting `_padding<n>` byte
+/// arrays where the C layout would pad implicitly, since `Pod` types can't contain
+/// implicit padding.
+fn insert_pod_padding(fields: &mut Vec<StateField>) {
+    let mut padded: Vec<StateField> = Vec::with_capacity(fields.len());
+    let mut pads = 0;
+    let mut pad = |padded: &mut Vec<StateField>, size: usize, version: u8| {
+        padded.push(StateField {
+            name: format!("_padding{}", pads),
+            ty: SolanaType::Array(Box::new(SolanaType::U8), size),
+            is_public: false,
+            growable: false,
+            version,
+            docs: Vec::new(),
+            span: ast::Span::dummy(),
+        });
+        pads += 1;
+    };
+
+    let mut offset = 0;
+    let mut struct_align = 1;
+    for field in fields.drain(..) {
+        let (size, align) = field.ty.pod_layout().unwrap_or((0, 1));
+        let gap = (align - offset % align) % align;
+        if gap > 0 {
+            pad(&mut padded, gap, field.version);
+        }
+        offset += gap + size;
+        struct_align = struct_align.max(align);
+        padded.push(field);
+    }
+
+    // Trailing padding up to the struct alignment
+    let gap = (struct_align - offset % struct_align) % struct_align;
+    if gap > 0 {
+        let version = padded.iter().map(|f| f.version).max().unwrap_or(1);
+        pad(&mut padded, gap, version);
+    }
+    *fields = padded;
+}
+
+/// An integer layout version in `#[version(n)]` (unnamed) or `#[migration(from = n)]`
+fn attribute_version(attr: &ast::Attribute, key: Option<&str>) -> Option<u8> {
+    let arg = attr
+        .args
+        .iter()
+        .find(|a| a.name.as_ref().map(|n| n.name.as_str()) == key)?;
+    match &arg.value {
+        ast::AttributeValue::Literal(ast::Literal::Int(v, _)) => u8::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+/// Lower an interface imported from an Anchor IDL; hand-written interfaces yield `None`
+fn lower_cpi_interface(i: &ast::InterfaceDef) -> Result<Option<CpiInterface>, CodegenError> {
+    let mut instructions = Vec::new();
+
+    for sig in &i.members {
+        let Some(anchor) = &sig.anchor else {
+            continue;
+        };
+
+        let args = sig.params[anchor.accounts.len()..]
+            .iter()
+            .map(|p| {
+                Ok(InstructionParam {
+                    name: p.name.name.to_string(),
+                    ty: lower_type(&p.ty)?,
+                    constraints: Vec::new(),
+                    is_mut: false,
+                })
+            })
+            .collect::<Result<Vec<_>, CodegenError>>()?;
+
+        instructions.push(CpiInstruction {
+            name: sig.name.name.to_string(),
+            discriminator: anchor.discriminator,
+            accounts: anchor
+                .accounts
+                .iter()
+                .map(|a| CpiAccount {
+                    name: a.name.to_string(),
+                    is_mut: a.is_mut,
+                    is_signer: a.is_signer,
+                })
+                .collect(),
+            args,
+        });
+    }
+
+    if instructions.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(CpiInterface {
+        name: i.name.name.to_string(),
+        instructions,
+    }))
+}
+
 /// Check if a function has the #[test] attribute
 fn has_test_attribute(attrs: &[ast::Attribute]) -> bool {
     attrs.iter().any(|a| a.name.name.as_str() == "test")
@@ -844,6 +1466,8 @@ fn lower_function(func: &ast::FnDef, ctx: &LoweringContext) -> Result<Instructio
             Ok(InstructionParam {
                 name: p.name.name.to_string(),
                 ty: lower_type(&p.ty)?,
+                constraints: lower_constraints(&p.attributes, ctx)?,
+                is_mut: is_mut_param(p),
             })
         })
         .collect::<Result<Vec<_>, CodegenError>>()?;
@@ -853,9 +1477,12 @@ fn lower_function(func: &ast::FnDef, ctx: &LoweringContext) -> Result<Instructio
     } else if func.return_params.len() == 1 {
         Some(lower_type(&func.return_params[0].ty)?)
     } else {
-        return Err(CodegenError::UnsupportedFeature(
-            "Multiple return values".to_string(),
-        ));
+        Some(SolanaType::Tuple(
+            func.return_params
+                .iter()
+                .map(|p| lower_type(&p.ty))
+                .collect::<Result<Vec<_>, _>>()?,
+        ))
     };
 
     let is_public = matches!(
@@ -890,8 +1517,16 @@ fn lower_function(func: &ast::FnDef, ctx: &LoweringContext) -> Result<Instructio
     // Check if body contains selfdestruct
     let closes_state = body_contains_selfdestruct(&body);
 
+    let mut state_growth = Vec::new();
+    collect_state_growth(&body, &params, ctx, false, &mut state_growth)?;
+
+    let state_constraints = lower_constraints(&func.attributes, ctx)?;
+    let params = mark_written_accounts(params, &collector, &state_constraints);
+
     Ok(Instruction {
         name,
+        docs: func.docs.clone(),
+        span: func.span,
         params,
         returns,
         body,
@@ -903,15 +1538,186 @@ fn lower_function(func: &ast::FnDef, ctx: &LoweringContext) -> Result<Instructio
         modifiers,
         mapping_accesses: collector.accesses,
         closes_state,
+        state_constraints,
+        state_growth,
     })
 }
 
+/// Whether a parameter asks to be writable with `#[mut]`
+fn is_mut_param(param: &ast::Param) -> bool {
+    param.attributes.iter().any(|a| a.name.name == "mut")
+}
+
+/// Make the account and signer parameters the body writes to, or that receive
+/// the lamports of the closed state account, writable
+fn mark_written_accounts(
+    mut params: Vec<InstructionParam>,
+    collector: &MappingAccessCollector,
+    state_constraints: &[AccountConstraint],
+) -> Vec<InstructionParam> {
+    for param in &mut params {
+        let receives_close = state_constraints
+            .iter()
+            .any(|c| matches!(c, AccountConstraint::Close(to) if *to == param.name));
+        if receives_close || collector.written_accounts.contains(&param.name) {
+            param.is_mut = true;
+        }
+    }
+    params
+}
+
+/// Lower the `#[constraint(...)]` attributes in `attrs`
+fn lower_constraints(
+    attrs: &[ast::Attribute],
+    ctx: &LoweringContext,
+) -> Result<Vec<AccountConstraint>, CodegenError> {
+    let mut constraints = Vec::new();
+    for attr in attrs.iter().filter(|a| a.name.name == "constraint") {
+        for arg in &attr.args {
+            let Some(key) = &arg.name else {
+                continue;
+            };
+            let expr = match &arg.value {
+                ast::AttributeValue::Ident(ident) => ast::Expr::Ident(ident.clone()),
+                ast::AttributeValue::Literal(lit) => ast::Expr::Literal(lit.clone()),
+                ast::AttributeValue::Expr(expr) => (**expr).clone(),
+            };
+            let account_name = || match &arg.value {
+                ast::AttributeValue::Ident(ident) => Ok(ident.name.to_string()),
+                _ => Err(CodegenError::unsupported_feature(
+                    format!("`{}` constraint without an account name", key.name),
+                    arg.span,
+                )),
+            };
+
+            // Constraints are checked before the handler runs, so they can't touch mapping PDAs
+            let mut collector = MappingAccessCollector::new();
+            let constraint = match key.name.as_str() {
+                "has_one" => AccountConstraint::HasOne(account_name()?),
+                "close" => AccountConstraint::Close(account_name()?),
+                "owner" => AccountConstraint::Owner(lower_expr(&expr, ctx, &mut collector)?),
+                "address" => AccountConstraint::Address(lower_expr(&expr, ctx, &mut collector)?),
+                "constraint" => {
+                    AccountConstraint::Constraint(lower_expr(&expr, ctx, &mut collector)?)
+                }
+                other => {
+                    return Err(CodegenError::unsupported_feature(
+                        format!("Unknown account constraint `{}`", other),
+                        key.span,
+                    ))
+                }
+            };
+            if !collector.accesses.is_empty() {
+                return Err(CodegenError::unsupported_feature(
+                    "Mapping access in an account constraint",
+                    arg.span,
+                ));
+            }
+            constraints.push(constraint);
+        }
+    }
+    Ok(constraints)
+}
+
+/// Collect writes to `#[growable]` state fields.
+///
+/// The state is reallocated during account validation, so the new size must be
+/// computable from the instruction arguments and the current state alone.
+fn collect_state_growth(
+    stmts: &[Statement],
+    params: &[InstructionParam],
+    ctx: &LoweringContext,
+    in_loop: bool,
+    growth: &mut Vec<StateGrowth>,
+) -> Result<(), CodegenError> {
+    for stmt in stmts {
+        let site = match &stmt.kind {
+            StatementKind::Assign {
+                target: Expression::StateAccess(field),
+                value,
+            } => Some((field, GrowthKind::Assign(value.clone()), value)),
+            StatementKind::Expr(Expression::MethodCall {
+                receiver,
+                method,
+                args,
+            }) if args.len() == 1 => match (receiver.as_ref(), method.as_str()) {
+                (Expression::StateAccess(field), "push") => {
+                    Some((field, GrowthKind::Push(args[0].clone()), &args[0]))
+                }
+                (Expression::StateAccess(field), "__assign__") => {
+                    Some((field, GrowthKind::Assign(args[0].clone()), &args[0]))
+                }
+                _ => None,
+            },
+            StatementKind::If {
+                then_block,
+                else_block,
+                ..
+            } => {
+                collect_state_growth(then_block, params, ctx, in_loop, growth)?;
+                if let Some(else_stmts) = else_block {
+                    collect_state_growth(else_stmts, params, ctx, in_loop, growth)?;
+                }
+                None
+            }
+            StatementKind::While { body, .. } | StatementKind::For { body, .. } => {
+                collect_state_growth(body, params, ctx, true, growth)?;
+                None
+            }
+            _ => None,
+        };
+
+        let Some((field, kind, value)) = site else {
+            continue;
+        };
+        let Some(ty) = ctx.growable_fields.get(field) else {
+            continue;
+        };
+        if in_loop {
+            return Err(CodegenError::unsupported_feature(
+                format!("Growing #[growable] field '{}' inside a loop", field),
+                stmt.span,
+            ));
+        }
+        if !is_argument_expr(value, params) {
+            return Err(CodegenError::unsupported_feature(
+                format!(
+                    "Value written to #[growable] field '{}' must be computable from the instruction arguments",
+                    field
+                ),
+                stmt.span,
+            ));
+        }
+        growth.push(StateGrowth {
+            field: field.clone(),
+            ty: ty.clone(),
+            kind,
+            span: stmt.span,
+        });
+    }
+    Ok(())
+}
+
+/// Whether an expression only uses instruction arguments, state fields and literals
+fn is_argument_expr(expr: &Expression, params: &[InstructionParam]) -> bool {
+    match expr {
+        Expression::Literal(_) | Expression::StateAccess(_) | Expression::MsgSender => true,
+        Expression::Var(name) => params.iter().any(|p| &p.name == name && !p.ty.is_account()),
+        Expression::Binary { left, right, .. } => {
+            is_argument_expr(left, params) && is_argument_expr(right, params)
+        }
+        Expression::Unary { expr, .. } => is_argument_expr(expr, params),
+        _ => false,
+    }
+}
+
 /// Check if a statement list contains a Selfdestruct statement
+#[allow(clippy::collapsible_match)]
 fn body_contains_selfdestruct(stmts: &[Statement]) -> bool {
     for stmt in stmts {
-        match stmt {
-            Statement::Selfdestruct { .. } => return true,
-            Statement::If {
+        match &stmt.kind {
+            StatementKind::Selfdestruct { .. } => return true,
+            StatementKind::If {
                 then_block,
                 else_block,
                 ..
@@ -925,12 +1731,12 @@ fn body_contains_selfdestruct(stmts: &[Statement]) -> bool {
                     }
                 }
             }
-            Statement::While { body, .. } => {
+            StatementKind::While { body, .. } => {
                 if body_contains_selfdestruct(body) {
                     return true;
                 }
             }
-            Statement::For { body, .. } => {
+            StatementKind::For { body, .. } => {
                 if body_contains_selfdestruct(body) {
                     return true;
                 }
@@ -954,14 +1760,19 @@ fn lower_constructor(
             Ok(InstructionParam {
                 name: p.name.name.to_string(),
                 ty: lower_type(&p.ty)?,
+                constraints: Vec::new(),
+                is_mut: is_mut_param(p),
             })
         })
         .collect::<Result<Vec<_>, CodegenError>>()?;
 
     let body = lower_block(&ctor.body, ctx, &mut collector)?;
+    let params = mark_written_accounts(params, &collector, &[]);
 
     Ok(Instruction {
         name: "initialize".to_string(),
+        docs: ctor.docs.clone(),
+        span: ctor.span,
         params,
         returns: None,
         body,
@@ -973,6 +1784,8 @@ fn lower_constructor(
         modifiers: Vec::new(),
         mapping_accesses: collector.accesses,
         closes_state: false, // Constructor never closes state
+        state_constraints: Vec::new(),
+        state_growth: Vec::new(),
     })
 }
 
@@ -989,6 +1802,8 @@ fn lower_modifier(
             Ok(InstructionParam {
                 name: p.name.name.to_string(),
                 ty: lower_type(&p.ty)?,
+                constraints: Vec::new(),
+                is_mut: false,
             })
         })
         .collect::<Result<Vec<_>, CodegenError>>()?;
@@ -1086,29 +1901,48 @@ fn lower_type(ty: &ast::TypeExpr) -> Result<SolanaType, CodegenError> {
                 "signer" => Ok(SolanaType::Signer),
                 "string" => Ok(SolanaType::String),
                 "bytes" => Ok(SolanaType::Bytes),
+                "account" => {
+                    let inner = path.generic_args.as_ref().and_then(|g| g.args.first());
+                    match inner.map(|t| t.name()).as_deref() {
+                        Some("TokenAccount") => Ok(SolanaType::Account(AccountKind::TokenAccount)),
+                        Some("Mint") => Ok(SolanaType::Account(AccountKind::Mint)),
+                        other => Err(CodegenError::unsupported_feature(
+                            format!("Account type account<{}>", other.unwrap_or("")),
+                            ty.span(),
+                        )),
+                    }
+                }
+                "unchecked_account" => Ok(SolanaType::Account(AccountKind::Unchecked)),
                 // Fixed-size bytes: bytes1 through bytes32
                 s if s.starts_with("bytes") => {
                     if let Ok(n) = s[5..].parse::<usize>() {
                         if (1..=32).contains(&n) {
                             Ok(SolanaType::FixedBytes(n))
                         } else {
-                            Err(CodegenError::UnsupportedFeature(format!(
-                                "Invalid bytes size: {}",
-                                n
-                            )))
+                            Err(CodegenError::unsupported_feature(
+                                format!("Invalid bytes size: {}", n),
+                                ty.span(),
+                            ))
                         }
                     } else {
                         Ok(SolanaType::Custom(s.to_string()))
                     }
                 }
-                other => Ok(SolanaType::Custom(other.to_string())),
+                other => match ast::FixedType::parse(other) {
+                    Some(fixed) => Ok(SolanaType::Fixed(fixed)),
+                    None => Ok(SolanaType::Custom(other.to_string())),
+                },
             }
         }
+        ast::TypeExpr::Array(arr) if arr.element.name() == "accounts" => {
+            Ok(SolanaType::RemainingAccounts)
+        }
         ast::TypeExpr::Array(arr) => {
             let elem = lower_type(&ast::TypeExpr::Path(arr.element.clone()))?;
             if arr.sizes.len() != 1 {
-                return Err(CodegenError::UnsupportedFeature(
-                    "Multi-dimensional arrays".to_string(),
+                return Err(CodegenError::unsupported_feature(
+                    "Multi-dimensional arrays",
+                    ty.span(),
                 ));
             }
             match &arr.sizes[0] {
@@ -1121,7 +1955,7 @@ fn lower_type(ty: &ast::TypeExpr) -> Result<SolanaType, CodegenError> {
             let value = lower_type(&mapping.value)?;
             Ok(SolanaType::Mapping(Box::new(key), Box::new(value)))
         }
-        ast::TypeExpr::Tuple(_) => Err(CodegenError::UnsupportedFeature("Tuple types".to_string())),
+        ast::TypeExpr::Tuple(_) => Err(CodegenError::unsupported_feature("Tuple types", ty.span())),
     }
 }
 
@@ -1142,29 +1976,45 @@ fn lower_stmt(
     ctx: &LoweringContext,
     collector: &mut MappingAccessCollector,
 ) -> Result<Statement, CodegenError> {
+    Ok(Statement {
+        kind: lower_stmt_kind(stmt, ctx, collector)?,
+        span: stmt.span(),
+    })
+}
+
+fn lower_stmt_kind(
+    stmt: &ast::Stmt,
+    ctx: &LoweringContext,
+    collector: &mut MappingAccessCollector,
+) -> Result<StatementKind, CodegenError> {
     match stmt {
-        ast::Stmt::VarDecl(v) => Ok(Statement::VarDecl {
+        ast::Stmt::VarDecl(v) => Ok(StatementKind::VarDecl {
             name: v.name.name.to_string(),
-            ty: lower_type(&v.ty)?,
+            ty: ctx.lower_local_type(&v.ty)?,
             value: v
                 .initializer
                 .as_ref()
                 .map(|e| lower_expr(e, ctx, collector))
                 .transpose()?,
         }),
-        ast::Stmt::Return(r) => Ok(Statement::Return(
-            r.value
-                .as_ref()
-                .map(|e| lower_expr(e, ctx, collector))
-                .transpose()?,
-        )),
+        ast::Stmt::Return(r) => Ok(StatementKind::Return(match &r.value {
+            // Tuples are only supported as multiple return values
+            Some(ast::Expr::Tuple(t)) => Some(Expression::Tuple(
+                t.elements
+                    .iter()
+                    .map(|e| lower_expr(e, ctx, collector))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            Some(e) => Some(lower_expr(e, ctx, collector)?),
+            None => None,
+        })),
         ast::Stmt::If(i) => lower_if_stmt(i, ctx, collector),
-        ast::Stmt::While(w) => Ok(Statement::While {
+        ast::Stmt::While(w) => Ok(StatementKind::While {
             condition: lower_expr(&w.condition, ctx, collector)?,
             body: lower_block(&w.body, ctx, collector)?,
         }),
         ast::Stmt::For(f) => lower_for_stmt(f, ctx, collector),
-        ast::Stmt::Emit(e) => Ok(Statement::Emit {
+        ast::Stmt::Emit(e) => Ok(StatementKind::Emit {
             event: e.event.name.to_string(),
             args: e
                 .args
@@ -1172,12 +2022,12 @@ fn lower_stmt(
                 .map(|a| lower_expr(&a.value, ctx, collector))
                 .collect::<Result<Vec<_>, _>>()?,
         }),
-        ast::Stmt::Require(r) => Ok(Statement::Require {
+        ast::Stmt::Require(r) => Ok(StatementKind::Require {
             condition: lower_expr(&r.condition, ctx, collector)?,
             message: r.message.as_ref().map(|s| s.to_string()),
         }),
         ast::Stmt::Revert(r) => match &r.kind {
-            ast::RevertKind::Message(msg) => Ok(Statement::Require {
+            ast::RevertKind::Message(msg) => Ok(StatementKind::Require {
                 condition: Expression::Literal(Literal::Bool(false)),
                 message: msg
                     .as_ref()
@@ -1189,7 +2039,7 @@ fn lower_stmt(
                     .iter()
                     .map(|a| lower_expr(&a.value, ctx, collector))
                     .collect::<Result<Vec<_>, _>>()?;
-                Ok(Statement::RevertWithError {
+                Ok(StatementKind::RevertWithError {
                     error_name: name.name.to_string(),
                     args: lowered_args,
                 })
@@ -1207,13 +2057,13 @@ fn lower_stmt(
                     }
                 }
             }
-            Ok(Statement::Delete(target))
+            Ok(StatementKind::Delete(target))
         }
-        ast::Stmt::Selfdestruct(s) => Ok(Statement::Selfdestruct {
+        ast::Stmt::Selfdestruct(s) => Ok(StatementKind::Selfdestruct {
             recipient: lower_expr(&s.recipient, ctx, collector)?,
         }),
-        ast::Stmt::Expr(e) => Ok(Statement::Expr(lower_expr(&e.expr, ctx, collector)?)),
-        ast::Stmt::Placeholder(_) => Ok(Statement::Placeholder),
+        ast::Stmt::Expr(e) => Ok(StatementKind::Expr(lower_expr(&e.expr, ctx, collector)?)),
+        ast::Stmt::Placeholder(_) => Ok(StatementKind::Placeholder),
     }
 }
 
@@ -1221,16 +2071,19 @@ fn lower_if_stmt(
     i: &ast::IfStmt,
     ctx: &LoweringContext,
     collector: &mut MappingAccessCollector,
-) -> Result<Statement, CodegenError> {
+) -> Result<StatementKind, CodegenError> {
     let condition = lower_expr(&i.condition, ctx, collector)?;
     let then_block = lower_block(&i.then_block, ctx, collector)?;
     let else_block = match &i.else_branch {
         Some(ast::ElseBranch::Else(block)) => Some(lower_block(block, ctx, collector)?),
-        Some(ast::ElseBranch::ElseIf(elif)) => Some(vec![lower_if_stmt(elif, ctx, collector)?]),
+        Some(ast::ElseBranch::ElseIf(elif)) => Some(vec![Statement {
+            kind: lower_if_stmt(elif, ctx, collector)?,
+            span: elif.span,
+        }]),
         None => None,
     };
 
-    Ok(Statement::If {
+    Ok(StatementKind::If {
         condition,
         then_block,
         else_block,
@@ -1241,24 +2094,28 @@ fn lower_for_stmt(
     f: &ast::ForStmt,
     ctx: &LoweringContext,
     collector: &mut MappingAccessCollector,
-) -> Result<Statement, CodegenError> {
+) -> Result<StatementKind, CodegenError> {
     let init = match &f.init {
-        Some(ast::ForInit::VarDecl(v)) => Some(Box::new(Statement::VarDecl {
-            name: v.name.name.to_string(),
-            ty: lower_type(&v.ty)?,
-            value: v
-                .initializer
-                .as_ref()
-                .map(|e| lower_expr(e, ctx, collector))
-                .transpose()?,
+        Some(ast::ForInit::VarDecl(v)) => Some(Box::new(Statement {
+            kind: StatementKind::VarDecl {
+                name: v.name.name.to_string(),
+                ty: ctx.lower_local_type(&v.ty)?,
+                value: v
+                    .initializer
+                    .as_ref()
+                    .map(|e| lower_expr(e, ctx, collector))
+                    .transpose()?,
+            },
+            span: v.span,
+        })),
+        Some(ast::ForInit::Expr(e)) => Some(Box::new(Statement {
+            kind: StatementKind::Expr(lower_expr(e, ctx, collector)?),
+            span: e.span(),
         })),
-        Some(ast::ForInit::Expr(e)) => {
-            Some(Box::new(Statement::Expr(lower_expr(e, ctx, collector)?)))
-        }
         None => None,
     };
 
-    Ok(Statement::For {
+    Ok(StatementKind::For {
         init,
         condition: f
             .condition
@@ -1493,6 +2350,18 @@ fn lower_expr(
                     }
                 }
 
+                // Handle ufixed64x6(x), fixed128x18(x), etc. - conversion to fixed-point
+                if let Some(ty) = ast::FixedType::parse(&func_name) {
+                    if c.args.len() == 1 {
+                        ctx.uses_fixed.set(true);
+                        let value = lower_expr(&c.args[0].value, ctx, collector)?;
+                        return Ok(Expression::ToFixed {
+                            ty,
+                            value: Box::new(value),
+                        });
+                    }
+                }
+
                 // Handle interface type cast: IERC20(address) -> InterfaceCast for CPI
                 if ctx.is_interface(&func_name) && c.args.len() == 1 {
                     let program_id = lower_expr(&c.args[0].value, ctx, collector)?;
@@ -1522,8 +2391,9 @@ fn lower_expr(
                         .collect::<Result<Vec<_>, _>>()?,
                 })
             } else {
-                Err(CodegenError::UnsupportedFeature(
-                    "Complex call expressions".to_string(),
+                Err(CodegenError::unsupported_feature(
+                    "Complex call expressions",
+                    c.span,
                 ))
             }
         }
@@ -1542,11 +2412,20 @@ fn lower_expr(
                 program_id,
             } = receiver
             {
+                // Accounts the imported instruction writes to
+                if let Some(cpi) = ctx.cpi_instruction(&interface_name, &method) {
+                    for (account, arg) in cpi.accounts.iter().zip(&args) {
+                        if account.is_mut {
+                            collector.mark_written(arg);
+                        }
+                    }
+                }
                 return Ok(Expression::CpiCall {
                     program: program_id,
                     interface_name,
                     method,
                     args,
+                    span: m.method.span,
                 });
             }
 
@@ -1556,21 +2435,33 @@ fn lower_expr(
                     ("msg", "sender") => return Ok(Expression::MsgSender),
                     ("msg", "value") => return Ok(Expression::MsgValue),
                     ("block", "timestamp") => return Ok(Expression::BlockTimestamp),
+                    _ => {}
+                }
+
+                match (ctx.intrinsic(name), method.as_str()) {
+                    // Math library: Math.mulDiv(x, y, denominator), ...
+                    (Some("Math"), name) => {
+                        if let Some(function) = MathFunction::from_name(name) {
+                            return Ok(Expression::Math { function, args });
+                        }
+                    }
                     // Solana Rent sysvar methods
-                    ("rent", "minimumBalance") if args.len() == 1 => {
+                    (Some("Rent"), "minimumBalance") if args.len() == 1 => {
                         return Ok(Expression::RentMinimumBalance {
                             data_len: Box::new(args[0].clone()),
                         });
                     }
-                    ("rent", "isExempt") if args.len() == 2 => {
+                    (Some("Rent"), "isExempt") if args.len() == 2 => {
                         return Ok(Expression::RentIsExempt {
                             lamports: Box::new(args[0].clone()),
                             data_len: Box::new(args[1].clone()),
                         });
                     }
                     // SPL Token operations: token.transfer(from, to, authority, amount)
-                    ("token", "transfer") if args.len() == 4 => {
+                    (Some("Token"), "transfer") if args.len() == 4 => {
                         collector.mark_uses_token_program();
+                        collector.mark_written(&args[0]);
+                        collector.mark_written(&args[1]);
                         return Ok(Expression::TokenTransfer {
                             from: Box::new(args[0].clone()),
                             to: Box::new(args[1].clone()),
@@ -1579,8 +2470,10 @@ fn lower_expr(
                         });
                     }
                     // SPL Token mint: token.mint(mint, to, authority, amount)
-                    ("token", "mint") if args.len() == 4 => {
+                    (Some("Token"), "mint") if args.len() == 4 => {
                         collector.mark_uses_token_program();
+                        collector.mark_written(&args[0]);
+                        collector.mark_written(&args[1]);
                         return Ok(Expression::TokenMint {
                             mint: Box::new(args[0].clone()),
                             to: Box::new(args[1].clone()),
@@ -1589,8 +2482,10 @@ fn lower_expr(
                         });
                     }
                     // SPL Token burn: token.burn(from, mint, authority, amount)
-                    ("token", "burn") if args.len() == 4 => {
+                    (Some("Token"), "burn") if args.len() == 4 => {
                         collector.mark_uses_token_program();
+                        collector.mark_written(&args[0]);
+                        collector.mark_written(&args[1]);
                         return Ok(Expression::TokenBurn {
                             from: Box::new(args[0].clone()),
                             mint: Box::new(args[1].clone()),
@@ -1599,7 +2494,7 @@ fn lower_expr(
                         });
                     }
                     // Get Associated Token Address: token.getATA(owner, mint)
-                    ("token", "getATA") if args.len() == 2 => {
+                    (Some("Token"), "getATA") if args.len() == 2 => {
                         collector.mark_uses_token_program();
                         return Ok(Expression::GetATA {
                             owner: Box::new(args[0].clone()),
@@ -1627,11 +2522,16 @@ fn lower_expr(
                     ("msg", "value") => return Ok(Expression::MsgValue),
                     ("block", "timestamp") => return Ok(Expression::BlockTimestamp),
                     ("block", "number") => return Ok(Expression::BlockTimestamp), // Solana uses slots
-                    // Solana Clock sysvar fields
-                    ("clock", "timestamp") => return Ok(Expression::ClockUnixTimestamp),
-                    ("clock", "unix_timestamp") => return Ok(Expression::ClockUnixTimestamp),
-                    ("clock", "slot") => return Ok(Expression::ClockSlot),
-                    ("clock", "epoch") => return Ok(Expression::ClockEpoch),
+                    _ => {}
+                }
+
+                // Solana Clock sysvar fields
+                match (ctx.intrinsic(name), field.as_str()) {
+                    (Some("Clock"), "timestamp" | "unix_timestamp") => {
+                        return Ok(Expression::ClockUnixTimestamp)
+                    }
+                    (Some("Clock"), "slot") => return Ok(Expression::ClockSlot),
+                    (Some("Clock"), "epoch") => return Ok(Expression::ClockEpoch),
                     _ => {}
                 }
             }
@@ -1746,14 +2646,17 @@ fn lower_expr(
             }
         }
         ast::Expr::Paren(e) => lower_expr(e, ctx, collector),
-        ast::Expr::If(_) => Err(CodegenError::UnsupportedFeature(
-            "If expressions".to_string(),
+        ast::Expr::If(_) => Err(CodegenError::unsupported_feature(
+            "If expressions",
+            expr.span(),
         )),
-        ast::Expr::Tuple(_) => Err(CodegenError::UnsupportedFeature(
-            "Tuple expressions".to_string(),
+        ast::Expr::Tuple(_) => Err(CodegenError::unsupported_feature(
+            "Tuple expressions",
+            expr.span(),
         )),
-        ast::Expr::New(_) => Err(CodegenError::UnsupportedFeature(
-            "New expressions (use CPI instead)".to_string(),
+        ast::Expr::New(_) => Err(CodegenError::unsupported_feature(
+            "New expressions (use CPI instead)",
+            expr.span(),
         )),
     }
 }
@@ -1763,13 +2666,18 @@ fn lower_literal(lit: &ast::Literal) -> Result<Expression, CodegenError> {
         ast::Literal::Bool(b, _) => Ok(Expression::Literal(Literal::Bool(*b))),
         ast::Literal::Int(n, _) => Ok(Expression::Literal(Literal::Uint(*n))),
         ast::Literal::HexInt(s, _) => {
-            let n = u128::from_str_radix(s.trim_start_matches("0x"), 16)
-                .map_err(|_| CodegenError::TypeConversion(format!("Invalid hex: {}", s)))?;
+            let n = u128::from_str_radix(s.trim_start_matches("0x"), 16).map_err(|_| {
+                CodegenError::type_conversion(format!("Invalid hex: {}", s), lit.span())
+            })?;
             Ok(Expression::Literal(Literal::Uint(n)))
         }
         ast::Literal::String(s, _) => Ok(Expression::Literal(Literal::String(s.to_string()))),
         ast::Literal::HexString(s, _) => Ok(Expression::Literal(Literal::String(s.to_string()))),
         ast::Literal::Address(s, _) => Ok(Expression::Literal(Literal::Pubkey(s.to_string()))),
+        ast::Literal::Decimal(digits, decimals, _) => Ok(Expression::Literal(Literal::Decimal {
+            digits: *digits,
+            decimals: *decimals,
+        })),
     }
 }
 
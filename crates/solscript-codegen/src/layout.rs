@@ -0,0 +1,493 @@
+//! Account Layout
+//!
+//! Computes the byte layout of the generated accounts from the IR, following the
+//! borsh encoding and the `#[max_len]` bounds that size `INIT_SPACE`, and compares
+//! two layouts for upgrade compatibility.
+
+use crate::ir::*;
+use crate::CodegenError;
+use serde::Serialize;
+use solscript_ast::Span;
+
+/// `#[max_len]` of `string` fields
+pub(crate) const MAX_STRING_LEN: usize = 200;
+/// `#[max_len]` of `bytes` fields
+pub(crate) const MAX_BYTES_LEN: usize = 1000;
+/// `#[max_len]` of dynamic arrays
+pub(crate) const MAX_VEC_LEN: usize = 100;
+
+/// Size of the Anchor account discriminator that precedes account data
+pub const DISCRIMINATOR_SIZE: usize = 8;
+
+/// Bytes of account metadata that rent is charged for on top of the data
+pub const ACCOUNT_STORAGE_OVERHEAD: usize = 128;
+/// Default rent of 3480 lamports per byte-year, times the two-year exemption threshold
+const RENT_EXEMPT_LAMPORTS_PER_BYTE: u64 = 3480 * 2;
+
+/// Minimum balance for an account with `data_len` bytes of data to be rent-exempt,
+/// at the default rent parameters
+pub fn rent_exempt_lamports(data_len: usize) -> u64 {
+    (ACCOUNT_STORAGE_OVERHEAD + data_len) as u64 * RENT_EXEMPT_LAMPORTS_PER_BYTE
+}
+
+/// Layout of every account and struct type of a program
+#[derive(Debug, Clone)]
+pub struct ProgramLayout {
+    pub state: AccountLayout,
+    /// One PDA account per mapping entry
+    pub mappings: Vec<AccountLayout>,
+    /// User-defined structs, stored inline in accounts
+    pub structs: Vec<AccountLayout>,
+}
+
+/// Byte layout of an account (after its discriminator) or a struct
+#[derive(Debug, Clone)]
+pub struct AccountLayout {
+    pub name: String,
+    /// Accounts start with an 8-byte discriminator, structs don't
+    pub is_account: bool,
+    pub fields: Vec<FieldLayout>,
+    /// Size in bytes, with dynamic fields at their maximum length; includes the
+    /// discriminator of accounts
+    pub size: usize,
+}
+
+/// A field of an [`AccountLayout`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldLayout {
+    pub name: String,
+    pub ty: String,
+    /// Byte offset; `None` after a dynamic field, whose encoded length varies
+    pub offset: Option<usize>,
+    /// Size in bytes (the maximum, for dynamic fields)
+    pub size: usize,
+    /// Whether the encoded size depends on the value (strings, vectors, options)
+    pub dynamic: bool,
+}
+
+impl ProgramLayout {
+    /// All layouts: state, mapping entries, then structs
+    pub fn accounts(&self) -> impl Iterator<Item = &AccountLayout> {
+        std::iter::once(&self.state)
+            .chain(&self.mappings)
+            .chain(&self.structs)
+    }
+}
+
+/// Compute the layout of a program's accounts
+pub fn program_layout(program: &SolanaProgram) -> Result<ProgramLayout, CodegenError> {
+    let sizer = Sizer { program };
+
+    let state_fields: Vec<(String, &SolanaType)> = program
+        .state
+        .fields
+        .iter()
+        .map(|f| (to_snake_case(&f.name), &f.ty))
+        .collect();
+    let state = sizer.layout(
+        format!("{}State", to_pascal_case(&program.name)),
+        true,
+        &state_fields,
+    )?;
+
+    let mut mappings = Vec::new();
+    for mapping in &program.mappings {
+        let mut value_ty = &mapping.value_ty;
+        while let SolanaType::Mapping(_, inner) = value_ty {
+            value_ty = inner;
+        }
+        mappings.push(sizer.layout(
+            format!("{}Entry", to_pascal_case(&mapping.name)),
+            true,
+            &[
+                ("key".to_string(), &mapping.key_ty),
+                ("value".to_string(), value_ty),
+            ],
+        )?);
+    }
+
+    let mut structs = Vec::new();
+    for s in &program.structs {
+        let fields: Vec<(String, &SolanaType)> = s
+            .fields
+            .iter()
+            .map(|f| (to_snake_case(&f.name), &f.ty))
+            .collect();
+        structs.push(sizer.layout(to_pascal_case(&s.name), false, &fields)?);
+    }
+
+    Ok(ProgramLayout {
+        state,
+        mappings,
+        structs,
+    })
+}
+
+/// Size and rent of one account type, as allocated by the generated program
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountCost {
+    pub name: String,
+    pub kind: AccountCostKind,
+    /// Allocated bytes, including the discriminator (`8 + INIT_SPACE`)
+    pub size: usize,
+    pub rent_exempt_lamports: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AccountCostKind {
+    /// The program's state account, created once by `initialize`
+    State,
+    /// A mapping entry PDA, created for each key written
+    MappingEntry,
+}
+
+impl ProgramLayout {
+    /// Size and rent of each account type (structs are stored inside accounts)
+    pub fn account_costs(&self) -> Vec<AccountCost> {
+        let cost = |account: &AccountLayout, kind| AccountCost {
+            name: account.name.clone(),
+            kind,
+            size: account.size,
+            rent_exempt_lamports: rent_exempt_lamports(account.size),
+        };
+
+        std::iter::once(cost(&self.state, AccountCostKind::State))
+            .chain(
+                self.mappings
+                    .iter()
+                    .map(|m| cost(m, AccountCostKind::MappingEntry)),
+            )
+            .collect()
+    }
+}
+
+/// A difference between two layouts of the same account or struct
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutChange {
+    pub account: String,
+    /// `None` when the whole account or struct was added or removed
+    pub field: Option<String>,
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// The field now starts at a different offset, or after different fields
+    Moved {
+        from: Option<usize>,
+        to: Option<usize>,
+    },
+    Resized {
+        from: usize,
+        to: usize,
+    },
+    Retyped {
+        from: String,
+        to: String,
+    },
+}
+
+impl LayoutChange {
+    /// Whether existing accounts can no longer be read with the new layout.
+    ///
+    /// Only additions are compatible: new accounts, and new fields after all
+    /// existing ones (which move nothing).
+    pub fn is_breaking(&self) -> bool {
+        !matches!(self.kind, ChangeKind::Added)
+    }
+}
+
+impl std::fmt::Display for LayoutChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let subject = match &self.field {
+            Some(field) => format!("{}.{}", self.account, field),
+            None => self.account.clone(),
+        };
+        let offset =
+            |o: &Option<usize>| o.map_or("a dynamic offset".to_string(), |o| o.to_string());
+        match &self.kind {
+            ChangeKind::Added => write!(f, "{} added", subject),
+            ChangeKind::Removed => write!(f, "{} removed", subject),
+            ChangeKind::Moved {
+                from: None,
+                to: None,
+            } => write!(f, "{} moved (the fields before it changed)", subject),
+            ChangeKind::Moved { from, to } => {
+                write!(
+                    f,
+                    "{} moved from {} to {}",
+                    subject,
+                    offset(from),
+                    offset(to)
+                )
+            }
+            ChangeKind::Resized { from, to } => {
+                write!(f, "{} resized from {} to {} bytes", subject, from, to)
+            }
+            ChangeKind::Retyped { from, to } => {
+                write!(f, "{} changed type from {} to {}", subject, from, to)
+            }
+        }
+    }
+}
+
+/// Compare an old and a new layout, matching accounts and fields by name
+pub fn diff_layouts(old: &ProgramLayout, new: &ProgramLayout) -> Vec<LayoutChange> {
+    let mut changes = Vec::new();
+
+    for old_account in old.accounts() {
+        match new.accounts().find(|a| a.name == old_account.name) {
+            Some(new_account) => diff_account(old_account, new_account, &mut changes),
+            None => changes.push(LayoutChange {
+                account: old_account.name.clone(),
+                field: None,
+                kind: ChangeKind::Removed,
+            }),
+        }
+    }
+    for new_account in new.accounts() {
+        if !old.accounts().any(|a| a.name == new_account.name) {
+            changes.push(LayoutChange {
+                account: new_account.name.clone(),
+                field: None,
+                kind: ChangeKind::Added,
+            });
+        }
+    }
+
+    changes
+}
+
+fn diff_account(old: &AccountLayout, new: &AccountLayout, changes: &mut Vec<LayoutChange>) {
+    let change = |field: &FieldLayout, kind| LayoutChange {
+        account: old.name.clone(),
+        field: Some(field.name.clone()),
+        kind,
+    };
+
+    for (index, old_field) in old.fields.iter().enumerate() {
+        let Some(new_field) = new.fields.iter().find(|f| f.name == old_field.name) else {
+            changes.push(change(old_field, ChangeKind::Removed));
+            continue;
+        };
+        if old_field.ty != new_field.ty {
+            let kind = if old_field.size != new_field.size {
+                ChangeKind::Resized {
+                    from: old_field.size,
+                    to: new_field.size,
+                }
+            } else {
+                ChangeKind::Retyped {
+                    from: old_field.ty.clone(),
+                    to: new_field.ty.clone(),
+                }
+            };
+            changes.push(change(old_field, kind));
+        }
+        // Past a dynamic field the offset isn't static; the field still moves
+        // if anything before it changed
+        let moved = match (old_field.offset, new_field.offset) {
+            (Some(from), Some(to)) => from != to,
+            _ => {
+                let new_index = new.fields.iter().position(|f| f.name == old_field.name);
+                let before_new = &new.fields[..new_index.unwrap_or(0)];
+                let before_old = &old.fields[..index];
+                before_new.len() != before_old.len()
+                    || before_new
+                        .iter()
+                        .zip(before_old)
+                        .any(|(n, o)| n.name != o.name || n.ty != o.ty)
+            }
+        };
+        if moved {
+            changes.push(change(
+                old_field,
+                ChangeKind::Moved {
+                    from: old_field.offset,
+                    to: new_field.offset,
+                },
+            ));
+        }
+    }
+
+    for new_field in &new.fields {
+        if !old.fields.iter().any(|f| f.name == new_field.name) {
+            changes.push(change(new_field, ChangeKind::Added));
+        }
+    }
+}
+
+/// Borsh sizes of IR types, resolving user-defined structs and enums
+struct Sizer<'a> {
+    program: &'a SolanaProgram,
+}
+
+impl Sizer<'_> {
+    fn layout(
+        &self,
+        name: String,
+        is_account: bool,
+        fields: &[(String, &SolanaType)],
+    ) -> Result<AccountLayout, CodegenError> {
+        let mut offset = Some(if is_account { DISCRIMINATOR_SIZE } else { 0 });
+        let mut size = offset.unwrap_or(0);
+        let mut layouts = Vec::new();
+
+        for (field_name, ty) in fields {
+            let field_size = self.size(ty)?;
+            let dynamic = self.is_dynamic(ty);
+            layouts.push(FieldLayout {
+                name: field_name.clone(),
+                ty: type_name(ty),
+                offset,
+                size: field_size,
+                dynamic,
+            });
+            size += field_size;
+            offset = if dynamic {
+                None
+            } else {
+                offset.map(|o| o + field_size)
+            };
+        }
+
+        Ok(AccountLayout {
+            name,
+            is_account,
+            fields: layouts,
+            size,
+        })
+    }
+
+    /// Encoded size in bytes, at the maximum length for dynamic types
+    fn size(&self, ty: &SolanaType) -> Result<usize, CodegenError> {
+        Ok(match ty {
+            SolanaType::Bool => 1,
+            SolanaType::String => 4 + MAX_STRING_LEN,
+            SolanaType::Bytes => 4 + MAX_BYTES_LEN,
+            SolanaType::Vec(inner) => 4 + MAX_VEC_LEN * self.size(inner)?,
+            SolanaType::Option(inner) => 1 + self.size(inner)?,
+            SolanaType::Array(inner, n) => n * self.size(inner)?,
+            SolanaType::Fixed(fixed) => usize::from(fixed.bits / 8),
+            SolanaType::Custom(name) => {
+                if let Some(s) = self.program.structs.iter().find(|s| s.name == *name) {
+                    let mut total = 0;
+                    for field in &s.fields {
+                        total += self.size(&field.ty)?;
+                    }
+                    total
+                } else if self.program.enums.iter().any(|e| e.name == *name) {
+                    // Enums only have unit variants
+                    1
+                } else {
+                    return Err(CodegenError::unsupported_feature(
+                        format!("No storage layout for type '{}'", name),
+                        Span::dummy(),
+                    ));
+                }
+            }
+            _ => match ty.pod_layout() {
+                Some((size, _)) => size,
+                None => {
+                    return Err(CodegenError::unsupported_feature(
+                        format!("No storage layout for type '{}'", type_name(ty)),
+                        Span::dummy(),
+                    ))
+                }
+            },
+        })
+    }
+
+    fn is_dynamic(&self, ty: &SolanaType) -> bool {
+        match ty {
+            SolanaType::String | SolanaType::Bytes | SolanaType::Vec(_) | SolanaType::Option(_) => {
+                true
+            }
+            SolanaType::Array(inner, _) => self.is_dynamic(inner),
+            SolanaType::Custom(name) => self
+                .program
+                .structs
+                .iter()
+                .find(|s| s.name == *name)
+                .is_some_and(|s| s.fields.iter().any(|f| self.is_dynamic(&f.ty))),
+            _ => false,
+        }
+    }
+}
+
+/// Rust spelling of a stored type
+fn type_name(ty: &SolanaType) -> String {
+    match ty {
+        SolanaType::U8 => "u8".to_string(),
+        SolanaType::U16 => "u16".to_string(),
+        SolanaType::U32 => "u32".to_string(),
+        SolanaType::U64 => "u64".to_string(),
+        SolanaType::U128 => "u128".to_string(),
+        SolanaType::I8 => "i8".to_string(),
+        SolanaType::I16 => "i16".to_string(),
+        SolanaType::I32 => "i32".to_string(),
+        SolanaType::I64 => "i64".to_string(),
+        SolanaType::I128 => "i128".to_string(),
+        SolanaType::Bool => "bool".to_string(),
+        SolanaType::Pubkey | SolanaType::Signer | SolanaType::Account(_) => "Pubkey".to_string(),
+        SolanaType::String => "String".to_string(),
+        SolanaType::Bytes => "Vec<u8>".to_string(),
+        SolanaType::FixedBytes(n) => format!("[u8; {}]", n),
+        SolanaType::Array(inner, n) => format!("[{}; {}]", type_name(inner), n),
+        SolanaType::Vec(inner) => format!("Vec<{}>", type_name(inner)),
+        SolanaType::Option(inner) => format!("Option<{}>", type_name(inner)),
+        SolanaType::Mapping(key, value) => {
+            format!("mapping({} => {})", type_name(key), type_name(value))
+        }
+        SolanaType::RemainingAccounts => "Vec<Pubkey>".to_string(),
+        SolanaType::Fixed(fixed) => crate::rust_gen::fixed_type_name(fixed),
+        SolanaType::Custom(name) => to_pascal_case(name),
+        SolanaType::Tuple(types) => format!(
+            "({})",
+            types.iter().map(type_name).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+// Same case conversions as the Rust generator, so names match the generated code
+fn to_snake_case(s: &str) -> String {
+    let mut result = String::new();
+    let mut prev_upper = false;
+
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && !prev_upper {
+                result.push('_');
+            }
+            result.push(c.to_lowercase().next().unwrap());
+            prev_upper = true;
+        } else {
+            result.push(c);
+            prev_upper = false;
+        }
+    }
+
+    result
+}
+
+fn to_pascal_case(s: &str) -> String {
+    let mut result = String::new();
+    let mut capitalize_next = true;
+
+    for c in s.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            result.push(c.to_uppercase().next().unwrap());
+            capitalize_next = false;
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
@@ -6,18 +6,30 @@
 mod error;
 mod idl_gen;
 mod ir;
+mod layout;
+mod native_gen;
 mod rust_gen;
+mod source_map;
 mod test_gen;
 mod ts_gen;
 
 pub use error::CodegenError;
-pub use idl_gen::IdlGenerator;
+pub use idl_gen::{IdlFormat, IdlGenerator, PLACEHOLDER_PROGRAM_ID};
 pub use ir::*;
+pub use layout::{
+    diff_layouts, program_layout, rent_exempt_lamports, AccountCost, AccountCostKind,
+    AccountLayout, ChangeKind, FieldLayout, LayoutChange, ProgramLayout, ACCOUNT_STORAGE_OVERHEAD,
+    DISCRIMINATOR_SIZE,
+};
+pub use native_gen::NativeGenerator;
 pub use rust_gen::RustGenerator;
+pub use source_map::{Mapping, SourceMap, SOURCE_MAP_VERSION};
 pub use test_gen::TestGenerator;
 pub use ts_gen::TypeScriptGenerator;
 
+use serde::{Deserialize, Serialize};
 use solscript_ast::Program;
+use std::borrow::Cow;
 
 /// Generate Anchor Rust code from a SolScript program
 pub fn generate(program: &Program) -> Result<GeneratedProject, CodegenError> {
@@ -29,6 +41,77 @@ pub fn generate(program: &Program) -> Result<GeneratedProject, CodegenError> {
     generator.generate(&ir)
 }
 
+/// Rust backend that emits the on-chain program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Anchor program (`anchor build`)
+    #[default]
+    Anchor,
+    /// Plain `solana-program` crate without Anchor (`cargo build-sbf`)
+    Native,
+}
+
+/// Settings for [`generate_with_options`]
+#[derive(Debug, Clone, Default)]
+pub struct CodegenOptions {
+    /// Rust backend for the on-chain program
+    pub backend: Backend,
+    /// Shape of `idl.json`
+    pub idl_format: IdlFormat,
+    /// Program address (base58) for `declare_id!`, the IDL and the TypeScript client;
+    /// a placeholder when unset
+    pub program_id: Option<String>,
+    /// Program address per cluster for Anchor.toml's `[programs.<cluster>]` tables
+    pub cluster_program_ids: std::collections::BTreeMap<String, String>,
+    /// Path of the SolScript source, recorded in the source map
+    pub source_path: Option<String>,
+}
+
+/// Generate a project from a SolScript program with the given options
+pub fn generate_with_options(
+    program: &Program,
+    options: &CodegenOptions,
+) -> Result<GeneratedProject, CodegenError> {
+    let ir = lower_to_ir(program)?;
+
+    let program_id = options
+        .program_id
+        .clone()
+        .unwrap_or_else(|| PLACEHOLDER_PROGRAM_ID.to_string());
+    let idl = IdlGenerator::new()
+        .with_format(options.idl_format)
+        .with_address(program_id.clone());
+    let mut project = match options.backend {
+        Backend::Anchor => RustGenerator::new()
+            .with_idl(idl)
+            .with_program_id(program_id)
+            .with_cluster_program_ids(options.cluster_program_ids.clone())
+            .generate(&ir)?,
+        Backend::Native => NativeGenerator::new()
+            .with_idl(idl)
+            .with_program_id(program_id)
+            .generate(&ir)?,
+    };
+    if let Some(path) = &options.source_path {
+        project.source_map.source = path.clone();
+    }
+    Ok(project)
+}
+
+/// Name of the deployed program's crate, which also names its
+/// `target/deploy/<name>-keypair.json`
+pub fn program_crate_name(program: &Program) -> Result<String, CodegenError> {
+    let ir = lower_to_ir(program)?;
+    let deployed = ir.last().ok_or_else(|| {
+        CodegenError::missing_element(
+            "No deployable contracts found (abstract contracts cannot be deployed)",
+            solscript_ast::Span::dummy(),
+        )
+    })?;
+    Ok(rust_gen::to_snake_case(&deployed.name))
+}
+
 /// A generated Anchor project
 #[derive(Debug)]
 pub struct GeneratedProject {
@@ -62,53 +145,89 @@ pub struct GeneratedProject {
     pub rust_tests: String,
     /// Whether there are any SolScript tests
     pub has_tests: bool,
+    /// Maps lines of the generated Rust back to SolScript spans (program.solmap)
+    pub source_map: SourceMap,
 }
 
+/// Directory of the generated program's sources, relative to the project root
+const PROGRAM_SRC_DIR: &str = "programs/solscript_program/src";
+
 impl GeneratedProject {
-    /// Write the project to a directory
-    pub fn write_to_dir(&self, dir: &std::path::Path) -> std::io::Result<()> {
-        use std::fs;
+    /// Strip the span markers left by the generators, collecting them into `source_map`
+    pub(crate) fn extract_source_map(mut self) -> Self {
+        let mut mappings = Vec::new();
+        for (file, text) in [
+            ("lib.rs", &mut self.lib_rs),
+            ("instructions.rs", &mut self.instructions_rs),
+            ("tests.rs", &mut self.rust_tests),
+        ] {
+            let path = format!("{}/{}", PROGRAM_SRC_DIR, file);
+            *text = source_map::extract(&path, text, &mut mappings);
+        }
+        self.source_map = SourceMap {
+            version: SOURCE_MAP_VERSION,
+            source: self.source_map.source,
+            mappings,
+        };
+        self
+    }
 
-        // Create directory structure
-        let programs_dir = dir.join("programs").join("solscript_program");
-        let src_dir = programs_dir.join("src");
-        let app_dir = dir.join("app");
-        let tests_dir = dir.join("tests");
-        fs::create_dir_all(&src_dir)?;
-        fs::create_dir_all(&app_dir)?;
-        fs::create_dir_all(&tests_dir)?;
-
-        // Write Rust program files
-        fs::write(src_dir.join("lib.rs"), &self.lib_rs)?;
-        fs::write(src_dir.join("state.rs"), &self.state_rs)?;
-        fs::write(src_dir.join("instructions.rs"), &self.instructions_rs)?;
-        fs::write(src_dir.join("error.rs"), &self.error_rs)?;
-        fs::write(src_dir.join("events.rs"), &self.events_rs)?;
-        fs::write(programs_dir.join("Cargo.toml"), &self.cargo_toml)?;
-        fs::write(dir.join("Anchor.toml"), &self.anchor_toml)?;
-
-        // Write TypeScript client
-        fs::write(app_dir.join("client.ts"), &self.client_ts)?;
-
-        // Write tests
-        fs::write(tests_dir.join("program.test.ts"), &self.tests_ts)?;
-
-        // Write Rust tests if any
+    /// Every file of the project, as paths relative to the project root and their contents
+    pub fn files(&self) -> Vec<(String, Cow<'_, str>)> {
+        let src = |file: &str| format!("{}/{}", PROGRAM_SRC_DIR, file);
+        let mut files = vec![
+            (src("lib.rs"), Cow::from(&self.lib_rs)),
+            (src("state.rs"), Cow::from(&self.state_rs)),
+            (src("instructions.rs"), Cow::from(&self.instructions_rs)),
+            (src("error.rs"), Cow::from(&self.error_rs)),
+            (src("events.rs"), Cow::from(&self.events_rs)),
+            (
+                "programs/solscript_program/Cargo.toml".to_string(),
+                Cow::from(&self.cargo_toml),
+            ),
+        ];
+        // Native projects have no Anchor workspace or Anchor test harness
+        if !self.anchor_toml.is_empty() {
+            files.push(("Anchor.toml".to_string(), Cow::from(&self.anchor_toml)));
+        }
+        files.push(("app/client.ts".to_string(), Cow::from(&self.client_ts)));
+        if !self.tests_ts.is_empty() {
+            files.push((
+                "tests/program.test.ts".to_string(),
+                Cow::from(&self.tests_ts),
+            ));
+        }
         if self.has_tests && !self.rust_tests.is_empty() {
-            fs::write(src_dir.join("tests.rs"), &self.rust_tests)?;
+            files.push((src("tests.rs"), Cow::from(&self.rust_tests)));
         }
+        files.push((
+            "target/idl/program.json".to_string(),
+            Cow::from(&self.idl_json),
+        ));
+        let source_map =
+            serde_json::to_string_pretty(&self.source_map).expect("source maps serialize to JSON");
+        files.push(("program.solmap".to_string(), Cow::from(source_map)));
+        files.push(("package.json".to_string(), Cow::from(&self.package_json)));
+        files.push(("README.md".to_string(), Cow::from(&self.readme)));
+        files.push((".gitignore".to_string(), Cow::from(&self.gitignore)));
+        files
+    }
 
-        // Write IDL
-        let target_dir = dir.join("target").join("idl");
-        fs::create_dir_all(&target_dir)?;
-        fs::write(target_dir.join("program.json"), &self.idl_json)?;
+    /// Write the project to a directory
+    pub fn write_to_dir(&self, dir: &std::path::Path) -> std::io::Result<()> {
+        use std::fs;
 
-        // Write package.json
-        fs::write(dir.join("package.json"), &self.package_json)?;
+        // Anchor expects these even when nothing is written into them
+        fs::create_dir_all(dir.join("app"))?;
+        fs::create_dir_all(dir.join("tests"))?;
 
-        // Write README and .gitignore
-        fs::write(dir.join("README.md"), &self.readme)?;
-        fs::write(dir.join(".gitignore"), &self.gitignore)?;
+        for (path, contents) in self.files() {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, contents.as_bytes())?;
+        }
 
         Ok(())
     }
@@ -124,6 +243,15 @@ mod tests {
         generate(&program).map_err(|e| format!("Codegen error: {:?}", e))
     }
 
+    fn parse_and_layout(source: &str) -> ProgramLayout {
+        let program = solscript_parser::parse(source).expect("parse");
+        let ir = lower_to_ir(&program)
+            .expect("lower")
+            .pop()
+            .expect("contract");
+        program_layout(&ir).expect("layout")
+    }
+
     #[test]
     fn test_simple_contract() {
         let source = r#"
@@ -370,6 +498,7 @@ mod tests {
         assert!(result.cargo_toml.contains("[package]"));
         assert!(result.cargo_toml.contains("anchor-lang"));
         assert!(result.cargo_toml.contains("[lib]"));
+        assert!(!result.cargo_toml.contains("bytemuck"));
     }
 
     // ========== Integration Tests ==========
@@ -922,9 +1051,6 @@ mod tests {
     #[test]
     #[ignore] // Requires Anchor installed, slower test
     fn anchor_build_integration() {
-        use std::fs;
-        use std::process::Command;
-
         let source = r#"
             contract Token {
                 uint256 public totalSupply;
@@ -967,9 +1093,33 @@ mod tests {
         let mut checker = solscript_typeck::TypeChecker::new(source.to_string());
         let _ = checker.check_program(&program); // Type check (errors are non-fatal for codegen)
         let project = generate(&program).expect("Codegen failed");
+        cargo_check_generated(&project, "token");
+    }
+
+    /// Integration test that verifies a CPI into an imported IDL program compiles
+    /// with Anchor, including the `'info` lifetimes of its handler.
+    /// Run with: cargo test --package solscript-codegen anchor_build_idl_cpi_integration -- --ignored
+    #[test]
+    #[ignore] // Requires Anchor installed, slower test
+    fn anchor_build_idl_cpi_integration() {
+        let mut program = solscript_parser::parse(IDL_CPI_SOURCE).expect("Parse failed");
+        let name = solscript_ast::Ident::new("Marinade", solscript_ast::Span::dummy());
+        let (interface, _) = solscript_parser::anchor_idl_to_interface(name, MARINADE_IDL).unwrap();
+        program
+            .items
+            .insert(0, solscript_ast::Item::Interface(interface));
+        let project = generate(&program).expect("Codegen failed");
+        cargo_check_generated(&project, "idl_cpi");
+    }
+
+    /// Write `project` to a temp directory and `cargo check` the generated program
+    fn cargo_check_generated(project: &GeneratedProject, label: &str) {
+        use std::fs;
+        use std::process::Command;
 
         // Create temp directory
-        let temp_dir = std::env::temp_dir().join(format!("solscript_test_{}", std::process::id()));
+        let temp_dir =
+            std::env::temp_dir().join(format!("solscript_test_{}_{}", label, std::process::id()));
         fs::create_dir_all(&temp_dir).expect("Failed to create temp dir");
 
         // Write project
@@ -1052,6 +1202,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_multiple_return_values() {
+        let source = r#"
+            contract Pool {
+                uint64 public reserveA;
+                uint64 public reserveB;
+
+                function getReserves() public view returns (uint64, uint64) {
+                    return (reserveA, reserveB);
+                }
+            }
+        "#;
+
+        let result = parse_and_generate(source).unwrap();
+        assert!(result.lib_rs.contains(
+            "pub fn get_reserves(ctx: Context<GetReserves>) -> Result<GetReservesReturn> {"
+        ));
+        assert!(result
+            .lib_rs
+            .contains("Ok((ctx.accounts.state.reserve_a, ctx.accounts.state.reserve_b).into())"));
+        assert!(result.state_rs.contains(
+            "pub struct GetReservesReturn {\n    pub field0: u64,\n    pub field1: u64,\n}"
+        ));
+        assert!(result
+            .state_rs
+            .contains("impl From<(u64, u64)> for GetReservesReturn {"));
+
+        // The IDL names the return struct, which decodes like the tuple
+        let idl: serde_json::Value = serde_json::from_str(&result.idl_json).unwrap();
+        let instruction = &idl["instructions"][0];
+        assert_eq!(instruction["name"], "get_reserves");
+        assert_eq!(
+            instruction["returns"],
+            serde_json::json!({ "defined": { "name": "GetReservesReturn" } })
+        );
+        let return_type = idl["types"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|t| t["name"] == "GetReservesReturn")
+            .expect("return struct in types");
+        assert_eq!(
+            return_type["type"]["fields"],
+            serde_json::json!([
+                { "name": "field0", "type": "u64" },
+                { "name": "field1", "type": "u64" }
+            ])
+        );
+
+        let program = solscript_parser::parse(source).unwrap();
+        let native = generate_with_options(
+            &program,
+            &CodegenOptions {
+                backend: Backend::Native,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(native.state_rs.contains(
+            "#[derive(BorshSerialize, BorshDeserialize, Clone)]\npub struct GetReservesReturn {"
+        ));
+    }
+
     #[test]
     fn test_enum_codegen() {
         let source = r#"
@@ -1254,6 +1467,319 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_std_intrinsics_lower_like_globals() {
+        let contract = |token: &str, clock: &str| {
+            format!(
+                r#"
+            contract TokenVault {{
+                int64 public lastTransfer;
+
+                function transferTokens(address from, address to, address auth, uint64 amt) public {{
+                    {token}.transfer(from, to, auth, amt);
+                    lastTransfer = {clock}.unix_timestamp;
+                }}
+            }}
+        "#
+            )
+        };
+        let global = parse_and_generate(&contract("token", "clock")).unwrap();
+
+        // As the driver loads it: the modules ahead of the importing file
+        let imported = format!(
+            "{}\n{}\nimport {{ Token as Spl }} from \"@solana/token\";\nimport {{ Clock }} from \"@solana/clock\";\n{}",
+            solscript_std::module("@solana/token").unwrap().source,
+            solscript_std::module("@solana/clock").unwrap().source,
+            contract("Spl", "Clock")
+        );
+        let imported = parse_and_generate(&imported).unwrap();
+        assert_eq!(imported.lib_rs, global.lib_rs);
+        assert_eq!(imported.instructions_rs, global.instructions_rs);
+        assert!(global.lib_rs.contains("anchor_spl::token::transfer"));
+        assert!(global.lib_rs.contains("unix_timestamp"));
+    }
+
+    #[test]
+    fn test_math_library() {
+        let source = format!(
+            "{}\nimport {{ Math }} from \"@solana/math\";\n{}",
+            solscript_std::module("@solana/math").unwrap().source,
+            r#"
+            contract Pool {
+                uint64 public supply;
+                uint64 public fee;
+
+                function deposit(uint64 amountA, uint64 amountB) public {
+                    supply = Math.sqrt(Math.mulDiv(amountA, amountB, 1));
+                    fee = Math.mulBpsUp(amountA, 30);
+                    require(Math.min(amountA, amountB) > 1000, "Too small");
+                }
+            }
+        "#
+        );
+        let result = parse_and_generate(&source).unwrap();
+        assert!(result.lib_rs.contains("mod solscript_math {"));
+        assert!(result.lib_rs.contains(
+            "solscript_math::sqrt(solscript_math::mul_div::<u128>(amount_a, amount_b, 1u128)?)?"
+        ));
+        assert!(result
+            .lib_rs
+            .contains("solscript_math::mul_bps_up(amount_a, 30u128)?"));
+        assert!(result
+            .lib_rs
+            .contains("solscript_math::min::<u128>(amount_a, amount_b)? > 1000u128"));
+
+        let program = solscript_parser::parse(&source).unwrap();
+        let native = generate_with_options(
+            &program,
+            &CodegenOptions {
+                backend: Backend::Native,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(native.lib_rs.contains("mod solscript_math {"));
+
+        // Only programs importing Math get the helpers
+        let plain = parse_and_generate("contract Pool { uint64 public supply; }").unwrap();
+        assert!(!plain.lib_rs.contains("solscript_math"));
+    }
+
+    /// The generated math helpers, compiled here against a stand-in `ProgramError`
+    mod math_helpers {
+        #[derive(Debug, PartialEq)]
+        pub enum ProgramError {
+            ArithmeticOverflow,
+            InvalidArgument,
+        }
+
+        include!("../templates/solscript_math.rs");
+
+        #[test]
+        fn test_sqrt() {
+            for x in [
+                0u128,
+                1,
+                2,
+                3,
+                4,
+                15,
+                16,
+                17,
+                1 << 64,
+                u64::MAX as u128,
+                u128::MAX,
+            ] {
+                let root: u128 = solscript_math::sqrt(x).unwrap();
+                assert!(root * root <= x, "sqrt({})", x);
+                assert!(
+                    (root + 1).checked_mul(root + 1).is_none_or(|next| next > x),
+                    "sqrt({})",
+                    x
+                );
+            }
+            let root: u64 = solscript_math::sqrt(u64::MAX).unwrap();
+            assert_eq!(root, u32::MAX as u64);
+        }
+
+        #[test]
+        fn test_mul_div() {
+            let div = |x: u128, y: u128, d: u128| solscript_math::mul_div::<u128>(x, y, d);
+            let div_up = |x: u128, y: u128, d: u128| solscript_math::mul_div_up::<u128>(x, y, d);
+
+            assert_eq!(div(7, 3, 2), Ok(10));
+            assert_eq!(div_up(7, 3, 2), Ok(11));
+            assert_eq!(div_up(8, 3, 2), Ok(12));
+            // The product overflows 128 bits, the result does not
+            assert_eq!(div(u128::MAX, u128::MAX, u128::MAX), Ok(u128::MAX));
+            assert_eq!(div(u128::MAX, 3, 4), Ok(u128::MAX / 4 * 3 + 2));
+            assert_eq!(div(1 << 100, 1 << 100, 1 << 90), Ok(1 << 110));
+            assert_eq!(div_up(u128::MAX, 2, 3), Ok(u128::MAX / 3 * 2));
+            assert_eq!(div(u128::MAX, 2, 7), Ok(u128::MAX / 7 * 2));
+            assert_eq!(div_up(u128::MAX, 2, 7), Ok(u128::MAX / 7 * 2 + 1));
+            assert_eq!(div(u128::MAX, 2, 1), Err(ProgramError::ArithmeticOverflow));
+            assert_eq!(div(1, 1, 0), Err(ProgramError::InvalidArgument));
+
+            // Narrowed to the caller's type
+            assert_eq!(
+                solscript_math::mul_div::<u64>(u64::MAX, 2u64, 2u64),
+                Ok(u64::MAX)
+            );
+            assert_eq!(
+                solscript_math::mul_div::<u64>(u64::MAX, 2u64, 1u64),
+                Err(ProgramError::ArithmeticOverflow)
+            );
+        }
+
+        #[test]
+        fn test_min_max_and_bps() {
+            assert_eq!(solscript_math::min::<u64>(3u64, 5u128), Ok(3));
+            assert_eq!(solscript_math::max::<u64>(3u64, 5u128), Ok(5));
+            assert_eq!(
+                solscript_math::max::<u8>(3u8, 300u16),
+                Err(ProgramError::ArithmeticOverflow)
+            );
+            assert_eq!(solscript_math::mul_bps::<u64>(10_001u64, 30u64), Ok(30));
+            assert_eq!(solscript_math::mul_bps_up::<u64>(10_001u64, 30u64), Ok(31));
+            assert_eq!(solscript_math::mul_bps_up::<u64>(10_000u64, 30u64), Ok(30));
+        }
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let source = r#"
+            contract Vault {
+                ufixed64x6 public rate;
+                fixed128x18 public delta;
+                uint64 public total;
+
+                function accrue(uint64 principal) public {
+                    ufixed64x6 amount = ufixed64x6(principal) * rate;
+                    require(1.0 < rate, "Rate too low");
+                    delta = -delta * -2.25;
+                    total = amount.mulUp(rate).floor();
+                }
+            }
+        "#;
+        let result = parse_and_generate(source).unwrap();
+        assert!(result.lib_rs.contains("mod solscript_math {"));
+        assert!(result.lib_rs.contains("mod solscript_fixed {"));
+        assert!(result
+            .lib_rs
+            .contains("impl<const D: u32> Space for UFixed64<D> {"));
+        assert!(result
+            .state_rs
+            .contains("pub rate: crate::solscript_fixed::UFixed64<6>,"));
+        assert!(result.lib_rs.contains(
+            "let amount: crate::solscript_fixed::UFixed64<6> = (crate::solscript_fixed::UFixed64::<6>::from_int(principal) * ctx.accounts.state.rate);"
+        ));
+        assert!(result.lib_rs.contains(
+            "crate::solscript_fixed::like(&rhs, crate::solscript_fixed::decimal(10, 1)) < rhs"
+        ));
+        assert!(result
+            .lib_rs
+            .contains("* crate::solscript_fixed::negative_decimal(225, 2))"));
+        assert!(result
+            .lib_rs
+            .contains("amount.mul_up(ctx.accounts.state.rate).floor()"));
+
+        // The IDL sees the scaled integer, the client a decimal string
+        assert!(result.idl_json.contains("fixed-point with 6 decimals"));
+        assert!(result.client_ts.contains("rate: string;"));
+        assert!(result.client_ts.contains("export function parseFixed("));
+
+        let program = solscript_parser::parse(source).unwrap();
+        let native = generate_with_options(
+            &program,
+            &CodegenOptions {
+                backend: Backend::Native,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(native
+            .lib_rs
+            .contains("PartialOrd, Ord, borsh::BorshSerialize, borsh::BorshDeserialize)]"));
+        assert!(!native.lib_rs.contains("Space for UFixed64"));
+
+        let plain = parse_and_generate("contract Pool { uint64 public supply; }").unwrap();
+        assert!(!plain.lib_rs.contains("solscript_fixed"));
+        assert!(!plain.client_ts.contains("parseFixed"));
+    }
+
+    /// The generated fixed-point arithmetic, compiled here against stand-in types
+    mod fixed_helpers {
+        #[derive(Debug, PartialEq)]
+        pub enum ProgramError {
+            ArithmeticOverflow,
+            InvalidArgument,
+        }
+
+        include!("../templates/solscript_math.rs");
+
+        #[allow(dead_code)]
+        mod solscript_fixed {
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+            pub struct UFixed64<const D: u32>(pub u64);
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+            pub struct UFixed128<const D: u32>(pub u128);
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+            pub struct Fixed64<const D: u32>(pub i64);
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+            pub struct Fixed128<const D: u32>(pub i128);
+
+            include!("../templates/solscript_fixed.rs");
+        }
+
+        use solscript_fixed::*;
+
+        #[test]
+        fn test_literals_and_conversion() {
+            let rate: UFixed64<6> = decimal(105, 2);
+            assert_eq!(rate, UFixed64(1_050_000));
+            let delta: Fixed128<18> = negative_decimal(225, 2);
+            assert_eq!(delta, Fixed128(-2_250_000_000_000_000_000));
+            assert_eq!(UFixed64::<6>::from_int(3u128), UFixed64(3_000_000));
+            assert_eq!(like(&rate, decimal(1, 0)), UFixed64::<6>(1_000_000));
+        }
+
+        #[test]
+        fn test_rounding() {
+            let x: UFixed64<2> = decimal(100, 2);
+            let three = UFixed64::<2>::from_int(3u64);
+            assert_eq!(x / three, UFixed64(33));
+            assert_eq!(x.div_up(three), UFixed64(34));
+            let small: UFixed64<2> = decimal(5, 2);
+            assert_eq!(small * small, UFixed64(0));
+            assert_eq!(small.mul_up(small), UFixed64(1));
+
+            // Toward zero, and away from zero, for negative values too
+            let y: Fixed64<2> = negative_decimal(100, 2);
+            let three = Fixed64::<2>::from_int(3i64);
+            assert_eq!(y / three, Fixed64(-33));
+            assert_eq!(y.div_up(three), Fixed64(-34));
+            assert_eq!(-y * three, Fixed64(300));
+
+            let z: Fixed64<2> = negative_decimal(150, 2);
+            assert_eq!((z.floor(), z.ceil()), (-2, -1));
+            let w: UFixed128<18> = decimal(15, 1);
+            assert_eq!((w.floor(), w.ceil()), (1, 2));
+            assert_eq!(UFixed128::<18>::from_int(7u64).ceil(), 7);
+        }
+
+        #[test]
+        fn test_wide_products() {
+            // The product of the raw values overflows 128 bits, the result does not
+            let big = UFixed128::<18>::from_int(u64::MAX);
+            let one: UFixed128<18> = decimal(1, 0);
+            assert_eq!(big * one, big);
+            assert_eq!(big / one, big);
+            let mut total = big;
+            total -= one;
+            total += one;
+            assert_eq!(total, big);
+        }
+
+        #[test]
+        #[should_panic(expected = "fixed-point overflow")]
+        fn test_overflow() {
+            let max = UFixed64::<6>(u64::MAX);
+            let _ = max * UFixed64::<6>::from_int(2u64);
+        }
+
+        #[test]
+        #[should_panic(expected = "fixed-point overflow")]
+        fn test_negative_unsigned() {
+            let _ = UFixed64::<6>::from_int(1u64) - UFixed64::<6>::from_int(2u64);
+        }
+
+        #[test]
+        #[should_panic(expected = "fixed-point division by zero")]
+        fn test_division_by_zero() {
+            let _ = UFixed64::<6>::from_int(1u64) / UFixed64::<6>::default();
+        }
+    }
+
     #[test]
     fn test_multiple_signers() {
         let source = r#"
@@ -1325,4 +1851,1090 @@ mod tests {
             "CPI invoke should be generated"
         );
     }
+
+    /// An Anchor IDL (legacy format) with one instruction, imported as `Marinade`
+    const MARINADE_IDL: &str = r#"{
+        "instructions": [{
+            "name": "liquidUnstake",
+            "accounts": [
+                { "name": "state", "isMut": true, "isSigner": false },
+                { "name": "msolMint", "isMut": false, "isSigner": false },
+                { "name": "owner", "isMut": true, "isSigner": true }
+            ],
+            "args": [{ "name": "msolAmount", "type": "u64" }]
+        }]
+    }"#;
+
+    const IDL_CPI_SOURCE: &str = r#"
+        contract Staker {
+            address public marinade;
+            address public marinadeState;
+            address public msolMint;
+
+            function unstake(uint64 amount) public {
+                Marinade(marinade).liquidUnstake(marinadeState, msolMint, msg.sender, amount);
+            }
+        }
+    "#;
+
+    #[test]
+    fn test_idl_cpi_serializes_idl_types() {
+        use std::process::Command;
+
+        let idl = r#"{
+            "instructions": [{
+                "name": "ping",
+                "discriminator": [1, 2, 3, 4, 5, 6, 7, 8],
+                "accounts": [],
+                "args": [
+                    { "name": "a", "type": "u64" },
+                    { "name": "b", "type": "u8" },
+                    { "name": "fee", "type": "u64" }
+                ]
+            }]
+        }"#;
+        let source = r#"
+            contract Caller {
+                address public pinger;
+
+                function call() public {
+                    Pinger(pinger).ping(5, 7, 300);
+                }
+            }
+        "#;
+        let mut program = solscript_parser::parse(source).unwrap();
+        let name = solscript_ast::Ident::new("Pinger", solscript_ast::Span::dummy());
+        let (interface, _) = solscript_parser::anchor_idl_to_interface(name, idl).unwrap();
+        program
+            .items
+            .insert(0, solscript_ast::Item::Interface(interface));
+        let lib_rs = generate(&program).unwrap().lib_rs;
+
+        // Run the generated encoding against borsh's little-endian integer layout
+        let start = lib_rs.find("let mut data =").unwrap();
+        let end = start + lib_rs[start..].find("let ix =").unwrap();
+        let harness = format!(
+            r#"
+            #[derive(Debug)]
+            enum ProgramError {{ ArithmeticOverflow }}
+            trait AnchorSerialize {{
+                fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()>;
+            }}
+            macro_rules! le {{ ($($ty:ty),*) => {{ $(impl AnchorSerialize for $ty {{
+                fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {{
+                    writer.write_all(&self.to_le_bytes())
+                }}
+            }})* }} }}
+            le!(u8, u64, u128);
+            struct PingerPingAccounts;
+            impl PingerPingAccounts {{ const DISCRIMINATOR: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8]; }}
+            fn data() -> Result<Vec<u8>, ProgramError> {{ {} Ok(data) }}
+            fn main() {{ println!("{{:?}}", data().unwrap()); }}
+            "#,
+            &lib_rs[start..end]
+        );
+
+        let dir = std::env::temp_dir().join(format!("solscript-cpi-data-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.rs"), harness).unwrap();
+        let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let build = Command::new(rustc)
+            .args(["--edition", "2021", "-o"])
+            .arg(dir.join("main"))
+            .arg(dir.join("main.rs"))
+            .output()
+            .unwrap();
+        assert!(
+            build.status.success(),
+            "{}",
+            String::from_utf8_lossy(&build.stderr)
+        );
+        let run = Command::new(dir.join("main")).output().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut expected = vec![1u8, 2, 3, 4, 5, 6, 7, 8];
+        expected.extend(5u64.to_le_bytes());
+        expected.push(7);
+        expected.extend(300u64.to_le_bytes());
+        assert_eq!(
+            String::from_utf8_lossy(&run.stdout).trim(),
+            format!("{:?}", expected)
+        );
+    }
+
+    #[test]
+    fn test_idl_cpi_codegen() {
+        let mut program = solscript_parser::parse(IDL_CPI_SOURCE).unwrap();
+        let name = solscript_ast::Ident::new("Marinade", solscript_ast::Span::dummy());
+        let (interface, _) = solscript_parser::anchor_idl_to_interface(name, MARINADE_IDL).unwrap();
+        program
+            .items
+            .insert(0, solscript_ast::Item::Interface(interface));
+
+        let result = generate(&program);
+        assert!(result.is_ok(), "Failed to generate: {:?}", result.err());
+        let result = result.unwrap();
+
+        // Account struct with flags from the IDL and the legacy discriminator
+        assert!(result
+            .instructions_rs
+            .contains("pub struct MarinadeLiquidUnstakeAccounts {"));
+        assert!(result
+            .instructions_rs
+            .contains("pub const DISCRIMINATOR: [u8; 8] = [30, 30, 119, 240, 191, 227, 12, 16];"));
+        assert!(result
+            .instructions_rs
+            .contains("AccountMeta::new(self.state, false)"));
+        assert!(result
+            .instructions_rs
+            .contains("AccountMeta::new_readonly(self.msol_mint, false)"));
+        assert!(result
+            .instructions_rs
+            .contains("AccountMeta::new(self.owner, true)"));
+
+        // Only the instruction args are serialized after the discriminator
+        assert!(result
+            .lib_rs
+            .contains("let cpi_accounts = MarinadeLiquidUnstakeAccounts {"));
+        assert!(result
+            .lib_rs
+            .contains("let mut data = MarinadeLiquidUnstakeAccounts::DISCRIMINATOR.to_vec();"));
+        assert!(result.lib_rs.contains(
+            "let arg: u64 = u64::try_from(amount).map_err(|_| ProgramError::ArithmeticOverflow)?;"
+        ));
+        assert!(!result
+            .lib_rs
+            .contains("ctx.accounts.state.msol_mint).into()"));
+
+        // Remaining accounts share the instruction accounts' lifetime
+        assert!(result.lib_rs.contains(
+            "pub fn unstake<'info>(ctx: Context<'_, '_, '_, 'info, Unstake<'info>>, amount: u64)"
+        ));
+        assert!(result
+            .lib_rs
+            .contains("anchor_lang::solana_program::program::invoke(&ix, &account_infos)"));
+
+        // Internal functions can't reach the accounts; the error points at the method
+        let source = IDL_CPI_SOURCE.replace("uint64 amount) public", "uint64 amount) internal");
+        let mut program = solscript_parser::parse(&source).unwrap();
+        let name = solscript_ast::Ident::new("Marinade", solscript_ast::Span::dummy());
+        let (interface, _) = solscript_parser::anchor_idl_to_interface(name, MARINADE_IDL).unwrap();
+        program
+            .items
+            .insert(0, solscript_ast::Item::Interface(interface));
+        let err = generate(&program).unwrap_err();
+        let CodegenError::UnsupportedFeature {
+            span: Some(span),
+            message,
+            ..
+        } = err
+        else {
+            panic!("Expected a located error, got {:?}", err);
+        };
+        let call = source.find("liquidUnstake(").unwrap();
+        assert_eq!(span.offset(), call, "{}", message);
+    }
+
+    #[test]
+    fn test_account_params_codegen() {
+        let source = r#"
+            contract Vault {
+                uint64 public total;
+
+                function deposit(account<TokenAccount> userAta, account<Mint> mint, unchecked_account feeRecipient, accounts[] extra) public {
+                    require(userAta.mint == mint.key, "Wrong mint");
+                    total = total + userAta.amount + feeRecipient.lamports;
+                    if (extra.length > 0) {
+                        address first = extra[0];
+                    }
+                }
+            }
+        "#;
+
+        let result = parse_and_generate(source);
+        assert!(result.is_ok(), "Failed to generate: {:?}", result.err());
+        let result = result.unwrap();
+
+        // Typed accounts go into the context, accounts[] does not
+        assert!(result
+            .instructions_rs
+            .contains("pub user_ata: Account<'info, TokenAccount>"));
+        assert!(result
+            .instructions_rs
+            .contains("pub mint: Account<'info, Mint>"));
+        assert!(result
+            .instructions_rs
+            .contains("pub fee_recipient: UncheckedAccount<'info>"));
+        assert!(result
+            .instructions_rs
+            .contains("use anchor_spl::token::{Mint, TokenAccount};"));
+        assert!(!result.instructions_rs.contains("pub extra"));
+        assert!(result.cargo_toml.contains("anchor-spl"));
+
+        // None of them are instruction args
+        assert!(result
+            .lib_rs
+            .contains("pub fn deposit(ctx: Context<Deposit>)"));
+
+        // Field access reads the deserialized accounts
+        assert!(result.lib_rs.contains("ctx.accounts.user_ata.mint"));
+        assert!(result.lib_rs.contains("ctx.accounts.mint.key()"));
+        assert!(result.lib_rs.contains("ctx.accounts.user_ata.amount"));
+        assert!(result
+            .lib_rs
+            .contains("ctx.accounts.fee_recipient.lamports()"));
+        assert!(result.lib_rs.contains("ctx.remaining_accounts.len()"));
+        assert!(result
+            .lib_rs
+            .contains("ctx.remaining_accounts[0u128 as usize].key()"));
+
+        // IDL lists them as accounts, the client takes remaining accounts
+        assert!(result.idl_json.contains("\"user_ata\""));
+        assert!(result.client_ts.contains("feeRecipient: PublicKey"));
+        assert!(result.client_ts.contains("extra: AccountMeta[]"));
+    }
+
+    #[test]
+    fn test_account_params_writable() {
+        let source = r#"
+            contract Vault {
+                uint64 public total;
+
+                function deposit(account<TokenAccount> userAta, account<TokenAccount> vault, account<Mint> mint, signer owner, #[mut] unchecked_account feeRecipient, uint64 amount) public {
+                    require(userAta.mint == mint.key, "Wrong mint");
+                    token.transfer(userAta, vault, owner, amount);
+                    total = total + amount;
+                }
+            }
+        "#;
+
+        let result = parse_and_generate(source).unwrap();
+
+        // Only the token accounts the transfer writes and the `#[mut]` account are mutable
+        assert!(result
+            .instructions_rs
+            .contains("    #[account(mut)]\n    pub user_ata: Account<'info, TokenAccount>"));
+        assert!(result
+            .instructions_rs
+            .contains("    #[account(mut)]\n    pub vault: Account<'info, TokenAccount>"));
+        assert!(result
+            .instructions_rs
+            .contains("    pub signer: Signer<'info>,\n    pub owner: Signer<'info>,\n"));
+        assert!(result
+            .instructions_rs
+            .contains("    #[account(mut)]\n    pub fee_recipient: UncheckedAccount<'info>"));
+        assert!(result.instructions_rs.contains(
+            "    pub vault: Account<'info, TokenAccount>,\n    pub mint: Account<'info, Mint>,\n"
+        ));
+
+        let idl: serde_json::Value = serde_json::from_str(&result.idl_json).unwrap();
+        let accounts = idl["instructions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|ix| ix["name"] == "deposit")
+            .unwrap()["accounts"]
+            .as_array()
+            .unwrap();
+        let writable =
+            |name: &str| accounts.iter().find(|a| a["name"] == name).unwrap()["writable"] == true;
+        assert!(writable("user_ata"));
+        assert!(writable("fee_recipient"));
+        assert!(!writable("mint"));
+        assert!(!writable("owner"));
+
+        // Accounts passed to an imported instruction are as writable as its IDL says
+        let mut program = solscript_parser::parse(
+            r#"
+            contract Staker {
+                address public marinade;
+
+                function unstake(unchecked_account marinadeState, unchecked_account msolMint, uint64 amount) public {
+                    Marinade(marinade).liquidUnstake(marinadeState, msolMint, msg.sender, amount);
+                }
+            }
+        "#,
+        )
+        .unwrap();
+        let name = solscript_ast::Ident::new("Marinade", solscript_ast::Span::dummy());
+        let (interface, _) = solscript_parser::anchor_idl_to_interface(name, MARINADE_IDL).unwrap();
+        program
+            .items
+            .insert(0, solscript_ast::Item::Interface(interface));
+        let result = generate(&program).unwrap();
+        assert!(result
+            .instructions_rs
+            .contains("    #[account(mut)]\n    pub marinade_state: UncheckedAccount<'info>"));
+        assert!(result.instructions_rs.contains(
+            "    /// CHECK: Unchecked account passed by the caller\n    pub msol_mint: UncheckedAccount<'info>"
+        ));
+
+        // The native backend only takes unchecked accounts
+        let program = solscript_parser::parse(
+            r#"
+            contract Vault {
+                uint64 public total;
+
+                function pay(unchecked_account payer, #[mut] unchecked_account feeRecipient) public {
+                    total = total + payer.lamports;
+                }
+            }
+        "#,
+        )
+        .unwrap();
+        let native = generate_with_options(
+            &program,
+            &CodegenOptions {
+                backend: Backend::Native,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(native
+            .instructions_rs
+            .contains("check_writable(fee_recipient)?;"));
+        assert!(!native.instructions_rs.contains("check_writable(mint)?;"));
+        assert!(!native
+            .instructions_rs
+            .contains("check_writable(user_ata)?;"));
+    }
+
+    #[test]
+    fn test_account_constraints_codegen() {
+        let source = r#"
+            contract Vault {
+                address public admin;
+                uint64 public minDeposit;
+
+                #[constraint(has_one = admin, close = admin)]
+                function shutdown(signer admin) public {}
+
+                function deposit(
+                    #[constraint(has_one = mint, constraint = userAta.amount >= minDeposit)] account<TokenAccount> userAta,
+                    account<Mint> mint,
+                    #[constraint(owner = admin)] unchecked_account feeRecipient
+                ) public view {}
+            }
+        "#;
+
+        let result = parse_and_generate(source);
+        assert!(result.is_ok(), "Failed to generate: {:?}", result.err());
+        let result = result.unwrap();
+
+        // Function-level constraints apply to the state account
+        assert!(result
+            .instructions_rs
+            .contains("#[account(mut, has_one = admin, close = admin)]\n    pub state:"));
+        // The close target must be writable
+        assert!(result
+            .instructions_rs
+            .contains("#[account(mut)]\n    pub admin: Signer<'info>"));
+
+        // Parameter constraints, without `mut` in a view function
+        assert!(result.instructions_rs.contains(
+            "#[account(has_one = mint, constraint = (user_ata.amount >= state.min_deposit))]\n    pub user_ata:"
+        ));
+        assert!(result
+            .instructions_rs
+            .contains("#[account(owner = state.admin)]\n    pub fee_recipient:"));
+        assert!(result
+            .instructions_rs
+            .contains("    pub mint: Account<'info, Mint>"));
+    }
+
+    #[test]
+    fn test_zero_copy_codegen() {
+        let source = r#"
+            #[zero_copy]
+            contract OrderBook {
+                uint8 public decimals;
+                uint64[512] public prices;
+                uint128 public volume;
+
+                constructor(uint8 d) {
+                    decimals = d;
+                }
+
+                function setPrice(uint64 index, uint64 price) public {
+                    prices[index] = price;
+                }
+
+                function getVolume() public view returns (uint128) {
+                    return volume;
+                }
+            }
+        "#;
+
+        let result = parse_and_generate(source);
+        assert!(result.is_ok(), "Failed to generate: {:?}", result.err());
+        let result = result.unwrap();
+
+        // Fields in declaration order, with explicit padding and no InitSpace
+        assert!(result.state_rs.contains(
+            "#[account(zero_copy)]\npub struct OrderBookState {\n    pub decimals: u8,\n    pub _padding0: [u8; 7],\n    pub prices: [u64; 512],\n    pub _padding1: [u8; 8],\n    pub volume: u128,\n}"
+        ));
+
+        // Accounts are loaded rather than deserialized
+        assert!(result
+            .instructions_rs
+            .contains("pub state: AccountLoader<'info, OrderBookState>"));
+        assert!(result
+            .instructions_rs
+            .contains("space = 8 + std::mem::size_of::<OrderBookState>()"));
+        assert!(result
+            .lib_rs
+            .contains("let mut state = ctx.accounts.state.load_init()?;"));
+        assert!(result
+            .lib_rs
+            .contains("let mut state = ctx.accounts.state.load_mut()?;"));
+        assert!(result
+            .lib_rs
+            .contains("let state = ctx.accounts.state.load()?;"));
+        assert!(result
+            .lib_rs
+            .contains("state.prices[index as usize] = price;"));
+        assert!(!result.lib_rs.contains("ctx.accounts.state.prices"));
+
+        // Padding up to the struct alignment goes last
+        let trailing = parse_and_generate(
+            "#[zero_copy]\ncontract Totals {\n    uint64 public total;\n    uint8 public decimals;\n}",
+        )
+        .unwrap();
+        assert!(trailing.state_rs.contains(
+            "    pub total: u64,\n    pub decimals: u8,\n    pub _padding0: [u8; 7],\n}"
+        ));
+
+        // The Pod derives come from bytemuck
+        assert!(result.cargo_toml.contains(
+            "bytemuck = { version = \"1\", features = [\"derive\", \"min_const_generics\"] }"
+        ));
+    }
+
+    #[test]
+    fn test_growable_state_realloc() {
+        let source = r#"
+            contract Registry {
+                #[growable]
+                string public name;
+                #[growable]
+                uint64[] public values;
+                uint64 public count;
+
+                function rename(string newName) public {
+                    name = newName;
+                }
+
+                function add(uint64 first, uint64 value) public {
+                    values.push(value);
+                    count = count + first;
+                }
+
+                function bump() public {
+                    count = count + 1;
+                }
+            }
+        "#;
+
+        let result = parse_and_generate(source);
+        assert!(result.is_ok(), "Failed to generate: {:?}", result.err());
+        let result = result.unwrap();
+
+        // Assignments grow by the length difference, pushes by the element size
+        assert!(result.instructions_rs.contains(
+            "realloc = state.to_account_info().data_len() + (new_name).len().saturating_sub(state.name.len()), realloc::payer = signer, realloc::zero = true"
+        ));
+        assert!(result
+            .instructions_rs
+            .contains("realloc = state.to_account_info().data_len() + 8,"));
+        // The grown bytes are zeroed
+        assert!(!result.instructions_rs.contains("realloc::zero = false"));
+
+        // Arguments are deserialized positionally, up to the last one used
+        assert!(result
+            .instructions_rs
+            .contains("#[instruction(first: u64, value: u64)]\npub struct Add<'info>"));
+        assert!(result
+            .instructions_rs
+            .contains("#[instruction(new_name: String)]\npub struct Rename<'info>"));
+
+        // Realloc needs the system program, other instructions are unchanged
+        assert!(result.instructions_rs.contains(
+            "pub struct Bump<'info> {\n    #[account(mut)]\n    pub state: Account<'info, RegistryState>,\n    #[account(mut)]\n    pub signer: Signer<'info>,\n}"
+        ));
+        assert_eq!(
+            result.instructions_rs.matches("pub system_program").count(),
+            2
+        );
+        assert!(result
+            .client_ts
+            .contains("`accounts.signer` pays the extra rent"));
+    }
+
+    #[test]
+    fn test_growable_in_loop_unsupported() {
+        let source = r#"
+            contract Registry {
+                #[growable]
+                uint64[] public values;
+
+                function fill(uint64 value) public {
+                    for (uint64 i = 0; i < 10; i++) {
+                        values.push(value);
+                    }
+                }
+            }
+        "#;
+
+        let result = parse_and_generate(source);
+        assert!(result.unwrap_err().contains("inside a loop"));
+    }
+
+    #[test]
+    fn test_upgradeable_migrate_instruction() {
+        let source = r#"
+            #[upgradeable]
+            contract Vault {
+                #[version(2)]
+                uint128 public scaled;
+                uint64 public balance;
+
+                constructor(uint64 initial) {
+                    balance = initial;
+                }
+
+                #[migration(from = 1, to = 2)]
+                function migrateV1ToV2() internal {
+                    scaled = 1000;
+                }
+            }
+        "#;
+
+        let result = parse_and_generate(source);
+        assert!(result.is_ok(), "Failed to generate: {:?}", result.err());
+        let result = result.unwrap();
+
+        // Fields are ordered by version behind the layout version byte
+        assert!(result.state_rs.contains(
+            "pub struct VaultState {\n    pub layout_version: u8,\n    pub balance: u64,\n    pub scaled: u128,\n}"
+        ));
+        assert!(result.state_rs.contains(
+            "#[derive(AnchorDeserialize)]\npub struct VaultStateV1 {\n    pub layout_version: u8,\n    pub balance: u64,\n}"
+        ));
+        assert!(result
+            .lib_rs
+            .contains("ctx.accounts.state.layout_version = 2;\n"));
+
+        // The migration runs as a helper from the generated instruction
+        assert!(result
+            .lib_rs
+            .contains("fn migrate_v1_to_v2(state: &mut crate::state::VaultState)"));
+        assert!(result
+            .lib_rs
+            .contains("pub fn migrate(ctx: Context<Migrate>)"));
+        assert!(result
+            .lib_rs
+            .contains("if from_version <= 1 {\n            migrate_v1_to_v2(&mut state)?;"));
+        assert!(result.lib_rs.contains("info.realloc(space, false)?;"));
+        assert!(!result.instructions_rs.contains("pub struct MigrateV1ToV2"));
+
+        // Only the upgrade authority may migrate
+        assert!(result
+            .instructions_rs
+            .contains("pub program: Program<'info, crate::program::Vault>,"));
+        assert!(result
+            .instructions_rs
+            .contains("program_data.upgrade_authority_address == Some(signer.key())"));
+        assert!(result.error_rs.contains("NotUpgradeAuthority,"));
+        assert!(result.idl_json.contains("\"name\": \"program_data\""));
+        assert!(result.client_ts.contains("async migrate("));
+    }
+
+    #[test]
+    fn test_upgradeable_without_older_layouts() {
+        let source = r#"
+            #[upgradeable]
+            contract Counter {
+                uint64 public count;
+
+                function bump() public {
+                    count += 1;
+                }
+            }
+        "#;
+
+        let result = parse_and_generate(source).unwrap();
+
+        // An initialize is synthesized to write the version; nothing to migrate yet
+        assert!(result
+            .lib_rs
+            .contains("pub fn initialize(ctx: Context<Initialize>) -> Result<()> {\n        ctx.accounts.state.layout_version = 1;"));
+        assert!(!result.lib_rs.contains("pub fn migrate"));
+        assert!(!result.error_rs.contains("NotUpgradeAuthority"));
+    }
+
+    #[test]
+    fn test_account_layout() {
+        let layout = parse_and_layout(
+            r#"
+            struct Item {
+                uint64 price;
+                address seller;
+            }
+
+            contract Shop {
+                address public owner;
+                uint64 public count;
+                string public name;
+                bool public paused;
+                mapping(address => uint64) public balances;
+            }
+        "#,
+        );
+
+        let state = &layout.state;
+        assert_eq!(state.name, "ShopState");
+        let offsets: Vec<_> = state.fields.iter().map(|f| f.offset).collect();
+        assert_eq!(offsets, vec![Some(8), Some(40), Some(48), None]);
+        assert_eq!(state.fields[2].size, 4 + 200);
+        assert!(state.fields[2].dynamic);
+        assert_eq!(state.size, 8 + 32 + 8 + 204 + 1);
+
+        assert_eq!(layout.mappings[0].name, "BalancesEntry");
+        assert_eq!(layout.mappings[0].size, 8 + 32 + 8);
+        assert_eq!(layout.structs[0].fields[1].offset, Some(8));
+        assert_eq!(layout.structs[0].size, 40);
+    }
+
+    #[test]
+    fn test_layout_diff() {
+        let old = parse_and_layout(
+            r#"
+            contract Shop {
+                address public owner;
+                uint64 public count;
+                uint32 public fee;
+                mapping(address => uint64) public balances;
+            }
+        "#,
+        );
+        let new = parse_and_layout(
+            r#"
+            contract Shop {
+                uint64 public count;
+                address public owner;
+                uint64 public fee;
+                bool public paused;
+            }
+        "#,
+        );
+
+        let changes = diff_layouts(&old, &new);
+        let kinds: Vec<(Option<&str>, &ChangeKind)> = changes
+            .iter()
+            .map(|c| (c.field.as_deref(), &c.kind))
+            .collect();
+        assert!(kinds.contains(&(
+            Some("owner"),
+            &ChangeKind::Moved {
+                from: Some(8),
+                to: Some(16)
+            }
+        )));
+        assert!(kinds.contains(&(Some("fee"), &ChangeKind::Resized { from: 4, to: 8 })));
+        assert!(kinds.contains(&(None, &ChangeKind::Removed)));
+        assert!(kinds.contains(&(Some("paused"), &ChangeKind::Added)));
+
+        // Appending fields is compatible
+        let appended = parse_and_layout(
+            r#"
+            contract Shop {
+                address public owner;
+                uint64 public count;
+                uint32 public fee;
+                bool public paused;
+                mapping(address => uint64) public balances;
+            }
+        "#,
+        );
+        let changes = diff_layouts(&old, &appended);
+        assert_eq!(changes.len(), 1);
+        assert!(!changes[0].is_breaking());
+    }
+
+    #[test]
+    fn test_account_costs() {
+        let layout = parse_and_layout(
+            r#"
+            contract Bank {
+                uint64 public total;
+                mapping(address => uint64) public balances;
+            }
+        "#,
+        );
+
+        // Empty accounts cost 890,880 lamports at default rent
+        assert_eq!(rent_exempt_lamports(0), 890_880);
+
+        let costs = layout.account_costs();
+        assert_eq!(costs.len(), 2);
+        assert_eq!(costs[0].kind, AccountCostKind::State);
+        assert_eq!(costs[0].size, 8 + 8);
+        assert_eq!(costs[0].rent_exempt_lamports, (128 + 16) * 6960);
+        assert_eq!(costs[1].name, "BalancesEntry");
+        assert_eq!(costs[1].kind, AccountCostKind::MappingEntry);
+        assert_eq!(costs[1].rent_exempt_lamports, 1_224_960);
+
+        let json = serde_json::to_string(&costs).unwrap();
+        assert!(json.contains(r#""kind":"mappingEntry","size":48,"rentExemptLamports":1224960"#));
+    }
+
+    #[test]
+    fn test_native_backend() {
+        let source = r#"
+            event Transfer(address indexed from, address indexed to, uint64 amount);
+
+            contract Token {
+                address public owner;
+                mapping(address => uint64) public balances;
+
+                constructor() {
+                    owner = msg.sender;
+                }
+
+                function mint(address to, uint64 amount) public {
+                    require(msg.sender == owner, "only owner");
+                    balances[to] += amount;
+                    emit Transfer(owner, to, amount);
+                }
+
+                function balanceOf(address who) public view returns (uint64) {
+                    return balances[who];
+                }
+            }
+        "#;
+        let program = solscript_parser::parse(source).expect("parse");
+        let result = generate_with_options(
+            &program,
+            &CodegenOptions {
+                backend: Backend::Native,
+                ..Default::default()
+            },
+        )
+        .expect("codegen");
+
+        for file in [
+            &result.lib_rs,
+            &result.state_rs,
+            &result.instructions_rs,
+            &result.error_rs,
+            &result.events_rs,
+            &result.cargo_toml,
+        ] {
+            assert!(!file.contains("anchor"), "{}", file);
+        }
+        assert!(result.anchor_toml.is_empty() && result.tests_ts.is_empty());
+        assert!(result.cargo_toml.contains("solana-program = "));
+
+        // Anchor's discriminators: sha256("global:initialize") and sha256("account:TokenState")
+        assert!(result
+            .lib_rs
+            .contains("[175, 175, 109, 31, 13, 152, 155, 237] => dispatch::initialize("));
+        assert!(result.lib_rs.contains("dispatch::balance_of("));
+        assert!(result
+            .lib_rs
+            .contains("let amount = <u64>::deserialize(data)"));
+        assert!(result
+            .lib_rs
+            .contains("Mint::try_accounts(program_id, &mut iter, &to)?"));
+        assert!(result.lib_rs.contains("set_return_data"));
+        assert!(result
+            .state_rs
+            .contains("const DISCRIMINATOR: [u8; 8] = [218, 112, 6, 149, 55, 186, 168, 163];"));
+        assert!(result.state_rs.contains("const INIT_SPACE: usize = 40;"));
+
+        // Manual account checks and re-derived mapping PDAs
+        assert!(result.instructions_rs.contains("check_signer(signer)?;"));
+        assert!(result
+            .instructions_rs
+            .contains(r#"&[b"balances", balances_entry_0_key_0.as_ref()]"#));
+        assert!(result
+            .instructions_rs
+            .contains("load_or_create_pda::<BalancesEntry>("));
+        assert!(result.events_rs.contains("impl Transfer {"));
+        assert!(result.error_rs.contains("RequireFailed = 6000,"));
+    }
+
+    #[test]
+    fn test_native_backend_rejects_anchor_only_features() {
+        let source = r#"
+            contract Pay {
+                function send(address to, uint64 amount) public {
+                    transfer(to, amount);
+                }
+            }
+        "#;
+        let program = solscript_parser::parse(source).expect("parse");
+        match generate_with_options(
+            &program,
+            &CodegenOptions {
+                backend: Backend::Native,
+                ..Default::default()
+            },
+        ) {
+            Err(CodegenError::UnsupportedFeature { message, .. }) => {
+                assert!(message.contains("SOL transfers"), "{}", message)
+            }
+            other => panic!("expected UnsupportedFeature, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_codegen_errors_point_at_source() {
+        use miette::Diagnostic;
+
+        let source = r#"
+            contract Pairs {
+                uint64 public total;
+
+                function pair(uint64 a, uint64 b) public {
+                    total = (a, b);
+                }
+            }
+        "#;
+        let program = solscript_parser::parse(source).expect("parse");
+        let err = generate(&program).expect_err("tuple expressions are unsupported");
+
+        let span = err.span().expect("lowering errors carry a span");
+        assert_eq!(&source[span.start..span.end], "(a, b)");
+        assert_eq!(
+            err.code().map(|c| c.to_string()).as_deref(),
+            Some("solscript::codegen::unsupported_feature")
+        );
+
+        let err = err.with_source_code(source);
+        assert!(err.source_code().is_some());
+        assert_eq!(err.labels().map(|l| l.count()), Some(1));
+    }
+
+    #[test]
+    fn test_idl_spec_and_legacy_formats() {
+        let source = r#"
+            /// Minimal token
+            contract Token {
+                address public owner;
+                mapping(address => uint64) public balances;
+
+                constructor() {
+                    owner = msg.sender;
+                }
+
+                /// Credit the caller
+                function deposit(uint64 amount) public {
+                    balances[msg.sender] += amount;
+                }
+
+                function balanceOf(address who) public view returns (uint64) {
+                    return balances[who];
+                }
+            }
+        "#;
+        let program = solscript_parser::parse(source).expect("parse");
+        let ir = lower_to_ir(&program).unwrap().pop().unwrap();
+        let address = "3ARMH9zfVCnU2TKiphU4xcEyWdA45fc1sjKEtYMdf3gr";
+
+        let json = IdlGenerator::new()
+            .with_address(address)
+            .generate(&ir)
+            .unwrap();
+        let idl: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(idl["address"], address);
+        assert_eq!(idl["metadata"]["spec"], "0.1.0");
+        assert_eq!(idl["docs"][0], "Minimal token");
+
+        let deposit = &idl["instructions"][2];
+        assert_eq!(deposit["name"], "deposit");
+        assert_eq!(deposit["docs"][0], "Credit the caller");
+        // sha256("global:deposit")[..8]
+        assert_eq!(
+            deposit["discriminator"],
+            serde_json::json!([242, 35, 198, 137, 82, 225, 242, 182])
+        );
+        let entry = &deposit["accounts"][2];
+        assert_eq!(entry["name"], "balances_entry_0");
+        assert_eq!(
+            entry["pda"]["seeds"],
+            serde_json::json!([
+                { "kind": "const", "value": b"balances".to_vec() },
+                { "kind": "account", "path": "signer" }
+            ])
+        );
+        assert_eq!(deposit["accounts"][3]["address"], PLACEHOLDER_PROGRAM_ID);
+
+        let balance_of = &idl["instructions"][1];
+        assert_eq!(balance_of["name"], "balance_of");
+        assert_eq!(
+            balance_of["accounts"][2]["pda"]["seeds"][1],
+            serde_json::json!({ "kind": "arg", "path": "who" })
+        );
+        assert_eq!(balance_of["returns"], "u64");
+
+        assert_eq!(idl["accounts"][0]["name"], "TokenState");
+        assert_eq!(idl["types"][1]["name"], "BalancesEntry");
+        assert_eq!(idl["types"][1]["type"]["fields"][0]["type"], "pubkey");
+        assert_eq!(idl["errors"][1]["code"], 6001);
+
+        let legacy = IdlGenerator::new()
+            .with_format(IdlFormat::Legacy)
+            .generate(&ir)
+            .unwrap();
+        let legacy: serde_json::Value = serde_json::from_str(&legacy).unwrap();
+        assert_eq!(legacy["metadata"]["address"], PLACEHOLDER_PROGRAM_ID);
+        assert_eq!(legacy["instructions"][0]["name"], "initialize");
+        assert_eq!(legacy["instructions"][0]["accounts"][0]["isMut"], true);
+        assert_eq!(
+            legacy["accounts"][0]["type"]["fields"][0]["type"],
+            "publicKey"
+        );
+    }
+
+    #[test]
+    fn test_program_id_threads_through_outputs() {
+        let source = r#"
+            contract Counter {
+                uint64 public count;
+
+                function increment() public {
+                    count += 1;
+                }
+            }
+        "#;
+        let program = solscript_parser::parse(source).expect("parse");
+        assert_eq!(program_crate_name(&program).unwrap(), "counter");
+
+        let localnet = "Be3KFfu36kEaRGWkE5AmbUyV8gjAuSCh2KCdVGNwhLQf";
+        let devnet = "3ARMH9zfVCnU2TKiphU4xcEyWdA45fc1sjKEtYMdf3gr";
+        let options = CodegenOptions {
+            program_id: Some(devnet.to_string()),
+            cluster_program_ids: [
+                ("devnet".to_string(), devnet.to_string()),
+                ("localnet".to_string(), localnet.to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let result = generate_with_options(&program, &options).unwrap();
+        assert!(result
+            .lib_rs
+            .contains(&format!("declare_id!(\"{}\");", devnet)));
+        assert!(result
+            .anchor_toml
+            .contains(&format!("[programs.devnet]\ncounter = \"{}\"", devnet)));
+        assert!(result
+            .anchor_toml
+            .contains(&format!("[programs.localnet]\ncounter = \"{}\"", localnet)));
+        assert!(result
+            .client_ts
+            .contains(&format!("new PublicKey('{}')", devnet)));
+        assert!(result
+            .idl_json
+            .contains(&format!("\"address\": \"{}\"", devnet)));
+
+        let native = generate_with_options(
+            &program,
+            &CodegenOptions {
+                backend: Backend::Native,
+                program_id: Some(localnet.to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(native
+            .lib_rs
+            .contains(&format!("solana_program::declare_id!(\"{}\");", localnet)));
+
+        // Without an id everything shares the placeholder
+        let result = generate(&program).unwrap();
+        assert!(result.anchor_toml.contains(&format!(
+            "[programs.localnet]\ncounter = \"{}\"",
+            PLACEHOLDER_PROGRAM_ID
+        )));
+    }
+
+    #[test]
+    fn test_source_map_points_back_to_solscript() {
+        let source = r#"contract Counter {
+    uint64 public count;
+
+    function add(uint64 n) public {
+        if (n > 10) {
+            count += 10;
+        } else {
+            count += n;
+        }
+    }
+}
+"#;
+        let program = solscript_parser::parse(source).expect("parse");
+        let options = CodegenOptions {
+            source_path: Some("counter.sol".to_string()),
+            ..Default::default()
+        };
+        let result = generate_with_options(&program, &options).unwrap();
+        assert!(!result.lib_rs.contains('\u{1}'));
+        assert_eq!(result.source_map.version, SOURCE_MAP_VERSION);
+        assert_eq!(result.source_map.source, "counter.sol");
+
+        let line = result
+            .lib_rs
+            .lines()
+            .position(|l| l.contains("+ 10"))
+            .unwrap()
+            + 1;
+        let mapping = result
+            .source_map
+            .lookup("programs/solscript_program/src/lib.rs", line)
+            .unwrap();
+        assert_eq!(
+            &source[mapping.span.start..mapping.span.end],
+            "count += 10;"
+        );
+
+        // The handler's signature maps to the whole function
+        let signature = result
+            .lib_rs
+            .lines()
+            .position(|l| l.contains("pub fn add("))
+            .unwrap()
+            + 1;
+        let mapping = result.source_map.lookup("src/lib.rs", signature).unwrap();
+        assert!(source[mapping.span.start..].starts_with("function add("));
+
+        let rustc = format!(
+            "error[E0308]: mismatched types\n  --> programs/solscript_program/src/lib.rs:{}:13\n",
+            line
+        );
+        let rewritten = result
+            .source_map
+            .rewrite_diagnostics(&rustc, |_| Some(source));
+        assert!(rewritten.contains(&format!(
+            "  --> counter.sol:6:13 (programs/solscript_program/src/lib.rs:{}:13)",
+            line
+        )));
+
+        let panic = format!("thread 'main' panicked at src/lib.rs:{}:9:", line);
+        let rewritten = result
+            .source_map
+            .rewrite_diagnostics(&panic, |_| Some(source));
+        assert!(rewritten.starts_with("thread 'main' panicked at counter.sol:6:13 (src/lib.rs:"));
+
+        // Locations outside the generated program are left alone
+        let other = "  --> src/main.rs:1:1\n";
+        assert_eq!(
+            result
+                .source_map
+                .rewrite_diagnostics(other, |_| Some(source)),
+            other
+        );
+    }
 }
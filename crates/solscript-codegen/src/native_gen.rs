@@ -0,0 +1,1035 @@
+//! Native Rust Code Generator
+//!
+//! Generates a plain `solana-program` crate from the Solana IR, without Anchor.
+//! Handler bodies are shared with the Anchor generator and run against a small
+//! runtime emitted into `lib.rs` (`Context`, `require!`, `emit!`, ...). Accounts
+//! are validated by hand, instruction data is borsh-decoded behind Anchor's
+//! 8-byte discriminators, and mapping PDAs are re-derived and checked, so the
+//! IDL and TypeScript client work unchanged against either backend.
+
+use crate::error::CodegenError;
+use crate::idl_gen::{discriminator, IdlGenerator, PLACEHOLDER_PROGRAM_ID};
+use crate::ir::*;
+use crate::layout::{program_layout, DISCRIMINATOR_SIZE};
+use crate::rust_gen::{to_pascal_case, to_snake_case, RustGenerator};
+use crate::GeneratedProject;
+
+/// Generates a `solana-program` project from Solana IR
+pub struct NativeGenerator {
+    /// Statement and expression generation shared with the Anchor backend
+    bodies: RustGenerator,
+    /// Generates the project's IDL
+    idl: IdlGenerator,
+    /// Program address for `declare_id!` and the TypeScript client
+    program_id: String,
+}
+
+impl Default for NativeGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NativeGenerator {
+    pub fn new() -> Self {
+        Self {
+            bodies: RustGenerator::native(),
+            idl: IdlGenerator::new(),
+            program_id: PLACEHOLDER_PROGRAM_ID.to_string(),
+        }
+    }
+
+    /// Generate the IDL with `idl` (format and program address)
+    pub fn with_idl(mut self, idl: IdlGenerator) -> Self {
+        self.idl = idl;
+        self
+    }
+
+    /// Declare the program at `program_id` (base58)
+    pub fn with_program_id(mut self, program_id: impl Into<String>) -> Self {
+        self.program_id = program_id.into();
+        self
+    }
+
+    /// Generate a complete native project from Solana IR
+    pub fn generate(
+        &mut self,
+        programs: &[SolanaProgram],
+    ) -> Result<GeneratedProject, CodegenError> {
+        if programs.is_empty() {
+            return Err(CodegenError::missing_element(
+                "No deployable contracts found (abstract contracts cannot be deployed)",
+                solscript_ast::Span::dummy(),
+            ));
+        }
+
+        // Same as the Anchor backend: the most derived contract is deployed
+        let program = programs.last().unwrap();
+        check_native_support(program)?;
+        self.bodies.prepare(program);
+
+        let lib_rs = self.generate_lib_rs(program)?;
+        let state_rs = self.generate_state_rs(program)?;
+        let instructions_rs = self.generate_instructions_rs(program)?;
+        let error_rs = self.generate_error_rs(program);
+        let events_rs = self.generate_events_rs(program);
+        let cargo_toml = self.generate_cargo_toml(program);
+
+        // The client and IDL only depend on discriminators and borsh layouts,
+        // which match the Anchor backend
+        let mut ts_gen =
+            crate::ts_gen::TypeScriptGenerator::new().with_program_id(self.program_id.clone());
+        let client_ts = ts_gen.generate(program)?;
+        let idl_json = self.idl.generate(program)?;
+
+        let rust_tests = self.bodies.generate_rust_tests(program)?;
+
+        Ok(GeneratedProject {
+            lib_rs,
+            state_rs,
+            instructions_rs,
+            error_rs,
+            events_rs,
+            // No Anchor workspace or anchor-based test harness for native programs
+            anchor_toml: String::new(),
+            cargo_toml,
+            client_ts,
+            tests_ts: String::new(),
+            idl_json,
+            package_json: self.bodies.generate_package_json(program),
+            readme: self.generate_readme(program),
+            gitignore: self.bodies.generate_gitignore(),
+            rust_tests,
+            has_tests: !program.tests.is_empty(),
+            source_map: Default::default(),
+        }
+        .extract_source_map())
+    }
+
+    fn generate_lib_rs(&mut self, program: &SolanaProgram) -> Result<String, CodegenError> {
+        let helper_fns = self.bodies.generate_helper_functions(program)?;
+
+        let public: Vec<&Instruction> = program
+            .instructions
+            .iter()
+            .filter(|i| i.is_public)
+            .collect();
+
+        let mut arms = String::new();
+        let mut dispatch = String::new();
+        let mut handlers = String::new();
+        for instruction in &public {
+            let name = to_snake_case(&instruction.name);
+            arms.push_str(&format!(
+                "        {} => dispatch::{}(program_id, accounts, &mut data),\n",
+                discriminator_literal(&format!("global:{}", name)),
+                name
+            ));
+            dispatch.push_str(&self.generate_dispatch_fn(instruction)?);
+            let handler = self
+                .bodies
+                .generate_instruction_handler(instruction, program)
+                .map_err(|e| e.or_at(instruction.span))?;
+            handlers.push_str(&handler);
+            handlers.push('\n');
+        }
+
+        let uses_args = public
+            .iter()
+            .any(|i| i.params.iter().any(|p| !p.ty.is_account()));
+        let split = if public.is_empty() {
+            "let discriminator = &data[..8];"
+        } else {
+            "let (discriminator, mut data) = data.split_at(8);"
+        };
+
+        Ok(format!(
+            r#"//! Generated by SolScript compiler (native backend)
+//! Contract: {name}
+
+use solana_program::{{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+}};
+
+/// Result of instruction handlers and internal functions
+pub type Result<T> = std::result::Result<T, ProgramError>;
+
+/// Fail with `$err` unless `$cond` holds
+macro_rules! require {{
+    ($cond:expr, $err:expr) => {{
+        if !($cond) {{
+            return Err($err.into());
+        }}
+    }};
+}}
+
+/// Convert a `CustomError` into a `ProgramError`
+macro_rules! error {{
+    ($err:expr) => {{
+        ProgramError::from($err)
+    }};
+}}
+
+/// Log an event the way Anchor's `emit!` does (discriminator followed by borsh data)
+macro_rules! emit {{
+    ($event:expr) => {{
+        solana_program::log::sol_log_data(&[&$event.data()])
+    }};
+}}
+
+mod state;
+mod instructions;
+mod error;
+mod events;
+
+pub use state::*;
+pub use instructions::*;
+pub use error::*;
+// Events are accessed via events:: prefix to avoid name collisions
+
+solana_program::declare_id!("{program_id}");
+
+/// Validated accounts and the program id passed to an instruction handler
+pub struct Context<'a, 'b, 'info, T> {{
+    pub program_id: &'a Pubkey,
+    pub accounts: &'b mut T,
+    pub remaining_accounts: &'a [AccountInfo<'info>],
+}}
+
+/// `.key()` on raw accounts, as on Anchor account types
+pub trait Key {{
+    fn key(&self) -> Pubkey;
+}}
+
+impl Key for AccountInfo<'_> {{
+    fn key(&self) -> Pubkey {{
+        *self.key
+    }}
+}}
+
+/// Prefix identifying an account type, `sha256("account:<Name>")[..8]` as in Anchor
+pub trait Discriminator {{
+    const DISCRIMINATOR: [u8; 8];
+}}
+
+/// Bytes reserved after the discriminator, with dynamic fields at their maximum length
+pub trait Space {{
+    const INIT_SPACE: usize;
+}}
+
+{helper_fns}
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+/// Route on the 8-byte instruction discriminator, `sha256("global:<name>")[..8]` as in Anchor
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {{
+    if data.len() < 8 {{
+        return Err(ProgramError::InvalidInstructionData);
+    }}
+    {split}
+    match discriminator {{
+{arms}        _ => Err(ProgramError::InvalidInstructionData),
+    }}
+}}
+
+/// Decode arguments, validate accounts, run the handler and write accounts back
+mod dispatch {{
+    use super::*;
+{borsh}
+{dispatch}}}
+
+pub mod handlers {{
+    use super::*;
+    #[allow(unused_imports)]
+    use solana_program::sysvar::{{clock::Clock, rent::Rent, Sysvar}};
+
+{handlers}}}
+"#,
+            name = program.name,
+            program_id = self.program_id,
+            borsh = if uses_args {
+                "    use borsh::BorshDeserialize;\n"
+            } else {
+                ""
+            },
+        ))
+    }
+
+    /// Per-instruction entry: decode args in order, load accounts, call the handler
+    fn generate_dispatch_fn(&self, instruction: &Instruction) -> Result<String, CodegenError> {
+        let name = to_snake_case(&instruction.name);
+        let ctx_type = to_pascal_case(&instruction.name);
+
+        let args: Vec<&InstructionParam> = instruction
+            .params
+            .iter()
+            .filter(|p| !p.ty.is_account())
+            .collect();
+        let seed_args = self.seed_args(instruction);
+
+        let mut content = format!(
+            "    pub fn {}(program_id: &Pubkey, accounts: &[AccountInfo], data: &mut &[u8]) -> ProgramResult {{\n",
+            name
+        );
+        for arg in &args {
+            content.push_str(&format!(
+                "        let {} = <{}>::deserialize(data).map_err(|_| ProgramError::InvalidInstructionData)?;\n",
+                to_snake_case(&arg.name),
+                self.bodies.type_to_rust(&arg.ty)
+            ));
+        }
+        if args.is_empty() {
+            content.push_str("        let _ = data;\n");
+        }
+
+        let seed_refs: String = seed_args
+            .iter()
+            .map(|(name, _)| format!(", &{}", to_snake_case(name)))
+            .collect();
+        content.push_str("        let mut iter = accounts.iter();\n");
+        content.push_str(&format!(
+            "        let mut accounts = {}::try_accounts(program_id, &mut iter{})?;\n",
+            ctx_type, seed_refs
+        ));
+
+        let call_args: String = args
+            .iter()
+            .map(|p| format!(", {}", to_snake_case(&p.name)))
+            .collect();
+        let call = format!(
+            "handlers::{}(\n            Context {{\n                program_id,\n                accounts: &mut accounts,\n                remaining_accounts: iter.as_slice(),\n            }}{}\n        )?",
+            name, call_args
+        );
+        if instruction.returns.is_some() {
+            content.push_str(&format!("        let result = {};\n", call));
+        } else {
+            content.push_str(&format!("        {};\n", call));
+        }
+        content.push_str("        accounts.exit()?;\n");
+        if instruction.returns.is_some() {
+            content.push_str(
+                "        let result = borsh::to_vec(&result).map_err(|_| ProgramError::InvalidAccountData)?;\n",
+            );
+            content.push_str("        solana_program::program::set_return_data(&result);\n");
+        }
+        content.push_str("        Ok(())\n    }\n\n");
+        Ok(content)
+    }
+
+    /// Instruction arguments that mapping seeds depend on, in parameter order
+    fn seed_args<'a>(&self, instruction: &'a Instruction) -> Vec<(&'a String, &'a SolanaType)> {
+        let mut seed_params = Vec::new();
+        for access in &instruction.mapping_accesses {
+            for key_expr in &access.key_exprs {
+                self.bodies
+                    .collect_seed_params(key_expr, instruction, &mut seed_params);
+            }
+        }
+        let mut args: Vec<_> = instruction
+            .params
+            .iter()
+            .filter(|p| seed_params.iter().any(|(name, _)| **name == p.name))
+            .map(|p| (&p.name, &p.ty))
+            .collect();
+        args.dedup_by(|a, b| a.0 == b.0);
+        args
+    }
+
+    fn generate_state_rs(&self, program: &SolanaProgram) -> Result<String, CodegenError> {
+        let layout = program_layout(program)?;
+        let mut content = String::new();
+
+        for enum_def in &program.enums {
+            content.push_str(
+                "#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Eq, Default)]\n",
+            );
+            content.push_str(&format!("pub enum {} {{\n", to_pascal_case(&enum_def.name)));
+            for (i, variant) in enum_def.variants.iter().enumerate() {
+                if i == 0 {
+                    content.push_str("    #[default]\n");
+                }
+                content.push_str(&format!("    {},\n", to_pascal_case(variant)));
+            }
+            content.push_str("}\n\n");
+        }
+
+        for struct_def in &program.structs {
+            content.push_str("#[derive(BorshSerialize, BorshDeserialize, Clone, Default)]\n");
+            content.push_str(&format!(
+                "pub struct {} {{\n",
+                to_pascal_case(&struct_def.name)
+            ));
+            for field in &struct_def.fields {
+                content.push_str(&format!(
+                    "    pub {}: {},\n",
+                    to_snake_case(&field.name),
+                    self.bodies.type_to_rust(&field.ty)
+                ));
+            }
+            content.push_str("}\n\n");
+        }
+
+        content.push_str(
+            &self
+                .bodies
+                .generate_return_structs(program, "BorshSerialize, BorshDeserialize, Clone"),
+        );
+
+        let state_name = format!("{}State", to_pascal_case(&program.name));
+        content.push_str("/// Program state account\n");
+        content.push_str("#[derive(BorshSerialize, BorshDeserialize, Clone, Default)]\n");
+        content.push_str(&format!("pub struct {} {{\n", state_name));
+        for field in &program.state.fields {
+            content.push_str(&format!(
+                "    pub {}: {},\n",
+                to_snake_case(&field.name),
+                self.bodies.type_to_rust(&field.ty)
+            ));
+        }
+        content.push_str("}\n\n");
+        content.push_str(&account_impls(&state_name, layout.state.size));
+
+        for (mapping, entry_layout) in program.mappings.iter().zip(&layout.mappings) {
+            let struct_name = format!("{}Entry", to_pascal_case(&mapping.name));
+            let mut value_ty = &mapping.value_ty;
+            while let SolanaType::Mapping(_, inner) = value_ty {
+                value_ty = inner;
+            }
+            content.push_str(&format!(
+                "/// PDA account for {} mapping entries\n#[derive(BorshSerialize, BorshDeserialize, Clone, Default)]\npub struct {} {{\n",
+                mapping.name, struct_name
+            ));
+            content.push_str(&format!(
+                "    /// The key for this entry\n    pub key: {},\n",
+                self.bodies.type_to_rust(&mapping.key_ty)
+            ));
+            content.push_str(&format!(
+                "    /// The value stored at this key\n    pub value: {},\n",
+                self.bodies.type_to_rust(value_ty)
+            ));
+            content.push_str("}\n\n");
+            content.push_str(&account_impls(&struct_name, entry_layout.size));
+        }
+
+        Ok(with_header(
+            "//! Program state definitions\n\nuse borsh::{BorshDeserialize, BorshSerialize};\n",
+            "\nuse crate::{Discriminator, Space};\n\n",
+            content,
+        ))
+    }
+
+    fn generate_instructions_rs(
+        &mut self,
+        program: &SolanaProgram,
+    ) -> Result<String, CodegenError> {
+        let mut content = String::from(
+            r#"//! Instruction accounts, validated and loaded by hand
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction, system_program,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::state::*;
+"#,
+        );
+
+        let mut structs = String::new();
+        for instruction in &program.instructions {
+            if instruction.is_public {
+                structs.push('\n');
+                structs.push_str(&self.generate_accounts_struct(instruction, program)?);
+            }
+        }
+
+        // `Key` is only needed when a mapping seed is an account key
+        if structs.contains(".key()") {
+            content.push_str("use crate::{Discriminator, Key, Result, Space};\n\n");
+        } else {
+            content.push_str("use crate::{Discriminator, Result, Space};\n\n");
+        }
+        content.push_str(ACCOUNT_HELPERS);
+        content.push_str(&structs);
+        Ok(content)
+    }
+
+    /// Accounts struct for one instruction, in the same order as the Anchor context
+    fn generate_accounts_struct(
+        &mut self,
+        instruction: &Instruction,
+        program: &SolanaProgram,
+    ) -> Result<String, CodegenError> {
+        self.bodies.track_instruction_params(instruction);
+
+        let name = to_pascal_case(&instruction.name);
+        let state_name = format!("{}State", to_pascal_case(&program.name));
+        let is_init = instruction.name == "initialize";
+        let needs_system_program = is_init
+            || instruction.mapping_accesses.iter().any(|a| a.is_write)
+            || instruction.is_payable;
+
+        let mut fields = String::new();
+        let mut reads = String::new();
+        let mut checks = String::new();
+        let mut loads = String::new();
+        let mut init = Vec::new();
+        let mut exit = String::new();
+
+        // State account
+        fields.push_str(&format!("    pub state: {},\n", state_name));
+        fields.push_str("    pub state_info: &'a AccountInfo<'info>,\n");
+        reads.push_str("        let state_info = next_account_info(accounts)?;\n");
+        if is_init {
+            // A fresh keypair account, created here and paid for by the signer
+            checks.push_str("        check_signer(state_info)?;\n");
+            checks.push_str("        check_writable(state_info)?;\n");
+            loads.push_str(&format!(
+                "        create_account(program_id, state_info, signer, system_program, 8 + {}::INIT_SPACE, &[])?;\n        let state = {}::default();\n",
+                state_name, state_name
+            ));
+        } else {
+            if !instruction.is_view {
+                checks.push_str("        check_writable(state_info)?;\n");
+            }
+            loads.push_str(&format!(
+                "        let state = load_account::<{}>(program_id, state_info)?;\n",
+                state_name
+            ));
+        }
+        init.push("state".to_string());
+        init.push("state_info".to_string());
+        if instruction.closes_state {
+            exit.push_str("        close_account(self.state_info, self.signer)?;\n");
+        } else if !instruction.is_view {
+            exit.push_str("        store_account(self.state_info, &self.state)?;\n");
+        }
+
+        // Signer (pays for created accounts and receives closed ones)
+        fields.push_str("    pub signer: &'a AccountInfo<'info>,\n");
+        reads.push_str("        let signer = next_account_info(accounts)?;\n");
+        checks.push_str("        check_signer(signer)?;\n        check_writable(signer)?;\n");
+        init.push("signer".to_string());
+
+        // Additional signers, then caller-supplied accounts
+        for param in &instruction.params {
+            if matches!(param.ty, SolanaType::Signer) {
+                let param_name = to_snake_case(&param.name);
+                fields.push_str(&format!(
+                    "    pub {}: &'a AccountInfo<'info>,\n",
+                    param_name
+                ));
+                reads.push_str(&format!(
+                    "        let {} = next_account_info(accounts)?;\n",
+                    param_name
+                ));
+                checks.push_str(&format!("        check_signer({})?;\n", param_name));
+                if param.is_mut {
+                    checks.push_str(&format!("        check_writable({})?;\n", param_name));
+                }
+                init.push(param_name);
+            }
+        }
+        for param in &instruction.params {
+            if matches!(param.ty, SolanaType::Account(_)) {
+                let param_name = to_snake_case(&param.name);
+                fields.push_str(&format!(
+                    "    /// Unchecked account passed by the caller\n    pub {}: &'a AccountInfo<'info>,\n",
+                    param_name
+                ));
+                reads.push_str(&format!(
+                    "        let {} = next_account_info(accounts)?;\n",
+                    param_name
+                ));
+                if param.is_mut {
+                    checks.push_str(&format!("        check_writable({})?;\n", param_name));
+                }
+                init.push(param_name);
+            }
+        }
+
+        // Mapping entry PDAs, re-derived from their seeds
+        for access in &instruction.mapping_accesses {
+            let entry_type = format!("{}Entry", to_pascal_case(&access.mapping_name));
+            let account = to_snake_case(&access.account_name);
+            let info = format!("{}_info", account);
+
+            fields.push_str(&format!("    pub {}: {},\n", account, entry_type));
+            fields.push_str(&format!("    pub {}: &'a AccountInfo<'info>,\n", info));
+            reads.push_str(&format!(
+                "        let {} = next_account_info(accounts)?;\n",
+                info
+            ));
+
+            let mut seeds = vec![format!("b\"{}\"", to_snake_case(&access.mapping_name))];
+            for (i, key_expr) in access.key_exprs.iter().enumerate() {
+                let key = format!("{}_key_{}", account, i);
+                loads.push_str(&format!(
+                    "        let {} = &({});\n",
+                    key,
+                    self.bodies.generate_key_seed_expr(key_expr)?
+                ));
+                seeds.push(format!("{}.as_ref()", key));
+            }
+            loads.push_str(&format!(
+                "        let {}_seeds: &[&[u8]] = &[{}];\n",
+                account,
+                seeds.join(", ")
+            ));
+
+            if access.should_close {
+                checks.push_str(&format!("        check_writable({})?;\n", info));
+                loads.push_str(&format!(
+                    "        let {} = load_pda::<{}>(program_id, {}, {}_seeds)?;\n",
+                    account, entry_type, info, account
+                ));
+                exit.push_str(&format!(
+                    "        close_account(self.{}, self.signer)?;\n",
+                    info
+                ));
+            } else if access.is_write {
+                checks.push_str(&format!("        check_writable({})?;\n", info));
+                loads.push_str(&format!(
+                    "        let {} = load_or_create_pda::<{}>(program_id, {}, {}_seeds, signer, system_program)?;\n",
+                    account, entry_type, info, account
+                ));
+                exit.push_str(&format!(
+                    "        store_account(self.{}, &self.{})?;\n",
+                    info, account
+                ));
+            } else {
+                loads.push_str(&format!(
+                    "        let {} = load_pda::<{}>(program_id, {}, {}_seeds)?;\n",
+                    account, entry_type, info, account
+                ));
+            }
+            init.push(account);
+            init.push(info);
+        }
+
+        if needs_system_program {
+            fields.push_str("    pub system_program: &'a AccountInfo<'info>,\n");
+            reads.push_str("        let system_program = next_account_info(accounts)?;\n");
+            checks.push_str("        check_program(system_program, &system_program::ID)?;\n");
+            init.push("system_program".to_string());
+        }
+
+        let seed_params: String = self
+            .seed_args(instruction)
+            .iter()
+            .map(|(name, ty)| {
+                format!(
+                    ",\n        {}: &{}",
+                    to_snake_case(name),
+                    self.bodies.type_to_rust(ty)
+                )
+            })
+            .collect();
+
+        let mut content = format!(
+            "/// Accounts for `{}`\npub struct {}<'a, 'info> {{\n{}}}\n\n",
+            to_snake_case(&instruction.name),
+            name,
+            fields
+        );
+        content.push_str(&format!("impl<'a, 'info> {}<'a, 'info> {{\n", name));
+        content.push_str(&format!(
+            "    /// Read the accounts in order, check them and deserialize their data\n    pub fn try_accounts(\n        program_id: &Pubkey,\n        accounts: &mut std::slice::Iter<'a, AccountInfo<'info>>{},\n    ) -> Result<Self> {{\n",
+            seed_params
+        ));
+        content.push_str(&reads);
+        content.push_str(&checks);
+        content.push_str(&loads);
+        content.push_str(&format!(
+            "        Ok(Self {{\n            {},\n        }})\n    }}\n\n",
+            init.join(",\n            ")
+        ));
+        content.push_str(
+            "    /// Persist modified accounts and close the ones the instruction released\n",
+        );
+        if exit.is_empty() {
+            content.push_str("    pub fn exit(&self) -> Result<()> {\n        Ok(())\n    }\n");
+        } else {
+            content.push_str(&format!(
+                "    pub fn exit(&self) -> Result<()> {{\n{}        Ok(())\n    }}\n",
+                exit
+            ));
+        }
+        content.push_str("}\n");
+        Ok(content)
+    }
+
+    fn generate_error_rs(&self, program: &SolanaProgram) -> String {
+        let mut variants = vec![
+            (
+                "RequireFailed".to_string(),
+                "Requirement failed".to_string(),
+            ),
+            (
+                "InvalidRecipient".to_string(),
+                "Invalid recipient account".to_string(),
+            ),
+        ];
+        for error in &program.errors {
+            variants.push((to_pascal_case(&error.name), error.name.clone()));
+        }
+
+        let mut content = String::from(
+            r#"//! Custom error definitions
+
+use solana_program::{msg, program_error::ProgramError};
+
+/// Program errors, numbered from 6000 like Anchor's `#[error_code]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomError {
+"#,
+        );
+        for (i, (variant, message)) in variants.iter().enumerate() {
+            content.push_str(&format!("    /// {}\n", message));
+            if i == 0 {
+                content.push_str(&format!("    {} = 6000,\n", variant));
+            } else {
+                content.push_str(&format!("    {},\n", variant));
+            }
+        }
+        content.push_str("}\n\nimpl CustomError {\n    pub fn message(&self) -> &'static str {\n        match self {\n");
+        for (variant, message) in &variants {
+            content.push_str(&format!(
+                "            Self::{} => \"{}\",\n",
+                variant, message
+            ));
+        }
+        content.push_str(
+            r#"        }
+    }
+}
+
+impl From<CustomError> for ProgramError {
+    fn from(e: CustomError) -> Self {
+        msg!("Error {:?}: {}", e, e.message());
+        ProgramError::Custom(e as u32)
+    }
+}
+"#,
+        );
+        content
+    }
+
+    fn generate_events_rs(&self, program: &SolanaProgram) -> String {
+        let mut content = String::new();
+        for event in &program.events {
+            let event_name = to_pascal_case(&event.name);
+            content.push_str("#[derive(BorshSerialize)]\n");
+            content.push_str(&format!("pub struct {} {{\n", event_name));
+            for field in &event.fields {
+                content.push_str(&format!(
+                    "    pub {}: {},\n",
+                    to_snake_case(&field.name),
+                    self.bodies.type_to_rust(&field.ty)
+                ));
+            }
+            content.push_str("}\n\n");
+            content.push_str(&format!(
+                r#"impl {} {{
+    /// `sha256("event:{}")[..8]`, as in Anchor
+    pub const DISCRIMINATOR: [u8; 8] = {};
+
+    /// Log payload: the discriminator followed by the borsh-encoded event
+    pub fn data(&self) -> Vec<u8> {{
+        let mut data = Self::DISCRIMINATOR.to_vec();
+        self.serialize(&mut data).expect("writing to a Vec cannot fail");
+        data
+    }}
+}}
+
+"#,
+                event_name,
+                event_name,
+                discriminator_literal(&format!("event:{}", event_name))
+            ));
+        }
+        with_header(
+            "//! Event definitions\n\nuse borsh::BorshSerialize;\n",
+            "\n",
+            content,
+        )
+    }
+
+    fn generate_cargo_toml(&self, program: &SolanaProgram) -> String {
+        let name = to_snake_case(&program.name);
+        format!(
+            r#"[package]
+name = "{}"
+version = "0.1.0"
+description = "Generated by SolScript compiler"
+edition = "2021"
+
+[lib]
+crate-type = ["cdylib", "lib"]
+name = "{}"
+
+[features]
+no-entrypoint = []
+default = []
+
+[dependencies]
+solana-program = "2.1"
+borsh = {{ version = "1.5", features = ["derive"] }}
+"#,
+            name, name
+        )
+    }
+
+    fn generate_readme(&self, program: &SolanaProgram) -> String {
+        format!(
+            r#"# {}
+
+Generated by the SolScript compiler with the native `solana-program` backend.
+
+## Build
+
+```bash
+cd programs/solscript_program
+cargo build-sbf
+```
+
+## Deploy
+
+```bash
+solana program deploy programs/solscript_program/target/deploy/{}.so
+```
+
+Instructions, accounts and events use Anchor's discriminators and borsh
+layouts, so `target/idl/program.json` and `app/client.ts` work unchanged.
+"#,
+            program.name,
+            to_snake_case(&program.name)
+        )
+    }
+}
+
+/// Account validation and (de)serialization used by the generated `try_accounts`
+const ACCOUNT_HELPERS: &str = r#"fn check_signer(info: &AccountInfo) -> Result<()> {
+    if !info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+fn check_writable(info: &AccountInfo) -> Result<()> {
+    if !info.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+fn check_program(info: &AccountInfo, id: &Pubkey) -> Result<()> {
+    if info.key != id || !info.executable {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Deserialize an account owned by this program, checking its discriminator
+#[allow(dead_code)]
+fn load_account<T: Discriminator + BorshDeserialize>(
+    program_id: &Pubkey,
+    info: &AccountInfo,
+) -> Result<T> {
+    if info.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let data = info.try_borrow_data()?;
+    if data.len() < 8 || data[..8] != T::DISCRIMINATOR {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    T::deserialize(&mut &data[8..]).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// Write the discriminator and borsh data back to an account
+#[allow(dead_code)]
+fn store_account<T: Discriminator + BorshSerialize>(info: &AccountInfo, value: &T) -> Result<()> {
+    let mut data = info.try_borrow_mut_data()?;
+    data[..8].copy_from_slice(&T::DISCRIMINATOR);
+    value
+        .serialize(&mut &mut data[8..])
+        .map_err(|_| ProgramError::AccountDataTooSmall)
+}
+
+/// Move all lamports to `destination` and hand the account back to the system program
+#[allow(dead_code)]
+fn close_account(info: &AccountInfo, destination: &AccountInfo) -> Result<()> {
+    **destination.try_borrow_mut_lamports()? += info.lamports();
+    **info.try_borrow_mut_lamports()? = 0;
+    info.assign(&system_program::ID);
+    info.realloc(0, false)?;
+    Ok(())
+}
+
+/// Check that `info` is the PDA for `seeds`, returning its bump
+#[allow(dead_code)]
+fn check_pda(program_id: &Pubkey, info: &AccountInfo, seeds: &[&[u8]]) -> Result<u8> {
+    let (address, bump) = Pubkey::find_program_address(seeds, program_id);
+    if *info.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(bump)
+}
+
+#[allow(dead_code)]
+fn load_pda<T: Discriminator + BorshDeserialize>(
+    program_id: &Pubkey,
+    info: &AccountInfo,
+    seeds: &[&[u8]],
+) -> Result<T> {
+    check_pda(program_id, info, seeds)?;
+    load_account(program_id, info)
+}
+
+/// Load a mapping entry PDA, creating it on first write with the payer covering rent
+#[allow(dead_code)]
+fn load_or_create_pda<'info, T: Discriminator + BorshDeserialize + Default + Space>(
+    program_id: &Pubkey,
+    info: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<T> {
+    let bump = check_pda(program_id, info, seeds)?;
+    if info.owner == program_id {
+        return load_account(program_id, info);
+    }
+    let bump = [bump];
+    let mut signer_seeds = seeds.to_vec();
+    signer_seeds.push(&bump);
+    create_account(program_id, info, payer, system_program, 8 + T::INIT_SPACE, &signer_seeds)?;
+    Ok(T::default())
+}
+
+/// Allocate `space` bytes owned by this program. Addresses that already hold
+/// lamports are topped up, allocated and assigned instead of created.
+#[allow(dead_code)]
+fn create_account<'info>(
+    program_id: &Pubkey,
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let pda_signer = [signer_seeds];
+    let signers: &[&[&[u8]]] = if signer_seeds.is_empty() { &[] } else { &pda_signer };
+    if info.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, info.key, rent, space as u64, program_id),
+            &[payer.clone(), info.clone(), system_program.clone()],
+            signers,
+        );
+    }
+    let top_up = rent.saturating_sub(info.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, info.key, top_up),
+            &[payer.clone(), info.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(info.key, space as u64),
+        &[info.clone(), system_program.clone()],
+        signers,
+    )?;
+    invoke_signed(
+        &system_instruction::assign(info.key, program_id),
+        &[info.clone(), system_program.clone()],
+        signers,
+    )
+}
+"#;
+
+/// Reject IR the native backend cannot express without Anchor or SPL crates
+fn check_native_support(program: &SolanaProgram) -> Result<(), CodegenError> {
+    let unsupported = |what: &str, span| {
+        Err(CodegenError::unsupported_feature(
+            format!("{} with the native backend (use --backend anchor)", what),
+            span,
+        ))
+    };
+    if program.state.zero_copy {
+        return unsupported("#[zero_copy] state", program.span);
+    }
+    if program.state.migratable().is_some() {
+        return unsupported("State migrations", program.span);
+    }
+    if !program.cpi_interfaces.is_empty() {
+        return unsupported("Calls into IDL-imported programs", program.span);
+    }
+    for instruction in program.instructions.iter().filter(|i| i.is_public) {
+        if instruction.uses_token_program {
+            return unsupported("SPL token operations", instruction.span);
+        }
+        if instruction.uses_sol_transfer {
+            return unsupported("SOL transfers", instruction.span);
+        }
+        if !instruction.state_growth.is_empty() {
+            return unsupported("#[growable] state fields", instruction.span);
+        }
+        if !instruction.state_constraints.is_empty()
+            || instruction.params.iter().any(|p| !p.constraints.is_empty())
+        {
+            return unsupported("#[constraint(...)] attributes", instruction.span);
+        }
+        if instruction.params.iter().any(|p| {
+            matches!(
+                p.ty,
+                SolanaType::Account(AccountKind::TokenAccount | AccountKind::Mint)
+            )
+        }) {
+            return unsupported("Token and mint account parameters", instruction.span);
+        }
+    }
+    Ok(())
+}
+
+/// `Discriminator` and `Space` impls for a program-owned account type
+fn account_impls(name: &str, account_size: usize) -> String {
+    format!(
+        r#"impl Discriminator for {name} {{
+    const DISCRIMINATOR: [u8; 8] = {};
+}}
+
+impl Space for {name} {{
+    const INIT_SPACE: usize = {};
+}}
+
+"#,
+        discriminator_literal(&format!("account:{}", name)),
+        account_size - DISCRIMINATOR_SIZE,
+        name = name,
+    )
+}
+
+/// Prepend module docs and imports, importing `Pubkey` only where it is used
+fn with_header(header: &str, trailer: &str, body: String) -> String {
+    let pubkey = if body.contains("Pubkey") {
+        "use solana_program::pubkey::Pubkey;\n"
+    } else {
+        ""
+    };
+    format!("{}{}{}{}", header, pubkey, trailer, body)
+}
+
+/// Anchor's discriminator for `preimage`, as a Rust array literal
+fn discriminator_literal(preimage: &str) -> String {
+    format!("{:?}", discriminator(preimage))
+}
@@ -4,7 +4,9 @@
 
 use crate::error::CodegenError;
 use crate::ir::*;
+use crate::layout::{MAX_BYTES_LEN, MAX_STRING_LEN, MAX_VEC_LEN};
 use crate::GeneratedProject;
+use std::collections::BTreeMap;
 
 /// Rust code generator for Anchor programs
 #[derive(Default)]
@@ -13,10 +15,27 @@ pub struct RustGenerator {
     events: Vec<Event>,
     /// Current instruction's signer parameter names (for generating ctx.accounts access)
     signer_params: std::collections::HashSet<String>,
+    /// Current instruction's account parameters (`account<T>`, `unchecked_account`, `accounts[]`)
+    account_params: std::collections::HashMap<String, SolanaType>,
     /// Internal (non-public) function names
     internal_functions: std::collections::HashSet<String>,
     /// Whether we're currently generating a helper function body (not inside #[program])
     in_helper_function: bool,
+    /// Anchor programs imported from an IDL, for typed CPI generation
+    cpi_interfaces: Vec<CpiInterface>,
+    /// Whether the state account is `#[zero_copy]` (accessed through an `AccountLoader`)
+    zero_copy: bool,
+    /// Whether we're currently generating an `#[account(...)]` constraint expression,
+    /// where the accounts struct's fields are referenced directly
+    in_account_attribute: bool,
+    /// Generates the project's IDL
+    idl: crate::idl_gen::IdlGenerator,
+    /// Program address for `declare_id!` and the TypeScript client
+    program_id: String,
+    /// Program address per cluster, listed under `[programs.<cluster>]` in Anchor.toml
+    cluster_program_ids: BTreeMap<String, String>,
+    /// Whether the generated code targets the native backend (plain borsh, no Anchor)
+    native: bool,
 }
 
 impl RustGenerator {
@@ -24,37 +43,62 @@ impl RustGenerator {
         Self {
             events: Vec::new(),
             signer_params: std::collections::HashSet::new(),
+            account_params: std::collections::HashMap::new(),
             internal_functions: std::collections::HashSet::new(),
             in_helper_function: false,
+            cpi_interfaces: Vec::new(),
+            zero_copy: false,
+            in_account_attribute: false,
+            idl: crate::idl_gen::IdlGenerator::new(),
+            program_id: crate::idl_gen::PLACEHOLDER_PROGRAM_ID.to_string(),
+            cluster_program_ids: BTreeMap::new(),
+            native: false,
         }
     }
 
+    /// Generate handler bodies and helpers for the native backend
+    pub(crate) fn native() -> Self {
+        Self {
+            native: true,
+            ..Self::new()
+        }
+    }
+
+    /// Generate the IDL with `idl` (format and program address)
+    pub fn with_idl(mut self, idl: crate::idl_gen::IdlGenerator) -> Self {
+        self.idl = idl;
+        self
+    }
+
+    /// Declare the program at `program_id` (base58)
+    pub fn with_program_id(mut self, program_id: impl Into<String>) -> Self {
+        self.program_id = program_id.into();
+        self
+    }
+
+    /// List these `cluster -> address` entries in Anchor.toml; localnet falls back to
+    /// the declared program id
+    pub fn with_cluster_program_ids(mut self, ids: BTreeMap<String, String>) -> Self {
+        self.cluster_program_ids = ids;
+        self
+    }
+
     /// Generate a complete Anchor project from Solana IR
     pub fn generate(
         &mut self,
         programs: &[SolanaProgram],
     ) -> Result<GeneratedProject, CodegenError> {
         if programs.is_empty() {
-            return Err(CodegenError::MissingElement(
-                "No deployable contracts found (abstract contracts cannot be deployed)".to_string(),
+            return Err(CodegenError::missing_element(
+                "No deployable contracts found (abstract contracts cannot be deployed)",
+                solscript_ast::Span::dummy(),
             ));
         }
 
         // Use the last contract (child contracts come after their parents)
         // In inheritance hierarchies, we generate code for the most derived contract
         let program = programs.last().unwrap();
-
-        // Store events for lookup during emit generation
-        self.events = program.events.clone();
-
-        // Collect internal function names for proper call generation
-        self.internal_functions.clear();
-        for instruction in &program.instructions {
-            if !instruction.is_public {
-                self.internal_functions
-                    .insert(to_snake_case(&instruction.name));
-            }
-        }
+        self.prepare(program);
 
         let lib_rs = self.generate_lib_rs(program)?;
         let state_rs = self.generate_state_rs(program)?;
@@ -65,7 +109,8 @@ impl RustGenerator {
         let cargo_toml = self.generate_cargo_toml(program);
 
         // Generate TypeScript client
-        let mut ts_gen = crate::ts_gen::TypeScriptGenerator::new();
+        let mut ts_gen =
+            crate::ts_gen::TypeScriptGenerator::new().with_program_id(self.program_id.clone());
         let client_ts = ts_gen.generate(program)?;
 
         // Generate TypeScript tests
@@ -73,8 +118,7 @@ impl RustGenerator {
         let tests_ts = test_gen.generate(program)?;
 
         // Generate IDL
-        let mut idl_gen = crate::idl_gen::IdlGenerator::new();
-        let idl_json = idl_gen.generate(program)?;
+        let idl_json = self.idl.generate(program)?;
 
         // Generate package.json
         let package_json = self.generate_package_json(program);
@@ -103,11 +147,33 @@ impl RustGenerator {
             gitignore,
             rust_tests,
             has_tests,
-        })
+            source_map: Default::default(),
+        }
+        .extract_source_map())
+    }
+
+    /// Collect what statement generation needs to know about the program
+    pub(crate) fn prepare(&mut self, program: &SolanaProgram) {
+        // Store events for lookup during emit generation
+        self.events = program.events.clone();
+        self.cpi_interfaces = program.cpi_interfaces.clone();
+        self.zero_copy = program.state.zero_copy;
+
+        // Collect internal function names for proper call generation
+        self.internal_functions.clear();
+        for instruction in &program.instructions {
+            if !instruction.is_public {
+                self.internal_functions
+                    .insert(to_snake_case(&instruction.name));
+            }
+        }
     }
 
     /// Generate Rust unit tests from #[test] functions
-    fn generate_rust_tests(&self, program: &SolanaProgram) -> Result<String, CodegenError> {
+    pub(crate) fn generate_rust_tests(
+        &self,
+        program: &SolanaProgram,
+    ) -> Result<String, CodegenError> {
         if program.tests.is_empty() {
             return Ok(String::new());
         }
@@ -162,6 +228,7 @@ impl RustGenerator {
 
         // Generate helper functions (internal/private functions)
         let helper_fns = self.generate_helper_functions(program)?;
+        let handlers = self.generate_instruction_handlers(program)?;
 
         Ok(format!(
             r#"//! Generated by SolScript compiler
@@ -178,7 +245,7 @@ pub use instructions::*;
 pub use error::*;
 // Events are accessed via events:: prefix to avoid name collisions
 
-declare_id!("11111111111111111111111111111111");
+declare_id!("{}");
 
 {}
 
@@ -189,23 +256,31 @@ pub mod {} {{
 {}
 }}
 "#,
-            program.name,
-            imports,
-            helper_fns,
-            name,
-            self.generate_instruction_handlers(program)?
+            program.name, imports, self.program_id, helper_fns, name, handlers
         ))
     }
 
-    fn generate_helper_functions(
+    pub(crate) fn generate_helper_functions(
         &mut self,
         program: &SolanaProgram,
     ) -> Result<String, CodegenError> {
         let mut helpers = String::new();
+        // Fixed-point arithmetic uses the math helpers' wide multiplication
+        if program.uses_math || program.uses_fixed {
+            helpers.push_str(MATH_HELPERS);
+            helpers.push('\n');
+        }
+        if program.uses_fixed {
+            helpers.push_str(&self.generate_fixed_helpers());
+            helpers.push('\n');
+        }
 
         for instruction in &program.instructions {
             if !instruction.is_public {
-                helpers.push_str(&self.generate_helper_function(instruction, program)?);
+                let helper = self
+                    .generate_helper_function(instruction, program)
+                    .map_err(|e| e.or_at(instruction.span))?;
+                helpers.push_str(&helper);
                 helpers.push('\n');
             }
         }
@@ -213,6 +288,44 @@ pub mod {} {{
         Ok(helpers)
     }
 
+    /// The `solscript_fixed` module: fixed-point types with the backend's borsh
+    /// derives, followed by their arithmetic
+    fn generate_fixed_helpers(&self) -> String {
+        let derives = if self.native {
+            "borsh::BorshSerialize, borsh::BorshDeserialize"
+        } else {
+            "AnchorSerialize, AnchorDeserialize"
+        };
+        let mut module = String::from(
+            "/// Fixed-point decimal types\n#[allow(dead_code)]\nmod solscript_fixed {\n    use super::*;\n\n",
+        );
+        for (name, repr, size) in [
+            ("UFixed64", "u64", 8),
+            ("UFixed128", "u128", 16),
+            ("Fixed64", "i64", 8),
+            ("Fixed128", "i128", 16),
+        ] {
+            module.push_str(&format!(
+                "    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, {derives})]\n    pub struct {name}<const D: u32>(pub {repr});\n\n",
+            ));
+            // Anchor sizes accounts through `InitSpace`
+            if !self.native {
+                module.push_str(&format!(
+                    "    impl<const D: u32> Space for {name}<D> {{\n        const INIT_SPACE: usize = {size};\n    }}\n\n",
+                ));
+            }
+        }
+        for line in FIXED_HELPERS.lines() {
+            if !line.is_empty() {
+                module.push_str("    ");
+                module.push_str(line);
+            }
+            module.push('\n');
+        }
+        module.push_str("}\n");
+        module
+    }
+
     fn generate_helper_function(
         &mut self,
         instruction: &Instruction,
@@ -244,14 +357,15 @@ pub mod {} {{
         let body = self.generate_helper_body(instruction, program)?;
         self.in_helper_function = false;
 
-        Ok(format!(
+        let code = format!(
             r#"/// Internal helper function: {}
 fn {}({}) -> {} {{
 {}
 }}
 "#,
             instruction.name, name, params_str, return_type, body
-        ))
+        );
+        Ok(crate::source_map::mark(instruction.span, code))
     }
 
     fn generate_helper_body(
@@ -284,15 +398,109 @@ fn {}({}) -> {} {{
         for instruction in &program.instructions {
             // Only generate public functions as Anchor instructions
             if instruction.is_public {
-                handlers.push_str(&self.generate_instruction_handler(instruction, program)?);
+                let handler = self
+                    .generate_instruction_handler(instruction, program)
+                    .map_err(|e| e.or_at(instruction.span))?;
+                handlers.push_str(&handler);
                 handlers.push('\n');
             }
         }
 
+        if let Some(upgrade) = program.state.migratable() {
+            handlers.push_str(&self.generate_migrate_handler(program, upgrade));
+            handlers.push('\n');
+        }
+
         Ok(handlers)
     }
 
-    fn generate_instruction_handler(
+    /// `migrate`: read an older state layout, carry its fields over, run the
+    /// `#[migration]` helpers in order and resize the account to the current layout
+    fn generate_migrate_handler(&self, program: &SolanaProgram, upgrade: &StateUpgrade) -> String {
+        let state_name = format!("{}State", to_pascal_case(&program.name));
+        let mut content = String::from(
+            "    /// Migrate the state account to the current layout (upgrade authority only)\n",
+        );
+        content.push_str("    pub fn migrate(ctx: Context<Migrate>) -> Result<()> {\n");
+        content.push_str("        let info = ctx.accounts.state.to_account_info();\n");
+        content.push_str("        let mut state = {\n");
+        content.push_str("            let data = info.try_borrow_data()?;\n");
+        content.push_str(&format!(
+            "            require!(\n                data.len() > 8 && data.starts_with({}::DISCRIMINATOR),\n                CustomError::UnknownStateVersion\n            );\n",
+            state_name
+        ));
+        content.push_str(&format!(
+            "            require!(data[8] != {}, CustomError::StateAlreadyMigrated);\n",
+            upgrade.version
+        ));
+        content.push_str("            let mut bytes: &[u8] = &data[8..];\n");
+        content.push_str("            match data[8] {\n");
+        for version in 1..upgrade.version {
+            let carried: Vec<String> = program
+                .state
+                .fields
+                .iter()
+                .filter(|f| f.version <= version)
+                .map(|f| {
+                    let name = to_snake_case(&f.name);
+                    format!("{}: old.{}", name, name)
+                })
+                .collect();
+            content.push_str(&format!(
+                "                {} => {{\n                    let old = {}V{}::deserialize(&mut bytes)?;\n                    {} {{\n                        {},\n                        ..Default::default()\n                    }}\n                }}\n",
+                version,
+                state_name,
+                version,
+                state_name,
+                carried.join(",\n                        ")
+            ));
+        }
+        content.push_str("                _ => return err!(CustomError::UnknownStateVersion),\n");
+        content.push_str("            }\n        };\n");
+
+        if !upgrade.migrations.is_empty() {
+            content.push_str("        let from_version = state.layout_version;\n");
+            for migration in &upgrade.migrations {
+                content.push_str(&format!(
+                    "        if from_version <= {} {{\n            {}(&mut state)?;\n        }}\n",
+                    migration.from,
+                    to_snake_case(&migration.function)
+                ));
+            }
+        }
+        content.push_str(&format!(
+            "        state.layout_version = {};\n\n",
+            upgrade.version
+        ));
+
+        content.push_str(&format!(
+            r#"        // Resize to the current layout; the signer pays any extra rent
+        let space = 8 + {}::INIT_SPACE;
+        let rent = Rent::get()?.minimum_balance(space);
+        if rent > info.lamports() {{
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {{
+                        from: ctx.accounts.signer.to_account_info(),
+                        to: info.clone(),
+                    }},
+                ),
+                rent - info.lamports(),
+            )?;
+        }}
+        info.realloc(space, false)?;
+        let mut data = info.try_borrow_mut_data()?;
+        state.try_serialize(&mut &mut data[..])?;
+        Ok(())
+    }}
+"#,
+            state_name
+        ));
+        content
+    }
+
+    pub(crate) fn generate_instruction_handler(
         &mut self,
         instruction: &Instruction,
         program: &SolanaProgram,
@@ -300,11 +508,11 @@ fn {}({}) -> {} {{
         let name = to_snake_case(&instruction.name);
         let ctx_type = to_pascal_case(&instruction.name);
 
-        // Generate parameters (skip Signer types as they're in ctx.accounts)
+        // Generate parameters (skip signers and accounts as they're in ctx.accounts)
         let params: Vec<String> = instruction
             .params
             .iter()
-            .filter(|p| !matches!(p.ty, SolanaType::Signer))
+            .filter(|p| !p.ty.is_account())
             .map(|p| format!("{}: {}", to_snake_case(&p.name), self.type_to_rust(&p.ty)))
             .collect();
 
@@ -315,18 +523,78 @@ fn {}({}) -> {} {{
         };
 
         // Generate return type
-        let return_type = match &instruction.returns {
-            Some(ty) => format!("Result<{}>", self.type_to_rust(ty)),
-            None => "Result<()>".to_string(),
+        let return_type = match (&instruction.returns, instruction.return_struct()) {
+            (_, Some((name, _))) => format!("Result<{}>", name),
+            (Some(ty), None) => format!("Result<{}>", self.type_to_rust(ty)),
+            (None, None) => "Result<()>".to_string(),
         };
 
         // Generate body
-        let body = self.generate_instruction_body(instruction, program)?;
+        let mut body = self.generate_instruction_body(instruction, program)?;
 
-        Ok(format!(
-            "    pub fn {}(ctx: Context<{}>{}) -> {} {{\n{}\n    }}\n",
-            name, ctx_type, params_str, return_type, body
-        ))
+        if let (Some(upgrade), "initialize") = (&program.state.upgrade, instruction.name.as_str()) {
+            body = format!(
+                "        ctx.accounts.state.layout_version = {};\n{}",
+                upgrade.version, body
+            );
+        }
+
+        // Zero-copy state is loaded once and accessed in place
+        if self.zero_copy && (body.contains("state.") || body.contains("&mut state")) {
+            let load = if instruction.name == "initialize" {
+                "let mut state = ctx.accounts.state.load_init()?;"
+            } else if instruction.is_view {
+                "let state = ctx.accounts.state.load()?;"
+            } else {
+                "let mut state = ctx.accounts.state.load_mut()?;"
+            };
+            body = format!("        {}\n{}", load, body);
+        }
+
+        // CPIs into imported IDL programs mix `ctx.remaining_accounts` with the
+        // instruction's accounts, so both must share the `'info` lifetime
+        let (generics, context) = if body.contains(IDL_CPI_REMAINING_ACCOUNTS) {
+            (
+                "<'info>",
+                format!("Context<'_, '_, '_, 'info, {}<'info>>", ctx_type),
+            )
+        } else {
+            ("", format!("Context<{}>", ctx_type))
+        };
+
+        let code = format!(
+            "    pub fn {}{}(ctx: {}{}) -> {} {{\n{}\n    }}\n",
+            name, generics, context, params_str, return_type, body
+        );
+        Ok(crate::source_map::mark(instruction.span, code))
+    }
+
+    /// Access to an account of the instruction: `ctx.accounts.{name}` in a
+    /// handler, plain `{name}` inside `#[account(...)]`
+    fn account_path(&self, name: &str) -> String {
+        if self.in_account_attribute {
+            name.to_string()
+        } else {
+            format!("ctx.accounts.{}", name)
+        }
+    }
+
+    /// Track signer and account params of an instruction (for generating ctx.accounts access)
+    pub(crate) fn track_instruction_params(&mut self, instruction: &Instruction) {
+        self.signer_params.clear();
+        self.account_params.clear();
+        for param in &instruction.params {
+            match param.ty {
+                SolanaType::Signer => {
+                    self.signer_params.insert(to_snake_case(&param.name));
+                }
+                SolanaType::Account(_) | SolanaType::RemainingAccounts => {
+                    self.account_params
+                        .insert(to_snake_case(&param.name), param.ty.clone());
+                }
+                _ => {}
+            }
+        }
     }
 
     fn generate_instruction_body(
@@ -334,13 +602,7 @@ fn {}({}) -> {} {{
         instruction: &Instruction,
         program: &SolanaProgram,
     ) -> Result<String, CodegenError> {
-        // Track signer params for this instruction
-        self.signer_params.clear();
-        for param in &instruction.params {
-            if matches!(param.ty, SolanaType::Signer) {
-                self.signer_params.insert(to_snake_case(&param.name));
-            }
-        }
+        self.track_instruction_params(instruction);
 
         let mut body = String::new();
 
@@ -392,35 +654,32 @@ fn {}({}) -> {} {{
         indent: usize,
         output: &mut String,
     ) -> Result<(), CodegenError> {
-        match stmt {
-            Statement::Placeholder => {
+        match &stmt.kind {
+            StatementKind::Placeholder => {
                 // Replace placeholder with the inner function body
                 for inner_stmt in inner_body {
                     output.push_str(&self.generate_statement(inner_stmt, indent)?);
                 }
             }
-            Statement::If {
+            StatementKind::If {
                 condition,
                 then_block,
                 else_block,
             } => {
                 // Need to recursively handle if statements that might contain placeholders
                 let ind = "    ".repeat(indent);
-                output.push_str(&format!(
-                    "{}if {} {{\n",
-                    ind,
-                    self.generate_expression(condition)?
-                ));
+                let mut code = format!("{}if {} {{\n", ind, self.generate_expression(condition)?);
                 for s in then_block {
-                    self.generate_inlined_statement(s, inner_body, indent + 1, output)?;
+                    self.generate_inlined_statement(s, inner_body, indent + 1, &mut code)?;
                 }
                 if let Some(else_stmts) = else_block {
-                    output.push_str(&format!("{}}} else {{\n", ind));
+                    code.push_str(&format!("{}}} else {{\n", ind));
                     for s in else_stmts {
-                        self.generate_inlined_statement(s, inner_body, indent + 1, output)?;
+                        self.generate_inlined_statement(s, inner_body, indent + 1, &mut code)?;
                     }
                 }
-                output.push_str(&format!("{}}}\n", ind));
+                code.push_str(&format!("{}}}\n", ind));
+                output.push_str(&crate::source_map::mark(stmt.span, code));
             }
             _ => {
                 // For other statements, generate normally
@@ -431,10 +690,19 @@ fn {}({}) -> {} {{
     }
 
     fn generate_statement(&self, stmt: &Statement, indent: usize) -> Result<String, CodegenError> {
+        let code = self.generate_statement_kind(&stmt.kind, indent)?;
+        Ok(crate::source_map::mark(stmt.span, code))
+    }
+
+    fn generate_statement_kind(
+        &self,
+        kind: &StatementKind,
+        indent: usize,
+    ) -> Result<String, CodegenError> {
         let ind = "    ".repeat(indent);
 
-        match stmt {
-            Statement::VarDecl { name, ty, value } => {
+        match kind {
+            StatementKind::VarDecl { name, ty, value } => {
                 let name = to_snake_case(name);
                 let ty_str = self.type_to_rust(ty);
                 match value {
@@ -451,13 +719,13 @@ fn {}({}) -> {} {{
                     )),
                 }
             }
-            Statement::Assign { target, value } => Ok(format!(
+            StatementKind::Assign { target, value } => Ok(format!(
                 "{}{} = {};\n",
                 ind,
                 self.generate_expression(target)?,
                 self.generate_expression(value)?
             )),
-            Statement::If {
+            StatementKind::If {
                 condition,
                 then_block,
                 else_block,
@@ -478,7 +746,7 @@ fn {}({}) -> {} {{
                 result.push('\n');
                 Ok(result)
             }
-            Statement::While { condition, body } => {
+            StatementKind::While { condition, body } => {
                 let mut result =
                     format!("{}while {} {{\n", ind, self.generate_expression(condition)?);
                 for s in body {
@@ -487,7 +755,7 @@ fn {}({}) -> {} {{
                 result.push_str(&format!("{}}}\n", ind));
                 Ok(result)
             }
-            Statement::For {
+            StatementKind::For {
                 init,
                 condition,
                 update,
@@ -518,11 +786,17 @@ fn {}({}) -> {} {{
                 result.push_str(&format!("{}}}\n", ind));
                 Ok(result)
             }
-            Statement::Return(expr) => match expr {
+            StatementKind::Return(expr) => match expr {
+                // Handlers return multiple values in their return struct
+                Some(e @ Expression::Tuple(_)) => Ok(format!(
+                    "{}Ok({}.into())\n",
+                    ind,
+                    self.generate_expression(e)?
+                )),
                 Some(e) => Ok(format!("{}Ok({})\n", ind, self.generate_expression(e)?)),
                 None => Ok(format!("{}Ok(())\n", ind)),
             },
-            Statement::Emit { event, args } => {
+            StatementKind::Emit { event, args } => {
                 // Look up the event to get field names
                 let event_def = self.events.iter().find(|e| e.name == *event);
                 let args_str: Vec<String> = args
@@ -544,7 +818,7 @@ fn {}({}) -> {} {{
                     args_str.join(", ")
                 ))
             }
-            Statement::Require {
+            StatementKind::Require {
                 condition,
                 message: _,
             } => Ok(format!(
@@ -552,7 +826,7 @@ fn {}({}) -> {} {{
                 ind,
                 self.generate_expression(condition)?
             )),
-            Statement::RevertWithError {
+            StatementKind::RevertWithError {
                 error_name,
                 args: _,
             } => {
@@ -565,7 +839,7 @@ fn {}({}) -> {} {{
                     to_pascal_case(error_name)
                 ))
             }
-            Statement::Delete(target) => {
+            StatementKind::Delete(target) => {
                 // Check if this is a mapping access (delete closes the PDA)
                 if let Expression::MappingAccess { account_name, .. } = target {
                     // PDA closing is handled by the `close = signer` account constraint
@@ -581,7 +855,7 @@ fn {}({}) -> {} {{
                     Ok(format!("{}{} = Default::default();\n", ind, target_expr))
                 }
             }
-            Statement::Selfdestruct { .. } => {
+            StatementKind::Selfdestruct { .. } => {
                 // Selfdestruct is handled by the close constraint on the state account
                 // The actual closing is done by Anchor based on the account constraint
                 // We just add a comment here for clarity
@@ -590,8 +864,10 @@ fn {}({}) -> {} {{
                     ind
                 ))
             }
-            Statement::Expr(expr) => Ok(format!("{}{};\n", ind, self.generate_expression(expr)?)),
-            Statement::Placeholder => {
+            StatementKind::Expr(expr) => {
+                Ok(format!("{}{};\n", ind, self.generate_expression(expr)?))
+            }
+            StatementKind::Placeholder => {
                 // Placeholder should be replaced during modifier inlining
                 // This should not appear in generated code
                 Ok(String::new())
@@ -606,16 +882,29 @@ fn {}({}) -> {} {{
                 let snake_name = to_snake_case(name);
                 // If this is a signer param, access it from ctx.accounts
                 if self.signer_params.contains(&snake_name) {
-                    Ok(format!("ctx.accounts.{}.key()", snake_name))
+                    Ok(format!("{}.key()", self.account_path(&snake_name)))
+                } else if let Some(ty) = self.account_params.get(&snake_name) {
+                    if matches!(ty, SolanaType::RemainingAccounts) {
+                        Ok("ctx.remaining_accounts".to_string())
+                    } else {
+                        Ok(format!("{}.key()", self.account_path(&snake_name)))
+                    }
                 } else {
                     Ok(snake_name)
                 }
             }
             Expression::StateAccess(field) => {
-                if self.in_helper_function {
+                if self.zero_copy && self.in_account_attribute {
+                    Ok(format!("state.load()?.{}", to_snake_case(field)))
+                } else if self.in_helper_function || self.zero_copy {
+                    // Zero-copy handlers load the state into a local `state` up front
                     Ok(format!("state.{}", to_snake_case(field)))
                 } else {
-                    Ok(format!("ctx.accounts.state.{}", to_snake_case(field)))
+                    Ok(format!(
+                        "{}.{}",
+                        self.account_path("state"),
+                        to_snake_case(field)
+                    ))
                 }
             }
             Expression::MappingAccess {
@@ -625,11 +914,11 @@ fn {}({}) -> {} {{
             } => {
                 // Access the PDA account's value field
                 Ok(format!(
-                    "ctx.accounts.{}.value",
-                    to_snake_case(account_name)
+                    "{}.value",
+                    self.account_path(&to_snake_case(account_name))
                 ))
             }
-            Expression::MsgSender => Ok("ctx.accounts.signer.key()".to_string()),
+            Expression::MsgSender => Ok(format!("{}.key()", self.account_path("signer"))),
             Expression::MsgValue => Ok("0u64 /* msg.value not supported */".to_string()),
             Expression::BlockTimestamp => Ok("Clock::get()?.unix_timestamp as u64".to_string()),
             // Solana Clock sysvar fields
@@ -652,9 +941,13 @@ fn {}({}) -> {} {{
                     lamports_str, len_str
                 ))
             }
+            Expression::Math { function, args } => self.generate_math_call(*function, args, ""),
             Expression::Binary { op, left, right } => {
-                let l = self.generate_expression(left)?;
-                let r = self.generate_expression(right)?;
+                let l = self.generate_operand(left)?;
+                let r = self.generate_operand(right)?;
+                // A decimal literal takes its type from the right operand
+                let left_literal =
+                    decimal_literal(left).is_some() && decimal_literal(right).is_none();
                 let op_str = match op {
                     BinaryOp::Add => "+",
                     BinaryOp::Sub => "-",
@@ -675,8 +968,31 @@ fn {}({}) -> {} {{
                     BinaryOp::Shl => "<<",
                     BinaryOp::Shr => ">>",
                 };
+                if left_literal {
+                    return Ok(format!(
+                        "({{ let rhs = {}; crate::solscript_fixed::like(&rhs, {}) {} rhs }})",
+                        r, l, op_str
+                    ));
+                }
                 Ok(format!("({} {} {})", l, op_str, r))
             }
+            Expression::Unary { .. } if decimal_literal(expr).is_some() => {
+                let (negative, digits, decimals) = decimal_literal(expr).unwrap();
+                Ok(generate_decimal(negative, digits, decimals, ""))
+            }
+            Expression::ToFixed { ty, value } => match decimal_literal(value) {
+                Some((negative, digits, decimals)) => Ok(generate_decimal(
+                    negative,
+                    digits,
+                    decimals,
+                    &format!("::<{}>", fixed_type_name(ty)),
+                )),
+                None => Ok(format!(
+                    "{}::from_int({})",
+                    fixed_type_name(ty).replace('<', "::<"),
+                    self.generate_expression(value)?
+                )),
+            },
             Expression::Unary { op, expr } => {
                 let e = self.generate_expression(expr)?;
                 let op_str = match op {
@@ -698,6 +1014,8 @@ fn {}({}) -> {} {{
                     // Internal functions receive state as first parameter
                     let state_arg = if self.in_helper_function {
                         "state".to_string()
+                    } else if self.zero_copy {
+                        "&mut state".to_string()
                     } else {
                         "&mut ctx.accounts.state".to_string()
                     };
@@ -737,7 +1055,12 @@ fn {}({}) -> {} {{
                 interface_name,
                 method,
                 args,
+                span,
             } => {
+                if let Some(cpi) = self.find_cpi_instruction(interface_name, method) {
+                    return self.generate_idl_cpi_call(program, interface_name, cpi, args, *span);
+                }
+
                 let prog = self.generate_expression(program)?;
                 let args_str: Vec<String> = args
                     .iter()
@@ -927,12 +1250,31 @@ fn {}({}) -> {} {{
                     owner_str, mint_str
                 ))
             }
+            Expression::Tuple(elements) => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|e| self.generate_expression(e))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(format!("({})", elements.join(", ")))
+            }
+            Expression::Index { .. } if self.account_accessor(expr)?.is_some() => {
+                let account = self.account_accessor(expr)?.unwrap_or_default();
+                Ok(format!("{}.key()", account))
+            }
             Expression::Index { expr, index } => {
                 let e = self.generate_expression(expr)?;
                 let i = self.generate_expression(index)?;
                 // Cast index to usize for array/vec indexing
                 Ok(format!("{}[{} as usize]", e, i))
             }
+            Expression::Field { expr, field } if self.account_accessor(expr)?.is_some() => {
+                let account = self.account_accessor(expr)?.unwrap_or_default();
+                match field.as_str() {
+                    "key" => Ok(format!("{}.key()", account)),
+                    "lamports" => Ok(format!("{}.lamports()", account)),
+                    _ => Ok(format!("{}.{}", account, to_snake_case(field))),
+                }
+            }
             Expression::Field { expr, field } => {
                 let e = self.generate_expression(expr)?;
                 // Convert Solidity's .length to Rust's .len() with cast to u128
@@ -1041,6 +1383,231 @@ fn {}({}) -> {} {{
         }
     }
 
+    /// The accessor for an account parameter, or for an element of `accounts[]`
+    fn account_accessor(&self, expr: &Expression) -> Result<Option<String>, CodegenError> {
+        match expr {
+            Expression::Var(name) => {
+                let snake_name = to_snake_case(name);
+                Ok(match self.account_params.get(&snake_name) {
+                    Some(SolanaType::Account(_)) => Some(self.account_path(&snake_name)),
+                    _ => None,
+                })
+            }
+            Expression::Index { expr, index } => match expr.as_ref() {
+                Expression::Var(name)
+                    if matches!(
+                        self.account_params.get(&to_snake_case(name)),
+                        Some(SolanaType::RemainingAccounts)
+                    ) =>
+                {
+                    Ok(Some(format!(
+                        "ctx.remaining_accounts[{} as usize]",
+                        self.generate_expression(index)?
+                    )))
+                }
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn find_cpi_instruction(&self, interface_name: &str, method: &str) -> Option<&CpiInstruction> {
+        self.cpi_interfaces
+            .iter()
+            .find(|i| i.name == interface_name)?
+            .instructions
+            .iter()
+            .find(|ix| ix.name == method)
+    }
+
+    /// Generate a CPI into a program imported from an IDL.
+    ///
+    /// The leading call arguments are the instruction accounts; their AccountInfos
+    /// are looked up among the instruction's own accounts and `remaining_accounts`.
+    fn generate_idl_cpi_call(
+        &self,
+        program: &Expression,
+        interface_name: &str,
+        cpi: &CpiInstruction,
+        args: &[Expression],
+        span: solscript_ast::Span,
+    ) -> Result<String, CodegenError> {
+        if self.in_helper_function {
+            return Err(CodegenError::unsupported_feature(
+                format!(
+                    "CPI to {}.{} from an internal function (call it from a public function instead)",
+                    interface_name, cpi.name
+                ),
+                span,
+            ));
+        }
+
+        let prog = self.generate_expression(program)?;
+        let args_str: Vec<String> = args
+            .iter()
+            .map(|a| self.generate_expression(a))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (account_args, data_args) = args_str.split_at(cpi.accounts.len().min(args_str.len()));
+
+        let fields: Vec<String> = cpi
+            .accounts
+            .iter()
+            .zip(account_args)
+            .map(|(account, arg)| format!("{}: {}", to_snake_case(&account.name), arg))
+            .collect();
+
+        let mut data_parts = vec![format!(
+            "let mut data = {}::DISCRIMINATOR.to_vec();",
+            cpi_accounts_struct_name(interface_name, &cpi.name)
+        )];
+        // Each argument is encoded as the IDL declares it, not as the expression's
+        // own type (integer literals are `u128`)
+        for (arg, param) in data_args.iter().zip(&cpi.args) {
+            let ty = self.type_to_rust(&param.ty);
+            let value = if param.ty.is_integer() {
+                format!(
+                    "{}::try_from({}).map_err(|_| ProgramError::ArithmeticOverflow)?",
+                    ty, arg
+                )
+            } else {
+                format!("({}).into()", arg)
+            };
+            data_parts.push(format!("let arg: {} = {};", ty, value));
+            data_parts.push("AnchorSerialize::serialize(&arg, &mut data).unwrap();".to_string());
+        }
+
+        Ok(format!(
+            r#"{{
+            // CPI to {interface_name}.{method}
+            let cpi_program = {prog};
+            let cpi_accounts = {struct_name} {{ {fields} }};
+
+            // Build instruction data with Anchor discriminator
+            {data_code}
+
+            let ix = anchor_lang::solana_program::instruction::Instruction {{
+                program_id: cpi_program,
+                accounts: cpi_accounts.to_account_metas(),
+                data,
+            }};
+
+            let mut available = ctx.accounts.to_account_infos();
+            {remaining}
+            let account_infos = cpi_accounts.to_account_infos(cpi_program, &available)?;
+            anchor_lang::solana_program::program::invoke(&ix, &account_infos)?
+        }}"#,
+            interface_name = interface_name,
+            method = cpi.name,
+            prog = prog,
+            struct_name = cpi_accounts_struct_name(interface_name, &cpi.name),
+            fields = fields.join(", "),
+            data_code = data_parts.join("\n            "),
+            remaining = IDL_CPI_REMAINING_ACCOUNTS,
+        ))
+    }
+
+    /// Generate account structs for instructions of programs imported from an IDL
+    fn generate_cpi_accounts_structs(&self, interface: &CpiInterface) -> String {
+        let mut content = String::new();
+
+        for ix in &interface.instructions {
+            let struct_name = cpi_accounts_struct_name(&interface.name, &ix.name);
+            let field_names: Vec<String> =
+                ix.accounts.iter().map(|a| to_snake_case(&a.name)).collect();
+
+            content.push_str(&format!(
+                "/// Accounts for the `{}` instruction of the imported `{}` program\n",
+                ix.name, interface.name
+            ));
+            content.push_str(&format!("pub struct {} {{\n", struct_name));
+            for field in &field_names {
+                content.push_str(&format!("    pub {}: Pubkey,\n", field));
+            }
+            content.push_str("}\n\n");
+
+            content.push_str(&format!("impl {} {{\n", struct_name));
+            content.push_str(&format!(
+                "    pub const DISCRIMINATOR: [u8; 8] = {:?};\n\n",
+                ix.discriminator
+            ));
+
+            content.push_str("    pub fn to_account_metas(&self) -> Vec<AccountMeta> {\n");
+            content.push_str("        vec![\n");
+            for (account, field) in ix.accounts.iter().zip(&field_names) {
+                let ctor = if account.is_mut {
+                    "new"
+                } else {
+                    "new_readonly"
+                };
+                content.push_str(&format!(
+                    "            AccountMeta::{}(self.{}, {}),\n",
+                    ctor, field, account.is_signer
+                ));
+            }
+            content.push_str("        ]\n    }\n\n");
+
+            let keys: Vec<String> = std::iter::once("program_id".to_string())
+                .chain(field_names.iter().map(|f| format!("self.{}", f)))
+                .collect();
+            content.push_str(&format!(
+                r#"    /// Resolve the AccountInfos for this CPI from the accounts passed to the instruction
+    pub fn to_account_infos<'info>(
+        &self,
+        program_id: Pubkey,
+        available: &[AccountInfo<'info>],
+    ) -> Result<Vec<AccountInfo<'info>>> {{
+        [{}]
+            .iter()
+            .map(|key| {{
+                available
+                    .iter()
+                    .find(|info| info.key == key)
+                    .cloned()
+                    .ok_or_else(|| error!(anchor_lang::error::ErrorCode::AccountNotEnoughKeys))
+            }})
+            .collect()
+    }}
+}}
+
+"#,
+                keys.join(", ")
+            ));
+        }
+
+        content
+    }
+
+    /// Call a generated `solscript_math` helper. The helper's result type is inferred
+    /// from where it is used, or given as `turbofish` where it cannot be.
+    fn generate_math_call(
+        &self,
+        function: MathFunction,
+        args: &[Expression],
+        turbofish: &str,
+    ) -> Result<String, CodegenError> {
+        let args: Vec<String> = args
+            .iter()
+            .map(|arg| self.generate_operand(arg))
+            .collect::<Result<_, _>>()?;
+        Ok(format!(
+            "solscript_math::{}{}({})?",
+            function.helper_name(),
+            turbofish,
+            args.join(", ")
+        ))
+    }
+
+    /// An operand of an operator or a math helper, whose type Rust cannot infer from
+    /// its use: a math helper's result is `u128` there, like an integer literal
+    fn generate_operand(&self, expr: &Expression) -> Result<String, CodegenError> {
+        match expr {
+            Expression::Math { function, args } => {
+                self.generate_math_call(*function, args, "::<u128>")
+            }
+            expr => self.generate_expression(expr),
+        }
+    }
+
     fn generate_literal(&self, lit: &Literal) -> Result<String, CodegenError> {
         match lit {
             Literal::Bool(b) => Ok(b.to_string()),
@@ -1059,6 +1626,9 @@ fn {}({}) -> {} {{
                 // bytes32(0), bytes4(0), etc. - zero-filled fixed bytes
                 Ok(format!("[0u8; {}]", n))
             }
+            Literal::Decimal { digits, decimals } => {
+                Ok(generate_decimal(false, *digits, *decimals, ""))
+            }
         }
     }
 
@@ -1084,6 +1654,10 @@ use anchor_lang::prelude::*;
             content.push_str("}\n\n");
         }
 
+        content.push_str(
+            &self.generate_return_structs(program, "AnchorSerialize, AnchorDeserialize, Clone"),
+        );
+
         // Generate user-defined structs (before state account so they can be used as field types)
         for struct_def in &program.structs {
             content.push_str("#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]\n");
@@ -1101,28 +1675,12 @@ use anchor_lang::prelude::*;
             content.push_str("}\n\n");
         }
 
-        // Generate state account struct with InitSpace derive for automatic space calculation
-        content.push_str("#[account]\n");
-        content.push_str("#[derive(InitSpace)]\n");
-        content.push_str(&format!(
-            "pub struct {}State {{\n",
-            to_pascal_case(&program.name)
-        ));
-
-        for field in &program.state.fields {
-            // Add #[max_len] attribute for dynamic types (String, Vec, etc.)
-            if let Some(max_len_attr) = self.get_max_len_attribute(&field.ty) {
-                content.push_str(&format!("    {}\n", max_len_attr));
-            }
-            content.push_str(&format!(
-                "    pub {}: {},\n",
-                to_snake_case(&field.name),
-                self.type_to_rust(&field.ty)
-            ));
+        if program.state.zero_copy {
+            content.push_str(&self.generate_zero_copy_state(program));
+        } else {
+            content.push_str(&self.generate_borsh_state(program));
         }
 
-        content.push_str("}\n\n");
-
         // Generate mapping entry account structs with InitSpace
         for mapping in &program.mappings {
             let struct_name = format!("{}Entry", to_pascal_case(&mapping.name));
@@ -1169,21 +1727,21 @@ use anchor_lang::prelude::*;
     /// Returns None for fixed-size types that don't need the attribute
     fn get_max_len_attribute(&self, ty: &SolanaType) -> Option<String> {
         match ty {
-            SolanaType::String => Some("#[max_len(200)]".to_string()),
-            SolanaType::Bytes => Some("#[max_len(1000)]".to_string()),
+            SolanaType::String => Some(format!("#[max_len({})]", MAX_STRING_LEN)),
+            SolanaType::Bytes => Some(format!("#[max_len({})]", MAX_BYTES_LEN)),
             SolanaType::Vec(elem) => {
                 // For Vec<T>, we need max_len for the outer vec
                 // and potentially nested max_len for the element if it's dynamic
                 if self.get_max_len_attribute(elem).is_some() {
                     // Nested dynamic type - use (outer_len, inner_len) format
                     let inner_len = match elem.as_ref() {
-                        SolanaType::String => 200,
-                        SolanaType::Bytes => 1000,
-                        _ => 100,
+                        SolanaType::String => MAX_STRING_LEN,
+                        SolanaType::Bytes => MAX_BYTES_LEN,
+                        _ => MAX_VEC_LEN,
                     };
-                    Some(format!("#[max_len(100, {})]", inner_len))
+                    Some(format!("#[max_len({}, {})]", MAX_VEC_LEN, inner_len))
                 } else {
-                    Some("#[max_len(100)]".to_string())
+                    Some(format!("#[max_len({})]", MAX_VEC_LEN))
                 }
             }
             SolanaType::Option(inner) => self.get_max_len_attribute(inner),
@@ -1191,7 +1749,83 @@ use anchor_lang::prelude::*;
         }
     }
 
-    fn generate_instructions_rs(&self, program: &SolanaProgram) -> Result<String, CodegenError> {
+    /// State account struct with InitSpace derive for automatic space calculation
+    fn generate_borsh_state(&self, program: &SolanaProgram) -> String {
+        // Upgradeable state starts from defaults for fields added since the old layout
+        let mut content = if program.state.upgrade.is_some() {
+            String::from("#[account]\n#[derive(InitSpace, Default)]\n")
+        } else {
+            String::from("#[account]\n#[derive(InitSpace)]\n")
+        };
+        content.push_str(&format!(
+            "pub struct {}State {{\n",
+            to_pascal_case(&program.name)
+        ));
+
+        for field in &program.state.fields {
+            // Add #[max_len] attribute for dynamic types (String, Vec, etc.)
+            if let Some(max_len_attr) = self.get_max_len_attribute(&field.ty) {
+                content.push_str(&format!("    {}\n", max_len_attr));
+            }
+            content.push_str(&format!(
+                "    pub {}: {},\n",
+                to_snake_case(&field.name),
+                self.type_to_rust(&field.ty)
+            ));
+        }
+
+        content.push_str("}\n\n");
+
+        // Older layouts, read by `migrate`
+        if let Some(upgrade) = program.state.migratable() {
+            for version in 1..upgrade.version {
+                content.push_str(&format!(
+                    "/// Layout version {} of the state account\n#[derive(AnchorDeserialize)]\npub struct {}StateV{} {{\n",
+                    version,
+                    to_pascal_case(&program.name),
+                    version
+                ));
+                for field in program.state.fields.iter().filter(|f| f.version <= version) {
+                    content.push_str(&format!(
+                        "    pub {}: {},\n",
+                        to_snake_case(&field.name),
+                        self.type_to_rust(&field.ty)
+                    ));
+                }
+                content.push_str("}\n\n");
+            }
+        }
+
+        content
+    }
+
+    /// `#[zero_copy]` state account struct, read in place through an `AccountLoader`.
+    ///
+    /// Fields are in declaration order, with the padding made explicit during
+    /// lowering since `Pod` types can't contain implicit padding.
+    fn generate_zero_copy_state(&self, program: &SolanaProgram) -> String {
+        let mut content = String::from("#[account(zero_copy)]\n");
+        content.push_str(&format!(
+            "pub struct {}State {{\n",
+            to_pascal_case(&program.name)
+        ));
+
+        for field in &program.state.fields {
+            content.push_str(&format!(
+                "    pub {}: {},\n",
+                to_snake_case(&field.name),
+                self.type_to_rust(&field.ty)
+            ));
+        }
+
+        content.push_str("}\n\n");
+        content
+    }
+
+    fn generate_instructions_rs(
+        &mut self,
+        program: &SolanaProgram,
+    ) -> Result<String, CodegenError> {
         // Check if any public instruction uses token program
         let uses_token = program
             .instructions
@@ -1205,6 +1839,9 @@ use anchor_lang::prelude::*;
         if uses_token {
             content.push_str("use anchor_spl::token::Token;\n");
         }
+        if uses_spl_accounts(program) {
+            content.push_str("use anchor_spl::token::{Mint, TokenAccount};\n");
+        }
 
         content.push_str("use crate::state::*;\n\n");
 
@@ -1216,64 +1853,140 @@ use anchor_lang::prelude::*;
             }
         }
 
+        if program.state.migratable().is_some() {
+            content.push_str(&self.generate_migrate_context(program));
+            content.push('\n');
+        }
+
+        for interface in &program.cpi_interfaces {
+            content.push_str(&self.generate_cpi_accounts_structs(interface));
+        }
+
         Ok(content)
     }
 
+    /// Accounts for `migrate`. The state is read by hand since it may hold an older
+    /// layout, and the signer must be the program's upgrade authority.
+    fn generate_migrate_context(&self, program: &SolanaProgram) -> String {
+        format!(
+            r#"#[derive(Accounts)]
+pub struct Migrate<'info> {{
+    /// CHECK: Holds an older state layout; the discriminator is checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub state: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::{}>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(signer.key())
+            @ crate::error::CustomError::NotUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}}
+"#,
+            to_pascal_case(&to_snake_case(&program.name))
+        )
+    }
+
     fn generate_context_struct(
-        &self,
+        &mut self,
         instruction: &Instruction,
         program: &SolanaProgram,
     ) -> Result<String, CodegenError> {
+        self.track_instruction_params(instruction);
+
         let name = to_pascal_case(&instruction.name);
         let state_name = format!("{}State", to_pascal_case(&program.name));
 
-        // Collect instruction params used in mapping seeds
+        // Collect instruction params used in mapping seeds and realloc sizes
         let mut seed_params: Vec<(&String, &SolanaType)> = Vec::new();
         for access in &instruction.mapping_accesses {
             for key_expr in &access.key_exprs {
                 self.collect_seed_params(key_expr, instruction, &mut seed_params);
             }
         }
+        for growth in &instruction.state_growth {
+            let (GrowthKind::Push(value) | GrowthKind::Assign(value)) = &growth.kind;
+            self.collect_seed_params(value, instruction, &mut seed_params);
+        }
 
         let mut content = String::new();
         content.push_str("#[derive(Accounts)]\n");
 
-        // Add #[instruction(...)] if there are params used in seeds
-        if !seed_params.is_empty() {
-            let params_str: Vec<String> = seed_params
+        // Add #[instruction(...)] if there are params used in seeds. Anchor deserializes
+        // these positionally, so every argument up to the last one used is listed.
+        let args: Vec<&InstructionParam> = instruction
+            .params
+            .iter()
+            .filter(|p| !p.ty.is_account())
+            .collect();
+        if let Some(last_used) = args
+            .iter()
+            .rposition(|p| seed_params.iter().any(|(name, _)| **name == p.name))
+        {
+            let params_str: Vec<String> = args[..=last_used]
                 .iter()
-                .map(|(name, ty)| format!("{}: {}", to_snake_case(name), self.type_to_rust(ty)))
+                .map(|p| format!("{}: {}", to_snake_case(&p.name), self.type_to_rust(&p.ty)))
                 .collect();
             content.push_str(&format!("#[instruction({})]\n", params_str.join(", ")));
         }
 
         content.push_str(&format!("pub struct {}<'info> {{\n", name));
 
-        // State account
+        // State account (zero-copy state is loaded on demand rather than deserialized)
+        let (state_account, state_space) = if program.state.zero_copy {
+            (
+                format!("AccountLoader<'info, {}>", state_name),
+                format!("std::mem::size_of::<{}>()", state_name),
+            )
+        } else {
+            (
+                format!("Account<'info, {}>", state_name),
+                format!("{}::INIT_SPACE", state_name),
+            )
+        };
         if instruction.name == "initialize" {
             content.push_str(&format!(
                 r#"    #[account(
         init,
         payer = signer,
-        space = 8 + {}::INIT_SPACE
+        space = 8 + {}
     )]
-    pub state: Account<'info, {}>,
+    pub state: {},
 "#,
-                state_name, state_name
-            ));
-        } else if instruction.is_view {
-            content.push_str(&format!("    pub state: Account<'info, {}>,\n", state_name));
-        } else if instruction.closes_state {
-            // Selfdestruct: close the state account and send rent to signer
-            content.push_str(&format!(
-                "    #[account(mut, close = signer)]\n    pub state: Account<'info, {}>,\n",
-                state_name
+                state_space, state_account
             ));
         } else {
-            content.push_str(&format!(
-                "    #[account(mut)]\n    pub state: Account<'info, {}>,\n",
-                state_name
-            ));
+            let mut constraints =
+                self.generate_account_constraints(&instruction.state_constraints)?;
+            let has_close = instruction
+                .state_constraints
+                .iter()
+                .any(|c| matches!(c, AccountConstraint::Close(_)));
+            if instruction.closes_state && !has_close {
+                // Selfdestruct: close the state account and send rent to signer
+                constraints.insert(0, "close = signer".to_string());
+            }
+            if !instruction.state_growth.is_empty() {
+                // Grow the account to fit #[growable] writes; the signer pays the extra rent
+                let growth = instruction
+                    .state_growth
+                    .iter()
+                    .map(|g| self.generate_state_growth(g))
+                    .collect::<Result<Vec<_>, _>>()?;
+                constraints.push(format!(
+                    "realloc = state.to_account_info().data_len() + {}",
+                    growth.join(" + ")
+                ));
+                constraints.push("realloc::payer = signer".to_string());
+                // Zero the new bytes: an account shrunk earlier in the same
+                // transaction would otherwise grow back over its old data
+                constraints.push("realloc::zero = true".to_string());
+            }
+            content.push_str(&account_attribute(!instruction.is_view, &constraints));
+            content.push_str(&format!("    pub state: {},\n", state_account));
         }
 
         // Signer
@@ -1283,6 +1996,8 @@ use anchor_lang::prelude::*;
         // Add additional signers for parameters with Signer type
         for param in &instruction.params {
             if matches!(param.ty, SolanaType::Signer) {
+                let constraints = self.generate_account_constraints(&param.constraints)?;
+                content.push_str(&account_attribute(param.is_mut, &constraints));
                 content.push_str(&format!(
                     "    pub {}: Signer<'info>,\n",
                     to_snake_case(&param.name)
@@ -1290,6 +2005,27 @@ use anchor_lang::prelude::*;
             }
         }
 
+        // Add account parameters (accounts[] maps to ctx.remaining_accounts instead)
+        for param in &instruction.params {
+            if let SolanaType::Account(kind) = param.ty {
+                if kind == AccountKind::Unchecked {
+                    content.push_str("    /// CHECK: Unchecked account passed by the caller\n");
+                }
+                let constraints = self.generate_account_constraints(&param.constraints)?;
+                content.push_str(&account_attribute(param.is_mut, &constraints));
+                let account_ty = match kind {
+                    AccountKind::TokenAccount => "Account<'info, TokenAccount>",
+                    AccountKind::Mint => "Account<'info, Mint>",
+                    AccountKind::Unchecked => "UncheckedAccount<'info>",
+                };
+                content.push_str(&format!(
+                    "    pub {}: {},\n",
+                    to_snake_case(&param.name),
+                    account_ty
+                ));
+            }
+        }
+
         // Add PDA accounts for mapping accesses
         for access in &instruction.mapping_accesses {
             let entry_type = format!("{}Entry", to_pascal_case(&access.mapping_name));
@@ -1370,6 +2106,7 @@ use anchor_lang::prelude::*;
 
         // System program (needed if any init_if_needed is used, for payable functions, or for SOL transfers)
         let needs_system_program = instruction.name == "initialize"
+            || !instruction.state_growth.is_empty()
             || instruction.mapping_accesses.iter().any(|a| a.is_write)
             || instruction.is_payable
             || instruction.uses_sol_transfer;
@@ -1387,8 +2124,79 @@ use anchor_lang::prelude::*;
         Ok(content)
     }
 
+    /// Render `#[constraint(...)]` entries as `#[account(...)]` arguments
+    fn generate_account_constraints(
+        &mut self,
+        constraints: &[AccountConstraint],
+    ) -> Result<Vec<String>, CodegenError> {
+        self.in_account_attribute = true;
+        let generated = self.generate_account_constraint_args(constraints);
+        self.in_account_attribute = false;
+        generated
+    }
+
+    fn generate_account_constraint_args(
+        &self,
+        constraints: &[AccountConstraint],
+    ) -> Result<Vec<String>, CodegenError> {
+        constraints
+            .iter()
+            .map(|c| {
+                let expr = |e: &Expression| self.generate_expression(e);
+                Ok(match c {
+                    AccountConstraint::HasOne(name) => format!("has_one = {}", to_snake_case(name)),
+                    AccountConstraint::Owner(e) => format!("owner = {}", expr(e)?),
+                    AccountConstraint::Address(e) => format!("address = {}", expr(e)?),
+                    AccountConstraint::Constraint(e) => format!("constraint = {}", expr(e)?),
+                    AccountConstraint::Close(name) => format!("close = {}", to_snake_case(name)),
+                })
+            })
+            .collect()
+    }
+
+    /// Bytes a `#[growable]` write adds to the state account (used in the realloc constraint)
+    fn generate_state_growth(&mut self, growth: &StateGrowth) -> Result<String, CodegenError> {
+        self.in_account_attribute = true;
+        let field = self.generate_expression(&Expression::StateAccess(growth.field.clone()));
+        let value = match &growth.kind {
+            GrowthKind::Push(value) | GrowthKind::Assign(value) => self.generate_expression(value),
+        };
+        self.in_account_attribute = false;
+        let (field, value) = (field?, value?);
+
+        let unsupported = || {
+            CodegenError::unsupported_feature(
+                format!("#[growable] field '{}' of this element type", growth.field),
+                growth.span,
+            )
+        };
+        match (&growth.kind, &growth.ty) {
+            // Borsh stores strings, bytes and vectors as a u32 length followed by the data
+            (GrowthKind::Push(_), SolanaType::Vec(elem)) => match elem.as_ref() {
+                SolanaType::String | SolanaType::Bytes => Ok(format!("4 + ({}).len()", value)),
+                elem => borsh_size(elem)
+                    .map(|size| size.to_string())
+                    .ok_or_else(unsupported),
+            },
+            (GrowthKind::Assign(_), SolanaType::String | SolanaType::Bytes) => {
+                Ok(format!("({}).len().saturating_sub({}.len())", value, field))
+            }
+            (GrowthKind::Assign(_), SolanaType::Vec(elem)) => {
+                let size = borsh_size(elem).ok_or_else(unsupported)?;
+                Ok(format!(
+                    "({}).len().saturating_sub({}.len()) * {}",
+                    value, field, size
+                ))
+            }
+            _ => Err(unsupported()),
+        }
+    }
+
     /// Generate the seed expression for a mapping key (used in #[account] attributes)
-    fn generate_key_seed_expr(&self, key_expr: &Expression) -> Result<String, CodegenError> {
+    pub(crate) fn generate_key_seed_expr(
+        &self,
+        key_expr: &Expression,
+    ) -> Result<String, CodegenError> {
         match key_expr {
             // In account attributes, we reference accounts directly without ctx.accounts prefix
             Expression::MsgSender => Ok("signer.key()".to_string()),
@@ -1396,6 +2204,9 @@ use anchor_lang::prelude::*;
             Expression::Literal(Literal::Pubkey(s)) => Ok(format!("Pubkey::default() /* {} */", s)),
             Expression::Literal(Literal::ZeroAddress) => Ok("Pubkey::default()".to_string()),
             Expression::Literal(Literal::ZeroBytes(n)) => Ok(format!("[0u8; {}]", n)),
+            Expression::StateAccess(field) if self.zero_copy => {
+                Ok(format!("state.load()?.{}", to_snake_case(field)))
+            }
             Expression::StateAccess(field) => {
                 // For state field access in seeds, reference via state account
                 Ok(format!("state.{}", to_snake_case(field)))
@@ -1411,7 +2222,7 @@ use anchor_lang::prelude::*;
     }
 
     /// Collect instruction parameters that are used in seed expressions
-    fn collect_seed_params<'a>(
+    pub(crate) fn collect_seed_params<'a>(
         &self,
         key_expr: &'a Expression,
         instruction: &'a Instruction,
@@ -1465,6 +2276,19 @@ pub enum CustomError {
             ));
         }
 
+        // Errors of the generated `migrate` instruction
+        if program.state.migratable().is_some() {
+            content.push_str(
+                r#"    #[msg("Signer is not the program's upgrade authority")]
+    NotUpgradeAuthority,
+    #[msg("State account already has the current layout")]
+    StateAlreadyMigrated,
+    #[msg("State account has an unknown layout version")]
+    UnknownStateVersion,
+"#,
+            );
+        }
+
         content.push_str("}\n");
 
         Ok(content)
@@ -1501,15 +2325,19 @@ use anchor_lang::prelude::*;
 
     fn generate_anchor_toml(&self, program: &SolanaProgram) -> String {
         let name = to_snake_case(&program.name);
+        let mut ids = self.cluster_program_ids.clone();
+        ids.entry("localnet".to_string())
+            .or_insert_with(|| self.program_id.clone());
+        let programs: String = ids
+            .iter()
+            .map(|(cluster, id)| format!("[programs.{}]\n{} = \"{}\"\n\n", cluster, name, id))
+            .collect();
         format!(
             r#"[features]
 seeds = false
 skip-lint = false
 
-[programs.localnet]
-{} = "11111111111111111111111111111111"
-
-[registry]
+{}[registry]
 url = "https://api.apr.dev"
 
 [provider]
@@ -1519,13 +2347,14 @@ wallet = "~/.config/solana/id.json"
 [scripts]
 test = "yarn run ts-mocha -p ./tsconfig.json -t 1000000 tests/**/*.ts"
 "#,
-            name
+            programs
         )
     }
 
     fn generate_cargo_toml(&self, program: &SolanaProgram) -> String {
         let name = to_snake_case(&program.name);
-        let uses_token = program.instructions.iter().any(|i| i.uses_token_program);
+        let uses_token =
+            program.instructions.iter().any(|i| i.uses_token_program) || uses_spl_accounts(program);
 
         let mut deps = String::from(
             "anchor-lang = { version = \"0.32.0\", features = [\"init-if-needed\"] }\n",
@@ -1533,6 +2362,13 @@ test = "yarn run ts-mocha -p ./tsconfig.json -t 1000000 tests/**/*.ts"
         if uses_token {
             deps.push_str("anchor-spl = \"0.32.0\"\n");
         }
+        // `#[account(zero_copy)]` derives bytemuck's Pod and Zeroable, and large
+        // arrays need its const generic impls
+        if program.state.zero_copy {
+            deps.push_str(
+                "bytemuck = { version = \"1\", features = [\"derive\", \"min_const_generics\"] }\n",
+            );
+        }
 
         format!(
             r#"[package]
@@ -1559,7 +2395,7 @@ default = []
         )
     }
 
-    fn type_to_rust(&self, ty: &SolanaType) -> String {
+    pub(crate) fn type_to_rust(&self, ty: &SolanaType) -> String {
         match ty {
             SolanaType::U8 => "u8".to_string(),
             SolanaType::U16 => "u16".to_string(),
@@ -1582,7 +2418,49 @@ default = []
             SolanaType::Option(inner) => format!("Option<{}>", self.type_to_rust(inner)),
             SolanaType::Mapping(_, _) => "/* Mapping - use PDAs */".to_string(),
             SolanaType::Custom(name) => to_pascal_case(name),
+            SolanaType::Account(_) => "Pubkey".to_string(),
+            SolanaType::RemainingAccounts => "Vec<Pubkey>".to_string(),
+            SolanaType::Fixed(fixed) => fixed_type_name(fixed),
+            SolanaType::Tuple(types) => format!(
+                "({})",
+                types
+                    .iter()
+                    .map(|t| self.type_to_rust(t))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Structs that public functions with multiple return values return, so
+    /// the values get a name in the IDL: `GetReservesReturn { field0, field1 }`,
+    /// built from the tuple the handler returns
+    pub(crate) fn generate_return_structs(&self, program: &SolanaProgram, derive: &str) -> String {
+        let mut content = String::new();
+        for instruction in program.instructions.iter().filter(|i| i.is_public) {
+            let Some((name, types)) = instruction.return_struct() else {
+                continue;
+            };
+            let fields: Vec<String> = (0..types.len()).map(|i| format!("field{}", i)).collect();
+            content.push_str(&format!(
+                "/// Return values of `{}`\n#[derive({})]\npub struct {} {{\n",
+                to_snake_case(&instruction.name),
+                derive,
+                name
+            ));
+            for (field, ty) in fields.iter().zip(types) {
+                content.push_str(&format!("    pub {}: {},\n", field, self.type_to_rust(ty)));
+            }
+            content.push_str("}\n\n");
+            let tuple = self.type_to_rust(instruction.returns.as_ref().unwrap());
+            content.push_str(&format!(
+                "impl From<{tuple}> for {name} {{\n    fn from(({fields}): {tuple}) -> Self {{\n        Self {{ {fields} }}\n    }}\n}}\n\n",
+                tuple = tuple,
+                name = name,
+                fields = fields.join(", ")
+            ));
         }
+        content
     }
 
     /// Get the innermost value type for nested mappings
@@ -1594,7 +2472,7 @@ default = []
         }
     }
 
-    fn generate_package_json(&self, program: &SolanaProgram) -> String {
+    pub(crate) fn generate_package_json(&self, program: &SolanaProgram) -> String {
         let name = to_snake_case(&program.name);
         format!(
             r#"{{
@@ -1727,7 +2605,7 @@ const client = new {}Client(provider);
         )
     }
 
-    fn generate_gitignore(&self) -> String {
+    pub(crate) fn generate_gitignore(&self) -> String {
         r#"# Anchor
 target/
 .anchor/
@@ -1763,7 +2641,7 @@ dist/
 }
 
 // Helper functions
-fn to_snake_case(s: &str) -> String {
+pub(crate) fn to_snake_case(s: &str) -> String {
     let mut result = String::new();
     let mut prev_upper = false;
 
@@ -1783,7 +2661,99 @@ fn to_snake_case(s: &str) -> String {
     result
 }
 
-fn to_pascal_case(s: &str) -> String {
+/// Borsh-serialized size of a fixed-size type
+fn borsh_size(ty: &SolanaType) -> Option<usize> {
+    match ty {
+        SolanaType::Bool => Some(1),
+        SolanaType::Array(inner, n) => borsh_size(inner).map(|size| size * n),
+        SolanaType::Fixed(fixed) => Some(usize::from(fixed.bits / 8)),
+        _ => ty.pod_layout().map(|(size, _)| size),
+    }
+}
+
+/// Rust type of a fixed-point decimal: `ufixed64x6` → `crate::solscript_fixed::UFixed64<6>`
+pub(crate) fn fixed_type_name(fixed: &solscript_ast::FixedType) -> String {
+    format!(
+        "crate::solscript_fixed::{}Fixed{}<{}>",
+        if fixed.signed { "" } else { "U" },
+        fixed.bits,
+        fixed.decimals
+    )
+}
+
+/// A decimal literal, possibly negated: `(negative, digits, decimals)`
+fn decimal_literal(expr: &Expression) -> Option<(bool, u128, u8)> {
+    match expr {
+        Expression::Literal(Literal::Decimal { digits, decimals }) => {
+            Some((false, *digits, *decimals))
+        }
+        Expression::Unary {
+            op: UnaryOp::Neg,
+            expr,
+        } => match expr.as_ref() {
+            Expression::Literal(Literal::Decimal { digits, decimals }) => {
+                Some((true, *digits, *decimals))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Construct a decimal literal, with its type inferred unless given by `turbofish`
+fn generate_decimal(negative: bool, digits: u128, decimals: u8, turbofish: &str) -> String {
+    format!(
+        "crate::solscript_fixed::{}{}({}, {})",
+        if negative {
+            "negative_decimal"
+        } else {
+            "decimal"
+        },
+        turbofish,
+        digits,
+        decimals
+    )
+}
+
+/// An `#[account(...)]` line, or nothing if the account needs no attribute
+fn account_attribute(is_mut: bool, constraints: &[String]) -> String {
+    let args: Vec<&str> = is_mut
+        .then_some("mut")
+        .into_iter()
+        .chain(constraints.iter().map(String::as_str))
+        .collect();
+    if args.is_empty() {
+        String::new()
+    } else {
+        format!("    #[account({})]\n", args.join(", "))
+    }
+}
+
+/// Whether any public instruction takes an SPL `account<TokenAccount>` or `account<Mint>` param
+fn uses_spl_accounts(program: &SolanaProgram) -> bool {
+    program
+        .instructions
+        .iter()
+        .filter(|i| i.is_public)
+        .flat_map(|i| &i.params)
+        .any(|p| {
+            matches!(
+                p.ty,
+                SolanaType::Account(AccountKind::TokenAccount | AccountKind::Mint)
+            )
+        })
+}
+
+/// Name of the generated account struct for a CPI into an imported program
+fn cpi_accounts_struct_name(interface_name: &str, method: &str) -> String {
+    format!(
+        "{}{}Accounts",
+        to_pascal_case(interface_name),
+        to_pascal_case(&to_snake_case(method))
+    )
+}
+
+pub(crate) fn to_pascal_case(s: &str) -> String {
     let mut result = String::new();
     let mut capitalize_next = true;
 
@@ -1800,3 +2770,12 @@ fn to_pascal_case(s: &str) -> String {
 
     result
 }
+
+/// Helpers for the `@solana/math` module. Arguments of any unsigned integer type are
+/// widened to `u128`, and results narrowed to the type the caller expects; a result
+/// that does not fit fails with `ArithmeticOverflow`.
+/// Generated by IDL CPI calls; marks handlers that need an explicit `'info` lifetime
+const IDL_CPI_REMAINING_ACCOUNTS: &str = "available.extend_from_slice(ctx.remaining_accounts);";
+
+const MATH_HELPERS: &str = include_str!("../templates/solscript_math.rs");
+const FIXED_HELPERS: &str = include_str!("../templates/solscript_fixed.rs");
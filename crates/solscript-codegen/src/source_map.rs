@@ -0,0 +1,191 @@
+//! Source maps from generated Rust back to SolScript
+//!
+//! While generating, each statement and handler is wrapped in marker lines carrying its
+//! source span. Once a file is complete the markers are stripped and turned into line
+//! ranges, so the mapping always matches the final text.
+
+use serde::{Deserialize, Serialize};
+use solscript_ast::Span;
+
+/// Starts a marker line; never appears in generated Rust
+const MARKER: char = '\u{1}';
+
+/// Current `.solmap` format version
+pub const SOURCE_MAP_VERSION: u32 = 1;
+
+/// Line ranges of generated files and the SolScript spans they came from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMap {
+    pub version: u32,
+    /// Path of the SolScript source the spans point into, unless a mapping names another
+    pub source: String,
+    pub mappings: Vec<Mapping>,
+}
+
+/// Generated lines `start_line..=end_line` (1-based) of `file` came from `span`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mapping {
+    /// Generated file, relative to the project root
+    pub file: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub span: Span,
+    /// SolScript file the span points into, when not the map's `source`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl SourceMap {
+    /// The innermost mapping covering `line` of a generated file. `file` may be relative
+    /// to the project root, to any directory above or below it, or absolute.
+    pub fn lookup(&self, file: &str, line: usize) -> Option<&Mapping> {
+        let file = file.replace('\\', "/");
+        self.mappings
+            .iter()
+            .filter(|m| same_file(&file, &m.file))
+            .filter(|m| (m.start_line..=m.end_line).contains(&line))
+            .min_by_key(|m| m.end_line - m.start_line)
+    }
+
+    /// Every SolScript file the mappings point into
+    pub fn sources(&self) -> Vec<&str> {
+        let mut sources: Vec<&str> = std::iter::once(self.source.as_str())
+            .chain(self.mappings.iter().filter_map(|m| m.source.as_deref()))
+            .collect();
+        sources.sort_unstable();
+        sources.dedup();
+        sources
+    }
+
+    /// Rewrite `<file>.rs:<line>:<col>` locations in rustc and cargo output (errors,
+    /// warnings, panics) to the SolScript locations they were generated from.
+    /// `source_text` returns the text of a file named in the map (see [`SourceMap::sources`]);
+    /// the generated location is kept in parentheses.
+    pub fn rewrite_diagnostics<'a>(
+        &self,
+        output: &str,
+        source_text: impl Fn(&str) -> Option<&'a str>,
+    ) -> String {
+        output
+            .split_inclusive('\n')
+            .map(|line| self.rewrite_line(line, &source_text))
+            .collect()
+    }
+
+    fn rewrite_line<'a>(
+        &self,
+        line: &str,
+        source_text: &impl Fn(&str) -> Option<&'a str>,
+    ) -> String {
+        let mut rewritten = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(found) = rest.find(".rs:") {
+            let after = found + ".rs:".len();
+            let start = rest[..found]
+                .rfind(|c: char| c.is_whitespace() || matches!(c, '\'' | '"' | '(' | '['))
+                .map_or(0, |i| i + 1);
+            let Some((generated_line, end)) = location(&rest[after..]) else {
+                rewritten.push_str(&rest[..after]);
+                rest = &rest[after..];
+                continue;
+            };
+            let end = after + end;
+            let mapping = self.lookup(&rest[start..found + ".rs".len()], generated_line);
+            let source = mapping.map(|m| m.source.as_deref().unwrap_or(&self.source));
+            match mapping.zip(source.and_then(source_text)) {
+                Some((mapping, text)) => {
+                    let (line, column) = line_col(text, mapping.span.start);
+                    let name = match source {
+                        Some(name) if !name.is_empty() => name,
+                        _ => "<source>",
+                    };
+                    rewritten.push_str(&rest[..start]);
+                    rewritten.push_str(&format!(
+                        "{}:{}:{} ({})",
+                        name,
+                        line,
+                        column,
+                        &rest[start..end]
+                    ));
+                }
+                None => rewritten.push_str(&rest[..end]),
+            }
+            rest = &rest[end..];
+        }
+        rewritten.push_str(rest);
+        rewritten
+    }
+}
+
+/// Whether two paths name the same generated file, one possibly more qualified
+fn same_file(a: &str, b: &str) -> bool {
+    a == b || a.ends_with(&format!("/{}", b)) || b.ends_with(&format!("/{}", a))
+}
+
+/// Parse `<line>:<col>` at the start of `s`, returning the line and the length parsed
+fn location(s: &str) -> Option<(usize, usize)> {
+    let line_len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let line = s[..line_len].parse().ok()?;
+    let col = s[line_len..].strip_prefix(':')?;
+    let col_len = col.find(|c: char| !c.is_ascii_digit()).unwrap_or(col.len());
+    if col_len == 0 {
+        return None;
+    }
+    Some((line, line_len + 1 + col_len))
+}
+
+/// 1-based line and column of a byte offset
+fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+/// Wrap generated code in markers recording the span it was generated from
+pub(crate) fn mark(span: Span, code: String) -> String {
+    if span.is_dummy() || code.is_empty() {
+        return code;
+    }
+    format!("{m}{}:{}\n{}{m}\n", span.start, span.end, code, m = MARKER)
+}
+
+/// Strip markers from `text`, recording their line ranges as mappings for `file`
+pub(crate) fn extract(file: &str, text: &str, mappings: &mut Vec<Mapping>) -> String {
+    if !text.contains(MARKER) {
+        return text.to_string();
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut open: Vec<(usize, Span)> = Vec::new();
+    let mut line = 1;
+    for raw in text.split_inclusive('\n') {
+        let Some(marker) = raw.strip_prefix(MARKER) else {
+            output.push_str(raw);
+            line += 1;
+            continue;
+        };
+        match marker.trim_end().split_once(':') {
+            Some((start, end)) => {
+                let span = Span::new(start.parse().unwrap_or(0), end.parse().unwrap_or(0));
+                open.push((line, span));
+            }
+            None => {
+                if let Some((start_line, span)) = open.pop() {
+                    if line > start_line {
+                        mappings.push(Mapping {
+                            file: file.to_string(),
+                            start_line,
+                            end_line: line - 1,
+                            span,
+                            source: None,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    output
+}
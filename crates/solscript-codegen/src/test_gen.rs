@@ -124,6 +124,7 @@ describe("{}", () => {{
         let call_args: Vec<String> = instruction
             .params
             .iter()
+            .filter(|p| !matches!(p.ty, SolanaType::Account(_) | SolanaType::RemainingAccounts))
             .map(|p| to_camel_case_lower(&p.name))
             .collect();
         let call_args_str = call_args.join(", ");
@@ -133,14 +134,22 @@ describe("{}", () => {{
             || instruction.name.to_lowercase() == "init"
             || instruction.name.to_lowercase() == "constructor";
 
-        let accounts_str = if is_init {
+        let mut accounts_str = if is_init || !instruction.state_growth.is_empty() {
             r#"//       state: stateAccount.publicKey,
     //       signer: authority.publicKey,
     //       systemProgram: SystemProgram.programId,"#
+                .to_string()
         } else {
             r#"//       state: stateAccount.publicKey,
     //       signer: authority.publicKey,"#
+                .to_string()
         };
+        for param in &instruction.params {
+            if matches!(param.ty, SolanaType::Account(_)) {
+                let name = to_camel_case_lower(&param.name);
+                accounts_str.push_str(&format!("\n    //       {}: {},", name, name));
+            }
+        }
 
         let signers_str = if is_init {
             "[stateAccount, authority]"
@@ -188,7 +197,10 @@ describe("{}", () => {{
             SolanaType::I64 | SolanaType::I128 => "new anchor.BN(1000000)".to_string(),
             SolanaType::Bool => "true".to_string(),
             SolanaType::String => format!("\"test_{}\"", name),
-            SolanaType::Pubkey | SolanaType::Signer => "Keypair.generate().publicKey".to_string(),
+            SolanaType::Pubkey | SolanaType::Signer | SolanaType::Account(_) => {
+                "Keypair.generate().publicKey".to_string()
+            }
+            SolanaType::RemainingAccounts => "[]".to_string(),
             SolanaType::Bytes => "Buffer.from(\"test\")".to_string(),
             SolanaType::FixedBytes(n) => format!("new Uint8Array({})", n),
             SolanaType::Array(inner, size) => {
@@ -201,11 +213,24 @@ describe("{}", () => {{
             }
             SolanaType::Option(_) => "null".to_string(),
             SolanaType::Mapping(_, _) => "new Map()".to_string(),
+            // 1.0, as the raw integer scaled by 10^decimals
+            SolanaType::Fixed(fixed) => format!(
+                "new anchor.BN(\"1{}\")",
+                "0".repeat(usize::from(fixed.decimals))
+            ),
             SolanaType::Custom(type_name) => {
                 // Generate a placeholder object for custom types
                 // In real usage, this should be filled with actual field values
                 format!("{{ /* {} instance - fill in fields */ }}", type_name)
             }
+            SolanaType::Tuple(types) => format!(
+                "[{}]",
+                types
+                    .iter()
+                    .map(|t| self.generate_mock_value(t, name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         }
     }
 }
@@ -8,6 +8,8 @@ use crate::CodegenError;
 /// TypeScript client generator
 pub struct TypeScriptGenerator {
     program_name: String,
+    /// Program address exported as `PROGRAM_ID`
+    program_id: String,
 }
 
 impl Default for TypeScriptGenerator {
@@ -20,9 +22,16 @@ impl TypeScriptGenerator {
     pub fn new() -> Self {
         Self {
             program_name: String::new(),
+            program_id: crate::idl_gen::PLACEHOLDER_PROGRAM_ID.to_string(),
         }
     }
 
+    /// Export `program_id` (base58) as the client's `PROGRAM_ID`
+    pub fn with_program_id(mut self, program_id: impl Into<String>) -> Self {
+        self.program_id = program_id.into();
+        self
+    }
+
     /// Generate the TypeScript client file
     pub fn generate(&mut self, ir: &SolanaProgram) -> Result<String, CodegenError> {
         self.program_name = to_camel_case(&ir.name);
@@ -33,6 +42,11 @@ impl TypeScriptGenerator {
         output.push_str(&self.generate_header());
         output.push('\n');
 
+        if ir.uses_fixed {
+            output.push_str(FIXED_POINT_HELPERS);
+            output.push('\n');
+        }
+
         // Types for state accounts
         output.push_str(&self.generate_state_types(ir)?);
         output.push('\n');
@@ -59,6 +73,7 @@ impl TypeScriptGenerator {
  */
 
 import {{
+  AccountMeta,
   Connection,
   PublicKey,
   Keypair,
@@ -70,10 +85,10 @@ import {{
 import * as anchor from '@coral-xyz/anchor';
 import {{ Program, AnchorProvider, BN }} from '@coral-xyz/anchor';
 
-// Program ID - replace with your deployed program ID
-export const PROGRAM_ID = new PublicKey('11111111111111111111111111111111');
+// Program ID
+export const PROGRAM_ID = new PublicKey('{}');
 "#,
-            self.program_name
+            self.program_name, self.program_id
         )
     }
 
@@ -145,6 +160,11 @@ export const PROGRAM_ID = new PublicKey('11111111111111111111111111111111');
                 error.name
             ));
         }
+        if ir.state.migratable().is_some() {
+            output.push_str("  NotUpgradeAuthority = 'NotUpgradeAuthority',\n");
+            output.push_str("  StateAlreadyMigrated = 'StateAlreadyMigrated',\n");
+            output.push_str("  UnknownStateVersion = 'UnknownStateVersion',\n");
+        }
 
         output.push_str("}\n\n");
         Ok(output)
@@ -203,6 +223,27 @@ export class {} {{
         for instruction in &ir.instructions {
             output.push_str(&self.generate_instruction_method(instruction)?);
         }
+        if let Some(upgrade) = ir.state.migratable() {
+            output.push_str(&format!(
+                r#"  /**
+   * Call the generated migrate instruction
+   *
+   * Moves the state account to layout version {}; `accounts.signer` must be the
+   * program's upgrade authority and pays for any extra rent.
+   */
+  async migrate(accounts: {{ state: PublicKey; signer: Keypair; programData: PublicKey }}): Promise<string> {{
+    const tx = new Transaction();
+
+    // tx.add(await this.program.methods.migrate().accounts({{...}}).instruction());
+
+    const signature = await this.provider.sendAndConfirm(tx, [accounts.signer]);
+    return signature;
+  }}
+
+"#,
+                upgrade.version
+            ));
+        }
 
         output.push_str("}\n");
         Ok(output)
@@ -217,11 +258,23 @@ export class {} {{
         // Build parameter list
         let mut params: Vec<String> = Vec::new();
 
-        // Add accounts parameter
-        params.push("accounts: { state: PublicKey; signer: Keypair }".to_string());
+        // Add accounts parameter, including any account params of the instruction
+        let mut accounts = vec![
+            "state: PublicKey".to_string(),
+            "signer: Keypair".to_string(),
+        ];
+        for param in &instruction.params {
+            if matches!(param.ty, SolanaType::Account(_)) {
+                accounts.push(format!("{}: PublicKey", to_camel_case_lower(&param.name)));
+            }
+        }
+        params.push(format!("accounts: {{ {} }}", accounts.join("; ")));
 
-        // Add instruction parameters
+        // Add instruction parameters (accounts[] is passed as remaining accounts)
         for param in &instruction.params {
+            if matches!(param.ty, SolanaType::Account(_)) {
+                continue;
+            }
             let ts_type = self.solana_type_to_ts(&param.ty);
             params.push(format!("{}: {}", to_camel_case_lower(&param.name), ts_type));
         }
@@ -235,10 +288,17 @@ export class {} {{
             "void".to_string()
         };
 
+        // #[growable] writes reallocate the state account, paid for by the signer
+        let realloc_doc = if instruction.state_growth.is_empty() {
+            ""
+        } else {
+            "\n   *\n   * Grows the state account; `accounts.signer` pays the extra rent."
+        };
+
         let mut output = String::new();
         output.push_str(&format!(
             r#"  /**
-   * Call the {} instruction
+   * Call the {} instruction{}
    */
   async {}({}): Promise<string> {{
     // Build and send transaction
@@ -253,7 +313,7 @@ export class {} {{
   }}
 
 "#,
-            instruction.name, method_name, params_str, method_name
+            instruction.name, realloc_doc, method_name, params_str, method_name
         ));
 
         Ok(output)
@@ -268,7 +328,8 @@ export class {} {{
             SolanaType::Bool => "boolean".to_string(),
             SolanaType::String => "string".to_string(),
             SolanaType::Pubkey => "PublicKey".to_string(),
-            SolanaType::Signer => "PublicKey".to_string(),
+            SolanaType::Signer | SolanaType::Account(_) => "PublicKey".to_string(),
+            SolanaType::RemainingAccounts => "AccountMeta[]".to_string(),
             SolanaType::Bytes => "Buffer".to_string(),
             SolanaType::FixedBytes(_) => "Uint8Array".to_string(),
             SolanaType::Array(inner, _size) => format!("{}[]", self.solana_type_to_ts(inner)),
@@ -281,11 +342,42 @@ export class {} {{
                     self.solana_type_to_ts(value)
                 )
             }
+            // Decimal strings such as "1.5", converted with parseFixed/formatFixed
+            SolanaType::Fixed(_) => "string".to_string(),
             SolanaType::Custom(name) => name.clone(),
+            SolanaType::Tuple(types) => format!(
+                "[{}]",
+                types
+                    .iter()
+                    .map(|t| self.solana_type_to_ts(t))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         }
     }
 }
 
+/// Conversions between decimal strings and the scaled integers fixed-point
+/// values are encoded as
+const FIXED_POINT_HELPERS: &str = r#"// Fixed-point values are encoded as integers scaled by 10^decimals
+export function parseFixed(value: string, decimals: number): BN {
+  const match = /^(-?)(\d+)(?:\.(\d+))?$/.exec(value.trim());
+  const fraction = match?.[3] ?? '';
+  if (!match || fraction.length > decimals) {
+    throw new Error(`Invalid decimal with at most ${decimals} places: ${value}`);
+  }
+  const raw = new BN(match[2] + fraction.padEnd(decimals, '0'));
+  return match[1] ? raw.neg() : raw;
+}
+
+export function formatFixed(raw: BN, decimals: number): string {
+  const digits = raw.abs().toString().padStart(decimals + 1, '0');
+  const whole = digits.slice(0, digits.length - decimals);
+  const fraction = digits.slice(digits.length - decimals).replace(/0+$/, '');
+  return (raw.isNeg() ? '-' : '') + whole + (fraction ? '.' + fraction : '');
+}
+"#;
+
 // Helper functions for case conversion
 fn to_camel_case(s: &str) -> String {
     let mut result = String::new();
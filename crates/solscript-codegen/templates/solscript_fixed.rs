@@ -0,0 +1,230 @@
+// Arithmetic of the fixed-point decimal types. The generator emits the type
+// definitions ahead of this, with the backend's borsh derives. A value is stored
+// as the integer `value * 10^D`.
+//
+// Overflow and division by zero panic, which aborts the instruction, rather than
+// returning `ProgramError::ArithmeticOverflow` or `InvalidArgument` like
+// `solscript_math`. Fixed-point arithmetic is written with Rust's operator traits,
+// whose methods must return `Self`, and the generator emits expressions without
+// knowing their types, so it cannot add `?` after the ones that are fixed-point.
+// The named methods (`from_int`, `mul_up`, `div_up`) panic as well, so that every
+// fixed-point expression fails the same way.
+
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use super::solscript_math::mul_div_wide;
+
+/// A type that decimal literals such as `1.5` convert to
+pub trait Decimal: Sized {
+    /// `digits / 10^decimals`, negated if `negative`
+    fn from_decimal(negative: bool, digits: u128, decimals: u32) -> Self;
+}
+
+/// The decimal literal `digits / 10^decimals`, typed by its context
+pub fn decimal<T: Decimal>(digits: u128, decimals: u32) -> T {
+    T::from_decimal(false, digits, decimals)
+}
+
+/// The negative decimal literal `-(digits / 10^decimals)`
+pub fn negative_decimal<T: Decimal>(digits: u128, decimals: u32) -> T {
+    T::from_decimal(true, digits, decimals)
+}
+
+/// `value`, typed like `other`; places a literal left of an operator
+pub fn like<T>(other: &T, value: T) -> T {
+    let _ = other;
+    value
+}
+
+fn overflow() -> ! {
+    panic!("fixed-point overflow")
+}
+
+/// Sign and magnitude of a raw value
+macro_rules! sign_magnitude {
+    (signed, $value:expr) => {
+        ($value < 0, $value.unsigned_abs() as u128)
+    };
+    (unsigned, $value:expr) => {
+        (false, $value as u128)
+    };
+}
+
+macro_rules! fixed_point {
+    ($name:ident, $repr:ty, $sign:ident) => {
+        impl<const D: u32> $name<D> {
+            /// `10^D`, the raw value of 1
+            pub const SCALE: $repr = <$repr>::pow(10, D);
+
+            /// Convert an integer
+            pub fn from_int(value: impl TryInto<$repr>) -> Self {
+                match value.try_into().ok().and_then(|v: $repr| v.checked_mul(Self::SCALE)) {
+                    Some(raw) => Self(raw),
+                    None => overflow(),
+                }
+            }
+
+            /// The largest integer not above the value
+            pub fn floor(self) -> $repr {
+                self.0.div_euclid(Self::SCALE)
+            }
+
+            /// The smallest integer not below the value
+            pub fn ceil(self) -> $repr {
+                if self.0.rem_euclid(Self::SCALE) == 0 {
+                    self.floor()
+                } else {
+                    self.floor() + 1
+                }
+            }
+
+            /// The product, rounded away from zero
+            pub fn mul_up(self, rhs: Self) -> Self {
+                self.mul_rounded(rhs, true)
+            }
+
+            /// The quotient, rounded away from zero
+            pub fn div_up(self, rhs: Self) -> Self {
+                self.div_rounded(rhs, true)
+            }
+
+            fn sign_magnitude(self) -> (bool, u128) {
+                sign_magnitude!($sign, self.0)
+            }
+
+            fn from_sign_magnitude(negative: bool, magnitude: u128) -> Self {
+                let raw = if negative {
+                    0i128
+                        .checked_sub_unsigned(magnitude)
+                        .and_then(|v| <$repr>::try_from(v).ok())
+                } else {
+                    <$repr>::try_from(magnitude).ok()
+                };
+                match raw {
+                    Some(raw) => Self(raw),
+                    None => overflow(),
+                }
+            }
+
+            fn mul_rounded(self, rhs: Self, round_up: bool) -> Self {
+                let (a_negative, a) = self.sign_magnitude();
+                let (b_negative, b) = rhs.sign_magnitude();
+                match mul_div_wide(a, b, Self::SCALE as u128, round_up) {
+                    Ok(product) => Self::from_sign_magnitude(a_negative != b_negative, product),
+                    Err(_) => overflow(),
+                }
+            }
+
+            fn div_rounded(self, rhs: Self, round_up: bool) -> Self {
+                let (a_negative, a) = self.sign_magnitude();
+                let (b_negative, b) = rhs.sign_magnitude();
+                if b == 0 {
+                    panic!("fixed-point division by zero");
+                }
+                match mul_div_wide(a, Self::SCALE as u128, b, round_up) {
+                    Ok(quotient) => Self::from_sign_magnitude(a_negative != b_negative, quotient),
+                    Err(_) => overflow(),
+                }
+            }
+        }
+
+        impl<const D: u32> Decimal for $name<D> {
+            fn from_decimal(negative: bool, digits: u128, decimals: u32) -> Self {
+                let magnitude = D
+                    .checked_sub(decimals)
+                    .and_then(|shift| 10u128.checked_pow(shift))
+                    .and_then(|factor| digits.checked_mul(factor));
+                match magnitude {
+                    Some(magnitude) => Self::from_sign_magnitude(negative, magnitude),
+                    None => overflow(),
+                }
+            }
+        }
+
+        impl<const D: u32> Add for $name<D> {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                match self.0.checked_add(rhs.0) {
+                    Some(raw) => Self(raw),
+                    None => overflow(),
+                }
+            }
+        }
+
+        impl<const D: u32> Sub for $name<D> {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                match self.0.checked_sub(rhs.0) {
+                    Some(raw) => Self(raw),
+                    None => overflow(),
+                }
+            }
+        }
+
+        /// Rounds toward zero; see `mul_up`
+        impl<const D: u32> Mul for $name<D> {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self {
+                self.mul_rounded(rhs, false)
+            }
+        }
+
+        /// Rounds toward zero; see `div_up`
+        impl<const D: u32> Div for $name<D> {
+            type Output = Self;
+
+            fn div(self, rhs: Self) -> Self {
+                self.div_rounded(rhs, false)
+            }
+        }
+
+        impl<const D: u32> AddAssign for $name<D> {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl<const D: u32> SubAssign for $name<D> {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl<const D: u32> MulAssign for $name<D> {
+            fn mul_assign(&mut self, rhs: Self) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl<const D: u32> DivAssign for $name<D> {
+            fn div_assign(&mut self, rhs: Self) {
+                *self = *self / rhs;
+            }
+        }
+    };
+}
+
+macro_rules! signed_fixed_point {
+    ($name:ident, $repr:ty) => {
+        fixed_point!($name, $repr, signed);
+
+        impl<const D: u32> Neg for $name<D> {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                match self.0.checked_neg() {
+                    Some(raw) => Self(raw),
+                    None => overflow(),
+                }
+            }
+        }
+    };
+}
+
+fixed_point!(UFixed64, u64, unsigned);
+fixed_point!(UFixed128, u128, unsigned);
+signed_fixed_point!(Fixed64, i64);
+signed_fixed_point!(Fixed128, i128);
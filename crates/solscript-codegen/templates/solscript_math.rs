@@ -0,0 +1,111 @@
+/// Integer math of the `@solana/math` module
+#[allow(dead_code)]
+mod solscript_math {
+    use super::*;
+
+    type MathResult<T> = core::result::Result<T, ProgramError>;
+
+    fn narrow<T: TryFrom<u128>>(value: u128) -> MathResult<T> {
+        T::try_from(value).map_err(|_| ProgramError::ArithmeticOverflow)
+    }
+
+    /// Square root, rounded down (Newton's method from a guess above the root)
+    pub fn sqrt<T: TryFrom<u128>>(x: impl Into<u128>) -> MathResult<T> {
+        let x = x.into();
+        if x < 2 {
+            return narrow(x);
+        }
+        let mut z = 1u128 << ((129 - x.leading_zeros()) / 2);
+        loop {
+            let y = (z + x / z) / 2;
+            if y >= z {
+                return narrow(z);
+            }
+            z = y;
+        }
+    }
+
+    pub fn min<T: TryFrom<u128>>(a: impl Into<u128>, b: impl Into<u128>) -> MathResult<T> {
+        narrow(a.into().min(b.into()))
+    }
+
+    pub fn max<T: TryFrom<u128>>(a: impl Into<u128>, b: impl Into<u128>) -> MathResult<T> {
+        narrow(a.into().max(b.into()))
+    }
+
+    pub fn mul_div<T: TryFrom<u128>>(
+        x: impl Into<u128>,
+        y: impl Into<u128>,
+        denominator: impl Into<u128>,
+    ) -> MathResult<T> {
+        narrow(mul_div_wide(x.into(), y.into(), denominator.into(), false)?)
+    }
+
+    pub fn mul_div_up<T: TryFrom<u128>>(
+        x: impl Into<u128>,
+        y: impl Into<u128>,
+        denominator: impl Into<u128>,
+    ) -> MathResult<T> {
+        narrow(mul_div_wide(x.into(), y.into(), denominator.into(), true)?)
+    }
+
+    pub fn mul_bps<T: TryFrom<u128>>(amount: impl Into<u128>, bps: impl Into<u128>) -> MathResult<T> {
+        narrow(mul_div_wide(amount.into(), bps.into(), 10_000, false)?)
+    }
+
+    pub fn mul_bps_up<T: TryFrom<u128>>(amount: impl Into<u128>, bps: impl Into<u128>) -> MathResult<T> {
+        narrow(mul_div_wide(amount.into(), bps.into(), 10_000, true)?)
+    }
+
+    /// `x * y / denominator` with the product in 256 bits
+    pub(super) fn mul_div_wide(x: u128, y: u128, denominator: u128, round_up: bool) -> MathResult<u128> {
+        if denominator == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let (hi, lo) = mul_wide(x, y);
+        if hi >= denominator {
+            return Err(ProgramError::ArithmeticOverflow);
+        }
+        let (quotient, remainder) = if hi == 0 {
+            (lo / denominator, lo % denominator)
+        } else {
+            div_wide(hi, lo, denominator)
+        };
+        if round_up && remainder > 0 {
+            quotient.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)
+        } else {
+            Ok(quotient)
+        }
+    }
+
+    /// The 256-bit product of `a` and `b`, as its high and low halves
+    fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+        let mask = u64::MAX as u128;
+        let (a_hi, a_lo) = (a >> 64, a & mask);
+        let (b_hi, b_lo) = (b >> 64, b & mask);
+        let low = a_lo * b_lo;
+        let cross_a = a_hi * b_lo;
+        let cross_b = a_lo * b_hi;
+        let mid = (low >> 64) + (cross_a & mask) + (cross_b & mask);
+        let lo = (low & mask) | (mid << 64);
+        let hi = a_hi * b_hi + (cross_a >> 64) + (cross_b >> 64) + (mid >> 64);
+        (hi, lo)
+    }
+
+    /// Quotient and remainder of the 256-bit `hi:lo` by `denominator`, which must be
+    /// greater than `hi` so the quotient fits in 128 bits
+    fn div_wide(hi: u128, lo: u128, denominator: u128) -> (u128, u128) {
+        let mut remainder = hi;
+        let mut quotient = 0u128;
+        for bit in (0..128).rev() {
+            let carry = remainder >> 127;
+            remainder = (remainder << 1) | ((lo >> bit) & 1);
+            quotient <<= 1;
+            if carry == 1 || remainder >= denominator {
+                remainder = remainder.wrapping_sub(denominator);
+                quotient |= 1;
+            }
+        }
+        (quotient, remainder)
+    }
+}
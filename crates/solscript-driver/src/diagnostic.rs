@@ -0,0 +1,174 @@
+//! Diagnostics collected by a session
+
+use crate::session::SourceFile;
+use miette::{GraphicalReportHandler, GraphicalTheme, LabeledSpan, NamedSource, SourceCode};
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A parse, type or codegen diagnostic, in a form that can be rendered for a terminal or
+/// serialized for tools
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable code, e.g. `solscript::typeck::mismatch`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    /// Where the diagnostic points, if anywhere
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_location: Option<SourceLocation>,
+    /// The diagnostic rendered with its source snippet, without colors
+    pub formatted_message: String,
+    #[serde(skip)]
+    report: Arc<miette::Report>,
+}
+
+/// A byte range of a source file, with the 1-based line and column it starts at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceLocation {
+    pub file: String,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Diagnostic {
+    /// Capture an error whose spans index the concatenated text of `files`. The
+    /// diagnostic points into the file its first label falls in, and is a warning
+    /// if the error says so.
+    pub fn error<E>(error: E, files: &[SourceFile]) -> Self
+    where
+        E: miette::Diagnostic + Send + Sync + 'static,
+    {
+        let severity = match error.severity() {
+            Some(miette::Severity::Warning | miette::Severity::Advice) => Severity::Warning,
+            Some(miette::Severity::Error) | None => Severity::Error,
+        };
+        let code = error.code().map(|c| c.to_string());
+        let help = error.help().map(|h| h.to_string());
+        let message = error.to_string();
+        let labels: Vec<LabeledSpan> = error.labels().map(Iterator::collect).unwrap_or_default();
+        let file = labels
+            .first()
+            .and_then(|label| files.iter().find(|f| f.contains(label.offset())));
+
+        let (source_location, report) = match file {
+            Some(file) => {
+                let labels: Vec<LabeledSpan> = labels
+                    .iter()
+                    .filter(|label| file.contains(label.offset()))
+                    .map(|label| {
+                        LabeledSpan::new(
+                            label.label().map(str::to_string),
+                            label.offset() - file.start,
+                            label.len(),
+                        )
+                    })
+                    .collect();
+                let (line, column) = line_col(&file.text, labels[0].offset());
+                let location = SourceLocation {
+                    file: file.name(),
+                    start: labels[0].offset(),
+                    end: labels[0].offset() + labels[0].len(),
+                    line,
+                    column,
+                };
+                let report = miette::Report::new(InFile {
+                    inner: Box::new(error),
+                    source: NamedSource::new(file.name(), file.text.clone()),
+                    labels,
+                });
+                (Some(location), report)
+            }
+            None => (None, miette::Report::new(error)),
+        };
+
+        let mut formatted_message = String::new();
+        GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+            .render_report(&mut formatted_message, report.as_ref())
+            .expect("rendering into a String cannot fail");
+
+        Self {
+            severity,
+            code,
+            message,
+            help,
+            source_location,
+            formatted_message,
+            report: Arc::new(report),
+        }
+    }
+
+    /// The diagnostic as a miette report, for printing with the terminal's theme
+    pub fn report(&self) -> &miette::Report {
+        &self.report
+    }
+}
+
+/// A diagnostic re-pointed from a compilation unit into one of its files
+#[derive(Debug)]
+struct InFile {
+    inner: Box<dyn miette::Diagnostic + Send + Sync>,
+    source: NamedSource<String>,
+    labels: Vec<LabeledSpan>,
+}
+
+impl fmt::Display for InFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl std::error::Error for InFile {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner.source()
+    }
+}
+
+impl miette::Diagnostic for InFile {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.inner.code()
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        self.inner.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.inner.help()
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.inner.url()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(self.labels.iter().cloned()))
+    }
+}
+
+/// 1-based line and column of a byte offset
+fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
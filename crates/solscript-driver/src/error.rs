@@ -0,0 +1,91 @@
+//! Driver error types
+
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+/// Errors in a standard-JSON request, or in the imports that make up a compilation unit
+#[derive(Error, Debug, Diagnostic)]
+pub enum DriverError {
+    #[error("Invalid standard-JSON input: {message}")]
+    #[diagnostic(
+        code(solscript::driver::invalid_input),
+        help(
+            "expected {{\"language\": \"SolScript\", \"sources\": {{...}}, \"settings\": {{...}}}}"
+        )
+    )]
+    InvalidInput { message: String },
+
+    #[error("Unsupported language '{language}'")]
+    #[diagnostic(
+        code(solscript::driver::unsupported_language),
+        help("set \"language\" to \"SolScript\"")
+    )]
+    UnsupportedLanguage { language: String },
+
+    #[error("Cannot read '{path}': {message}")]
+    #[diagnostic(
+        code(solscript::driver::import_not_found),
+        help("relative imports are resolved against the importing file")
+    )]
+    ImportNotFound {
+        path: String,
+        message: String,
+        #[label("imported here")]
+        span: SourceSpan,
+    },
+
+    #[error("Unknown package '{name}'")]
+    #[diagnostic(
+        code(solscript::driver::unknown_package),
+        help("add it to [dependencies] in solscript.toml and run `solscript install`, or import a relative path starting with './'")
+    )]
+    UnknownPackage {
+        name: String,
+        #[label("imported here")]
+        span: SourceSpan,
+    },
+
+    #[error("Unknown standard library module '{path}'")]
+    #[diagnostic(
+        code(solscript::driver::unknown_module),
+        help("the standard library has {modules}")
+    )]
+    UnknownModule {
+        path: String,
+        modules: String,
+        #[label("imported here")]
+        span: SourceSpan,
+    },
+
+    #[error("Import cycle: {cycle}")]
+    #[diagnostic(
+        code(solscript::driver::import_cycle),
+        help("move the shared definitions into a file both can import")
+    )]
+    ImportCycle {
+        cycle: String,
+        #[label("closes the cycle")]
+        span: SourceSpan,
+    },
+
+    #[error("'{name}' is not defined in '{path}'")]
+    #[diagnostic(code(solscript::driver::unknown_import))]
+    UnknownImport {
+        name: String,
+        path: String,
+        #[label("not found")]
+        span: SourceSpan,
+    },
+
+    #[error("Program '{name}' has no IDL at '{path}'")]
+    #[diagnostic(
+        code(solscript::driver::program_not_built),
+        help("build it first, for example with `solscript build --workspace`")
+    )]
+    ProgramNotBuilt {
+        name: String,
+        path: String,
+        #[label("imported here")]
+        span: SourceSpan,
+    },
+}
@@ -0,0 +1,339 @@
+//! SolScript Compilation Driver
+//!
+//! Runs the compiler pipeline (parse → type check → lower → generate) for the CLI and for
+//! tools that embed the compiler. A [`Session`] holds one compilation unit (the given
+//! source files and the files they import), its codegen options, the diagnostics every
+//! stage reports and, on success, the generated [`Artifacts`].
+//! [`standard_json`] wraps sessions in a JSON request/response interface.
+
+mod diagnostic;
+mod error;
+mod modules;
+mod session;
+pub mod standard_json;
+
+pub use diagnostic::{Diagnostic, Severity, SourceLocation};
+pub use error::DriverError;
+pub use session::{Artifacts, Session, SourceFile};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNTER: &str = r#"
+        contract Counter {
+            uint64 public count;
+
+            function increment() public {
+                count += 1;
+            }
+        }
+    "#;
+
+    #[test]
+    fn test_session_compiles_and_reports_costs() {
+        let mut session = Session::new("counter.sol", COUNTER);
+        let artifacts = session.compile().expect("compiles");
+
+        assert!(!session.has_errors());
+        assert_eq!(artifacts.program_name, "counter");
+        assert!(artifacts.project.lib_rs.contains("pub fn increment"));
+        assert_eq!(artifacts.project.source_map.source, "counter.sol");
+        assert!(!artifacts.account_costs.is_empty());
+    }
+
+    #[test]
+    fn test_session_collects_type_errors() {
+        let source = r#"
+            contract Broken {
+                uint64 public count;
+
+                function set() public {
+                    count = missing;
+                }
+            }
+        "#;
+        let mut session = Session::new("broken.sol", source);
+        assert!(session.compile().is_none());
+
+        let diagnostic = &session.diagnostics()[0];
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(
+            diagnostic.code.as_deref(),
+            Some("solscript::typeck::undefined_var")
+        );
+        let location = diagnostic.source_location.as_ref().expect("located");
+        assert_eq!(location.file, "broken.sol");
+        assert_eq!(location.line, 6);
+        assert!(diagnostic.formatted_message.contains("count = missing"));
+    }
+
+    #[test]
+    fn test_session_compiles_files_as_one_unit() {
+        let base = "contract Base {\n    uint64 public total;\n\n    function bump() public {\n        total += 1;\n    }\n}\n";
+        let app =
+            "contract App is Base {\n    function reset() public {\n        total = 0;\n    }\n}\n";
+        let mut session = Session::from_files(vec![
+            ("src/base.sol".into(), base.to_string()),
+            ("src/app.sol".into(), app.to_string()),
+        ]);
+        let artifacts = session.compile().expect("compiles");
+        assert_eq!(artifacts.program_name, "app");
+        assert_eq!(session.path(), std::path::Path::new("src/app.sol"));
+
+        // Inherited code maps back into the file that defines it, with file-local spans
+        let map = &artifacts.project.source_map;
+        assert_eq!(map.source, "src/app.sol");
+        let bump = map
+            .mappings
+            .iter()
+            .find(|m| m.source.as_deref() == Some("src/base.sol"))
+            .expect("a mapping into base.sol");
+        assert!(bump.span.end <= base.len());
+        assert_eq!(map.sources(), ["src/app.sol", "src/base.sol"]);
+
+        let broken = base.replace("total += 1", "total += missing");
+        let mut session = Session::from_files(vec![
+            ("src/base.sol".into(), broken),
+            ("src/app.sol".into(), app.to_string()),
+        ]);
+        assert!(session.compile().is_none());
+        let location = session.diagnostics()[0].source_location.clone().unwrap();
+        assert_eq!((location.file.as_str(), location.line), ("src/base.sol", 5));
+
+        let mut session = Session::from_files(vec![
+            ("src/base.sol".into(), base.to_string()),
+            ("src/app.sol".into(), "contract App is Base {".to_string()),
+        ]);
+        assert!(session.parse().is_none());
+        let location = session.diagnostics()[0].source_location.clone().unwrap();
+        assert_eq!(location.file, "src/app.sol");
+    }
+
+    #[test]
+    fn test_session_loads_imports() {
+        let dir = std::env::temp_dir().join(format!("solscript-imports-{}", std::process::id()));
+        let write = |path: &str, text: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, text).unwrap();
+            path
+        };
+        write(
+            "lib/ownable.sol",
+            "import { Limits } from \"mathlib/limits.sol\";\n\ncontract Ownable {\n    Limits public limits;\n}\n",
+        );
+        write(
+            "packages/mathlib/src/limits.sol",
+            "struct Limits {\n    uint64 max;\n}\n",
+        );
+        let main = write(
+            "src/main.sol",
+            "import { Ownable as Owned } from \"../lib/ownable.sol\";\n\ncontract Vault is Owned {\n    function cap() public view returns (uint64) {\n        return limits.max;\n    }\n}\n",
+        );
+
+        let mut session = Session::load(&main).unwrap();
+        session.add_package("mathlib", dir.join("packages/mathlib"));
+        let artifacts = session.compile().expect("compiles");
+        assert_eq!(artifacts.program_name, "vault");
+        let names: Vec<_> = session
+            .files()
+            .iter()
+            .map(|f| f.path.strip_prefix(&dir).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                std::path::Path::new("packages/mathlib/src/limits.sol"),
+                std::path::Path::new("lib/ownable.sol"),
+                std::path::Path::new("src/main.sol")
+            ]
+        );
+
+        // Diagnostics carry the imported file
+        write(
+            "lib/ownable.sol",
+            "contract Ownable {\n    function f() public {\n        missing = 1;\n    }\n}\n",
+        );
+        write(
+            "src/main.sol",
+            "import { Ownable } from \"../lib/ownable.sol\";\n\ncontract Vault is Ownable {}\n",
+        );
+        let mut session = Session::load(&main).unwrap();
+        assert!(session.compile().is_none());
+        let location = session.diagnostics()[0].source_location.clone().unwrap();
+        assert!(location.file.ends_with("ownable.sol"), "{}", location.file);
+        assert_eq!(location.line, 3);
+
+        let code = |main_text: &str| {
+            write("src/main.sol", main_text);
+            let mut session = Session::load(&main).unwrap();
+            assert!(session.parse().is_none());
+            session.diagnostics()[0].code.clone().unwrap()
+        };
+        write(
+            "lib/ownable.sol",
+            "import { Vault } from \"../src/main.sol\";\ncontract Ownable {}\n",
+        );
+        assert_eq!(
+            code("import { Ownable } from \"../lib/ownable.sol\";\ncontract Vault {}\n"),
+            "solscript::driver::import_cycle"
+        );
+        write("lib/ownable.sol", "contract Ownable {}\n");
+        assert_eq!(
+            code("import { Owner } from \"../lib/ownable.sol\";\ncontract Vault {}\n"),
+            "solscript::driver::unknown_import"
+        );
+        assert_eq!(
+            code("import { Ownable } from \"./ownable.sol\";\ncontract Vault {}\n"),
+            "solscript::driver::import_not_found"
+        );
+        assert_eq!(
+            code("import { Ownable } from \"solmate/auth.sol\";\ncontract Vault {}\n"),
+            "solscript::driver::unknown_package"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_imports_programs() {
+        let dir = std::env::temp_dir().join(format!("solscript-programs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let idl = dir.join("vault.json");
+        let main = "import { Vault } from \"vault\";\n\ncontract Router {\n    function route(address vault, address owner, uint64 amount) public {\n        Vault(vault).deposit(owner, amount);\n    }\n}\n";
+
+        let mut session = Session::from_files(vec![("src/router.sol".into(), main.to_string())]);
+        session.add_program("vault", &idl);
+        assert!(session.parse().is_none());
+        assert_eq!(
+            session.diagnostics()[0].code.as_deref(),
+            Some("solscript::driver::program_not_built")
+        );
+
+        std::fs::write(
+            &idl,
+            r#"{"instructions": [
+                {"name": "deposit", "accounts": [{"name": "owner", "writable": true, "signer": true}], "args": [{"name": "amount", "type": "u64"}]},
+                {"name": "configure", "accounts": [], "args": [{"name": "config", "type": {"defined": "Config"}}]}
+            ]}"#,
+        )
+        .unwrap();
+        let mut session = Session::from_files(vec![("src/router.sol".into(), main.to_string())]);
+        session.add_program("vault", &idl);
+        let program = session.parse().expect("parses");
+        assert!(program.items.iter().any(
+            |item| matches!(item, solscript_ast::Item::Interface(i) if i.name.name == "Vault")
+        ));
+
+        // Instructions that cannot be imported are reported, without failing the unit
+        assert!(!session.has_errors());
+        let warning = &session.diagnostics()[0];
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(
+            warning.code.as_deref(),
+            Some("solscript::parse::unsupported_idl_instruction")
+        );
+        assert!(warning.message.contains("'configure'"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_imports_std() {
+        let main = "import { Token as Spl } from \"@solana/token\";\nimport { Clock } from \"@solana/clock\";\n\ncontract Payout {\n    function pay(address from, address to, uint64 amount) public {\n        require(Clock.unix_timestamp > 0, \"Too early\");\n        Spl.transfer(from, to, msg.sender, amount);\n    }\n}\n";
+        let mut session = Session::from_files(vec![("src/payout.sol".into(), main.to_string())]);
+        let artifacts = session.compile().expect("compiles");
+        assert_eq!(artifacts.program_name, "payout");
+        let names: Vec<_> = session.files().iter().map(|f| f.name()).collect();
+        assert_eq!(names, ["@solana/token", "@solana/clock", "src/payout.sol"]);
+
+        let mut session = Session::from_files(vec![(
+            "src/payout.sol".into(),
+            "import { Tokens } from \"@solana/token\";\ncontract Payout {}\n".to_string(),
+        )]);
+        assert!(session.parse().is_none());
+        assert_eq!(
+            session.diagnostics()[0].code.as_deref(),
+            Some("solscript::driver::unknown_import")
+        );
+
+        let mut session = Session::from_files(vec![(
+            "src/payout.sol".into(),
+            "import { Token } from \"@solana/tokens\";\ncontract Payout {}\n".to_string(),
+        )]);
+        assert!(session.parse().is_none());
+        assert_eq!(
+            session.diagnostics()[0].code.as_deref(),
+            Some("solscript::driver::unknown_module")
+        );
+    }
+
+    #[test]
+    fn test_session_compiles_amm_example() {
+        let source = include_str!("../../../examples/amm/amm.sol");
+        let mut session = Session::from_files(vec![("amm.sol".into(), source.to_string())]);
+        let artifacts = session.compile().expect("compiles");
+        let lib_rs = &artifacts.project.lib_rs;
+        assert!(lib_rs.contains("mod solscript_math {"));
+        assert!(lib_rs.contains("let fee: u64 = solscript_math::mul_bps_up(amount_in, "));
+        assert!(lib_rs.contains(
+            "pub fn get_reserves(ctx: Context<GetReserves>) -> Result<GetReservesReturn> {"
+        ));
+        assert!(lib_rs.contains("Ok((amount_a, amount_b).into())"));
+        assert!(artifacts
+            .project
+            .state_rs
+            .contains("pub struct RemoveLiquidityReturn {"));
+    }
+
+    #[test]
+    fn test_standard_json_round_trip() {
+        let input = serde_json::json!({
+            "language": "SolScript",
+            "sources": {
+                "counter.sol": { "content": COUNTER },
+                "broken.sol": { "content": "contract {" }
+            },
+            "settings": {
+                "backend": "native",
+                "programId": "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS",
+                "outputSelection": ["idl", "accountSizes"]
+            }
+        });
+        let output = standard_json::compile_json(&input.to_string());
+        let json = serde_json::to_value(&output).unwrap();
+
+        assert!(output.has_errors());
+        assert_eq!(json["errors"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            json["errors"][0]["sourceLocation"]["file"],
+            serde_json::json!("broken.sol")
+        );
+
+        let counter = &json["contracts"]["counter.sol"];
+        assert_eq!(counter["program"], "counter");
+        assert_eq!(
+            counter["idl"]["address"],
+            "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS"
+        );
+        assert!(counter["accountSizes"].is_array());
+        assert!(counter.get("files").is_none());
+        assert!(json["contracts"].get("broken.sol").is_none());
+    }
+
+    #[test]
+    fn test_standard_json_rejects_malformed_requests() {
+        let output = standard_json::compile_json("{\"sources\": 1}");
+        assert_eq!(
+            output.errors[0].code.as_deref(),
+            Some("solscript::driver::invalid_input")
+        );
+
+        let output = standard_json::compile_json(r#"{"language": "Solidity", "sources": {}}"#);
+        assert_eq!(
+            output.errors[0].code.as_deref(),
+            Some("solscript::driver::unsupported_language")
+        );
+    }
+}
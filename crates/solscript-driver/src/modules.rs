@@ -0,0 +1,510 @@
+//! Source imports: `import { Ownable } from "./ownable.sol";`
+//!
+//! An import of a `.sol` file pulls that file into the compilation unit, ahead of the
+//! file importing it. Paths starting with `.` resolve against the importing file; any
+//! other path names a package, `<package>/<path>`, and resolves against the package's
+//! directory (or its `src/`). An aliased name, `Ownable as Owned`, is renamed back to
+//! the imported item throughout the importing file.
+//!
+//! An import of a standard library module, `import { Token } from "@solana/token";`,
+//! pulls in the module's source, which is bundled with the compiler.
+//!
+//! An import of a program the session knows, `import { Vault } from "vault";`, reads
+//! the program's Anchor IDL instead, like `import { Vault } from "./vault.json";`.
+
+use crate::diagnostic::Diagnostic;
+use crate::error::DriverError;
+use crate::session::SourceFile;
+use smol_str::SmolStr;
+use solscript_ast::{self as ast, ImportStmt, Item, Program, Span};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+/// Whether an import refers to SolScript source rather than an Anchor IDL
+fn is_source_import(import: &ImportStmt) -> bool {
+    import.source.ends_with(".sol") || solscript_std::is_std_import(import)
+}
+
+/// Collects the files of a unit in dependency order: every file after the files it
+/// imports
+pub(crate) struct Loader<'a> {
+    packages: &'a BTreeMap<String, PathBuf>,
+    /// Files given to the session, which may not exist on disk
+    given: HashMap<PathBuf, &'a str>,
+    files: Vec<(PathBuf, String)>,
+    /// Top-level names of each loaded file
+    exports: HashMap<PathBuf, Vec<SmolStr>>,
+    /// Files being loaded, innermost last
+    stack: Vec<PathBuf>,
+}
+
+impl<'a> Loader<'a> {
+    pub fn new(packages: &'a BTreeMap<String, PathBuf>, given: &'a [SourceFile]) -> Self {
+        Self {
+            packages,
+            given: given
+                .iter()
+                .map(|file| (identity(&file.path), file.text.as_str()))
+                .collect(),
+            files: Vec::new(),
+            exports: HashMap::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    /// The loaded files, in the order they are compiled
+    pub fn finish(self) -> Vec<(PathBuf, String)> {
+        self.files
+    }
+
+    /// Load `path` and, before it, every file it imports. Returns its top-level names.
+    pub fn load(&mut self, path: &Path, text: &str) -> Result<Vec<SmolStr>, Box<Diagnostic>> {
+        let key = identity(path);
+        if let Some(exports) = self.exports.get(&key) {
+            return Ok(exports.clone());
+        }
+        let file = SourceFile {
+            path: path.to_path_buf(),
+            text: text.to_string(),
+            start: 0,
+        };
+        let fail = |error| Box::new(Diagnostic::error(error, std::slice::from_ref(&file)));
+
+        let program = solscript_parser::parse(text)
+            .map_err(|e| Box::new(Diagnostic::error(e, std::slice::from_ref(&file))))?;
+
+        self.stack.push(key.clone());
+        for item in &program.items {
+            let Item::Import(import) = item else {
+                continue;
+            };
+            if !is_source_import(import) {
+                continue;
+            }
+
+            let target = self.resolve(path, import).map_err(fail)?;
+            let target_key = identity(&target);
+            if let Some(at) = self.stack.iter().position(|k| *k == target_key) {
+                let cycle = self.stack[at..]
+                    .iter()
+                    .chain([&target_key])
+                    .map(|k| k.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                return Err(fail(DriverError::ImportCycle {
+                    cycle,
+                    span: source_span(import.span),
+                }));
+            }
+
+            let target_text = match self.given.get(&target_key) {
+                Some(text) => text.to_string(),
+                None if solscript_std::is_std_import(import) => {
+                    solscript_std::module(&import.source)
+                        .map_or_else(String::new, |module| module.source.to_string())
+                }
+                None => std::fs::read_to_string(&target).map_err(|e| {
+                    fail(DriverError::ImportNotFound {
+                        path: import.source.to_string(),
+                        message: e.to_string(),
+                        span: source_span(import.span),
+                    })
+                })?,
+            };
+            let exports = self.load(&target, &target_text)?;
+            for imported in &import.items {
+                if !exports.contains(&imported.name.name) {
+                    return Err(fail(DriverError::UnknownImport {
+                        name: imported.name.name.to_string(),
+                        path: import.source.to_string(),
+                        span: source_span(imported.name.span),
+                    }));
+                }
+            }
+        }
+        self.stack.pop();
+
+        let exports: Vec<SmolStr> = program.items.iter().filter_map(item_name).collect();
+        self.exports.insert(key, exports.clone());
+        self.files.push((path.to_path_buf(), text.to_string()));
+        Ok(exports)
+    }
+
+    /// Path of the file an import names. A standard library module's path is its
+    /// import path.
+    fn resolve(&self, importer: &Path, import: &ImportStmt) -> Result<PathBuf, DriverError> {
+        let source = import.source.as_str();
+        if solscript_std::is_std_import(import) {
+            if solscript_std::module(source).is_none() {
+                return Err(DriverError::UnknownModule {
+                    path: source.to_string(),
+                    modules: solscript_std::modules()
+                        .iter()
+                        .map(|module| module.path)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    span: source_span(import.span),
+                });
+            }
+            return Ok(PathBuf::from(source));
+        }
+        if source.starts_with('.') || Path::new(source).is_absolute() {
+            let dir = importer.parent().unwrap_or(Path::new(""));
+            return Ok(normalize(&dir.join(source)));
+        }
+
+        let (package, rest) = source.split_once('/').unwrap_or((source, ""));
+        let Some(dir) = self.packages.get(package) else {
+            return Err(DriverError::UnknownPackage {
+                name: package.to_string(),
+                span: source_span(import.span),
+            });
+        };
+        let path = dir.join(rest);
+        let in_src = dir.join("src").join(rest);
+        Ok(if !path.is_file() && in_src.is_file() {
+            in_src
+        } else {
+            path
+        })
+    }
+}
+
+/// Point every import of a known program at the program's IDL, so it is expanded like
+/// an IDL import
+pub(crate) fn resolve_programs(
+    program: &mut Program,
+    programs: &BTreeMap<String, PathBuf>,
+) -> Result<(), DriverError> {
+    for item in &mut program.items {
+        let Item::Import(import) = item else {
+            continue;
+        };
+        let Some(idl) = programs.get(import.source.as_str()) else {
+            continue;
+        };
+        if !idl.is_file() {
+            return Err(DriverError::ProgramNotBuilt {
+                name: import.source.to_string(),
+                path: idl.display().to_string(),
+                span: source_span(import.span),
+            });
+        }
+        import.source = idl.display().to_string().into();
+    }
+    Ok(())
+}
+
+/// Rename every aliased import back to the name of the item it imports, in the file
+/// that imports it
+pub(crate) fn resolve_aliases(program: &mut Program, files: &[SourceFile]) {
+    let mut renames = Vec::new();
+    for item in &program.items {
+        let Item::Import(import) = item else {
+            continue;
+        };
+        if !is_source_import(import) {
+            continue;
+        }
+        let Some(file) = files.iter().find(|f| f.contains(import.span.start)) else {
+            continue;
+        };
+        for imported in &import.items {
+            if let Some(alias) = &imported.alias {
+                let range = file.start..file.start + file.text.len();
+                renames.push((range, alias.name.clone(), imported.name.name.clone()));
+            }
+        }
+    }
+
+    for (range, from, to) in renames {
+        let rename = Rename {
+            from: &from,
+            to: &to,
+        };
+        for item in &mut program.items {
+            if item_span(item).is_some_and(|span| range.contains(&span.start)) {
+                rename.item(item);
+            }
+        }
+    }
+}
+
+/// Top-level name an item defines
+fn item_name(item: &Item) -> Option<SmolStr> {
+    Some(
+        match item {
+            Item::Import(_) => return None,
+            Item::Contract(c) => &c.name,
+            Item::Interface(i) => &i.name,
+            Item::Struct(s) => &s.name,
+            Item::Enum(e) => &e.name,
+            Item::Event(e) => &e.name,
+            Item::Error(e) => &e.name,
+            Item::Function(f) => &f.name,
+        }
+        .name
+        .clone(),
+    )
+}
+
+fn item_span(item: &Item) -> Option<Span> {
+    Some(match item {
+        Item::Import(_) => return None,
+        Item::Contract(c) => c.span,
+        Item::Interface(i) => i.span,
+        Item::Struct(s) => s.span,
+        Item::Enum(e) => e.span,
+        Item::Event(e) => e.span,
+        Item::Error(e) => e.span,
+        Item::Function(f) => f.span,
+    })
+}
+
+/// How a file is told apart from others: its canonical path if it exists on disk
+fn identity(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| normalize(path))
+}
+
+/// `path` without `.` and `dir/..` components
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normal.file_name().is_some() => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+fn source_span(span: Span) -> miette::SourceSpan {
+    (span.start, span.end - span.start).into()
+}
+
+/// Renames references to one name; definitions keep their names
+struct Rename<'a> {
+    from: &'a str,
+    to: &'a SmolStr,
+}
+
+impl Rename<'_> {
+    fn ident(&self, ident: &mut ast::Ident) {
+        if ident.name == self.from {
+            ident.name = self.to.clone();
+        }
+    }
+
+    fn item(&self, item: &mut Item) {
+        match item {
+            Item::Import(_) | Item::Enum(_) => {}
+            Item::Contract(c) => {
+                c.bases.iter_mut().for_each(|base| self.type_path(base));
+                for member in &mut c.members {
+                    self.member(member);
+                }
+            }
+            Item::Interface(i) => {
+                i.bases.iter_mut().for_each(|base| self.type_path(base));
+                for sig in &mut i.members {
+                    sig.params.iter_mut().for_each(|p| self.ty(&mut p.ty));
+                    sig.return_params
+                        .iter_mut()
+                        .for_each(|p| self.ty(&mut p.ty));
+                }
+            }
+            Item::Struct(s) => s.fields.iter_mut().for_each(|f| self.ty(&mut f.ty)),
+            Item::Event(e) => e.params.iter_mut().for_each(|p| self.ty(&mut p.ty)),
+            Item::Error(e) => e.params.iter_mut().for_each(|p| self.ty(&mut p.ty)),
+            Item::Function(f) => self.function(f),
+        }
+    }
+
+    fn member(&self, member: &mut ast::ContractMember) {
+        use ast::ContractMember as M;
+        match member {
+            M::StateVar(v) => {
+                self.ty(&mut v.ty);
+                if let Some(init) = &mut v.initializer {
+                    self.expr(init);
+                }
+            }
+            M::Constructor(c) => {
+                c.params.iter_mut().for_each(|p| self.ty(&mut p.ty));
+                c.modifiers.iter_mut().for_each(|m| self.modifier(m));
+                self.block(&mut c.body);
+            }
+            M::Function(f) => self.function(f),
+            M::Modifier(m) => {
+                m.params.iter_mut().for_each(|p| self.ty(&mut p.ty));
+                self.block(&mut m.body);
+            }
+            M::Event(e) => e.params.iter_mut().for_each(|p| self.ty(&mut p.ty)),
+            M::Error(e) => e.params.iter_mut().for_each(|p| self.ty(&mut p.ty)),
+            M::Struct(s) => s.fields.iter_mut().for_each(|f| self.ty(&mut f.ty)),
+            M::Enum(_) => {}
+        }
+    }
+
+    fn function(&self, f: &mut ast::FnDef) {
+        f.params.iter_mut().for_each(|p| self.ty(&mut p.ty));
+        f.return_params.iter_mut().for_each(|p| self.ty(&mut p.ty));
+        f.modifiers.iter_mut().for_each(|m| self.modifier(m));
+        if let Some(body) = &mut f.body {
+            self.block(body);
+        }
+    }
+
+    /// Modifiers include base constructor calls, `constructor() Ownable(msg.sender)`
+    fn modifier(&self, modifier: &mut ast::ModifierInvocation) {
+        self.ident(&mut modifier.name);
+        self.args(&mut modifier.args);
+    }
+
+    fn ty(&self, ty: &mut ast::TypeExpr) {
+        match ty {
+            ast::TypeExpr::Path(path) => self.type_path(path),
+            ast::TypeExpr::Mapping(m) => {
+                self.ty(&mut m.key);
+                self.ty(&mut m.value);
+            }
+            ast::TypeExpr::Array(a) => self.type_path(&mut a.element),
+            ast::TypeExpr::Tuple(t) => t.elements.iter_mut().for_each(|e| self.ty(e)),
+        }
+    }
+
+    fn type_path(&self, path: &mut ast::TypePath) {
+        if let Some(first) = path.segments.first_mut() {
+            self.ident(first);
+        }
+        if let Some(generics) = &mut path.generic_args {
+            generics.args.iter_mut().for_each(|arg| self.ty(arg));
+        }
+    }
+
+    fn block(&self, block: &mut ast::Block) {
+        block.stmts.iter_mut().for_each(|stmt| self.stmt(stmt));
+    }
+
+    fn stmt(&self, stmt: &mut ast::Stmt) {
+        use ast::Stmt as S;
+        match stmt {
+            S::VarDecl(v) => self.var_decl(v),
+            S::Return(r) => {
+                if let Some(value) = &mut r.value {
+                    self.expr(value);
+                }
+            }
+            S::If(i) => self.if_stmt(i),
+            S::While(w) => {
+                self.expr(&mut w.condition);
+                self.block(&mut w.body);
+            }
+            S::For(f) => {
+                match &mut f.init {
+                    Some(ast::ForInit::VarDecl(v)) => self.var_decl(v),
+                    Some(ast::ForInit::Expr(e)) => self.expr(e),
+                    None => {}
+                }
+                if let Some(condition) = &mut f.condition {
+                    self.expr(condition);
+                }
+                if let Some(update) = &mut f.update {
+                    self.expr(update);
+                }
+                self.block(&mut f.body);
+            }
+            S::Emit(e) => {
+                self.ident(&mut e.event);
+                self.args(&mut e.args);
+            }
+            S::Require(r) => self.expr(&mut r.condition),
+            S::Revert(r) => {
+                if let ast::RevertKind::Error { name, args } = &mut r.kind {
+                    self.ident(name);
+                    self.args(args);
+                }
+            }
+            S::Delete(d) => self.expr(&mut d.target),
+            S::Selfdestruct(s) => self.expr(&mut s.recipient),
+            S::Placeholder(_) => {}
+            S::Expr(e) => self.expr(&mut e.expr),
+        }
+    }
+
+    fn var_decl(&self, v: &mut ast::VarDeclStmt) {
+        self.ty(&mut v.ty);
+        if let Some(init) = &mut v.initializer {
+            self.expr(init);
+        }
+    }
+
+    fn if_stmt(&self, i: &mut ast::IfStmt) {
+        self.expr(&mut i.condition);
+        self.block(&mut i.then_block);
+        match &mut i.else_branch {
+            Some(ast::ElseBranch::ElseIf(elif)) => self.if_stmt(elif),
+            Some(ast::ElseBranch::Else(block)) => self.block(block),
+            None => {}
+        }
+    }
+
+    fn args(&self, args: &mut [ast::Arg]) {
+        args.iter_mut().for_each(|arg| self.expr(&mut arg.value));
+    }
+
+    fn expr(&self, expr: &mut ast::Expr) {
+        use ast::Expr as E;
+        match expr {
+            E::Literal(_) => {}
+            E::Ident(ident) => self.ident(ident),
+            E::Binary(b) => {
+                self.expr(&mut b.left);
+                self.expr(&mut b.right);
+            }
+            E::Unary(u) => self.expr(&mut u.expr),
+            E::Ternary(t) => {
+                self.expr(&mut t.condition);
+                self.expr(&mut t.then_expr);
+                self.expr(&mut t.else_expr);
+            }
+            E::Call(c) => {
+                self.expr(&mut c.callee);
+                self.args(&mut c.args);
+            }
+            E::MethodCall(m) => {
+                self.expr(&mut m.receiver);
+                self.args(&mut m.args);
+            }
+            E::FieldAccess(f) => self.expr(&mut f.expr),
+            E::Index(i) => {
+                self.expr(&mut i.expr);
+                self.expr(&mut i.index);
+            }
+            E::Array(a) => a.elements.iter_mut().for_each(|e| self.expr(e)),
+            E::Tuple(t) => t.elements.iter_mut().for_each(|e| self.expr(e)),
+            E::New(n) => {
+                self.type_path(&mut n.ty);
+                self.args(&mut n.args);
+            }
+            E::If(i) => self.if_expr(i),
+            E::Assign(a) => {
+                self.expr(&mut a.target);
+                self.expr(&mut a.value);
+            }
+            E::Paren(e) => self.expr(e),
+        }
+    }
+
+    fn if_expr(&self, i: &mut ast::IfExpr) {
+        self.expr(&mut i.condition);
+        self.block(&mut i.then_block);
+        match i.else_branch.as_mut() {
+            ast::IfExprElse::ElseIf(elif) => self.if_expr(elif),
+            ast::IfExprElse::Else(block) => self.block(block),
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! A single compilation: parse, type check, lower and generate
+
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::modules::{self, Loader};
+use solscript_ast::{Program, Span};
+use solscript_codegen::{AccountCost, CodegenOptions, GeneratedProject, SolanaProgram, SourceMap};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Compiles one compilation unit, collecting the diagnostics of every stage it runs.
+///
+/// A unit is one or more source files compiled as a single program, as if concatenated,
+/// preceded by the files they import. The last file is the entry point: the last contract
+/// in it is the one deployed. Each stage returns `None` when it fails; the reasons are in
+/// [`Session::diagnostics`].
+#[derive(Debug)]
+pub struct Session {
+    files: Vec<SourceFile>,
+    /// Text of every file, concatenated; AST spans index into it
+    source: String,
+    /// Directory of each package imports can name
+    packages: BTreeMap<String, PathBuf>,
+    /// Anchor IDL of each program imports can name
+    programs: BTreeMap<String, PathBuf>,
+    options: CodegenOptions,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// A file of a compilation unit
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// Path the file was read from, or the name it is reported under
+    pub path: PathBuf,
+    pub text: String,
+    /// Byte offset of the file in the unit's text
+    pub start: usize,
+}
+
+impl SourceFile {
+    /// Name the file is reported under
+    pub fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    /// Whether a byte offset of the unit's text falls in this file
+    pub fn contains(&self, offset: usize) -> bool {
+        (self.start..=self.start + self.text.len()).contains(&offset)
+    }
+
+    /// Directory the file's relative imports are resolved against
+    fn dir(&self) -> &Path {
+        self.path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+    }
+}
+
+/// Everything a successful compile produces
+#[derive(Debug)]
+pub struct Artifacts {
+    /// Name of the deployed program crate (and of its `target/deploy` keypair)
+    pub program_name: String,
+    /// The generated Anchor or native project
+    pub project: GeneratedProject,
+    /// Sizes and rent-exempt minimums of the accounts the program creates
+    pub account_costs: Vec<AccountCost>,
+}
+
+impl Session {
+    /// A session compiling `source`, which was read from (or is named) `path`
+    pub fn new(path: impl Into<PathBuf>, source: impl Into<String>) -> Self {
+        Self::from_files(vec![(path.into(), source.into())])
+    }
+
+    /// A session compiling several files as one unit, the entry point last.
+    ///
+    /// # Panics
+    ///
+    /// If `files` is empty.
+    pub fn from_files(files: Vec<(PathBuf, String)>) -> Self {
+        assert!(!files.is_empty(), "a compilation unit needs a source file");
+
+        let mut session = Self {
+            files: Vec::new(),
+            source: String::new(),
+            packages: BTreeMap::new(),
+            programs: BTreeMap::new(),
+            options: CodegenOptions::default(),
+            diagnostics: Vec::new(),
+        };
+        session.set_files(files);
+        session.options.source_path = Some(session.entry().name());
+        session
+    }
+
+    fn set_files(&mut self, files: Vec<(PathBuf, String)>) {
+        self.source.clear();
+        self.files = files
+            .into_iter()
+            .map(|(path, text)| {
+                let start = self.source.len();
+                self.source.push_str(&text);
+                self.source.push('\n');
+                SourceFile { path, text, start }
+            })
+            .collect();
+    }
+
+    /// A session compiling the file at `path`
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        Self::load_files(&[path.to_path_buf()])
+    }
+
+    /// A session compiling the files at `paths` as one unit, the entry point last
+    pub fn load_files(paths: &[PathBuf]) -> std::io::Result<Self> {
+        let files = paths
+            .iter()
+            .map(|path| Ok((path.clone(), std::fs::read_to_string(path)?)))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let mut session = Self::from_files(files);
+        // The source map must name the file wherever the generated project is built
+        session.options.source_path = Some(canonical_name(session.entry()));
+        Ok(session)
+    }
+
+    /// Set the codegen options, keeping the source path unless `options` sets one
+    pub fn with_options(mut self, options: CodegenOptions) -> Self {
+        self.set_options(options);
+        self
+    }
+
+    /// Set the codegen options, keeping the source path unless `options` sets one
+    pub fn set_options(&mut self, options: CodegenOptions) {
+        let source_path = self.options.source_path.take();
+        self.options = CodegenOptions {
+            source_path: options.source_path.or(source_path),
+            ..options
+        };
+    }
+
+    /// Let imports name `package`, as `"<package>/<path>"`, resolved against `dir`
+    pub fn add_package(&mut self, package: impl Into<String>, dir: impl Into<PathBuf>) {
+        self.packages.insert(package.into(), dir.into());
+    }
+
+    /// Let `import { Name } from "<program>"` bind `Name` to the interface of another
+    /// program, built from its Anchor IDL at `idl`, for cross-program calls
+    pub fn add_program(&mut self, program: impl Into<String>, idl: impl Into<PathBuf>) {
+        self.programs.insert(program.into(), idl.into());
+    }
+
+    pub fn options(&self) -> &CodegenOptions {
+        &self.options
+    }
+
+    pub fn options_mut(&mut self) -> &mut CodegenOptions {
+        &mut self.options
+    }
+
+    /// Path of the entry point
+    pub fn path(&self) -> &Path {
+        &self.entry().path
+    }
+
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// Text of the whole unit, which AST spans index into
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn entry(&self) -> &SourceFile {
+        &self.files[self.files.len() - 1]
+    }
+
+    fn file_at(&self, offset: usize) -> Option<&SourceFile> {
+        self.files.iter().find(|file| file.contains(offset))
+    }
+
+    /// Diagnostics reported so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Remove and return the diagnostics reported so far
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    /// Load the files the unit imports, parse it, and expand its Anchor IDL imports
+    /// (relative to the importing file) and program imports
+    pub fn parse(&mut self) -> Option<Program> {
+        self.load_imports()?;
+        let mut program = match solscript_parser::parse(&self.source) {
+            Ok(program) => program,
+            Err(e) => return self.fail(e),
+        };
+        modules::resolve_aliases(&mut program, &self.files);
+        if let Err(e) = modules::resolve_programs(&mut program, &self.programs) {
+            return self.fail(e);
+        }
+
+        let resolved =
+            solscript_parser::resolve_idl_imports_by(&mut program, &self.source, |span| {
+                self.file_at(span.start)
+                    .map_or(Path::new("."), SourceFile::dir)
+                    .to_path_buf()
+            });
+        match resolved {
+            Ok(warnings) => {
+                warnings.into_iter().for_each(|w| self.report(w));
+                Some(program)
+            }
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Add the files the unit imports, each ahead of the first file importing it.
+    /// Syntax errors are found here too, reported against the file they are in.
+    fn load_imports(&mut self) -> Option<()> {
+        let mut loader = Loader::new(&self.packages, &self.files);
+        let loaded = self
+            .files
+            .iter()
+            .try_for_each(|file| loader.load(&file.path, &file.text).map(drop));
+        match loaded {
+            Ok(()) => {
+                let files = loader.finish();
+                self.set_files(files);
+                Some(())
+            }
+            Err(diagnostic) => {
+                self.diagnostics.push(*diagnostic);
+                None
+            }
+        }
+    }
+
+    /// Type check a parsed program, returning whether it is well-typed
+    pub fn typecheck(&mut self, program: &Program) -> bool {
+        match solscript_typeck::typecheck(program, &self.source) {
+            Ok(()) => true,
+            Err(errors) => {
+                for error in errors {
+                    self.report(error);
+                }
+                false
+            }
+        }
+    }
+
+    /// Parse and type check
+    pub fn check(&mut self) -> Option<Program> {
+        let program = self.parse()?;
+        self.typecheck(&program).then_some(program)
+    }
+
+    /// Lower a checked program to the IR of its deployable contract
+    pub fn lower(&mut self, program: &Program) -> Option<SolanaProgram> {
+        match solscript_codegen::lower_to_ir(program) {
+            Ok(mut ir) => match ir.pop() {
+                Some(deployed) => Some(deployed),
+                None => self.fail(solscript_codegen::CodegenError::missing_element(
+                    "No deployable contracts found (abstract contracts cannot be deployed)",
+                    solscript_ast::Span::dummy(),
+                )),
+            },
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Name of the deployed program crate, which names its keypair and build output
+    pub fn program_name(&mut self, program: &Program) -> Option<String> {
+        match solscript_codegen::program_crate_name(program) {
+            Ok(name) => Some(name),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Generate the project for a checked program with the session's options
+    pub fn generate(&mut self, program: &Program) -> Option<Artifacts> {
+        let program_name = self.program_name(program)?;
+        let mut project = match solscript_codegen::generate_with_options(program, &self.options) {
+            Ok(project) => project,
+            Err(e) => return self.fail(e),
+        };
+        self.locate_source_map(&mut project.source_map);
+        let ir = self.lower(program)?;
+        let account_costs = match solscript_codegen::program_layout(&ir) {
+            Ok(layout) => layout.account_costs(),
+            Err(e) => return self.fail(e),
+        };
+
+        Some(Artifacts {
+            program_name,
+            project,
+            account_costs,
+        })
+    }
+
+    /// Run every stage: parse, type check and generate
+    pub fn compile(&mut self) -> Option<Artifacts> {
+        let program = self.check()?;
+        self.generate(&program)
+    }
+
+    /// Point source map spans into the files they came from rather than the unit
+    fn locate_source_map(&self, map: &mut SourceMap) {
+        if self.files.len() == 1 {
+            return;
+        }
+        for mapping in &mut map.mappings {
+            let Some(file) = self.file_at(mapping.span.start) else {
+                continue;
+            };
+            mapping.span = Span::new(
+                mapping.span.start - file.start,
+                mapping.span.end - file.start,
+            );
+            if !std::ptr::eq(file, self.entry()) {
+                mapping.source = Some(canonical_name(file));
+            }
+        }
+    }
+
+    fn report<E>(&mut self, error: E)
+    where
+        E: miette::Diagnostic + Send + Sync + 'static,
+    {
+        self.diagnostics.push(Diagnostic::error(error, &self.files));
+    }
+
+    fn fail<T, E>(&mut self, error: E) -> Option<T>
+    where
+        E: miette::Diagnostic + Send + Sync + 'static,
+    {
+        self.report(error);
+        None
+    }
+}
+
+/// Absolute path of a file read from disk, or its name if it has no path
+fn canonical_name(file: &SourceFile) -> String {
+    file.path
+        .canonicalize()
+        .map_or_else(|_| file.name(), |path| path.display().to_string())
+}
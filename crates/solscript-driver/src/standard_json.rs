@@ -0,0 +1,178 @@
+//! Standard-JSON compiler interface
+//!
+//! Like `solc --standard-json`: a JSON request names the sources and settings, and the
+//! response holds the artifacts of every source that compiled and the diagnostics of those
+//! that did not. The response is always valid JSON, even for a malformed request.
+
+use crate::diagnostic::Diagnostic;
+use crate::error::DriverError;
+use crate::session::Session;
+use serde::{Deserialize, Serialize};
+use solscript_codegen::{AccountCost, Backend, CodegenOptions, IdlFormat, SourceMap};
+use std::collections::BTreeMap;
+
+/// A standard-JSON compile request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct StandardJsonInput {
+    /// Must be `SolScript`
+    pub language: String,
+    /// Sources to compile, keyed by file name
+    pub sources: BTreeMap<String, SourceInput>,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SourceInput {
+    pub content: String,
+}
+
+/// Compile settings shared by every source of a request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Settings {
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub idl_format: IdlFormat,
+    /// Program address for `declare_id!`, the IDL and the client (a placeholder if unset)
+    pub program_id: Option<String>,
+    /// Program address per cluster, for the generated Anchor.toml
+    #[serde(default)]
+    pub cluster_program_ids: BTreeMap<String, String>,
+    /// Artifacts to include in the response (all by default)
+    #[serde(default = "OutputKind::all")]
+    pub output_selection: Vec<OutputKind>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            backend: Backend::default(),
+            idl_format: IdlFormat::default(),
+            program_id: None,
+            cluster_program_ids: BTreeMap::new(),
+            output_selection: OutputKind::all(),
+        }
+    }
+}
+
+/// An artifact that can be selected in `outputSelection`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputKind {
+    /// Every file of the generated project
+    Files,
+    Idl,
+    SourceMap,
+    AccountSizes,
+}
+
+impl OutputKind {
+    fn all() -> Vec<Self> {
+        vec![Self::Files, Self::Idl, Self::SourceMap, Self::AccountSizes]
+    }
+}
+
+/// A standard-JSON compile response
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StandardJsonOutput {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<Diagnostic>,
+    /// Artifacts of each source that compiled, keyed by file name
+    pub contracts: BTreeMap<String, ContractOutput>,
+}
+
+/// Artifacts of one source's deployed program
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractOutput {
+    /// Name of the program crate
+    pub program: String,
+    /// Generated files, keyed by path relative to the project root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idl: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_map: Option<SourceMap>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_sizes: Option<Vec<AccountCost>>,
+}
+
+impl StandardJsonOutput {
+    /// Whether any source failed to compile
+    pub fn has_errors(&self) -> bool {
+        self.errors
+            .iter()
+            .any(|e| e.severity == crate::Severity::Error)
+    }
+}
+
+/// Compile a standard-JSON request given as text
+pub fn compile_json(input: &str) -> StandardJsonOutput {
+    match serde_json::from_str::<StandardJsonInput>(input) {
+        Ok(request) => compile(&request),
+        Err(e) => rejected(DriverError::InvalidInput {
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Compile every source of a standard-JSON request
+pub fn compile(input: &StandardJsonInput) -> StandardJsonOutput {
+    if !input.language.eq_ignore_ascii_case("solscript") {
+        return rejected(DriverError::UnsupportedLanguage {
+            language: input.language.clone(),
+        });
+    }
+
+    let settings = &input.settings;
+    let selected = |kind| settings.output_selection.contains(&kind);
+    let mut output = StandardJsonOutput::default();
+    for (name, source) in &input.sources {
+        let mut session = Session::new(name, source.content.clone()).with_options(CodegenOptions {
+            backend: settings.backend,
+            idl_format: settings.idl_format,
+            program_id: settings.program_id.clone(),
+            cluster_program_ids: settings.cluster_program_ids.clone(),
+            ..Default::default()
+        });
+        let artifacts = session.compile();
+        output.errors.extend(session.take_diagnostics());
+        let Some(artifacts) = artifacts else {
+            continue;
+        };
+
+        let project = &artifacts.project;
+        let contract = ContractOutput {
+            program: artifacts.program_name.clone(),
+            files: selected(OutputKind::Files).then(|| {
+                project
+                    .files()
+                    .into_iter()
+                    .map(|(path, contents)| (path, contents.into_owned()))
+                    .collect()
+            }),
+            idl: selected(OutputKind::Idl)
+                .then(|| serde_json::from_str(&project.idl_json).ok())
+                .flatten(),
+            source_map: selected(OutputKind::SourceMap).then(|| project.source_map.clone()),
+            account_sizes: selected(OutputKind::AccountSizes)
+                .then(|| artifacts.account_costs.clone()),
+        };
+        output.contracts.insert(name.clone(), contract);
+    }
+    output
+}
+
+/// A response rejecting the whole request
+fn rejected(error: DriverError) -> StandardJsonOutput {
+    StandardJsonOutput {
+        errors: vec![Diagnostic::error(error, &[])],
+        ..Default::default()
+    }
+}
@@ -41,6 +41,7 @@ pub fn get_completions(doc: &Document, position: Position) -> Vec<CompletionItem
 
         // Add built-in completions
         items.extend(get_builtin_completions());
+        items.extend(get_intrinsic_completions(doc));
     }
 
     items
@@ -143,6 +144,10 @@ fn get_type_completions() -> Vec<CompletionItem> {
         ("address", "Address type (Pubkey)"),
         ("bytes", "Dynamic byte array"),
         ("bytes32", "Fixed 32-byte array"),
+        ("ufixed64x6", "Unsigned fixed-point decimal, 6 decimals"),
+        ("ufixed128x18", "Unsigned fixed-point decimal, 18 decimals"),
+        ("fixed64x6", "Signed fixed-point decimal, 6 decimals"),
+        ("fixed128x18", "Signed fixed-point decimal, 18 decimals"),
     ];
 
     types
@@ -172,43 +177,6 @@ fn get_builtin_completions() -> Vec<CompletionItem> {
             insert_text: Some("block.timestamp".to_string()),
             ..Default::default()
         },
-        CompletionItem {
-            label: "clock.unix_timestamp".to_string(),
-            kind: Some(CompletionItemKind::PROPERTY),
-            detail: Some("Unix timestamp from Clock sysvar".to_string()),
-            insert_text: Some("clock.unix_timestamp".to_string()),
-            ..Default::default()
-        },
-        CompletionItem {
-            label: "clock.slot".to_string(),
-            kind: Some(CompletionItemKind::PROPERTY),
-            detail: Some("Current slot from Clock sysvar".to_string()),
-            insert_text: Some("clock.slot".to_string()),
-            ..Default::default()
-        },
-        CompletionItem {
-            label: "clock.epoch".to_string(),
-            kind: Some(CompletionItemKind::PROPERTY),
-            detail: Some("Current epoch from Clock sysvar".to_string()),
-            insert_text: Some("clock.epoch".to_string()),
-            ..Default::default()
-        },
-        CompletionItem {
-            label: "rent.minimumBalance".to_string(),
-            kind: Some(CompletionItemKind::FUNCTION),
-            detail: Some("Get minimum rent-exempt balance".to_string()),
-            insert_text: Some("rent.minimumBalance(${1:dataSize})".to_string()),
-            insert_text_format: Some(InsertTextFormat::SNIPPET),
-            ..Default::default()
-        },
-        CompletionItem {
-            label: "rent.isExempt".to_string(),
-            kind: Some(CompletionItemKind::FUNCTION),
-            detail: Some("Check if account is rent-exempt".to_string()),
-            insert_text: Some("rent.isExempt(${1:lamports}, ${2:dataSize})".to_string()),
-            insert_text_format: Some(InsertTextFormat::SNIPPET),
-            ..Default::default()
-        },
         CompletionItem {
             label: "assert".to_string(),
             kind: Some(CompletionItemKind::FUNCTION),
@@ -228,9 +196,77 @@ fn get_builtin_completions() -> Vec<CompletionItem> {
     ]
 }
 
+/// Standard library objects in scope, such as `clock` or an imported `Token`, with
+/// each of their members
+fn get_intrinsic_completions(doc: &Document) -> Vec<CompletionItem> {
+    let mut items = Vec::new();
+    for (name, intrinsic) in doc.intrinsics() {
+        items.push(CompletionItem {
+            label: name.to_string(),
+            kind: Some(CompletionItemKind::MODULE),
+            detail: intrinsic.docs.first().cloned(),
+            ..Default::default()
+        });
+        for member in &intrinsic.members {
+            let completion = member_completion(member);
+            items.push(CompletionItem {
+                label: format!("{}.{}", name, completion.label),
+                insert_text: Some(format!(
+                    "{}.{}",
+                    name,
+                    completion
+                        .insert_text
+                        .as_deref()
+                        .unwrap_or(&completion.label)
+                )),
+                ..completion
+            });
+        }
+    }
+    items
+}
+
+/// Completion of a standard library member, with its signature and docs
+fn member_completion(member: &solscript_ast::FnSig) -> CompletionItem {
+    let documentation = (!member.docs.is_empty()).then(|| {
+        Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: member.docs.join("\n"),
+        })
+    });
+    if solscript_std::is_property(member) {
+        return CompletionItem {
+            label: member.name.name.to_string(),
+            kind: Some(CompletionItemKind::PROPERTY),
+            detail: Some(solscript_std::signature(member)),
+            documentation,
+            ..Default::default()
+        };
+    }
+    let params: Vec<String> = member
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| format!("${{{}:{}}}", i + 1, param.name.name))
+        .collect();
+    CompletionItem {
+        label: member.name.name.to_string(),
+        kind: Some(CompletionItemKind::METHOD),
+        detail: Some(solscript_std::signature(member)),
+        documentation,
+        insert_text: Some(format!("{}({})", member.name.name, params.join(", "))),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        ..Default::default()
+    }
+}
+
 fn get_member_completions(doc: &Document, object_name: &str) -> Vec<CompletionItem> {
     let mut items = Vec::new();
 
+    if let Some(intrinsic) = doc.intrinsics().get(object_name) {
+        return intrinsic.members.iter().map(member_completion).collect();
+    }
+
     match object_name {
         "msg" => {
             items.push(CompletionItem {
@@ -260,76 +296,6 @@ fn get_member_completions(doc: &Document, object_name: &str) -> Vec<CompletionIt
                 ..Default::default()
             });
         }
-        "clock" => {
-            items.push(CompletionItem {
-                label: "unix_timestamp".to_string(),
-                kind: Some(CompletionItemKind::PROPERTY),
-                detail: Some("Unix timestamp".to_string()),
-                ..Default::default()
-            });
-            items.push(CompletionItem {
-                label: "slot".to_string(),
-                kind: Some(CompletionItemKind::PROPERTY),
-                detail: Some("Current slot".to_string()),
-                ..Default::default()
-            });
-            items.push(CompletionItem {
-                label: "epoch".to_string(),
-                kind: Some(CompletionItemKind::PROPERTY),
-                detail: Some("Current epoch".to_string()),
-                ..Default::default()
-            });
-        }
-        "rent" => {
-            items.push(CompletionItem {
-                label: "minimumBalance".to_string(),
-                kind: Some(CompletionItemKind::METHOD),
-                detail: Some("Get minimum rent-exempt balance".to_string()),
-                insert_text: Some("minimumBalance(${1:dataSize})".to_string()),
-                insert_text_format: Some(InsertTextFormat::SNIPPET),
-                ..Default::default()
-            });
-            items.push(CompletionItem {
-                label: "isExempt".to_string(),
-                kind: Some(CompletionItemKind::METHOD),
-                detail: Some("Check if account is rent-exempt".to_string()),
-                insert_text: Some("isExempt(${1:lamports}, ${2:dataSize})".to_string()),
-                insert_text_format: Some(InsertTextFormat::SNIPPET),
-                ..Default::default()
-            });
-        }
-        "token" => {
-            items.push(CompletionItem {
-                label: "transfer".to_string(),
-                kind: Some(CompletionItemKind::METHOD),
-                detail: Some("Transfer SPL tokens".to_string()),
-                insert_text: Some(
-                    "transfer(${1:from}, ${2:to}, ${3:authority}, ${4:amount})".to_string(),
-                ),
-                insert_text_format: Some(InsertTextFormat::SNIPPET),
-                ..Default::default()
-            });
-            items.push(CompletionItem {
-                label: "mint".to_string(),
-                kind: Some(CompletionItemKind::METHOD),
-                detail: Some("Mint SPL tokens".to_string()),
-                insert_text: Some(
-                    "mint(${1:mint}, ${2:to}, ${3:authority}, ${4:amount})".to_string(),
-                ),
-                insert_text_format: Some(InsertTextFormat::SNIPPET),
-                ..Default::default()
-            });
-            items.push(CompletionItem {
-                label: "burn".to_string(),
-                kind: Some(CompletionItemKind::METHOD),
-                detail: Some("Burn SPL tokens".to_string()),
-                insert_text: Some(
-                    "burn(${1:from}, ${2:mint}, ${3:authority}, ${4:amount})".to_string(),
-                ),
-                insert_text_format: Some(InsertTextFormat::SNIPPET),
-                ..Default::default()
-            });
-        }
         _ => {
             // Try to find symbols in the AST
             if let Some(ast) = &doc.ast {
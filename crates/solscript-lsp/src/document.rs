@@ -1,7 +1,10 @@
 //! Document management for the language server
 
 use ropey::Rope;
+use smol_str::SmolStr;
 use solscript_ast::Program;
+use solscript_std::Intrinsic;
+use std::collections::HashMap;
 
 /// Represents an open document in the editor
 pub struct Document {
@@ -112,6 +115,14 @@ impl Document {
         }
     }
 
+    /// Standard library objects the document can name: the global ones, and those it
+    /// imports if it parses
+    pub fn intrinsics(&self) -> HashMap<SmolStr, &'static Intrinsic> {
+        self.ast
+            .as_ref()
+            .map_or_else(solscript_std::globals, solscript_std::in_scope)
+    }
+
     /// Get the line text at a line number
     pub fn line_text(&self, line: u32) -> Option<String> {
         let line_idx = line as usize;
@@ -8,6 +8,9 @@ pub fn get_hover(doc: &Document, position: Position) -> Option<Hover> {
     let word = doc.word_at(position.line, position.character)?;
 
     // Check built-in objects first
+    if let Some(hover) = get_intrinsic_hover(doc, &word) {
+        return Some(hover);
+    }
     if let Some(hover) = get_builtin_hover(&word) {
         return Some(hover);
     }
@@ -32,6 +35,35 @@ pub fn get_hover(doc: &Document, position: Position) -> Option<Hover> {
     None
 }
 
+/// Docs and members of a standard library object, such as `clock` or an imported
+/// `Token`
+fn get_intrinsic_hover(doc: &Document, word: &str) -> Option<Hover> {
+    let intrinsics = doc.intrinsics();
+    let intrinsic = intrinsics.get(word)?;
+    let mut value = format!(
+        "**{}** - `{}` from `{}`",
+        word, intrinsic.name, intrinsic.module
+    );
+    if !intrinsic.docs.is_empty() {
+        value.push_str(&format!("\n\n{}", intrinsic.docs.join("\n")));
+    }
+    value.push_str("\n\nMembers:");
+    for member in &intrinsic.members {
+        value.push_str(&format!("\n- `{}`", solscript_std::signature(member)));
+        if let Some(line) = member.docs.first() {
+            value.push_str(&format!(": {}", line));
+        }
+    }
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: None,
+    })
+}
+
 fn get_builtin_hover(word: &str) -> Option<Hover> {
     let (contents, detail) = match word {
         "msg" => (
@@ -46,18 +78,6 @@ fn get_builtin_hover(word: &str) -> Option<Hover> {
             "**block** - Block information",
             "Properties:\n- `timestamp`: Current block timestamp\n- `number`: Current block number (slot)",
         ),
-        "clock" => (
-            "**clock** - Solana Clock sysvar",
-            "Properties:\n- `unix_timestamp`: Unix timestamp (i64)\n- `slot`: Current slot (u64)\n- `epoch`: Current epoch (u64)",
-        ),
-        "rent" => (
-            "**rent** - Solana Rent sysvar",
-            "Methods:\n- `minimumBalance(dataSize: uint64)`: Get minimum rent-exempt balance\n- `isExempt(lamports: uint64, dataSize: uint64)`: Check if account is rent-exempt",
-        ),
-        "token" => (
-            "**token** - SPL Token operations",
-            "Methods:\n- `transfer(from, to, authority, amount)`: Transfer tokens\n- `mint(mint, to, authority, amount)`: Mint tokens\n- `burn(from, mint, authority, amount)`: Burn tokens",
-        ),
         "require" => (
             "**require** - Condition check",
             "```solscript\nrequire(condition, \"error message\");\n```\nReverts if condition is false.",
@@ -132,7 +152,7 @@ fn get_type_hover(word: &str) -> Option<Hover> {
             "Signer type",
             "An address parameter that must sign the transaction",
         ),
-        _ => return None,
+        _ => return get_fixed_point_hover(word),
     };
 
     Some(Hover {
@@ -144,6 +164,22 @@ fn get_type_hover(word: &str) -> Option<Hover> {
     })
 }
 
+/// Hover for `ufixed64x6`, `fixed128x18` and the other fixed-point types
+fn get_fixed_point_hover(word: &str) -> Option<Hover> {
+    let fixed = solscript_ast::FixedType::parse(word)?;
+    let sign = if fixed.signed { "signed" } else { "unsigned" };
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!(
+                "**{}** - {}-bit {} fixed-point decimal\n\n{} decimal places, stored as the value times 10^{}. `*` and `/` round toward zero; `mulUp`/`divUp` round away from zero.",
+                word, fixed.bits, sign, fixed.decimals, fixed.decimals
+            ),
+        }),
+        range: None,
+    })
+}
+
 fn get_keyword_hover(word: &str) -> Option<Hover> {
     let info = match word {
         "contract" => (
@@ -1,5 +1,6 @@
 //! Parser error types
 
+use crate::SkippedInstruction;
 use miette::{Diagnostic, SourceSpan};
 use thiserror::Error;
 
@@ -46,11 +47,41 @@ pub enum ParseError {
         src: String,
     },
 
-    #[error("Invalid float literal: {message}")]
+    #[error("Invalid decimal literal: {message}")]
     #[diagnostic(code(solscript::parse::invalid_float))]
     InvalidFloat {
         message: String,
-        #[label("invalid float")]
+        #[label("invalid decimal")]
+        span: SourceSpan,
+        #[source_code]
+        src: String,
+    },
+
+    #[error("Invalid Anchor IDL '{path}': {message}")]
+    #[diagnostic(
+        code(solscript::parse::invalid_idl),
+        help("IDL imports must point to an Anchor IDL JSON file, relative to the importing file")
+    )]
+    InvalidIdl {
+        path: String,
+        message: String,
+        #[label("imported here")]
+        span: SourceSpan,
+        #[source_code]
+        src: String,
+    },
+
+    #[error("Instruction '{instruction}' of '{path}' was not imported: argument '{argument}' has an unsupported type")]
+    #[diagnostic(
+        severity(Warning),
+        code(solscript::parse::unsupported_idl_instruction),
+        help("IDL arguments with defined types, options or nested vectors cannot be expressed in SolScript yet, so the instruction cannot be called")
+    )]
+    UnsupportedIdlInstruction {
+        path: String,
+        instruction: String,
+        argument: String,
+        #[label("imported here")]
         span: SourceSpan,
         #[source_code]
         src: String,
@@ -96,6 +127,35 @@ impl ParseError {
         }
     }
 
+    pub fn invalid_idl(
+        path: impl Into<String>,
+        message: impl Into<String>,
+        span: (usize, usize),
+        src: &str,
+    ) -> Self {
+        Self::InvalidIdl {
+            path: path.into(),
+            message: message.into(),
+            span: SourceSpan::new(span.0.into(), span.1 - span.0),
+            src: src.to_string(),
+        }
+    }
+
+    pub fn unsupported_idl_instruction(
+        path: impl Into<String>,
+        skipped: &SkippedInstruction,
+        span: (usize, usize),
+        src: &str,
+    ) -> Self {
+        Self::UnsupportedIdlInstruction {
+            path: path.into(),
+            instruction: skipped.name.clone(),
+            argument: skipped.argument.clone(),
+            span: SourceSpan::new(span.0.into(), span.1 - span.0),
+            src: src.to_string(),
+        }
+    }
+
     pub fn invalid_int(message: impl Into<String>, span: (usize, usize), src: &str) -> Self {
         Self::InvalidInt {
             message: message.into(),
@@ -103,6 +163,14 @@ impl ParseError {
             src: src.to_string(),
         }
     }
+
+    pub fn invalid_float(message: impl Into<String>, span: (usize, usize), src: &str) -> Self {
+        Self::InvalidFloat {
+            message: message.into(),
+            span: SourceSpan::new(span.0.into(), span.1 - span.0),
+            src: src.to_string(),
+        }
+    }
 }
 
 /// Convert pest error to our ParseError
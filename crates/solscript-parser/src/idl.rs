@@ -0,0 +1,331 @@
+//! Anchor IDL imports
+//!
+//! `import { Marinade } from "./idl/marinade.json";` binds `Marinade` to a
+//! synthetic interface built from the Anchor IDL, so that calls such as
+//! `Marinade(programId).deposit(state, msolMint, ..., amount)` are type checked
+//! against the IDL and lowered to a CPI with the right accounts and discriminator.
+//!
+//! Both the legacy (`isMut`/`isSigner`, `publicKey`) and the 0.30+
+//! (`writable`/`signer`, `pubkey`, explicit discriminators) IDL formats are accepted.
+
+use crate::ParseError;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use solscript_ast::*;
+use std::path::{Path, PathBuf};
+
+/// Expand every `import { .. } from "*.json"` in the program into synthetic
+/// interfaces, reading the IDL files relative to `base_dir`.
+///
+/// The import statements are kept; one interface per imported name (or alias)
+/// is inserted right after its import. Instructions that could not be imported
+/// are returned as warnings.
+pub fn resolve_idl_imports(
+    program: &mut Program,
+    source: &str,
+    base_dir: &Path,
+) -> Result<Vec<ParseError>, ParseError> {
+    resolve_idl_imports_by(program, source, |_| base_dir.to_path_buf())
+}
+
+/// Like [`resolve_idl_imports`], for programs parsed from several files: each IDL
+/// path is read relative to `base_dir(span)`, where `span` is its import statement.
+pub fn resolve_idl_imports_by(
+    program: &mut Program,
+    source: &str,
+    base_dir: impl Fn(Span) -> PathBuf,
+) -> Result<Vec<ParseError>, ParseError> {
+    let mut items = Vec::with_capacity(program.items.len());
+    let mut warnings = Vec::new();
+
+    for item in program.items.drain(..) {
+        let interfaces = match &item {
+            Item::Import(import) if is_idl_import(import) => {
+                let span = (import.span.start, import.span.end);
+                let path = base_dir(import.span).join(import.source.as_str());
+                let json = std::fs::read_to_string(&path).map_err(|e| {
+                    ParseError::invalid_idl(import.source.as_str(), e.to_string(), span, source)
+                })?;
+
+                let mut interfaces = Vec::new();
+                for imported in &import.items {
+                    let name = imported.alias.clone().unwrap_or(imported.name.clone());
+                    let (interface, skipped) =
+                        anchor_idl_to_interface(name, &json).map_err(|message| {
+                            ParseError::invalid_idl(import.source.as_str(), message, span, source)
+                        })?;
+                    interfaces.push(interface);
+                    warnings.extend(skipped.iter().map(|skipped| {
+                        ParseError::unsupported_idl_instruction(
+                            import.source.as_str(),
+                            skipped,
+                            span,
+                            source,
+                        )
+                    }));
+                }
+                interfaces
+            }
+            _ => Vec::new(),
+        };
+
+        items.push(item);
+        items.extend(interfaces.into_iter().map(Item::Interface));
+    }
+
+    program.items = items;
+    Ok(warnings)
+}
+
+/// Whether an import refers to an Anchor IDL rather than SolScript source
+pub fn is_idl_import(import: &ImportStmt) -> bool {
+    import.source.ends_with(".json")
+}
+
+/// An IDL instruction left out of an imported interface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedInstruction {
+    pub name: String,
+    /// The first argument whose type SolScript cannot express
+    pub argument: String,
+}
+
+/// Build an interface named `name` from the JSON text of an Anchor IDL.
+///
+/// Instructions whose arguments use types SolScript cannot express (defined
+/// types, options, nested vectors) are left out of the interface and returned
+/// alongside it.
+pub fn anchor_idl_to_interface(
+    name: Ident,
+    json: &str,
+) -> Result<(InterfaceDef, Vec<SkippedInstruction>), String> {
+    let idl: Idl = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let span = name.span;
+
+    let mut members = Vec::new();
+    let mut skipped = Vec::new();
+    for ix in &idl.instructions {
+        let mut accounts = Vec::new();
+        flatten_accounts(&ix.accounts, "", &mut accounts);
+
+        let mut params: Vec<Param> = accounts
+            .iter()
+            .map(|account| Param {
+                ty: simple_type("address", span),
+                attributes: Vec::new(),
+                storage_location: None,
+                name: Ident::new(account.name.clone(), span),
+                span,
+            })
+            .collect();
+
+        let args: Result<Vec<Param>, &IdlField> = ix
+            .args
+            .iter()
+            .map(|arg| {
+                let ty = idl_type_to_type_expr(&arg.ty, span).ok_or(arg)?;
+                Ok(Param {
+                    ty,
+                    attributes: Vec::new(),
+                    storage_location: None,
+                    name: Ident::new(arg.name.as_str(), span),
+                    span,
+                })
+            })
+            .collect();
+        match args {
+            Ok(args) => params.extend(args),
+            Err(arg) => {
+                skipped.push(SkippedInstruction {
+                    name: ix.name.clone(),
+                    argument: arg.name.clone(),
+                });
+                continue;
+            }
+        }
+
+        let discriminator = match &ix.discriminator {
+            Some(bytes) => bytes.as_slice().try_into().map_err(|_| {
+                format!(
+                    "instruction '{}' has a {}-byte discriminator, expected 8",
+                    ix.name,
+                    bytes.len()
+                )
+            })?,
+            None => sighash(&ix.name),
+        };
+
+        members.push(FnSig {
+            docs: Vec::new(),
+            name: Ident::new(ix.name.as_str(), span),
+            generic_params: None,
+            params,
+            visibility: Some(Visibility::External),
+            state_mutability: Vec::new(),
+            modifiers: Vec::new(),
+            return_params: Vec::new(),
+            anchor: Some(AnchorInstruction {
+                discriminator,
+                accounts,
+            }),
+            span,
+        });
+    }
+
+    let interface = InterfaceDef {
+        docs: Vec::new(),
+        attributes: Vec::new(),
+        name,
+        bases: Vec::new(),
+        members,
+        span,
+    };
+    Ok((interface, skipped))
+}
+
+/// Anchor's legacy instruction discriminator: `sha256("global:<snake_name>")[..8]`
+fn sighash(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("global:{}", to_snake_case(name)).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+/// Flatten composite account groups, prefixing nested names with their group
+fn flatten_accounts(items: &[IdlAccountItem], prefix: &str, out: &mut Vec<AnchorAccountMeta>) {
+    for item in items {
+        match item {
+            IdlAccountItem::Composite { name, accounts } => {
+                flatten_accounts(accounts, &join_name(prefix, name), out);
+            }
+            IdlAccountItem::Single {
+                name,
+                writable,
+                signer,
+            } => out.push(AnchorAccountMeta {
+                name: join_name(prefix, name).into(),
+                is_mut: *writable,
+                is_signer: *signer,
+            }),
+        }
+    }
+}
+
+fn join_name(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        return name.to_string();
+    }
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}{}", prefix, first.to_ascii_uppercase(), chars.as_str()),
+        None => prefix.to_string(),
+    }
+}
+
+fn idl_type_to_type_expr(ty: &serde_json::Value, span: Span) -> Option<TypeExpr> {
+    match ty {
+        serde_json::Value::String(s) => Some(simple_type(idl_primitive(s)?, span)),
+        serde_json::Value::Object(obj) => {
+            if let Some(inner) = obj.get("vec") {
+                let element = idl_primitive(inner.as_str()?)?;
+                Some(array_type(element, None, span))
+            } else if let Some(serde_json::Value::Array(array)) = obj.get("array") {
+                let element = idl_primitive(array.first()?.as_str()?)?;
+                let size = array.get(1)?.as_u64()?;
+                if element == "uint8" && (1..=32).contains(&size) {
+                    Some(simple_type(&format!("bytes{}", size), span))
+                } else {
+                    Some(array_type(element, Some(size), span))
+                }
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn idl_primitive(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "u8" => "uint8",
+        "u16" => "uint16",
+        "u32" => "uint32",
+        "u64" => "uint64",
+        "u128" => "uint128",
+        "i8" => "int8",
+        "i16" => "int16",
+        "i32" => "int32",
+        "i64" => "int64",
+        "i128" => "int128",
+        "bool" => "bool",
+        "string" => "string",
+        "bytes" => "bytes",
+        "publicKey" | "pubkey" => "address",
+        _ => return None,
+    })
+}
+
+fn simple_type(name: &str, span: Span) -> TypeExpr {
+    TypeExpr::Path(TypePath::simple(Ident::new(name, span)))
+}
+
+fn array_type(element: &str, size: Option<u64>, span: Span) -> TypeExpr {
+    TypeExpr::Array(Box::new(ArrayType {
+        element: TypePath::simple(Ident::new(element, span)),
+        sizes: vec![size],
+        span,
+    }))
+}
+
+fn to_snake_case(s: &str) -> String {
+    let mut result = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            result.push('_');
+        }
+        result.push(c.to_ascii_lowercase());
+    }
+    result
+}
+
+// Subset of the Anchor IDL schema needed to build interfaces
+
+#[derive(Deserialize)]
+struct Idl {
+    #[serde(default)]
+    instructions: Vec<IdlInstruction>,
+}
+
+#[derive(Deserialize)]
+struct IdlInstruction {
+    name: String,
+    #[serde(default)]
+    discriminator: Option<Vec<u8>>,
+    #[serde(default)]
+    accounts: Vec<IdlAccountItem>,
+    #[serde(default)]
+    args: Vec<IdlField>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IdlAccountItem {
+    Composite {
+        name: String,
+        accounts: Vec<IdlAccountItem>,
+    },
+    Single {
+        name: String,
+        #[serde(default, alias = "isMut")]
+        writable: bool,
+        #[serde(default, alias = "isSigner")]
+        signer: bool,
+    },
+}
+
+#[derive(Deserialize)]
+struct IdlField {
+    name: String,
+    #[serde(rename = "type")]
+    ty: serde_json::Value,
+}
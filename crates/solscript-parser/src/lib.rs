@@ -5,9 +5,11 @@
 #![allow(unused_assignments)] // Suppress false positives from derive macros
 
 mod error;
+mod idl;
 mod parser;
 
 pub use error::*;
+pub use idl::*;
 pub use parser::*;
 
 use pest_derive::Parser;
@@ -385,6 +387,34 @@ mod tests {
         assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
     }
 
+    #[test]
+    fn test_parse_decimal_literal() {
+        let source = "contract Rates {\n    ufixed64x6 public rate = 12.05;\n}";
+        let program = parse(source).unwrap();
+        let solscript_ast::Item::Contract(contract) = &program.items[0] else {
+            panic!("expected a contract");
+        };
+        let solscript_ast::ContractMember::StateVar(var) = &contract.members[0] else {
+            panic!("expected a state variable");
+        };
+        assert_eq!(var.ty.name(), "ufixed64x6");
+        assert!(matches!(
+            var.initializer,
+            Some(solscript_ast::Expr::Literal(
+                solscript_ast::Literal::Decimal(1205, 2, _)
+            ))
+        ));
+
+        let source = format!(
+            "contract Rates {{\n    ufixed public rate = 0.{};\n}}",
+            "1".repeat(39)
+        );
+        assert!(matches!(
+            parse(&source),
+            Err(ParseError::InvalidFloat { .. })
+        ));
+    }
+
     #[test]
     fn test_parse_bitwise_operations() {
         let source = r#"
@@ -672,4 +702,234 @@ mod tests {
             panic!("Expected expression statement");
         }
     }
+
+    const MARINADE_IDL: &str = r#"{
+        "version": "0.1.0",
+        "name": "marinade_finance",
+        "instructions": [
+            {
+                "name": "deposit",
+                "accounts": [
+                    { "name": "state", "isMut": true, "isSigner": false },
+                    { "name": "msolMint", "isMut": true, "isSigner": false },
+                    {
+                        "name": "liqPool",
+                        "accounts": [
+                            { "name": "solLegPda", "isMut": true, "isSigner": false }
+                        ]
+                    },
+                    { "name": "transferFrom", "isMut": true, "isSigner": true }
+                ],
+                "args": [{ "name": "lamports", "type": "u64" }]
+            },
+            {
+                "name": "configure",
+                "accounts": [],
+                "args": [{ "name": "data", "type": { "defined": "ConfigData" } }]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_param_constraints() {
+        let source = r#"
+            contract Vault {
+                #[constraint(has_one = admin)]
+                function deposit(
+                    #[constraint(has_one = mint, constraint = ata.amount > 0)] account<TokenAccount> ata,
+                    signer admin
+                ) public {}
+            }
+        "#;
+        let program = parse(source).unwrap();
+
+        let contract = match &program.items[0] {
+            solscript_ast::Item::Contract(c) => c,
+            _ => panic!("Expected contract"),
+        };
+        let func = match &contract.members[0] {
+            solscript_ast::ContractMember::Function(f) => f,
+            _ => panic!("Expected function"),
+        };
+        assert_eq!(func.attributes[0].name.name.as_str(), "constraint");
+
+        let attr = &func.params[0].attributes[0];
+        assert_eq!(attr.args.len(), 2);
+        assert_eq!(attr.args[0].name.as_ref().unwrap().name.as_str(), "has_one");
+        assert!(matches!(
+            &attr.args[0].value,
+            solscript_ast::AttributeValue::Ident(i) if i.name == "mint"
+        ));
+        assert_eq!(
+            attr.args[1].name.as_ref().unwrap().name.as_str(),
+            "constraint"
+        );
+        assert!(matches!(
+            &attr.args[1].value,
+            solscript_ast::AttributeValue::Expr(e) if matches!(**e, solscript_ast::Expr::Binary(_))
+        ));
+        assert!(func.params[1].attributes.is_empty());
+    }
+
+    #[test]
+    fn test_anchor_idl_to_interface() {
+        let name = solscript_ast::Ident::new("Marinade", solscript_ast::Span::dummy());
+        let (interface, skipped) = anchor_idl_to_interface(name, MARINADE_IDL).unwrap();
+
+        // `configure` uses a defined type and is left out
+        assert_eq!(interface.members.len(), 1);
+        assert_eq!(
+            skipped,
+            vec![SkippedInstruction {
+                name: "configure".to_string(),
+                argument: "data".to_string(),
+            }]
+        );
+        let deposit = &interface.members[0];
+        assert_eq!(deposit.name.name.as_str(), "deposit");
+
+        let params: Vec<(String, String)> = deposit
+            .params
+            .iter()
+            .map(|p| (p.name.name.to_string(), p.ty.name()))
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                ("state".to_string(), "address".to_string()),
+                ("msolMint".to_string(), "address".to_string()),
+                ("liqPoolSolLegPda".to_string(), "address".to_string()),
+                ("transferFrom".to_string(), "address".to_string()),
+                ("lamports".to_string(), "uint64".to_string()),
+            ]
+        );
+
+        let anchor = deposit.anchor.as_ref().unwrap();
+        assert_eq!(anchor.accounts.len(), 4);
+        assert!(anchor.accounts[3].is_mut && anchor.accounts[3].is_signer);
+        assert!(!anchor.accounts[0].is_signer);
+        // sha256("global:deposit")[..8]
+        assert_eq!(anchor.discriminator, [242, 35, 198, 137, 82, 225, 242, 182]);
+    }
+
+    #[test]
+    fn test_anchor_idl_new_format() {
+        let idl = r#"{
+            "address": "MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD",
+            "metadata": { "name": "marinade", "version": "0.1.0", "spec": "0.1.0" },
+            "instructions": [
+                {
+                    "name": "liquid_unstake",
+                    "discriminator": [30, 30, 119, 240, 191, 227, 12, 16],
+                    "accounts": [
+                        { "name": "state", "writable": true },
+                        { "name": "get_msol_from_authority", "signer": true }
+                    ],
+                    "args": [
+                        { "name": "msol_amount", "type": "u64" },
+                        { "name": "recipient", "type": "pubkey" },
+                        { "name": "memo", "type": { "array": ["u8", 32] } }
+                    ]
+                }
+            ]
+        }"#;
+
+        let name = solscript_ast::Ident::new("Marinade", solscript_ast::Span::dummy());
+        let (interface, skipped) = anchor_idl_to_interface(name, idl).unwrap();
+        assert!(skipped.is_empty());
+        let unstake = &interface.members[0];
+        let anchor = unstake.anchor.as_ref().unwrap();
+
+        assert_eq!(anchor.discriminator, [30, 30, 119, 240, 191, 227, 12, 16]);
+        assert!(anchor.accounts[0].is_mut && !anchor.accounts[0].is_signer);
+        assert!(!anchor.accounts[1].is_mut && anchor.accounts[1].is_signer);
+        assert_eq!(unstake.params[3].ty.name(), "address");
+        assert_eq!(unstake.params[4].ty.name(), "bytes32");
+    }
+
+    #[test]
+    fn test_resolve_idl_imports() {
+        let dir = std::env::temp_dir().join(format!("solscript-idl-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("idl")).unwrap();
+        std::fs::write(dir.join("idl").join("marinade.json"), MARINADE_IDL).unwrap();
+
+        let source = r#"
+            import { Marinade as Mnde } from "./idl/marinade.json";
+
+            contract Staker {
+            }
+        "#;
+        let mut program = parse(source).unwrap();
+        let result = resolve_idl_imports(&mut program, source, &dir);
+        assert!(result.is_ok(), "Failed to resolve: {:?}", result.err());
+        let warnings = result.unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            &warnings[0],
+            ParseError::UnsupportedIdlInstruction { instruction, .. } if instruction == "configure"
+        ));
+
+        let interface = program
+            .items
+            .iter()
+            .find_map(|item| match item {
+                solscript_ast::Item::Interface(i) => Some(i),
+                _ => None,
+            })
+            .expect("IDL import should produce an interface");
+        assert_eq!(interface.name.name.as_str(), "Mnde");
+
+        let missing = r#"import { Missing } from "./idl/missing.json";"#;
+        let mut program = parse(missing).unwrap();
+        let err = resolve_idl_imports(&mut program, missing, &dir).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidIdl { .. }));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_doc_comments() {
+        let source = r#"
+            /// A simple counter
+            /// with two lines of docs
+            contract Counter {
+                /// Current value
+                uint64 public count;
+
+                // Not a doc comment
+                function reset() public {
+                    count = 0;
+                }
+
+                /// Add one
+                #[inline]
+                function increment() public { count += 1; } /// trailing
+                function decrement() public {
+                    count -= 1;
+                }
+            }
+        "#;
+        let program = parse(source).unwrap();
+        let solscript_ast::Item::Contract(contract) = &program.items[0] else {
+            panic!("expected a contract");
+        };
+        assert_eq!(
+            contract.docs,
+            ["A simple counter", "with two lines of docs"]
+        );
+
+        let docs: Vec<&[String]> = contract
+            .members
+            .iter()
+            .map(|member| match member {
+                solscript_ast::ContractMember::StateVar(v) => v.docs.as_slice(),
+                solscript_ast::ContractMember::Function(f) => f.docs.as_slice(),
+                _ => &[],
+            })
+            .collect();
+        assert_eq!(docs[0], ["Current value"]);
+        assert!(docs[1].is_empty());
+        assert_eq!(docs[2], ["Add one"]);
+        assert!(docs[3].is_empty());
+    }
 }
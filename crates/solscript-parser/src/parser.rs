@@ -44,6 +44,24 @@ fn span_from_pair(pair: &Pair) -> Span {
     Span::new(span.start(), span.end())
 }
 
+/// The `///` comment lines directly above an item, without the markers. Comments
+/// are silent in the grammar, so they are read back from the source text.
+fn doc_comments(pair: &Pair) -> Vec<String> {
+    let span = pair.as_span();
+    let mut lines = span.get_input()[..span.start()].rsplit('\n');
+    // Only whitespace may precede the item on its own line
+    if !lines.next().unwrap_or_default().trim().is_empty() {
+        return Vec::new();
+    }
+    let mut docs: Vec<String> = lines
+        .map(str::trim)
+        .take_while(|line| line.starts_with("///"))
+        .map(|line| line.trim_start_matches('/').trim().to_string())
+        .collect();
+    docs.reverse();
+    docs
+}
+
 fn parse_ident(pair: Pair) -> Ident {
     Ident::new(pair.as_str(), span_from_pair(&pair))
 }
@@ -116,6 +134,7 @@ fn parse_string_content(s: &str) -> SmolStr {
 
 fn parse_contract(pair: Pair) -> Result<ContractDef, ParseError> {
     let span = span_from_pair(&pair);
+    let docs = doc_comments(&pair);
     let mut attributes = Vec::new();
     let mut is_abstract = false;
     let mut name = None;
@@ -171,6 +190,7 @@ fn parse_contract(pair: Pair) -> Result<ContractDef, ParseError> {
     }
 
     Ok(ContractDef {
+        docs,
         attributes,
         is_abstract,
         name: name.unwrap(),
@@ -182,6 +202,7 @@ fn parse_contract(pair: Pair) -> Result<ContractDef, ParseError> {
 
 fn parse_state_var(pair: Pair) -> Result<StateVar, ParseError> {
     let span = span_from_pair(&pair);
+    let docs = doc_comments(&pair);
     let mut attributes = Vec::new();
     let mut ty = None;
     let mut visibility = None;
@@ -200,6 +221,7 @@ fn parse_state_var(pair: Pair) -> Result<StateVar, ParseError> {
     }
 
     Ok(StateVar {
+        docs,
         attributes,
         ty: ty.unwrap(),
         visibility,
@@ -211,6 +233,7 @@ fn parse_state_var(pair: Pair) -> Result<StateVar, ParseError> {
 
 fn parse_constructor(pair: Pair) -> Result<ConstructorDef, ParseError> {
     let span = span_from_pair(&pair);
+    let docs = doc_comments(&pair);
     let mut params = Vec::new();
     let mut modifiers = Vec::new();
     let mut body = None;
@@ -225,6 +248,7 @@ fn parse_constructor(pair: Pair) -> Result<ConstructorDef, ParseError> {
     }
 
     Ok(ConstructorDef {
+        docs,
         params,
         modifiers,
         body: body.unwrap(),
@@ -289,6 +313,7 @@ fn parse_modifier_stmt(pair: Pair) -> Result<Stmt, ParseError> {
 
 fn parse_interface(pair: Pair) -> Result<InterfaceDef, ParseError> {
     let span = span_from_pair(&pair);
+    let docs = doc_comments(&pair);
     let mut attributes = Vec::new();
     let mut name = None;
     let mut bases = Vec::new();
@@ -315,6 +340,7 @@ fn parse_interface(pair: Pair) -> Result<InterfaceDef, ParseError> {
     }
 
     Ok(InterfaceDef {
+        docs,
         attributes,
         name: name.unwrap(),
         bases,
@@ -325,6 +351,7 @@ fn parse_interface(pair: Pair) -> Result<InterfaceDef, ParseError> {
 
 fn parse_function_sig(pair: Pair) -> Result<FnSig, ParseError> {
     let span = span_from_pair(&pair);
+    let docs = doc_comments(&pair);
     let mut name = None;
     let mut generic_params = None;
     let mut params = Vec::new();
@@ -347,6 +374,7 @@ fn parse_function_sig(pair: Pair) -> Result<FnSig, ParseError> {
     }
 
     Ok(FnSig {
+        docs,
         name: name.unwrap(),
         generic_params,
         params,
@@ -354,6 +382,7 @@ fn parse_function_sig(pair: Pair) -> Result<FnSig, ParseError> {
         state_mutability,
         modifiers,
         return_params,
+        anchor: None,
         span,
     })
 }
@@ -516,6 +545,7 @@ fn parse_error_param(pair: Pair) -> Result<ErrorParam, ParseError> {
 
 fn parse_function(pair: Pair) -> Result<FnDef, ParseError> {
     let span = span_from_pair(&pair);
+    let docs = doc_comments(&pair);
     let mut attributes = Vec::new();
     let mut name = None;
     let mut generic_params = None;
@@ -542,6 +572,7 @@ fn parse_function(pair: Pair) -> Result<FnDef, ParseError> {
     }
 
     Ok(FnDef {
+        docs,
         attributes,
         name: name.unwrap(),
         generic_params,
@@ -642,12 +673,14 @@ fn parse_param_list(pair: Pair) -> Result<Vec<Param>, ParseError> {
 
 fn parse_param(pair: Pair) -> Result<Param, ParseError> {
     let span = span_from_pair(&pair);
+    let mut attributes = Vec::new();
     let mut ty = None;
     let mut storage_location = None;
     let mut name = None;
 
     for inner in pair.into_inner() {
         match inner.as_rule() {
+            Rule::attribute => attributes.push(parse_attribute(inner)?),
             Rule::type_expr => ty = Some(parse_type_expr(inner)?),
             Rule::storage_location => storage_location = Some(parse_storage_location(inner)),
             Rule::ident => name = Some(parse_ident(inner)),
@@ -656,6 +689,7 @@ fn parse_param(pair: Pair) -> Result<Param, ParseError> {
     }
 
     Ok(Param {
+        attributes,
         ty: ty.unwrap(),
         storage_location,
         name: name.unwrap(),
@@ -760,22 +794,14 @@ fn parse_attribute_arg(pair: Pair) -> Result<AttributeArg, ParseError> {
     let mut value = None;
 
     for inner in pair.into_inner() {
+        // A value following an identifier means the identifier was the arg name
+        if let Some(AttributeValue::Ident(ident)) = value.take() {
+            name = Some(ident);
+        }
+
         match inner.as_rule() {
             Rule::ident => {
-                if name.is_none() && value.is_none() {
-                    // Could be name or value
-                    let ident = parse_ident(inner);
-                    value = Some(AttributeValue::Ident(ident));
-                } else {
-                    name = value.take().and_then(|v| {
-                        if let AttributeValue::Ident(i) = v {
-                            Some(i)
-                        } else {
-                            None
-                        }
-                    });
-                    value = Some(AttributeValue::Ident(parse_ident(inner)));
-                }
+                value = Some(AttributeValue::Ident(parse_ident(inner)));
             }
             Rule::literal => {
                 value = Some(AttributeValue::Literal(parse_literal(inner)?));
@@ -787,6 +813,9 @@ fn parse_attribute_arg(pair: Pair) -> Result<AttributeArg, ParseError> {
                     span_from_pair(&inner),
                 )));
             }
+            Rule::expr => {
+                value = Some(AttributeValue::Expr(Box::new(parse_expr(inner)?)));
+            }
             _ => {}
         }
     }
@@ -1750,6 +1779,25 @@ fn parse_literal(pair: Pair) -> Result<Literal, ParseError> {
                 .unwrap_or(0);
             Ok(Literal::Int(value, span))
         }
+        Rule::decimal_lit => {
+            let s = inner.as_str();
+            let decimals = s.len() - s.find('.').unwrap() - 1;
+            if decimals > 38 {
+                return Err(ParseError::invalid_float(
+                    "more than 38 digits after the decimal point",
+                    (span.start, span.end),
+                    inner.as_span().get_input(),
+                ));
+            }
+            let digits: u128 = s.replace('.', "").parse().map_err(|_| {
+                ParseError::invalid_float(
+                    "too many digits",
+                    (span.start, span.end),
+                    inner.as_span().get_input(),
+                )
+            })?;
+            Ok(Literal::Decimal(digits, decimals as u8, span))
+        }
         Rule::address_lit => {
             // 0x followed by 40 hex digits
             let s = inner.as_str();
@@ -0,0 +1,253 @@
+//! The SolScript standard library
+//!
+//! Standard library modules are SolScript source embedded in the compiler, imported by
+//! their `@`-prefixed path:
+//!
+//! ```solidity
+//! import { Token } from "@solana/token";
+//! ```
+//!
+//! Operations the compiler implements itself are declared in the modules as
+//! `#[intrinsic]` interfaces, with a signature and `///` docs for every member. The
+//! type checker, the code generator and the language server all read the intrinsics
+//! from here. A member without parameters declared `view` is a property, read without
+//! parentheses (`Clock.slot`). `#[intrinsic(global = token)]` also makes the object
+//! available as `token` without an import.
+
+use smol_str::SmolStr;
+use solscript_ast::{
+    AttributeValue, FnSig, ImportStmt, InterfaceDef, Item, Param, Program, StateMutability,
+};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// A standard library module
+#[derive(Debug)]
+pub struct Module {
+    /// The path it is imported by, such as `@solana/token`
+    pub path: &'static str,
+    pub source: &'static str,
+}
+
+static MODULES: &[Module] = &[
+    Module {
+        path: "@solana/clock",
+        source: include_str!("../std/solana/clock.sol"),
+    },
+    Module {
+        path: "@solana/math",
+        source: include_str!("../std/solana/math.sol"),
+    },
+    Module {
+        path: "@solana/rent",
+        source: include_str!("../std/solana/rent.sol"),
+    },
+    Module {
+        path: "@solana/token",
+        source: include_str!("../std/solana/token.sol"),
+    },
+];
+
+/// Every standard library module
+pub fn modules() -> &'static [Module] {
+    MODULES
+}
+
+/// The module imported by `path`
+pub fn module(path: &str) -> Option<&'static Module> {
+    MODULES.iter().find(|module| module.path == path)
+}
+
+/// Whether an import names a standard library module rather than a file or package
+pub fn is_std_import(import: &ImportStmt) -> bool {
+    import.source.starts_with('@')
+}
+
+/// An object whose members the compiler implements, declared by an `#[intrinsic]`
+/// interface
+#[derive(Debug)]
+pub struct Intrinsic {
+    pub name: SmolStr,
+    /// Path of the module declaring it
+    pub module: &'static str,
+    /// Name it can be used by without an import
+    pub global: Option<SmolStr>,
+    pub docs: Vec<String>,
+    pub members: Vec<FnSig>,
+}
+
+impl Intrinsic {
+    /// The member called `name`
+    pub fn member(&self, name: &str) -> Option<&FnSig> {
+        self.members.iter().find(|member| member.name.name == name)
+    }
+
+    /// Name of the type the object has in the type checker, which no declared type
+    /// can have
+    pub fn type_name(&self) -> String {
+        format!("{}::{}", self.module, self.name)
+    }
+}
+
+/// Whether a member is a property, read without parentheses
+pub fn is_property(member: &FnSig) -> bool {
+    member.params.is_empty() && member.state_mutability.contains(&StateMutability::View)
+}
+
+/// A member as it is written in source, without its docs: `slot() returns (uint64)`
+pub fn signature(member: &FnSig) -> String {
+    let params = |params: &[Param]| {
+        params
+            .iter()
+            .map(|param| format!("{} {}", param.ty.name(), param.name.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut signature = format!("{}({})", member.name.name, params(&member.params));
+    if !member.return_params.is_empty() {
+        let returns = member
+            .return_params
+            .iter()
+            .map(|param| param.ty.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        signature.push_str(&format!(" returns ({})", returns));
+    }
+    signature
+}
+
+/// Whether an interface declares an intrinsic object rather than a program to call
+pub fn is_intrinsic(interface: &InterfaceDef) -> bool {
+    interface
+        .attributes
+        .iter()
+        .any(|attribute| attribute.name.name == "intrinsic")
+}
+
+/// Every intrinsic object of the standard library
+pub fn intrinsics() -> &'static [Intrinsic] {
+    static INTRINSICS: OnceLock<Vec<Intrinsic>> = OnceLock::new();
+    INTRINSICS.get_or_init(|| {
+        let mut intrinsics = Vec::new();
+        for module in MODULES {
+            let program = solscript_parser::parse(module.source).unwrap_or_else(|e| {
+                panic!(
+                    "standard library module {} does not parse: {}",
+                    module.path, e
+                )
+            });
+            for item in program.items {
+                let Item::Interface(interface) = item else {
+                    continue;
+                };
+                if !is_intrinsic(&interface) {
+                    continue;
+                }
+                let global = interface
+                    .attributes
+                    .iter()
+                    .flat_map(|attribute| &attribute.args)
+                    .find(|arg| arg.name.as_ref().is_some_and(|name| name.name == "global"))
+                    .and_then(|arg| match &arg.value {
+                        AttributeValue::Ident(ident) => Some(ident.name.clone()),
+                        _ => None,
+                    });
+                intrinsics.push(Intrinsic {
+                    name: interface.name.name,
+                    module: module.path,
+                    global,
+                    docs: interface.docs,
+                    members: interface.members,
+                });
+            }
+        }
+        intrinsics
+    })
+}
+
+/// The intrinsic object whose type is named `type_name`
+pub fn intrinsic_of_type(type_name: &str) -> Option<&'static Intrinsic> {
+    intrinsics()
+        .iter()
+        .find(|intrinsic| intrinsic.type_name() == type_name)
+}
+
+/// The intrinsic objects every program can name without an import
+pub fn globals() -> HashMap<SmolStr, &'static Intrinsic> {
+    intrinsics()
+        .iter()
+        .filter_map(|intrinsic| Some((intrinsic.global.clone()?, intrinsic)))
+        .collect()
+}
+
+/// The intrinsic objects `program` can name: every global one, and those it imports
+/// from the standard library, under their name and alias
+pub fn in_scope(program: &Program) -> HashMap<SmolStr, &'static Intrinsic> {
+    let mut scope = globals();
+    for item in &program.items {
+        let Item::Import(import) = item else {
+            continue;
+        };
+        for imported in &import.items {
+            let Some(intrinsic) = intrinsics().iter().find(|intrinsic| {
+                intrinsic.module == import.source && intrinsic.name == imported.name.name
+            }) else {
+                continue;
+            };
+            scope.insert(imported.name.name.clone(), intrinsic);
+            if let Some(alias) = &imported.alias {
+                scope.insert(alias.name.clone(), intrinsic);
+            }
+        }
+    }
+    scope
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modules_declare_documented_intrinsics() {
+        for module in modules() {
+            assert!(
+                solscript_parser::parse(module.source).is_ok(),
+                "{} does not parse",
+                module.path
+            );
+        }
+
+        let token = intrinsics()
+            .iter()
+            .find(|intrinsic| intrinsic.name == "Token")
+            .unwrap();
+        assert_eq!(token.module, "@solana/token");
+        assert_eq!(token.global.as_deref(), Some("token"));
+        assert_eq!(token.member("transfer").unwrap().params.len(), 4);
+        assert!(intrinsics()
+            .iter()
+            .flat_map(|intrinsic| &intrinsic.members)
+            .all(|member| !member.docs.is_empty()));
+
+        let clock = intrinsic_of_type("@solana/clock::Clock").unwrap();
+        assert!(is_property(clock.member("slot").unwrap()));
+        assert!(!is_property(token.member("getATA").unwrap()));
+        assert_eq!(
+            signature(token.member("getATA").unwrap()),
+            "getATA(address owner, address mint) returns (address)"
+        );
+    }
+
+    #[test]
+    fn test_in_scope() {
+        let program = solscript_parser::parse(
+            "import { Token as Spl } from \"@solana/token\";\ncontract A {}",
+        )
+        .unwrap();
+        let scope = in_scope(&program);
+        assert_eq!(scope["Spl"].name, "Token");
+        assert_eq!(scope["Token"].name, "Token");
+        assert_eq!(scope["clock"].name, "Clock");
+        assert!(!scope.contains_key("Clock"));
+    }
+}
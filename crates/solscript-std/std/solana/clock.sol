@@ -0,0 +1,19 @@
+// @solana/clock: the Clock sysvar
+//
+// import { Clock } from "@solana/clock";
+
+/// The Clock sysvar: network time, read with `Clock.slot` and the like
+#[intrinsic(global = clock)]
+interface Clock {
+    /// Unix timestamp of the current slot, in seconds
+    function timestamp() external view returns (int64);
+
+    /// Unix timestamp of the current slot, in seconds (same as `timestamp`)
+    function unix_timestamp() external view returns (int64);
+
+    /// The current slot
+    function slot() external view returns (uint64);
+
+    /// The current epoch
+    function epoch() external view returns (uint64);
+}
@@ -0,0 +1,33 @@
+// @solana/math: overflow-safe integer math
+//
+// import { Math } from "@solana/math";
+
+/// Integer math on unsigned integers of any width; signed arguments are rejected by
+/// the type checker. Results are checked: a result that does not fit the type it is
+/// assigned to fails the instruction instead of wrapping.
+#[intrinsic]
+interface Math {
+    /// Square root of `x`, rounded down
+    function sqrt(uint256 x) external pure returns (uint256);
+
+    /// The smaller of `a` and `b`
+    function min(uint256 a, uint256 b) external pure returns (uint256);
+
+    /// The larger of `a` and `b`
+    function max(uint256 a, uint256 b) external pure returns (uint256);
+
+    /// `x * y / denominator`, rounded down. The product is computed in 256 bits, so
+    /// it cannot overflow; fails if `denominator` is zero or the result does not fit.
+    function mulDiv(uint256 x, uint256 y, uint256 denominator) external pure returns (uint256);
+
+    /// `x * y / denominator`, rounded up. The product is computed in 256 bits, so it
+    /// cannot overflow; fails if `denominator` is zero or the result does not fit.
+    function mulDivUp(uint256 x, uint256 y, uint256 denominator) external pure returns (uint256);
+
+    /// `bps` basis points of `amount` (`amount * bps / 10000`), rounded down
+    function mulBps(uint256 amount, uint256 bps) external pure returns (uint256);
+
+    /// `bps` basis points of `amount` (`amount * bps / 10000`), rounded up, as for a
+    /// fee charged to the caller
+    function mulBpsUp(uint256 amount, uint256 bps) external pure returns (uint256);
+}
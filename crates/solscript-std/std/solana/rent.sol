@@ -0,0 +1,13 @@
+// @solana/rent: the Rent sysvar
+//
+// import { Rent } from "@solana/rent";
+
+/// The Rent sysvar: the lamports an account must hold to be rent-exempt
+#[intrinsic(global = rent)]
+interface Rent {
+    /// Lamports an account of `dataLen` bytes needs to be rent-exempt
+    function minimumBalance(uint64 dataLen) external view returns (uint64);
+
+    /// Whether `lamports` make an account of `dataLen` bytes rent-exempt
+    function isExempt(uint64 lamports, uint64 dataLen) external view returns (bool);
+}
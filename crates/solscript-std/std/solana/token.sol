@@ -0,0 +1,21 @@
+// @solana/token: SPL Token operations
+//
+// import { Token } from "@solana/token";
+
+/// SPL Token operations. Each call is a CPI into the SPL Token program, which is
+/// added to the instruction's accounts.
+#[intrinsic(global = token)]
+interface Token {
+    /// Move `amount` tokens from the `from` token account to `to`, signed by `authority`
+    function transfer(address from, address to, address authority, uint64 amount) external;
+
+    /// Mint `amount` new tokens of `mint` into the `to` token account, signed by the
+    /// mint `authority`
+    function mint(address mint, address to, address authority, uint64 amount) external;
+
+    /// Burn `amount` tokens of `mint` from the `from` token account, signed by `authority`
+    function burn(address from, address mint, address authority, uint64 amount) external;
+
+    /// The associated token account of `owner` for `mint`
+    function getATA(address owner, address mint) external view returns (address);
+}
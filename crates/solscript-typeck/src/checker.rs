@@ -2,7 +2,7 @@
 
 use indexmap::IndexMap;
 use smol_str::SmolStr;
-use solscript_ast::{self as ast, Span};
+use solscript_ast::{self as ast, FixedType, Span};
 
 use crate::error::TypeError;
 use crate::scope::{ScopeKind, SymbolTable};
@@ -27,6 +27,8 @@ pub struct TypeChecker {
     self_type: Option<Type>,
     /// All contracts for inheritance lookup
     contracts: std::collections::HashMap<String, ast::ContractDef>,
+    /// Standard library intrinsics the program can name
+    intrinsics: std::collections::HashMap<SmolStr, &'static solscript_std::Intrinsic>,
 }
 
 impl TypeChecker {
@@ -39,11 +41,14 @@ impl TypeChecker {
             return_type: None,
             self_type: None,
             contracts: std::collections::HashMap::new(),
+            intrinsics: std::collections::HashMap::new(),
         }
     }
 
     /// Check a program
     pub fn check_program(&mut self, program: &ast::Program) -> Result<(), Vec<TypeError>> {
+        self.intrinsics = solscript_std::in_scope(program);
+
         // First pass: collect all type definitions
         for item in &program.items {
             self.collect_type_def(item);
@@ -133,6 +138,8 @@ impl TypeChecker {
                     }
                 }
             }
+            // Intrinsics are typed from the standard library instead
+            ast::Item::Interface(i) if solscript_std::is_intrinsic(i) => {}
             ast::Item::Interface(i) => {
                 let def = self.build_interface_def(i);
                 self.symbols
@@ -344,6 +351,18 @@ impl TypeChecker {
     fn resolve_type_expr(&mut self, ty: &ast::TypeExpr) -> Type {
         match ty {
             ast::TypeExpr::Path(path) => self.resolve_type_path(path),
+            // `accounts[]`: variadic accounts passed as ctx.remaining_accounts
+            ast::TypeExpr::Array(arr) if arr.element.name() == "accounts" => {
+                if arr.sizes != [None] {
+                    self.error(TypeError::invalid_account_type(
+                        "`accounts` must be declared as a dynamic array: `accounts[]`",
+                        self.span(arr.span),
+                        &self.source,
+                    ));
+                    return Type::Error;
+                }
+                Type::DynamicArray(Box::new(Type::Named(NamedType::new("unchecked_account"))))
+            }
             ast::TypeExpr::Array(arr) => {
                 let elem = self.resolve_type_path(&arr.element);
                 // Handle multiple dimensions
@@ -380,6 +399,13 @@ impl TypeChecker {
             return Type::Primitive(prim);
         }
 
+        // Solana account parameter types
+        match name.as_str() {
+            "account" => return self.resolve_account_type(path),
+            "unchecked_account" => return Type::Named(NamedType::new("unchecked_account")),
+            _ => {}
+        }
+
         // Look up user-defined type
         if self.symbols.lookup_type(name).is_some() {
             let type_args = path
@@ -398,6 +424,39 @@ impl TypeChecker {
         }
     }
 
+    /// Resolve `account<T>`, where T is an SPL account type
+    fn resolve_account_type(&mut self, path: &ast::TypePath) -> Type {
+        let inner = path
+            .generic_args
+            .as_ref()
+            .and_then(|g| match g.args.as_slice() {
+                [ast::TypeExpr::Path(inner)] => Some(inner.name().clone()),
+                _ => None,
+            });
+
+        match inner {
+            Some(inner) if inner == "TokenAccount" || inner == "Mint" => Type::Named(
+                NamedType::with_args("account", vec![Type::Named(NamedType::new(inner))]),
+            ),
+            Some(inner) => {
+                self.error(TypeError::invalid_account_type(
+                    format!("unsupported account type `{}`", inner),
+                    self.span(path.span),
+                    &self.source,
+                ));
+                Type::Error
+            }
+            None => {
+                self.error(TypeError::invalid_account_type(
+                    "`account` takes exactly one type argument",
+                    self.span(path.span),
+                    &self.source,
+                ));
+                Type::Error
+            }
+        }
+    }
+
     // =========================================================================
     // Item Checking
     // =========================================================================
@@ -409,7 +468,7 @@ impl TypeChecker {
             ast::Item::Enum(e) => self.check_enum(e),
             ast::Item::Function(f) => self.check_function(f),
             ast::Item::Interface(_) => {} // Already collected
-            ast::Item::Import(_) => {}    // Handled separately
+            ast::Item::Import(_) => {}    // Loaded into the program by the driver
             ast::Item::Event(_) => {}     // Events are just declarations
             ast::Item::Error(_) => {}     // Errors are just declarations
         }
@@ -436,12 +495,50 @@ impl TypeChecker {
         }
 
         // Add this contract's state variables to scope
+        let zero_copy = contract
+            .attributes
+            .iter()
+            .any(|a| a.name.name == "zero_copy");
         for member in &contract.members {
             if let ast::ContractMember::StateVar(f) = member {
                 let ty = self.resolve_type_expr(&f.ty);
+                if ty.is_account() {
+                    self.error(TypeError::invalid_account_type(
+                        "account types can only be used as function parameters",
+                        self.span(f.ty.span()),
+                        &self.source,
+                    ));
+                }
+                // Only dynamically sized fields can grow
+                let growable = f.attributes.iter().find(|a| a.name.name == "growable");
+                if let Some(attr) = growable {
+                    let is_dynamic = matches!(
+                        ty,
+                        Type::Primitive(PrimitiveType::String | PrimitiveType::Bytes)
+                            | Type::DynamicArray(_)
+                    );
+                    if !is_dynamic && !matches!(ty, Type::Error) {
+                        self.error(TypeError::invalid_attribute(
+                            "#[growable] only applies to `string`, `bytes` and `T[]` fields",
+                            self.span(attr.span),
+                            &self.source,
+                        ));
+                    }
+                }
+                // Mappings live in their own PDAs, not in the zero-copy state
+                let is_mapping = matches!(ty, Type::Mapping(..));
+                if zero_copy && !is_mapping && !matches!(ty, Type::Error) && !ty.is_pod() {
+                    self.error(TypeError::invalid_zero_copy_field(
+                        &f.name.name,
+                        &ty,
+                        self.span(f.ty.span()),
+                        &self.source,
+                    ));
+                }
                 self.symbols.define_variable(f.name.name.clone(), ty, true);
             }
         }
+        self.check_upgrade_attributes(contract, zero_copy);
 
         // First pass: Register all function signatures so they can be called internally
         for member in &contract.members {
@@ -470,6 +567,134 @@ impl TypeChecker {
         self.self_type = None;
     }
 
+    /// Validate `#[upgradeable]`, `#[version(n)]` state fields and
+    /// `#[migration(from = a, to = b)]` functions.
+    fn check_upgrade_attributes(&mut self, contract: &ast::ContractDef, zero_copy: bool) {
+        let upgradeable = contract
+            .attributes
+            .iter()
+            .find(|a| a.name.name == "upgradeable");
+        if let Some(attr) = upgradeable {
+            if !attr.args.is_empty() {
+                self.error(TypeError::invalid_attribute(
+                    "#[upgradeable] takes no arguments; migrations are gated by the program's upgrade authority",
+                    self.span(attr.span),
+                    &self.source,
+                ));
+            }
+            if zero_copy {
+                self.error(TypeError::invalid_attribute(
+                    "#[upgradeable] state cannot be #[zero_copy]",
+                    self.span(attr.span),
+                    &self.source,
+                ));
+            }
+        }
+
+        // Layout version of the newest state fields
+        let mut latest = 1;
+        for member in &contract.members {
+            let ast::ContractMember::StateVar(f) = member else {
+                continue;
+            };
+            let Some(attr) = f.attributes.iter().find(|a| a.name.name == "version") else {
+                continue;
+            };
+            if upgradeable.is_none() {
+                self.error(TypeError::invalid_attribute(
+                    "#[version] requires an #[upgradeable] contract",
+                    self.span(attr.span),
+                    &self.source,
+                ));
+            } else if matches!(f.ty, ast::TypeExpr::Mapping(_)) {
+                self.error(TypeError::invalid_attribute(
+                    "mappings live outside the state account and are not versioned",
+                    self.span(attr.span),
+                    &self.source,
+                ));
+            } else if let [arg] = attr.args.as_slice() {
+                match layout_version(arg) {
+                    Some(v) if arg.name.is_none() => latest = latest.max(v),
+                    _ => self.error(TypeError::invalid_attribute(
+                        "#[version] expects a layout version between 1 and 255, e.g. #[version(2)]",
+                        self.span(arg.span),
+                        &self.source,
+                    )),
+                }
+            } else {
+                self.error(TypeError::invalid_attribute(
+                    "#[version] expects a layout version between 1 and 255, e.g. #[version(2)]",
+                    self.span(attr.span),
+                    &self.source,
+                ));
+            }
+        }
+
+        let mut migrated_from = std::collections::HashSet::new();
+        for member in &contract.members {
+            let ast::ContractMember::Function(f) = member else {
+                continue;
+            };
+            let Some(attr) = f.attributes.iter().find(|a| a.name.name == "migration") else {
+                continue;
+            };
+            if upgradeable.is_none() {
+                self.error(TypeError::invalid_attribute(
+                    "#[migration] requires an #[upgradeable] contract",
+                    self.span(attr.span),
+                    &self.source,
+                ));
+                continue;
+            }
+            if !f.params.is_empty() || !f.return_params.is_empty() {
+                self.error(TypeError::invalid_attribute(
+                    "migration functions take no parameters and return nothing; they update state in place",
+                    self.span(f.name.span),
+                    &self.source,
+                ));
+            }
+            if matches!(
+                f.visibility,
+                Some(ast::Visibility::Public | ast::Visibility::External)
+            ) {
+                self.error(TypeError::invalid_attribute(
+                    "migration functions only run from the generated `migrate` instruction and cannot be public",
+                    self.span(f.name.span),
+                    &self.source,
+                ));
+            }
+
+            let bound = |key: &str| {
+                attr.args
+                    .iter()
+                    .find(|a| a.name.as_ref().is_some_and(|n| n.name == key))
+                    .and_then(layout_version)
+            };
+            let (Some(from), Some(to)) = (bound("from"), bound("to")) else {
+                self.error(TypeError::invalid_attribute(
+                    "#[migration] expects layout versions, e.g. #[migration(from = 1, to = 2)]",
+                    self.span(attr.span),
+                    &self.source,
+                ));
+                continue;
+            };
+            let message = if to != from + 1 {
+                "migrations step one layout version at a time (`to = from + 1`)".to_string()
+            } else if to > latest {
+                format!("no state field has #[version({})]", to)
+            } else if !migrated_from.insert(from) {
+                format!("duplicate migration from version {}", from)
+            } else {
+                continue;
+            };
+            self.error(TypeError::invalid_attribute(
+                message,
+                self.span(attr.span),
+                &self.source,
+            ));
+        }
+    }
+
     fn check_struct(&mut self, s: &ast::StructDef) {
         // Check for duplicate fields
         let mut seen_fields = std::collections::HashSet::new();
@@ -513,6 +738,225 @@ impl TypeChecker {
         }
     }
 
+    /// Validate `#[constraint(...)]` attributes on a function (which target its
+    /// state account) and on its account parameters, and `#[mut]` on the latter.
+    ///
+    /// `param_types` are the resolved types of `f.params`, already in scope.
+    fn check_account_constraints(&mut self, f: &ast::FnDef, param_types: &[Type]) {
+        let params: Vec<(&ast::Param, &Type)> = f.params.iter().zip(param_types).collect();
+
+        let state_attrs = f.attributes.iter().filter(|a| a.name.name == "constraint");
+        for attr in state_attrs {
+            for arg in &attr.args {
+                self.check_constraint_arg(arg, None, &params, f);
+            }
+        }
+
+        let is_view = f
+            .state_mutability
+            .iter()
+            .any(|m| matches!(m, ast::StateMutability::View | ast::StateMutability::Pure));
+        for (param, ty) in &params {
+            if let Some(attr) = param.attributes.iter().find(|a| a.name.name == "mut") {
+                if !is_constraint_target(ty) {
+                    self.error(TypeError::invalid_constraint(
+                        format!("`{}` is not an account or signer", param.name.name),
+                        self.span(attr.span),
+                        &self.source,
+                    ));
+                } else if is_view {
+                    self.error(TypeError::invalid_constraint(
+                        format!("a view function cannot write to `{}`", param.name.name),
+                        self.span(attr.span),
+                        &self.source,
+                    ));
+                }
+            }
+            for attr in param
+                .attributes
+                .iter()
+                .filter(|a| a.name.name == "constraint")
+            {
+                if !is_constraint_target(ty) {
+                    self.error(TypeError::invalid_constraint(
+                        format!("`{}` is not an account or signer", param.name.name),
+                        self.span(attr.span),
+                        &self.source,
+                    ));
+                    continue;
+                }
+                for arg in &attr.args {
+                    self.check_constraint_arg(arg, Some(ty), &params, f);
+                }
+            }
+        }
+    }
+
+    /// Check one `key = value` constraint; `target` is `None` for the state account
+    fn check_constraint_arg(
+        &mut self,
+        arg: &ast::AttributeArg,
+        target: Option<&Type>,
+        params: &[(&ast::Param, &Type)],
+        f: &ast::FnDef,
+    ) {
+        let span = self.span(arg.span);
+        let Some(key) = &arg.name else {
+            self.error(TypeError::invalid_constraint(
+                "expected `key = value`",
+                span,
+                &self.source,
+            ));
+            return;
+        };
+
+        match key.name.as_str() {
+            "has_one" | "close" => {
+                let Some(account) = constraint_account_name(&arg.value) else {
+                    self.error(TypeError::invalid_constraint(
+                        format!("`{}` takes the name of an account", key.name),
+                        span,
+                        &self.source,
+                    ));
+                    return;
+                };
+                if !params
+                    .iter()
+                    .any(|(p, ty)| p.name.name == account.name && is_constraint_target(ty))
+                {
+                    self.error(TypeError::invalid_constraint(
+                        format!("`{}` is not an account parameter", account.name),
+                        self.span(account.span),
+                        &self.source,
+                    ));
+                    return;
+                }
+
+                if key.name == "has_one" {
+                    let field_ty = match target {
+                        None => self.state_field_type(&account.name),
+                        Some(Type::Named(named)) => account_field_type(named, &account.name),
+                        Some(_) => None,
+                    };
+                    match field_ty {
+                        Some(ty)
+                            if self.types_compatible(
+                                &Type::Primitive(PrimitiveType::Address),
+                                &ty,
+                            ) => {}
+                        Some(ty) => self.error(TypeError::invalid_constraint(
+                            format!(
+                                "field `{}` has type `{}`, expected `address`",
+                                account.name, ty
+                            ),
+                            span,
+                            &self.source,
+                        )),
+                        None => self.error(TypeError::invalid_constraint(
+                            format!("the account has no field `{}`", account.name),
+                            span,
+                            &self.source,
+                        )),
+                    }
+                } else if target.is_some() {
+                    self.error(TypeError::invalid_constraint(
+                        "`close` can only be used on the state account (put it on the function)",
+                        span,
+                        &self.source,
+                    ));
+                } else if f
+                    .state_mutability
+                    .iter()
+                    .any(|m| matches!(m, ast::StateMutability::View | ast::StateMutability::Pure))
+                {
+                    self.error(TypeError::invalid_constraint(
+                        "a view function cannot close the state account",
+                        span,
+                        &self.source,
+                    ));
+                }
+            }
+            "owner" | "address" | "constraint" => {
+                let expr = match &arg.value {
+                    ast::AttributeValue::Ident(ident) => ast::Expr::Ident(ident.clone()),
+                    ast::AttributeValue::Literal(lit) => ast::Expr::Literal(lit.clone()),
+                    ast::AttributeValue::Expr(expr) => (**expr).clone(),
+                };
+                if !self.check_constraint_expr(&expr, params) {
+                    return;
+                }
+
+                let expected = if key.name == "constraint" {
+                    Type::Primitive(PrimitiveType::Bool)
+                } else {
+                    Type::Primitive(PrimitiveType::Address)
+                };
+                // Field accesses and calls carry no span of their own, so point at the argument
+                let found = self.check_expr(&expr);
+                if !matches!(found, Type::Error) && !self.types_compatible(&expected, &found) {
+                    self.error(TypeError::type_mismatch(
+                        &expected,
+                        &found,
+                        span,
+                        &self.source,
+                    ));
+                }
+            }
+            other => self.error(TypeError::invalid_constraint(
+                format!("unknown constraint `{}`", other),
+                self.span(key.span),
+                &self.source,
+            )),
+        }
+    }
+
+    /// Constraint expressions are evaluated during account validation, so they
+    /// may only use accounts, state fields and simple operators
+    fn check_constraint_expr(&mut self, expr: &ast::Expr, params: &[(&ast::Param, &Type)]) -> bool {
+        match expr {
+            ast::Expr::Literal(_) => true,
+            ast::Expr::Ident(ident) => {
+                match params.iter().find(|(p, _)| p.name.name == ident.name) {
+                    Some((_, ty)) if !is_constraint_target(ty) => {
+                        self.error(TypeError::invalid_constraint(
+                            format!("`{}` is not an account; constraints can only use accounts and state", ident.name),
+                            self.span(ident.span),
+                            &self.source,
+                        ));
+                        false
+                    }
+                    _ => true,
+                }
+            }
+            ast::Expr::Binary(b) => {
+                self.check_constraint_expr(&b.left, params)
+                    && self.check_constraint_expr(&b.right, params)
+            }
+            ast::Expr::Unary(u) => self.check_constraint_expr(&u.expr, params),
+            ast::Expr::FieldAccess(fa) => self.check_constraint_expr(&fa.expr, params),
+            ast::Expr::Paren(inner) => self.check_constraint_expr(inner, params),
+            _ => {
+                self.error(TypeError::invalid_constraint(
+                    "unsupported expression in a constraint",
+                    self.span(expr.span()),
+                    &self.source,
+                ));
+                false
+            }
+        }
+    }
+
+    /// Type of a state field of the contract being checked
+    fn state_field_type(&self, field: &str) -> Option<Type> {
+        let Some(Type::Named(named)) = &self.self_type else {
+            return None;
+        };
+        match self.symbols.lookup_type(&named.name) {
+            Some(TypeDef::Contract(c)) => c.state_fields.get(field).cloned(),
+            _ => None,
+        }
+    }
+
     fn check_function(&mut self, f: &ast::FnDef) {
         let fn_ty = self.build_function_type(f);
         self.return_type = Some((*fn_ty.return_type).clone());
@@ -525,12 +969,16 @@ impl TypeChecker {
         self.symbols.push_scope(ScopeKind::Function);
 
         // Add parameters to scope
+        let mut param_types = Vec::with_capacity(f.params.len());
         for param in &f.params {
             let ty = self.resolve_type_expr(&param.ty);
             self.symbols
-                .define_variable(param.name.name.clone(), ty, false);
+                .define_variable(param.name.name.clone(), ty.clone(), false);
+            param_types.push(ty);
         }
 
+        self.check_account_constraints(f, &param_types);
+
         // Check function body (if present - abstract functions have no body)
         if let Some(body) = &f.body {
             self.check_block(body);
@@ -940,15 +1388,26 @@ impl TypeChecker {
             ast::Literal::String(_, _) => Type::Primitive(PrimitiveType::String),
             ast::Literal::HexString(_, _) => Type::Primitive(PrimitiveType::Bytes),
             ast::Literal::Address(_, _) => Type::Primitive(PrimitiveType::Address),
+            ast::Literal::Decimal(_, decimals, _) => {
+                Type::Primitive(PrimitiveType::DecimalLiteral {
+                    decimals: *decimals,
+                    negative: false,
+                })
+            }
         }
     }
 
     fn check_ident_expr(&mut self, ident: &ast::Ident) -> Type {
         let name = &ident.name;
 
+        // Standard library intrinsics: token, Clock, ...
+        if let Some(intrinsic) = self.intrinsics.get(name) {
+            return Type::Named(NamedType::new(intrinsic.type_name()));
+        }
+
         // Handle built-in objects
         match name.as_str() {
-            "msg" | "block" | "tx" | "token" | "clock" | "rent" => {
+            "msg" | "block" | "tx" => {
                 // These are built-in objects with specific fields/methods
                 // For now, return a placeholder type
                 return Type::Named(NamedType::new(name.clone()));
@@ -983,6 +1442,10 @@ impl TypeChecker {
             return Type::Error;
         }
 
+        if left_ty.is_fixed_point() || right_ty.is_fixed_point() {
+            return self.check_fixed_binary_expr(bin, &left_ty, &right_ty);
+        }
+
         match bin.op {
             // Arithmetic operators
             ast::BinaryOp::Add
@@ -1061,6 +1524,79 @@ impl TypeChecker {
         }
     }
 
+    /// Binary operations on fixed-point values: both sides have the same fixed-point
+    /// type, or one is a decimal literal that converts to the other's
+    fn check_fixed_binary_expr(
+        &mut self,
+        bin: &ast::BinaryExpr,
+        left_ty: &Type,
+        right_ty: &Type,
+    ) -> Type {
+        let fixed_ty = match (left_ty, right_ty) {
+            (Type::Primitive(PrimitiveType::Fixed(_)), _)
+                if self.types_compatible(left_ty, right_ty) =>
+            {
+                left_ty.clone()
+            }
+            (_, Type::Primitive(PrimitiveType::Fixed(_)))
+                if self.types_compatible(right_ty, left_ty) =>
+            {
+                right_ty.clone()
+            }
+            _ => {
+                let message = match (left_ty, right_ty) {
+                    (
+                        Type::Primitive(PrimitiveType::DecimalLiteral { .. }),
+                        Type::Primitive(PrimitiveType::DecimalLiteral { .. }),
+                    ) => "operation on two decimal literals; write its result as one literal"
+                        .to_string(),
+                    (Type::Primitive(PrimitiveType::DecimalLiteral { .. }), fixed)
+                    | (fixed, Type::Primitive(PrimitiveType::DecimalLiteral { .. }))
+                        if fixed.is_fixed_point() =>
+                    {
+                        format!("the decimal literal does not fit `{}`", fixed)
+                    }
+                    _ if left_ty.is_fixed_point() && right_ty.is_fixed_point() => format!(
+                        "`{}` and `{}` are different fixed-point types",
+                        left_ty, right_ty
+                    ),
+                    _ => format!(
+                        "`{}` and `{}` mix a fixed-point value with another type",
+                        left_ty, right_ty
+                    ),
+                };
+                self.error(TypeError::invalid_fixed_point(
+                    message,
+                    self.span(bin.span),
+                    &self.source,
+                ));
+                return Type::Error;
+            }
+        };
+
+        match bin.op {
+            ast::BinaryOp::Add | ast::BinaryOp::Sub | ast::BinaryOp::Mul | ast::BinaryOp::Div => {
+                fixed_ty
+            }
+            ast::BinaryOp::Eq
+            | ast::BinaryOp::Ne
+            | ast::BinaryOp::Lt
+            | ast::BinaryOp::Le
+            | ast::BinaryOp::Gt
+            | ast::BinaryOp::Ge => Type::Primitive(PrimitiveType::Bool),
+            _ => {
+                self.error(TypeError::invalid_binary_op(
+                    &format!("{:?}", bin.op),
+                    left_ty,
+                    right_ty,
+                    self.span(bin.span),
+                    &self.source,
+                ));
+                Type::Error
+            }
+        }
+    }
+
     fn check_unary_expr(&mut self, un: &ast::UnaryExpr) -> Type {
         let expr_ty = self.check_expr(&un.expr);
 
@@ -1069,10 +1605,16 @@ impl TypeChecker {
         }
 
         match un.op {
-            ast::UnaryOp::Neg => {
-                if expr_ty.is_integer() {
-                    expr_ty
-                } else {
+            ast::UnaryOp::Neg => match expr_ty {
+                Type::Primitive(PrimitiveType::Fixed(ty)) if ty.signed => expr_ty,
+                Type::Primitive(PrimitiveType::DecimalLiteral { decimals, negative }) => {
+                    Type::Primitive(PrimitiveType::DecimalLiteral {
+                        decimals,
+                        negative: !negative,
+                    })
+                }
+                _ if expr_ty.is_integer() => expr_ty,
+                _ => {
                     self.error(TypeError::InvalidUnaryOp {
                         op: "-".to_string(),
                         ty: expr_ty.to_string(),
@@ -1084,7 +1626,7 @@ impl TypeChecker {
                     });
                     Type::Error
                 }
-            }
+            },
             ast::UnaryOp::Not => {
                 if expr_ty.is_bool() {
                     Type::Primitive(PrimitiveType::Bool)
@@ -1344,8 +1886,9 @@ impl TypeChecker {
 
             // Handle uint256(expr), uint64(expr), etc. - type cast to integer
             // Handle bytes1(expr), bytes32(expr), etc. - type cast to fixed bytes
+            // Handle ufixed64x6(expr), etc. - conversion of an integer to fixed-point
             if let Some(prim) = PrimitiveType::parse(name) {
-                if prim.is_integer() || prim.is_fixed_bytes() {
+                if prim.is_integer() || prim.is_fixed_bytes() || prim.is_fixed_point() {
                     if call.args.len() != 1 {
                         self.error(TypeError::wrong_arg_count(
                             1,
@@ -1355,7 +1898,21 @@ impl TypeChecker {
                         ));
                         return Type::Error;
                     }
-                    self.check_expr(&call.args[0].value);
+                    let arg_ty = self.check_expr(&call.args[0].value);
+                    let converts = if prim.is_fixed_point() {
+                        arg_ty.is_integer()
+                            || self.types_compatible(&Type::Primitive(prim), &arg_ty)
+                    } else {
+                        !arg_ty.is_fixed_point()
+                    };
+                    if !converts {
+                        self.error(TypeError::invalid_fixed_point(
+                            format!("cannot convert `{}` to `{}`", arg_ty, prim),
+                            self.span(call.span),
+                            &self.source,
+                        ));
+                        return Type::Error;
+                    }
                     return Type::Primitive(prim);
                 }
             }
@@ -1464,81 +2021,14 @@ impl TypeChecker {
                     "gasprice" => return Type::Primitive(PrimitiveType::Uint256),
                     _ => {}
                 },
-                "token" => {
-                    // SPL Token operations: transfer(from, to, authority, amount)
-                    // mint(mint, to, authority, amount), burn(from, mint, authority, amount)
-                    match method_name.as_str() {
-                        "transfer" | "mint" | "burn" => {
-                            // All take 4 args: 3 addresses and 1 amount
-                            if arg_types.len() != 4 {
-                                self.error(TypeError::wrong_arg_count(
-                                    4,
-                                    arg_types.len(),
-                                    self.span(mc.span),
-                                    &self.source,
-                                ));
-                                return Type::Error;
-                            }
-                            return Type::Unit;
-                        }
-                        "getATA" => {
-                            // getATA(owner, mint) -> address
-                            if arg_types.len() != 2 {
-                                self.error(TypeError::wrong_arg_count(
-                                    2,
-                                    arg_types.len(),
-                                    self.span(mc.span),
-                                    &self.source,
-                                ));
-                                return Type::Error;
-                            }
-                            return Type::Primitive(PrimitiveType::Address);
-                        }
-                        _ => {}
-                    }
-                }
-                // Solana Rent sysvar: rent.minimumBalance(size), rent.isExempt(balance, size)
-                "rent" => {
-                    match method_name.as_str() {
-                        "minimumBalance" => {
-                            // minimumBalance(dataLen: uint64) -> uint64
-                            if arg_types.len() != 1 {
-                                self.error(TypeError::wrong_arg_count(
-                                    1,
-                                    arg_types.len(),
-                                    self.span(mc.span),
-                                    &self.source,
-                                ));
-                                return Type::Error;
-                            }
-                            return Type::Primitive(PrimitiveType::Uint64);
-                        }
-                        "isExempt" => {
-                            // isExempt(lamports: uint64, dataLen: uint64) -> bool
-                            if arg_types.len() != 2 {
-                                self.error(TypeError::wrong_arg_count(
-                                    2,
-                                    arg_types.len(),
-                                    self.span(mc.span),
-                                    &self.source,
-                                ));
-                                return Type::Error;
-                            }
-                            return Type::Primitive(PrimitiveType::Bool);
-                        }
-                        _ => {}
-                    }
-                }
-                // Solana Clock sysvar methods
-                "clock" => {
-                    if method_name.as_str() == "get" {
-                        // clock.get() returns a Clock-like type (for now just return the type itself)
-                        return Type::Named(NamedType::new(SmolStr::from("clock")));
-                    }
-                }
                 _ => {}
             }
 
+            // Standard library intrinsics: token.transfer(...), Rent.isExempt(...)
+            if let Some(intrinsic) = solscript_std::intrinsic_of_type(type_name) {
+                return self.check_intrinsic_call(intrinsic, mc, &arg_types, &receiver_ty);
+            }
+
             // Look up the method on the named type
             let method_info = self
                 .symbols
@@ -1632,6 +2122,13 @@ impl TypeChecker {
             }
         }
 
+        // Fixed-point methods: x.floor(), x.mulUp(y), ...
+        if let Type::Primitive(PrimitiveType::Fixed(ty)) = receiver_ty {
+            if let Some(result) = self.check_fixed_method(ty, mc, &arg_types) {
+                return result;
+            }
+        }
+
         // Cannot call methods on non-named types
         self.error(TypeError::undefined_method(
             &method_name,
@@ -1642,6 +2139,103 @@ impl TypeChecker {
         Type::Error
     }
 
+    /// Check a call of a fixed-point method, or `None` if there is no such method.
+    /// `floor()` and `ceil()` round to the integer type of the same width;
+    /// `mulUp(y)` and `divUp(y)` are `*` and `/` rounded away from zero.
+    fn check_fixed_method(
+        &mut self,
+        ty: FixedType,
+        mc: &ast::MethodCallExpr,
+        arg_types: &[Type],
+    ) -> Option<Type> {
+        let fixed_ty = Type::Primitive(PrimitiveType::Fixed(ty));
+        let (arity, result) = match mc.method.name.as_str() {
+            "floor" | "ceil" => {
+                let int = match (ty.signed, ty.bits) {
+                    (true, 64) => PrimitiveType::Int64,
+                    (false, 64) => PrimitiveType::Uint64,
+                    (true, _) => PrimitiveType::Int128,
+                    (false, _) => PrimitiveType::Uint128,
+                };
+                (0, Type::Primitive(int))
+            }
+            "mulUp" | "divUp" => (1, fixed_ty.clone()),
+            _ => return None,
+        };
+        if arg_types.len() != arity {
+            self.error(TypeError::wrong_arg_count(
+                arity,
+                arg_types.len(),
+                self.span(mc.span),
+                &self.source,
+            ));
+            return Some(Type::Error);
+        }
+        if let Some(arg_ty) = arg_types.first() {
+            if !self.types_compatible(&fixed_ty, arg_ty) {
+                self.error(TypeError::type_mismatch(
+                    &fixed_ty,
+                    arg_ty,
+                    self.span(mc.args[0].value.span()),
+                    &self.source,
+                ));
+            }
+        }
+        Some(result)
+    }
+
+    /// Check a call of a standard library intrinsic against its declaration
+    fn check_intrinsic_call(
+        &mut self,
+        intrinsic: &solscript_std::Intrinsic,
+        mc: &ast::MethodCallExpr,
+        arg_types: &[Type],
+        receiver_ty: &Type,
+    ) -> Type {
+        let Some(sig) = intrinsic
+            .member(&mc.method.name)
+            .filter(|sig| !solscript_std::is_property(sig))
+        else {
+            self.error(TypeError::undefined_method(
+                &mc.method.name,
+                receiver_ty,
+                self.span(mc.span),
+                &self.source,
+            ));
+            return Type::Error;
+        };
+        let fn_ty = self.build_fn_sig_type(sig);
+
+        if arg_types.len() != fn_ty.params.len() {
+            self.error(TypeError::wrong_arg_count(
+                fn_ty.params.len(),
+                arg_types.len(),
+                self.span(mc.span),
+                &self.source,
+            ));
+            return Type::Error;
+        }
+        for ((arg, arg_ty), param_ty) in mc.args.iter().zip(arg_types).zip(&fn_ty.params) {
+            if !self.types_compatible(param_ty, arg_ty) {
+                self.error(TypeError::type_mismatch(
+                    param_ty,
+                    arg_ty,
+                    self.span(arg.value.span()),
+                    &self.source,
+                ));
+            } else if param_ty.is_integer() && !param_ty.is_signed() && arg_ty.is_signed() {
+                // Intrinsics widen unsigned arguments, which a negative value can't be
+                self.error(TypeError::signed_argument(
+                    param_ty,
+                    arg_ty,
+                    self.span(arg.value.span()),
+                    &self.source,
+                ));
+            }
+        }
+        *fn_ty.return_type
+    }
+
     fn check_field_access(&mut self, fa: &ast::FieldAccessExpr) -> Type {
         let expr_ty = self.check_expr(&fa.expr);
 
@@ -1654,6 +2248,16 @@ impl TypeChecker {
             let type_name = named.name.as_str();
             let field_name = fa.field.name.as_str();
 
+            // Standard library intrinsics: clock.slot, Clock.unix_timestamp, ...
+            if let Some(intrinsic) = solscript_std::intrinsic_of_type(type_name) {
+                if let Some(sig) = intrinsic
+                    .member(field_name)
+                    .filter(|sig| solscript_std::is_property(sig))
+                {
+                    return *self.build_fn_sig_type(sig).return_type;
+                }
+            }
+
             match type_name {
                 "msg" => match field_name {
                     "sender" => return Type::Primitive(PrimitiveType::Address),
@@ -1671,14 +2275,12 @@ impl TypeChecker {
                     "gasprice" => return Type::Primitive(PrimitiveType::Uint256),
                     _ => {}
                 },
-                // Solana-specific: clock.timestamp, clock.slot, clock.epoch
-                "clock" => match field_name {
-                    "timestamp" => return Type::Primitive(PrimitiveType::Int64),
-                    "slot" => return Type::Primitive(PrimitiveType::Uint64),
-                    "epoch" => return Type::Primitive(PrimitiveType::Uint64),
-                    "unix_timestamp" => return Type::Primitive(PrimitiveType::Int64),
-                    _ => {}
-                },
+                // Account parameters: SPL account fields and the account address
+                "account" | "unchecked_account" => {
+                    if let Some(ty) = account_field_type(named, field_name) {
+                        return ty;
+                    }
+                }
                 _ => {
                     // Look up field on user-defined type
                     let field_ty = self
@@ -1845,7 +2447,10 @@ impl TypeChecker {
             | ast::AssignOp::DivAssign
             | ast::AssignOp::RemAssign => {
                 // Compound assignment: both must be numeric and compatible
-                if (!target_ty.is_integer() || !self.types_compatible(&target_ty, &value_ty))
+                let numeric = target_ty.is_integer()
+                    || matches!(target_ty, Type::Primitive(PrimitiveType::Fixed(_)))
+                        && !matches!(a.op, ast::AssignOp::RemAssign);
+                if (!numeric || !self.types_compatible(&target_ty, &value_ty))
                     && !matches!(target_ty, Type::Error)
                     && !matches!(value_ty, Type::Error)
                 {
@@ -1940,6 +2545,11 @@ impl TypeChecker {
             (Type::Var(_), _) | (_, Type::Var(_)) => true, // Type variables are compatible with anything
             // Allow integer literals to be compatible with any integer type
             (Type::Primitive(a), Type::Primitive(b)) if a.is_integer() && b.is_integer() => true,
+            // A decimal literal converts to a fixed-point type with as many decimals
+            (
+                Type::Primitive(PrimitiveType::Fixed(ty)),
+                Type::Primitive(PrimitiveType::DecimalLiteral { decimals, negative }),
+            ) => *decimals <= ty.decimals && (ty.signed || !negative),
             // Signer is compatible with Address (signers are addresses that have signed)
             (Type::Primitive(PrimitiveType::Address), Type::Primitive(PrimitiveType::Signer)) => {
                 true
@@ -1947,6 +2557,12 @@ impl TypeChecker {
             (Type::Primitive(PrimitiveType::Signer), Type::Primitive(PrimitiveType::Address)) => {
                 true
             }
+            // Account parameters can be used where their address is expected
+            (Type::Primitive(PrimitiveType::Address), found @ Type::Named(_))
+                if found.is_account() =>
+            {
+                true
+            }
             (Type::Primitive(a), Type::Primitive(b)) => a == b,
             (Type::Unit, Type::Unit) => true,
             (Type::Never, _) => true, // Never is compatible with anything
@@ -1981,3 +2597,44 @@ impl TypeChecker {
         }
     }
 }
+
+/// Fields readable on an account parameter (`account<T>` or `unchecked_account`)
+fn account_field_type(account: &NamedType, field: &str) -> Option<Type> {
+    let inner = account.type_args.first().map(|t| t.to_string());
+    let ty = match (account.name.as_str(), inner.as_deref(), field) {
+        (_, _, "key") => PrimitiveType::Address,
+        ("account", Some("TokenAccount"), "mint" | "owner") => PrimitiveType::Address,
+        ("account", Some("TokenAccount"), "amount") => PrimitiveType::Uint64,
+        ("account", Some("Mint"), "supply") => PrimitiveType::Uint64,
+        ("account", Some("Mint"), "decimals") => PrimitiveType::Uint8,
+        ("unchecked_account", _, "lamports") => PrimitiveType::Uint64,
+        _ => return None,
+    };
+    Some(Type::Primitive(ty))
+}
+
+/// A state layout version in `#[version(n)]` / `#[migration(from = n, ...)]`;
+/// the version is stored in a `u8` ahead of the state fields
+fn layout_version(arg: &ast::AttributeArg) -> Option<u128> {
+    match &arg.value {
+        ast::AttributeValue::Literal(ast::Literal::Int(v, _)) if (1..=255).contains(v) => Some(*v),
+        _ => None,
+    }
+}
+
+/// Whether `#[constraint(...)]` can be placed on a parameter of this type
+fn is_constraint_target(ty: &Type) -> bool {
+    match ty {
+        Type::Named(n) => n.name == "account" || n.name == "unchecked_account",
+        Type::Primitive(PrimitiveType::Signer) => true,
+        _ => false,
+    }
+}
+
+/// The account named by `has_one = x` / `close = x`
+fn constraint_account_name(value: &ast::AttributeValue) -> Option<&ast::Ident> {
+    match value {
+        ast::AttributeValue::Ident(ident) => Some(ident),
+        _ => None,
+    }
+}
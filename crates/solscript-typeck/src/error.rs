@@ -222,6 +222,83 @@ pub enum TypeError {
         #[source_code]
         src: String,
     },
+
+    #[error("Invalid account type: {message}")]
+    #[diagnostic(
+        code(solscript::typeck::invalid_account_type),
+        help("account parameters are `account<TokenAccount>`, `account<Mint>`, `unchecked_account` or `accounts[]`")
+    )]
+    InvalidAccountType {
+        message: String,
+        #[label("{message}")]
+        span: SourceSpan,
+        #[source_code]
+        src: String,
+    },
+
+    #[error("Field `{name}` of type `{ty}` cannot be stored in a zero-copy account")]
+    #[diagnostic(
+        code(solscript::typeck::invalid_zero_copy_field),
+        help("#[zero_copy] state can only hold fixed-size integers, `address`, `bytesN` and fixed-size arrays of those")
+    )]
+    InvalidZeroCopyField {
+        name: String,
+        ty: String,
+        #[label("not plain old data")]
+        span: SourceSpan,
+        #[source_code]
+        src: String,
+    },
+
+    #[error("Invalid attribute: {message}")]
+    #[diagnostic(code(solscript::typeck::invalid_attribute))]
+    InvalidAttribute {
+        message: String,
+        #[label("{message}")]
+        span: SourceSpan,
+        #[source_code]
+        src: String,
+    },
+
+    #[error("Invalid fixed-point operation: {message}")]
+    #[diagnostic(
+        code(solscript::typeck::invalid_fixed_point),
+        help("fixed-point values combine with the same fixed-point type or a decimal literal; convert integers with `ufixed64x6(n)` and back with `.floor()` or `.ceil()`")
+    )]
+    InvalidFixedPoint {
+        message: String,
+        #[label("{message}")]
+        span: SourceSpan,
+        #[source_code]
+        src: String,
+    },
+
+    #[error("Signed argument: expected `{expected}`, found `{found}`")]
+    #[diagnostic(
+        code(solscript::typeck::signed_argument),
+        help("this parameter only takes unsigned integers; check the value is not negative and convert it, e.g. `uint64(x)`")
+    )]
+    SignedArgument {
+        expected: String,
+        found: String,
+        #[label("`{found}` may be negative")]
+        span: SourceSpan,
+        #[source_code]
+        src: String,
+    },
+
+    #[error("Invalid account constraint: {message}")]
+    #[diagnostic(
+        code(solscript::typeck::invalid_constraint),
+        help("supported constraints are `has_one = field`, `owner = expr`, `address = expr`, `constraint = expr` and `close = account`")
+    )]
+    InvalidConstraint {
+        message: String,
+        #[label("{message}")]
+        span: SourceSpan,
+        #[source_code]
+        src: String,
+    },
 }
 
 impl TypeError {
@@ -250,6 +327,64 @@ impl TypeError {
         }
     }
 
+    pub fn invalid_account_type(
+        message: impl Into<String>,
+        span: (usize, usize),
+        src: &str,
+    ) -> Self {
+        Self::InvalidAccountType {
+            message: message.into(),
+            span: SourceSpan::new(span.0.into(), span.1 - span.0),
+            src: src.to_string(),
+        }
+    }
+
+    pub fn invalid_zero_copy_field(name: &str, ty: &Type, span: (usize, usize), src: &str) -> Self {
+        Self::InvalidZeroCopyField {
+            name: name.to_string(),
+            ty: ty.to_string(),
+            span: SourceSpan::new(span.0.into(), span.1 - span.0),
+            src: src.to_string(),
+        }
+    }
+
+    pub fn invalid_attribute(message: impl Into<String>, span: (usize, usize), src: &str) -> Self {
+        Self::InvalidAttribute {
+            message: message.into(),
+            span: SourceSpan::new(span.0.into(), span.1 - span.0),
+            src: src.to_string(),
+        }
+    }
+
+    pub fn invalid_fixed_point(
+        message: impl Into<String>,
+        span: (usize, usize),
+        src: &str,
+    ) -> Self {
+        Self::InvalidFixedPoint {
+            message: message.into(),
+            span: SourceSpan::new(span.0.into(), span.1 - span.0),
+            src: src.to_string(),
+        }
+    }
+
+    pub fn signed_argument(expected: &Type, found: &Type, span: (usize, usize), src: &str) -> Self {
+        Self::SignedArgument {
+            expected: expected.to_string(),
+            found: found.to_string(),
+            span: SourceSpan::new(span.0.into(), span.1 - span.0),
+            src: src.to_string(),
+        }
+    }
+
+    pub fn invalid_constraint(message: impl Into<String>, span: (usize, usize), src: &str) -> Self {
+        Self::InvalidConstraint {
+            message: message.into(),
+            span: SourceSpan::new(span.0.into(), span.1 - span.0),
+            src: src.to_string(),
+        }
+    }
+
     pub fn undefined_field(field: &str, ty: &Type, span: (usize, usize), src: &str) -> Self {
         Self::UndefinedField {
             field: field.to_string(),
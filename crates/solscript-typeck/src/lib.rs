@@ -714,4 +714,446 @@ mod tests {
             .iter()
             .any(|e| matches!(e, TypeError::TypeMismatch { .. })));
     }
+
+    #[test]
+    fn test_idl_interface_arg_types() {
+        let idl = r#"{
+            "instructions": [{
+                "name": "deposit",
+                "accounts": [
+                    { "name": "state", "isMut": true, "isSigner": false },
+                    { "name": "transferFrom", "isMut": true, "isSigner": true }
+                ],
+                "args": [{ "name": "lamports", "type": "u64" }]
+            }]
+        }"#;
+        let check_with_idl = |source: &str| {
+            let mut program = solscript_parser::parse(source).expect("Parse failed");
+            let name = solscript_ast::Ident::new("Marinade", solscript_ast::Span::dummy());
+            let (interface, _) = solscript_parser::anchor_idl_to_interface(name, idl).unwrap();
+            program
+                .items
+                .push(solscript_ast::Item::Interface(interface));
+            typecheck(&program, source)
+        };
+
+        let ok = check_with_idl(
+            r#"
+            contract Staker {
+                address public marinade;
+                address public state;
+
+                function stake(uint64 amount) public {
+                    Marinade(marinade).deposit(state, msg.sender, amount);
+                }
+            }
+        "#,
+        );
+        assert!(ok.is_ok(), "Expected success: {:?}", ok.err());
+
+        // Accounts must be addresses
+        let errors = check_with_idl(
+            r#"
+            contract Staker {
+                address public marinade;
+
+                function stake(uint64 amount) public {
+                    Marinade(marinade).deposit(true, msg.sender, amount);
+                }
+            }
+        "#,
+        )
+        .unwrap_err();
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, TypeError::TypeMismatch { .. })));
+
+        // Missing accounts are an arity error
+        let errors = check_with_idl(
+            r#"
+            contract Staker {
+                address public marinade;
+
+                function stake(uint64 amount) public {
+                    Marinade(marinade).deposit(amount);
+                }
+            }
+        "#,
+        )
+        .unwrap_err();
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, TypeError::WrongArgCount { .. })));
+    }
+
+    #[test]
+    fn test_account_params() {
+        let result = check(
+            r#"
+            contract Vault {
+                uint64 public total;
+
+                function deposit(account<TokenAccount> userAta, account<Mint> mint, unchecked_account feeRecipient, accounts[] extra) public {
+                    require(userAta.owner == msg.sender, "Not owner");
+                    require(userAta.mint == mint.key, "Wrong mint");
+                    uint8 decimals = mint.decimals;
+                    total = total + userAta.amount + feeRecipient.lamports;
+                    if (extra.length > 0) {
+                        address first = extra[0];
+                    }
+                }
+            }
+        "#,
+        );
+        assert!(result.is_ok(), "Unexpected errors: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_account_params_invalid() {
+        // Unknown account type
+        let result = check(
+            r#"
+            contract Vault {
+                function deposit(account<Vault> vault) public {}
+            }
+        "#,
+        );
+        assert!(result
+            .unwrap_err()
+            .iter()
+            .any(|e| matches!(e, TypeError::InvalidAccountType { .. })));
+
+        // Accounts can't be stored in state
+        let result = check(
+            r#"
+            contract Vault {
+                account<Mint> mint;
+            }
+        "#,
+        );
+        assert!(result
+            .unwrap_err()
+            .iter()
+            .any(|e| matches!(e, TypeError::InvalidAccountType { .. })));
+
+        // No such field on a mint
+        let result = check(
+            r#"
+            contract Vault {
+                function peek(account<Mint> mint) public view returns (uint64) {
+                    return mint.amount;
+                }
+            }
+        "#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_account_constraints() {
+        let result = check(
+            r#"
+            contract Vault {
+                address public admin;
+                uint64 public minDeposit;
+
+                #[constraint(has_one = admin, close = admin)]
+                function shutdown(signer admin) public {}
+
+                function deposit(
+                    #[constraint(has_one = mint, constraint = userAta.amount >= minDeposit)] account<TokenAccount> userAta,
+                    #[constraint(address = admin)] account<Mint> mint,
+                    #[constraint(owner = admin)] #[mut] unchecked_account feeRecipient
+                ) public {}
+            }
+        "#,
+        );
+        assert!(result.is_ok(), "Unexpected errors: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_account_constraints_invalid() {
+        let invalid = [
+            // has_one field must exist and be an address
+            "#[constraint(has_one = minDeposit)] function f(signer minDeposit) public {}",
+            "function f(#[constraint(has_one = owner)] account<Mint> mint, signer owner) public {}",
+            // has_one needs a matching account
+            "#[constraint(has_one = admin)] function f() public {}",
+            // Constraints only see accounts and state
+            "function f(#[constraint(constraint = amount > 0)] account<TokenAccount> ata, uint64 amount) public {}",
+            // Only accounts and signers take constraints
+            "function f(#[constraint(address = admin)] uint64 x) public {}",
+            // close is for the state account, and not in view functions
+            "function f(#[constraint(close = admin)] unchecked_account a, signer admin) public {}",
+            "#[constraint(close = admin)] function f(signer admin) public view {}",
+            "#[constraint(frobnicate = admin)] function f(signer admin) public {}",
+            // Only accounts and signers can be writable, and not in view functions
+            "function f(#[mut] uint64 x) public {}",
+            "function f(#[mut] unchecked_account a) public view {}",
+        ];
+        for func in invalid {
+            let source = format!(
+                "contract Vault {{ address public admin; uint64 public minDeposit; {} }}",
+                func
+            );
+            let errors = check(&source).unwrap_err();
+            assert!(
+                errors
+                    .iter()
+                    .any(|e| matches!(e, TypeError::InvalidConstraint { .. })),
+                "Expected an invalid constraint error for `{}`, got {:?}",
+                func,
+                errors
+            );
+        }
+
+        // Constraint expressions are type checked, and mismatches point at the argument
+        let source = r#"
+            contract Vault {
+                function f(#[constraint(constraint = ata.amount)] account<TokenAccount> ata) public {}
+            }
+        "#;
+        let errors = check(source).unwrap_err();
+        let span = errors
+            .iter()
+            .find_map(|e| match e {
+                TypeError::TypeMismatch { span, .. } => Some(*span),
+                _ => None,
+            })
+            .expect("a type mismatch");
+        assert_eq!(
+            &source[span.offset()..span.offset() + span.len()],
+            "constraint = ata.amount"
+        );
+    }
+
+    #[test]
+    fn test_zero_copy_fields() {
+        let result = check(
+            r#"
+            #[zero_copy]
+            contract OrderBook {
+                address public admin;
+                uint64[512] public prices;
+                bytes32 public root;
+                mapping(address => string) public names;
+            }
+        "#,
+        );
+        assert!(result.is_ok(), "Unexpected errors: {:?}", result.err());
+
+        let result = check(
+            r#"
+            #[zero_copy]
+            contract OrderBook {
+                string public name;
+                bool public paused;
+                uint64[] public prices;
+            }
+        "#,
+        );
+        let errors = result.unwrap_err();
+        let count = errors
+            .iter()
+            .filter(|e| matches!(e, TypeError::InvalidZeroCopyField { .. }))
+            .count();
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_growable_fields() {
+        let result = check(
+            r#"
+            contract Registry {
+                #[growable]
+                string public name;
+                #[growable]
+                uint64[] public values;
+            }
+        "#,
+        );
+        assert!(result.is_ok(), "Unexpected errors: {:?}", result.err());
+
+        let result = check(
+            r#"
+            contract Registry {
+                #[growable]
+                uint64 public count;
+            }
+        "#,
+        );
+        assert!(result
+            .unwrap_err()
+            .iter()
+            .any(|e| matches!(e, TypeError::InvalidAttribute { .. })));
+    }
+
+    #[test]
+    fn test_upgradeable_versions_and_migrations() {
+        let result = check(
+            r#"
+            #[upgradeable]
+            contract Vault {
+                uint64 public balance;
+                #[version(2)]
+                uint64 public scaled;
+
+                #[migration(from = 1, to = 2)]
+                function migrateV1ToV2() internal {
+                    scaled = balance * 1000;
+                }
+            }
+        "#,
+        );
+        assert!(result.is_ok(), "Unexpected errors: {:?}", result.err());
+
+        let result = check(
+            r#"
+            #[upgradeable]
+            contract Vault {
+                uint64 public balance;
+                #[version(0)]
+                uint64 public scaled;
+
+                #[migration(from = 1, to = 3)]
+                function skip() internal {}
+
+                #[migration(from = 1, to = 2)]
+                function withParams(uint64 factor) public {}
+            }
+
+            contract Plain {
+                #[version(2)]
+                uint64 public balance;
+            }
+        "#,
+        );
+        let errors = result.unwrap_err();
+        let count = errors
+            .iter()
+            .filter(|e| matches!(e, TypeError::InvalidAttribute { .. }))
+            .count();
+        // bad version, version skip, unknown target version, params, public, not upgradeable
+        assert_eq!(count, 6, "Unexpected errors: {:?}", errors);
+    }
+
+    #[test]
+    fn test_std_intrinsics() {
+        let result = check(
+            r#"
+            import { Token as Spl } from "@solana/token";
+            import { Clock } from "@solana/clock";
+
+            contract Payout {
+                uint64 public lastSlot;
+
+                function pay(address from, address to, uint64 amount) public {
+                    require(Clock.unix_timestamp > 0, "Too early");
+                    lastSlot = clock.slot;
+                    Spl.transfer(from, to, msg.sender, amount);
+                    address ata = token.getATA(to, from);
+                    require(rent.isExempt(amount, 165), "Not exempt");
+                }
+            }
+        "#,
+        );
+        assert!(result.is_ok(), "Unexpected errors: {:?}", result.err());
+
+        let result = check(
+            r#"
+            contract Payout {
+                function pay(address to, uint64 amount) public {
+                    token.transfer(to, to, amount);
+                    token.transfer(to, to, "me", amount);
+                    token.send(to, amount);
+                    uint64 slot = clock.slots;
+                    Token.burn(to, to, to, amount);
+                }
+            }
+        "#,
+        );
+        let errors = result.unwrap_err();
+        assert!(matches!(errors[0], TypeError::WrongArgCount { .. }));
+        assert!(matches!(errors[1], TypeError::TypeMismatch { .. }));
+        assert!(matches!(errors[2], TypeError::UndefinedMethod { .. }));
+        assert!(matches!(errors[3], TypeError::UndefinedField { .. }));
+        // Not imported
+        assert!(matches!(errors[4], TypeError::UndefinedVariable { .. }));
+        assert_eq!(errors.len(), 5, "Unexpected errors: {:?}", errors);
+    }
+
+    #[test]
+    fn test_math_rejects_signed_operands() {
+        let result = check(
+            r#"
+            import { Math } from "@solana/math";
+
+            contract Pool {
+                function f(int64 a, uint64 b) public pure returns (uint64) {
+                    uint64 low = Math.min(uint64(a), b);
+                    return Math.max(a, b) + Math.mulBps(b, a) + low;
+                }
+            }
+        "#,
+        );
+        let errors = result.unwrap_err();
+        assert!(matches!(
+            &errors[0],
+            TypeError::SignedArgument { found, .. } if found == "int64"
+        ));
+        assert!(matches!(errors[1], TypeError::SignedArgument { .. }));
+        assert_eq!(errors.len(), 2, "Unexpected errors: {:?}", errors);
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let result = check(
+            r#"
+            contract Vault {
+                ufixed64x6 public rate = 1.05;
+                fixed128x18 public delta;
+
+                function accrue(uint64 principal) public returns (uint64) {
+                    ufixed64x6 amount = ufixed64x6(principal) * rate;
+                    amount += 0.5;
+                    delta = -delta * -2.25;
+                    require(amount.mulUp(rate) >= amount / 3.0, "Rounding");
+                    require(1.0 < rate, "No interest");
+                    return amount.floor() + amount.ceil();
+                }
+            }
+        "#,
+        );
+        assert!(result.is_ok(), "Unexpected errors: {:?}", result.err());
+
+        let result = check(
+            r#"
+            contract Vault {
+                ufixed64x6 public rate;
+                fixed128x18 public delta;
+
+                function f(uint64 amount) public {
+                    rate = rate * amount;
+                    rate = rate + delta;
+                    rate = rate * 1.0000001;
+                    rate = -rate;
+                    uint64 whole = uint64(rate);
+                    ufixed64x6 half = 1.0 / 2.0;
+                    rate = rate % 2.0;
+                    rate = 1;
+                }
+            }
+        "#,
+        );
+        let errors = result.unwrap_err();
+        assert!(matches!(errors[0], TypeError::InvalidFixedPoint { .. }));
+        assert!(matches!(errors[1], TypeError::InvalidFixedPoint { .. }));
+        assert!(matches!(errors[2], TypeError::InvalidFixedPoint { .. }));
+        assert!(matches!(errors[3], TypeError::InvalidUnaryOp { .. }));
+        assert!(matches!(errors[4], TypeError::InvalidFixedPoint { .. }));
+        assert!(matches!(errors[5], TypeError::InvalidFixedPoint { .. }));
+        assert!(matches!(errors[6], TypeError::InvalidBinaryOp { .. }));
+        assert!(matches!(errors[7], TypeError::TypeMismatch { .. }));
+        assert_eq!(errors.len(), 8, "Unexpected errors: {:?}", errors);
+    }
 }
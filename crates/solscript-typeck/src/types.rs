@@ -2,6 +2,7 @@
 
 use indexmap::IndexMap;
 use smol_str::SmolStr;
+use solscript_ast::FixedType;
 use std::fmt;
 
 /// A unique identifier for a type
@@ -46,6 +47,11 @@ impl Type {
         matches!(self, Type::Primitive(p) if p.is_integer())
     }
 
+    /// Check if this is a fixed-point decimal type or a decimal literal
+    pub fn is_fixed_point(&self) -> bool {
+        matches!(self, Type::Primitive(p) if p.is_fixed_point())
+    }
+
     /// Check if this is a signed integer type
     pub fn is_signed(&self) -> bool {
         matches!(self, Type::Primitive(p) if p.is_signed())
@@ -61,6 +67,28 @@ impl Type {
         matches!(self, Type::Primitive(PrimitiveType::Address))
     }
 
+    /// Check if this is a Solana account parameter type
+    /// (`account<T>`, `unchecked_account` or `accounts[]`)
+    pub fn is_account(&self) -> bool {
+        match self {
+            Type::Named(n) => n.name == "account" || n.name == "unchecked_account",
+            Type::DynamicArray(t) => t.is_account(),
+            _ => false,
+        }
+    }
+
+    /// Check if this type is plain old data, storable in a `#[zero_copy]` account
+    /// (fixed-size integers, addresses, `bytesN` and fixed-size arrays of those)
+    pub fn is_pod(&self) -> bool {
+        match self {
+            Type::Primitive(p) => {
+                p.is_integer() || p.is_fixed_bytes() || *p == PrimitiveType::Address
+            }
+            Type::Array(t, _) => t.is_pod(),
+            _ => false,
+        }
+    }
+
     /// Check if this type contains any type variables
     pub fn has_type_vars(&self) -> bool {
         match self {
@@ -211,11 +239,26 @@ pub enum PrimitiveType {
     Bytes30,
     Bytes31,
     Bytes32,
+    /// Fixed-point decimal: `fixed64x6`, `ufixed128x18`
+    Fixed(FixedType),
+    /// Type of a decimal literal such as `1.5`, convertible to any fixed-point type
+    /// with at least `decimals` decimals (and signed, if `negative`)
+    DecimalLiteral {
+        decimals: u8,
+        negative: bool,
+    },
 }
 
 impl PrimitiveType {
     pub fn is_numeric(&self) -> bool {
-        self.is_integer()
+        self.is_integer() || self.is_fixed_point()
+    }
+
+    pub fn is_fixed_point(&self) -> bool {
+        matches!(
+            self,
+            PrimitiveType::Fixed(_) | PrimitiveType::DecimalLiteral { .. }
+        )
     }
 
     pub fn is_integer(&self) -> bool {
@@ -470,7 +513,7 @@ impl PrimitiveType {
             "bytes30" => Some(Self::Bytes30),
             "bytes31" => Some(Self::Bytes31),
             "bytes32" => Some(Self::Bytes32),
-            _ => None,
+            _ => FixedType::parse(s).map(Self::Fixed),
         }
     }
 }
@@ -545,6 +588,8 @@ impl fmt::Display for PrimitiveType {
             PrimitiveType::Bool => write!(f, "bool"),
             PrimitiveType::Address => write!(f, "address"),
             PrimitiveType::Signer => write!(f, "signer"),
+            PrimitiveType::Fixed(ty) => write!(f, "{}", ty),
+            PrimitiveType::DecimalLiteral { .. } => write!(f, "decimal literal"),
             PrimitiveType::String => write!(f, "string"),
             PrimitiveType::Bytes => write!(f, "bytes"),
             PrimitiveType::Bytes1 => write!(f, "bytes1"),
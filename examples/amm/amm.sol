@@ -3,6 +3,8 @@
 // A simple constant product AMM (x * y = k) for token swaps.
 // Demonstrates DeFi primitives: liquidity pools, swaps, and LP tokens.
 
+import { Math } from "@solana/math";
+
 contract SimpleAMM {
     // Pool State
     address public tokenA;
@@ -54,8 +56,9 @@ contract SimpleAMM {
         reserveA = amountA;
         reserveB = amountB;
 
-        // Calculate initial LP tokens using geometric mean approximation
-        uint64 lpTokens = sqrt(amountA * amountB);
+        // Initial LP tokens are the geometric mean of the deposits. The product is
+        // taken in 256 bits so it cannot overflow.
+        uint64 lpTokens = Math.sqrt(Math.mulDiv(amountA, amountB, 1));
 
         // Lock minimum liquidity to prevent manipulation
         lpBalances[address(0)] = 1000;
@@ -78,24 +81,21 @@ contract SimpleAMM {
         require(amountA > 0 && amountB > 0, "Zero amount");
 
         // Calculate optimal amounts based on current ratio
-        uint64 optimalB = (amountA * reserveB) / reserveA;
+        uint64 optimalB = Math.mulDiv(amountA, reserveB, reserveA);
         uint64 actualA = amountA;
         uint64 actualB = amountB;
 
         if (optimalB <= amountB) {
             actualB = optimalB;
         } else {
-            uint64 optimalA = (amountB * reserveA) / reserveB;
+            uint64 optimalA = Math.mulDiv(amountB, reserveA, reserveB);
             actualA = optimalA;
         }
 
         // Calculate LP tokens to mint
-        uint64 lpFromA = (actualA * totalLPSupply) / reserveA;
-        uint64 lpFromB = (actualB * totalLPSupply) / reserveB;
-        uint64 lpTokens = lpFromA;
-        if (lpFromB < lpFromA) {
-            lpTokens = lpFromB;
-        }
+        uint64 lpFromA = Math.mulDiv(actualA, totalLPSupply, reserveA);
+        uint64 lpFromB = Math.mulDiv(actualB, totalLPSupply, reserveB);
+        uint64 lpTokens = Math.min(lpFromA, lpFromB);
 
         require(lpTokens >= minLPTokens, "Slippage exceeded");
 
@@ -121,8 +121,8 @@ contract SimpleAMM {
         require(lpBalances[msg.sender] >= lpTokens, "Insufficient LP balance");
 
         // Calculate token amounts to return
-        uint64 amountA = (lpTokens * reserveA) / totalLPSupply;
-        uint64 amountB = (lpTokens * reserveB) / totalLPSupply;
+        uint64 amountA = Math.mulDiv(lpTokens, reserveA, totalLPSupply);
+        uint64 amountB = Math.mulDiv(lpTokens, reserveB, totalLPSupply);
 
         require(amountA >= minAmountA && amountB >= minAmountB, "Slippage exceeded");
 
@@ -142,13 +142,12 @@ contract SimpleAMM {
         require(initialized, "Not initialized");
         require(amountIn > 0, "Zero amount");
 
-        // Apply fee (10000 basis points = 100%)
-        uint64 amountInWithFee = amountIn * (10000 - swapFee);
+        // Apply fee (in basis points), rounded in the pool's favor
+        uint64 fee = Math.mulBpsUp(amountIn, swapFee);
+        uint64 amountInAfterFee = amountIn - fee;
 
         // Constant product formula
-        uint64 numerator = amountInWithFee * reserveB;
-        uint64 denominator = (reserveA * 10000) + amountInWithFee;
-        uint64 amountOut = numerator / denominator;
+        uint64 amountOut = Math.mulDiv(amountInAfterFee, reserveB, reserveA + amountInAfterFee);
 
         require(amountOut >= minAmountOut, "Slippage exceeded");
         require(amountOut < reserveB, "Insufficient liquidity");
@@ -168,12 +167,11 @@ contract SimpleAMM {
         require(amountIn > 0, "Zero amount");
 
         // Apply fee
-        uint64 amountInWithFee = amountIn * (10000 - swapFee);
+        uint64 fee = Math.mulBpsUp(amountIn, swapFee);
+        uint64 amountInAfterFee = amountIn - fee;
 
         // Constant product formula
-        uint64 numerator = amountInWithFee * reserveA;
-        uint64 denominator = (reserveB * 10000) + amountInWithFee;
-        uint64 amountOut = numerator / denominator;
+        uint64 amountOut = Math.mulDiv(amountInAfterFee, reserveA, reserveB + amountInAfterFee);
 
         require(amountOut >= minAmountOut, "Slippage exceeded");
         require(amountOut < reserveA, "Insufficient liquidity");
@@ -192,7 +190,7 @@ contract SimpleAMM {
         if (reserveA == 0) {
             return 0;
         }
-        return (reserveB * 1000000) / reserveA;
+        return Math.mulDiv(reserveB, 1000000, reserveA);
     }
 
     // Get current price of tokenB in terms of tokenA (6 decimal precision)
@@ -200,7 +198,7 @@ contract SimpleAMM {
         if (reserveB == 0) {
             return 0;
         }
-        return (reserveA * 1000000) / reserveB;
+        return Math.mulDiv(reserveA, 1000000, reserveB);
     }
 
     // Calculate expected output for a given input
@@ -212,11 +210,9 @@ contract SimpleAMM {
             reserveOut = reserveA;
         }
 
-        uint64 amountInWithFee = amountIn * (10000 - swapFee);
-        uint64 numerator = amountInWithFee * reserveOut;
-        uint64 denominator = (reserveIn * 10000) + amountInWithFee;
-
-        return numerator / denominator;
+        uint64 fee = Math.mulBpsUp(amountIn, swapFee);
+        uint64 amountInAfterFee = amountIn - fee;
+        return Math.mulDiv(amountInAfterFee, reserveOut, reserveIn + amountInAfterFee);
     }
 
     // Get pool reserves
@@ -237,21 +233,4 @@ contract SimpleAMM {
         swapFee = newFee;
         emit FeeUpdated(oldFee, newFee);
     }
-
-    // Integer square root (Babylonian method)
-    function sqrt(uint64 x) internal pure returns (uint64) {
-        if (x == 0) {
-            return 0;
-        }
-
-        uint64 z = (x + 1) / 2;
-        uint64 y = x;
-
-        while (z < y) {
-            y = z;
-            z = (x / z + z) / 2;
-        }
-
-        return y;
-    }
 }